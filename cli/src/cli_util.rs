@@ -33,12 +33,13 @@ use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
 use jj_lib::backend::{BackendError, ChangeId, CommitId, MergedTreeId};
 use jj_lib::commit::Commit;
+use jj_lib::fileset::{self, FilesetExpression, FilesetParseContext, FilesetParseError};
 use jj_lib::git::{GitConfigParseError, GitExportError, GitImportError, GitRemoteManagementError};
 use jj_lib::git_backend::GitBackend;
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::hex_util::to_reverse_hex;
 use jj_lib::id_prefix::IdPrefixContext;
-use jj_lib::matchers::{EverythingMatcher, Matcher, PrefixMatcher};
+use jj_lib::matchers::{EverythingMatcher, Matcher};
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::object_id::ObjectId;
 use jj_lib::op_heads_store::{self, OpHeadResolutionError};
@@ -58,7 +59,8 @@ use jj_lib::revset::{
 use jj_lib::rewrite::restore_tree;
 use jj_lib::settings::{ConfigResultExt as _, UserSettings};
 use jj_lib::signing::SignInitError;
-use jj_lib::str_util::{StringPattern, StringPatternParseError};
+use jj_lib::str_util::StringPattern;
+use jj_lib::time_util::DatePatternContext;
 use jj_lib::transaction::Transaction;
 use jj_lib::tree::TreeMergeError;
 use jj_lib::working_copy::{
@@ -414,7 +416,15 @@ impl From<RevsetEvaluationError> for CommandError {
 
 impl From<RevsetParseError> for CommandError {
     fn from(err: RevsetParseError) -> Self {
-        let message = iter::successors(Some(&err), |e| e.origin()).join("\n");
+        let mut message = iter::successors(Some(&err), |e| e.origin()).join("\n");
+        // The fileset error has its own span within the function argument
+        if let Some(RevsetParseErrorKind::FilesetParseError(fileset_err)) =
+            iter::successors(Some(&err), |e| e.origin())
+                .last()
+                .map(|e| e.kind())
+        {
+            message.push_str(&format!("\n{fileset_err}"));
+        }
         // Only for the top-level error as we can't attach hint to inner errors
         let hint = match err.kind() {
             RevsetParseErrorKind::NotPostfixOperator {
@@ -461,6 +471,12 @@ impl From<TemplateParseError> for CommandError {
     }
 }
 
+impl From<FilesetParseError> for CommandError {
+    fn from(err: FilesetParseError) -> Self {
+        user_error(format!("Failed to parse fileset: {err}"))
+    }
+}
+
 impl From<FsPathParseError> for CommandError {
     fn from(err: FsPathParseError) -> Self {
         user_error(err)
@@ -857,6 +873,10 @@ impl WorkspaceCommandHelper {
         self.user_repo.git_backend()
     }
 
+    pub fn settings(&self) -> &UserSettings {
+        &self.settings
+    }
+
     pub fn check_working_copy_writable(&self) -> Result<(), CommandError> {
         if self.may_update_working_copy {
             Ok(())
@@ -1053,16 +1073,40 @@ impl WorkspaceCommandHelper {
         RepoPathBuf::parse_fs_path(&self.cwd, self.workspace_root(), input)
     }
 
+    /// Parses the path arguments, and returns their union. A path is relative
+    /// to cwd. The arguments are parsed as fileset expressions unless
+    /// `ui.allow-filesets` is disabled.
+    pub fn parse_file_patterns(
+        &self,
+        values: &[String],
+    ) -> Result<FilesetExpression, CommandError> {
+        let expressions: Vec<_> = if self.settings.config().get_bool("ui.allow-filesets")? {
+            let ctx = FilesetParseContext {
+                cwd: &self.cwd,
+                workspace_root: self.workspace_root(),
+            };
+            values
+                .iter()
+                .map(|value| fileset::parse_maybe_bare(value, &ctx))
+                .try_collect()?
+        } else {
+            values
+                .iter()
+                .map(|value| {
+                    Ok::<_, FsPathParseError>(FilesetExpression::prefix_path(
+                        self.parse_file_path(value)?,
+                    ))
+                })
+                .try_collect()?
+        };
+        Ok(FilesetExpression::union_all(expressions))
+    }
+
     pub fn matcher_from_values(&self, values: &[String]) -> Result<Box<dyn Matcher>, CommandError> {
         if values.is_empty() {
             Ok(Box::new(EverythingMatcher))
         } else {
-            // TODO: Add support for globs and other formats
-            let paths: Vec<_> = values
-                .iter()
-                .map(|v| self.parse_file_path(v))
-                .try_collect()?;
-            Ok(Box::new(PrefixMatcher::new(paths)))
+            Ok(self.parse_file_patterns(values)?.to_matcher())
         }
     }
 
@@ -1288,6 +1332,7 @@ Set which revision the branch points to with `jj branch set {branch_name} -r <RE
         RevsetParseContext {
             aliases_map: &self.revset_aliases_map,
             user_email: self.settings.user_email(),
+            date_pattern_context: DatePatternContext::from_current_time(),
             workspace: Some(workspace_context),
         }
     }
@@ -1374,16 +1419,9 @@ Set which revision the branch points to with `jj branch set {branch_name} -r <RE
         Ok(())
     }
 
-    pub fn check_rewritable<'a>(
-        &self,
-        commits: impl IntoIterator<Item = &'a Commit>,
-    ) -> Result<(), CommandError> {
-        let to_rewrite_revset = RevsetExpression::commits(
-            commits
-                .into_iter()
-                .map(|commit| commit.id().clone())
-                .collect(),
-        );
+    /// Returns the expression for the set of commits that can't be rewritten:
+    /// the ancestors of `immutable_heads()` and the root commit.
+    pub fn immutable_revset(&self) -> Result<Rc<RevsetExpression>, CommandError> {
         let (params, immutable_heads_str) = self
             .revset_aliases_map
             .get_function("immutable_heads")
@@ -1394,11 +1432,24 @@ Set which revision the branch points to with `jj branch set {branch_name} -r <RE
             ));
         }
         let immutable_heads_revset = self.parse_revset(immutable_heads_str, None)?;
-        let immutable_revset = immutable_heads_revset
+        Ok(immutable_heads_revset
             .ancestors()
             .union(&RevsetExpression::commit(
                 self.repo().store().root_commit_id().clone(),
-            ));
+            )))
+    }
+
+    pub fn check_rewritable<'a>(
+        &self,
+        commits: impl IntoIterator<Item = &'a Commit>,
+    ) -> Result<(), CommandError> {
+        let to_rewrite_revset = RevsetExpression::commits(
+            commits
+                .into_iter()
+                .map(|commit| commit.id().clone())
+                .collect(),
+        );
+        let immutable_revset = self.immutable_revset()?;
         let revset = self.evaluate_revset(to_rewrite_revset.intersection(&immutable_revset))?;
         if let Some(commit) = revset.iter().commits(self.repo().store()).next() {
             let commit = commit?;
@@ -1632,8 +1683,9 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
         // are millions of commits added to the repo, assuming the revset engine can
         // efficiently skip non-conflicting commits. Filter out empty commits mostly so
         // `jj new <conflicted commit>` doesn't result in a message about new conflicts.
-        let conflicts = RevsetExpression::filter(RevsetFilterPredicate::HasConflict)
-            .intersection(&RevsetExpression::filter(RevsetFilterPredicate::File(None)));
+        let conflicts = RevsetExpression::filter(RevsetFilterPredicate::HasConflict).intersection(
+            &RevsetExpression::filter(RevsetFilterPredicate::File(FilesetExpression::all())),
+        );
         let removed_conflicts_expr = new_heads.range(&old_heads).intersection(&conflicts);
         let added_conflicts_expr = old_heads.range(&new_heads).intersection(&conflicts);
 
@@ -2069,14 +2121,6 @@ Discard the conflicting changes with `jj restore --from {}`.",
     Ok(())
 }
 
-pub fn parse_string_pattern(src: &str) -> Result<StringPattern, StringPatternParseError> {
-    if let Some((kind, pat)) = src.split_once(':') {
-        StringPattern::from_str_kind(pat, kind)
-    } else {
-        Ok(StringPattern::exact(src))
-    }
-}
-
 /// Resolves revsets into revisions for use; useful for rebases or operations
 /// that take multiple parents.
 pub fn resolve_all_revs(
@@ -2488,7 +2532,7 @@ impl FromStr for RemoteBranchNamePattern {
         // The kind prefix applies to both branch and remote fragments. It's
         // weird that unanchored patterns like substring:branch@remote is split
         // into two, but I can't think of a better syntax.
-        // TODO: should we disable substring pattern? what if we added regex?
+        // TODO: should we disable substring and regex patterns?
         let (maybe_kind, pat) = src
             .split_once(':')
             .map_or((None, src), |(kind, pat)| (Some(kind), pat));
@@ -0,0 +1,113 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::io::Write;
+
+use jj_lib::absorb::{absorb_hunks, split_hunks_to_trees, AbsorbSource};
+use jj_lib::revset::RevsetExpression;
+use tracing::instrument;
+
+use crate::cli_util::{internal_error, CommandError, CommandHelper, RevisionArg};
+use crate::ui::Ui;
+
+/// Move changes from a revision into the stack of mutable revisions
+///
+/// Each hunk of the changes in the source revision is moved into the mutable
+/// ancestor that last modified the lines the hunk touches. A hunk is
+/// left in the source if its lines were last modified by several revisions, or
+/// by an immutable one. Added and deleted files are never absorbed.
+///
+/// The descendants of the rewritten revisions, including the source, are
+/// rebased.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct AbsorbArgs {
+    /// Source revision to absorb from
+    #[arg(long, short, default_value = "@")]
+    from: RevisionArg,
+    /// Destination revisions to absorb into
+    ///
+    /// Only ancestors of the source revision are considered.
+    #[arg(long, visible_alias = "to", default_value = "all()")]
+    into: RevisionArg,
+    /// Move only changes to these paths (instead of all paths)
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    paths: Vec<String>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_absorb(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &AbsorbArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let source_commit = workspace_command.resolve_single_rev(&args.from, ui)?;
+    workspace_command.check_rewritable([&source_commit])?;
+    let destinations_expression = RevsetExpression::commits(source_commit.parent_ids().to_vec())
+        .ancestors()
+        .intersection(&workspace_command.parse_revset(&args.into, Some(ui))?)
+        .minus(&workspace_command.immutable_revset()?);
+    let destinations: HashSet<_> = workspace_command
+        .evaluate_revset(destinations_expression)?
+        .iter()
+        .collect();
+    let matcher = workspace_command.matcher_from_values(&args.paths)?;
+
+    let repo = workspace_command.repo().as_ref();
+    let source = AbsorbSource::from_commit(repo, source_commit)?;
+    let selected_trees = split_hunks_to_trees(repo, &source, &destinations, matcher.as_ref())
+        .map_err(internal_error)?;
+    for (path, reason) in &selected_trees.skipped_paths {
+        writeln!(
+            ui.warning(),
+            "Skipping {}: {reason}",
+            workspace_command.format_file_path(path)
+        )?;
+    }
+    if selected_trees.target_commits.is_empty() {
+        writeln!(ui.stderr(), "Nothing changed.")?;
+        return Ok(());
+    }
+
+    let mut tx = workspace_command.start_transaction();
+    let stats = absorb_hunks(
+        command.settings(),
+        tx.mut_repo(),
+        &source,
+        selected_trees.target_commits,
+    )
+    .map_err(internal_error)?;
+    writeln!(ui.stderr(), "Absorbed changes into these revisions:")?;
+    for commit in stats.rewritten_destinations.iter().rev() {
+        write!(ui.stderr(), "  ")?;
+        tx.write_commit_summary(ui.stderr_formatter().as_mut(), commit)?;
+        writeln!(ui.stderr())?;
+    }
+    if stats.num_rebased > 0 {
+        writeln!(
+            ui.stderr(),
+            "Rebased {} descendant commits",
+            stats.num_rebased
+        )?;
+    }
+    tx.finish(
+        ui,
+        format!(
+            "absorb changes into {} commits",
+            stats.rewritten_destinations.len()
+        ),
+    )?;
+    Ok(())
+}
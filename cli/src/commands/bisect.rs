@@ -0,0 +1,396 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use jj_lib::backend::CommitId;
+use jj_lib::bisect::{BisectionResult, Bisector, Evaluation, NextStep};
+use jj_lib::commit::Commit;
+use jj_lib::object_id::ObjectId;
+use jj_lib::revset::RevsetExpression;
+use tracing::instrument;
+
+use super::run::shell_command_for;
+use crate::cli_util::{
+    internal_error, internal_error_with_message, resolve_multiple_nonempty_revsets, user_error,
+    user_error_with_hint, CommandError, CommandHelper, RevisionArg, WorkspaceCommandHelper,
+};
+use crate::ui::Ui;
+
+/// Find the revision that introduced a bug by bisecting a range of revisions
+///
+/// Start a bisection with `jj bisect start`, and mark at least one good and
+/// one bad revision. Each step checks out the revision that splits the
+/// remaining revisions most evenly, to be tested and marked with `jj bisect
+/// good`, `jj bisect bad`, or `jj bisect skip`. Ancestors of good revisions
+/// are assumed to be good, and descendants of bad revisions are assumed to be
+/// bad.
+///
+/// Alternatively, `jj bisect run --command` tests each revision
+/// automatically.
+///
+/// The state of the bisection is stored in the workspace's `.jj` directory
+/// until `jj bisect reset`.
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum BisectCommand {
+    Start(BisectStartArgs),
+    /// Mark revisions as good
+    ///
+    /// Then check out the next revision to test, or show the result if there's
+    /// nothing left to test.
+    Good(BisectMarkArgs),
+    /// Mark revisions as bad
+    ///
+    /// Then check out the next revision to test, or show the result if there's
+    /// nothing left to test.
+    Bad(BisectMarkArgs),
+    /// Mark revisions as untestable
+    ///
+    /// Then check out the next revision to test, or show the result if there's
+    /// nothing left to test.
+    Skip(BisectMarkArgs),
+    Run(BisectRunArgs),
+    Reset(BisectResetArgs),
+}
+
+/// Start a bisection
+#[derive(clap::Args, Clone, Debug)]
+pub struct BisectStartArgs {
+    /// The revisions to bisect
+    #[arg(long, default_value = "all()")]
+    range: RevisionArg,
+    /// Revisions known to be good
+    #[arg(long)]
+    good: Vec<RevisionArg>,
+    /// Revisions known to be bad
+    #[arg(long)]
+    bad: Vec<RevisionArg>,
+    /// Edit each revision to test instead of creating a new working-copy
+    /// commit on top of it
+    #[arg(long)]
+    edit: bool,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct BisectMarkArgs {
+    /// The revisions to mark [default: the revision being tested, or `@`]
+    #[arg(long, short)]
+    revisions: Vec<RevisionArg>,
+}
+
+/// Test each revision with a command until the first bad one is found
+///
+/// The command is run in the workspace root with the revision checked out. An
+/// exit code of 0 marks the revision as good, 125 marks it as untestable, and
+/// any other code below 128 marks it as bad. Other exit codes abort the
+/// bisection.
+#[derive(clap::Args, Clone, Debug)]
+pub struct BisectRunArgs {
+    /// The shell command to test each revision with
+    #[arg(long)]
+    command: String,
+}
+
+/// Stop bisecting and forget about the marked revisions
+#[derive(clap::Args, Clone, Debug)]
+pub struct BisectResetArgs {}
+
+/// The state of a bisection, as stored in `.jj/bisect.toml`.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+struct BisectState {
+    range: String,
+    edit: bool,
+    /// The revision being tested.
+    current: Option<String>,
+    good: Vec<String>,
+    bad: Vec<String>,
+    skipped: Vec<String>,
+}
+
+impl BisectState {
+    fn path(workspace_command: &WorkspaceCommandHelper) -> PathBuf {
+        workspace_command
+            .workspace_root()
+            .join(".jj")
+            .join("bisect.toml")
+    }
+
+    fn load(workspace_command: &WorkspaceCommandHelper) -> Result<Self, CommandError> {
+        match std::fs::read_to_string(Self::path(workspace_command)) {
+            Ok(text) => toml_edit::de::from_str(&text).map_err(|err| {
+                internal_error_with_message("Failed to parse the bisection state", err)
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Err(user_error_with_hint(
+                "No bisection in progress",
+                "Start one with `jj bisect start`",
+            )),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save(&self, workspace_command: &WorkspaceCommandHelper) -> Result<(), CommandError> {
+        let text = toml_edit::ser::to_string_pretty(self).map_err(internal_error)?;
+        std::fs::write(Self::path(workspace_command), text)?;
+        Ok(())
+    }
+
+    fn mark(&mut self, commit_id: &CommitId, evaluation: Evaluation) {
+        let hex = commit_id.hex();
+        for list in [&mut self.good, &mut self.bad, &mut self.skipped] {
+            list.retain(|id| *id != hex);
+        }
+        match evaluation {
+            Evaluation::Good => self.good.push(hex),
+            Evaluation::Bad => self.bad.push(hex),
+            Evaluation::Skip => self.skipped.push(hex),
+        }
+    }
+}
+
+pub fn cmd_bisect(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &BisectCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        BisectCommand::Start(sub_args) => cmd_bisect_start(ui, command, sub_args),
+        BisectCommand::Good(sub_args) => cmd_bisect_mark(ui, command, sub_args, Evaluation::Good),
+        BisectCommand::Bad(sub_args) => cmd_bisect_mark(ui, command, sub_args, Evaluation::Bad),
+        BisectCommand::Skip(sub_args) => cmd_bisect_mark(ui, command, sub_args, Evaluation::Skip),
+        BisectCommand::Run(sub_args) => cmd_bisect_run(ui, command, sub_args),
+        BisectCommand::Reset(sub_args) => cmd_bisect_reset(ui, command, sub_args),
+    }
+}
+
+#[instrument(skip_all)]
+fn cmd_bisect_start(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectStartArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    if BisectState::path(&workspace_command).exists() {
+        return Err(user_error_with_hint(
+            "A bisection is already in progress",
+            "Use `jj bisect reset` to stop it first",
+        ));
+    }
+    // Check that the range is valid before storing it
+    workspace_command.parse_revset(&args.range, Some(ui))?;
+    let mut state = BisectState {
+        range: args.range.to_string(),
+        edit: args.edit,
+        ..Default::default()
+    };
+    for (revisions, evaluation) in [(&args.good, Evaluation::Good), (&args.bad, Evaluation::Bad)] {
+        if !revisions.is_empty() {
+            for commit in resolve_multiple_nonempty_revsets(revisions, &workspace_command, ui)? {
+                state.mark(commit.id(), evaluation);
+            }
+        }
+    }
+    advance(ui, &mut workspace_command, &mut state)?;
+    Ok(())
+}
+
+#[instrument(skip_all)]
+fn cmd_bisect_mark(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectMarkArgs,
+    evaluation: Evaluation,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut state = BisectState::load(&workspace_command)?;
+    let commit_ids = if args.revisions.is_empty() {
+        let revision = state.current.as_deref().unwrap_or("@");
+        vec![workspace_command
+            .resolve_single_rev(revision, ui)?
+            .id()
+            .clone()]
+    } else {
+        resolve_multiple_nonempty_revsets(&args.revisions, &workspace_command, ui)?
+            .iter()
+            .map(|commit| commit.id().clone())
+            .collect()
+    };
+    for commit_id in &commit_ids {
+        state.mark(commit_id, evaluation);
+    }
+    advance(ui, &mut workspace_command, &mut state)?;
+    Ok(())
+}
+
+#[instrument(skip_all)]
+fn cmd_bisect_run(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectRunArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut state = BisectState::load(&workspace_command)?;
+    // Test the revision that's already checked out first, if any
+    let mut next_commit = match &state.current {
+        Some(hex) => Some(workspace_command.resolve_single_rev(hex, ui)?),
+        None => advance(ui, &mut workspace_command, &mut state)?,
+    };
+    while let Some(commit) = next_commit {
+        writeln!(ui.stderr(), "Running '{}'", args.command)?;
+        let status = shell_command_for(&args.command)
+            .current_dir(workspace_command.workspace_root())
+            .status()
+            .map_err(|err| user_error(format!("Failed to run '{}': {err}", args.command)))?;
+        let evaluation = match status.code() {
+            Some(0) => Evaluation::Good,
+            Some(125) => Evaluation::Skip,
+            Some(1..=127) => Evaluation::Bad,
+            _ => {
+                return Err(user_error(format!(
+                    "Command '{}' failed ({status}), stopping the bisection",
+                    args.command,
+                )));
+            }
+        };
+        write!(ui.stderr(), "The revision ")?;
+        workspace_command.write_commit_summary(ui.stderr_formatter().as_mut(), &commit)?;
+        writeln!(ui.stderr(), " is {}", evaluation_name(evaluation))?;
+        state.mark(commit.id(), evaluation);
+        // Reload the workspace to pick up the effects of the command
+        workspace_command = command.workspace_helper(ui)?;
+        next_commit = advance(ui, &mut workspace_command, &mut state)?;
+    }
+    Ok(())
+}
+
+#[instrument(skip_all)]
+fn cmd_bisect_reset(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    _args: &BisectResetArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    // Make sure there's a bisection to reset
+    BisectState::load(&workspace_command)?;
+    std::fs::remove_file(BisectState::path(&workspace_command))?;
+    writeln!(ui.stderr(), "Stopped bisecting")?;
+    Ok(())
+}
+
+fn evaluation_name(evaluation: Evaluation) -> &'static str {
+    match evaluation {
+        Evaluation::Good => "good",
+        Evaluation::Bad => "bad",
+        Evaluation::Skip => "skipped",
+    }
+}
+
+/// Checks out the next revision to test, or prints the result if there's
+/// nothing left to test. Saves the updated state, and returns the checked-out
+/// revision.
+fn advance(
+    ui: &mut Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    state: &mut BisectState,
+) -> Result<Option<Commit>, CommandError> {
+    state.current = None;
+    if state.good.is_empty() || state.bad.is_empty() {
+        state.save(workspace_command)?;
+        writeln!(
+            ui.stderr(),
+            "Mark at least one good and one bad revision to start bisecting"
+        )?;
+        return Ok(None);
+    }
+
+    let parse_ids = |ids: &[String]| -> Result<Vec<CommitId>, CommandError> {
+        ids.iter()
+            .map(|hex| {
+                CommitId::try_from_hex(hex).map_err(|_| {
+                    internal_error(format!("Invalid commit id in the bisection state: {hex}"))
+                })
+            })
+            .collect()
+    };
+    let good_ids = parse_ids(&state.good)?;
+    let bad_ids = parse_ids(&state.bad)?;
+    let skipped_ids = parse_ids(&state.skipped)?;
+    // Only the part of the range before the bad revisions matters, which can
+    // then be passed on without symbols
+    let range_expression = workspace_command
+        .parse_revset(&state.range, Some(ui))?
+        .intersection(&RevsetExpression::commits(bad_ids.clone()).ancestors());
+    let range_ids: Vec<_> = workspace_command
+        .evaluate_revset(range_expression)?
+        .iter()
+        .collect();
+
+    let repo = workspace_command.repo().clone();
+    let mut bisector = Bisector::new(repo.as_ref(), RevsetExpression::commits(range_ids));
+    for (ids, evaluation) in [
+        (good_ids, Evaluation::Good),
+        (bad_ids, Evaluation::Bad),
+        (skipped_ids, Evaluation::Skip),
+    ] {
+        for id in ids {
+            bisector.mark(id, evaluation);
+        }
+    }
+    let next_step = bisector.next_step().map_err(internal_error)?;
+    match next_step {
+        NextStep::Evaluate(commit) => {
+            state.current = Some(commit.id().hex());
+            state.save(workspace_command)?;
+            if state.edit {
+                workspace_command.check_rewritable([&commit])?;
+            }
+            let mut tx = workspace_command.start_transaction();
+            if state.edit {
+                tx.edit(&commit)?;
+            } else {
+                tx.check_out(&commit)?;
+            }
+            write!(ui.stderr(), "Now testing: ")?;
+            tx.write_commit_summary(ui.stderr_formatter().as_mut(), &commit)?;
+            writeln!(ui.stderr())?;
+            tx.finish(ui, format!("bisect: check out {}", commit.id().hex()))?;
+            Ok(Some(commit))
+        }
+        NextStep::Done(result) => {
+            state.save(workspace_command)?;
+            match result {
+                BisectionResult::Indeterminate => {
+                    writeln!(ui.stderr(), "No bad revision was found in the range")?;
+                }
+                BisectionResult::Found(commits) => {
+                    if let [commit] = commits.as_slice() {
+                        write!(ui.stderr(), "The first bad revision is: ")?;
+                        workspace_command
+                            .write_commit_summary(ui.stderr_formatter().as_mut(), commit)?;
+                        writeln!(ui.stderr())?;
+                    } else {
+                        writeln!(ui.stderr(), "The first bad revision is one of:")?;
+                        for commit in &commits {
+                            write!(ui.stderr(), "  ")?;
+                            workspace_command
+                                .write_commit_summary(ui.stderr_formatter().as_mut(), commit)?;
+                            writeln!(ui.stderr())?;
+                        }
+                    }
+                }
+            }
+            Ok(None)
+        }
+    }
+}
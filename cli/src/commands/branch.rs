@@ -21,17 +21,19 @@ use itertools::Itertools;
 use jj_lib::backend::CommitId;
 use jj_lib::git;
 use jj_lib::object_id::ObjectId;
-use jj_lib::op_store::{RefTarget, RemoteRef};
+use jj_lib::op_store::{BranchTarget, RefTarget, RemoteRef};
 use jj_lib::repo::Repo;
 use jj_lib::revset::{self, RevsetExpression};
 use jj_lib::str_util::StringPattern;
 use jj_lib::view::View;
 
 use crate::cli_util::{
-    parse_string_pattern, user_error, user_error_with_hint, CommandError, CommandHelper,
-    RemoteBranchName, RemoteBranchNamePattern, RevisionArg,
+    user_error, user_error_with_hint, CommandError, CommandHelper, RemoteBranchName,
+    RemoteBranchNamePattern, RevisionArg,
 };
+use crate::commit_templater::RefName;
 use crate::formatter::Formatter;
+use crate::ref_name_templater;
 use crate::ui::Ui;
 
 /// Manage branches.
@@ -78,7 +80,7 @@ pub struct BranchDeleteArgs {
     /// By default, the specified name matches exactly. Use `glob:` prefix to
     /// select branches by wildcard pattern. For details, see
     /// https://github.com/martinvonz/jj/blob/main/docs/revsets.md#string-patterns.
-    #[arg(required_unless_present_any(&["glob"]), value_parser = parse_string_pattern)]
+    #[arg(required_unless_present_any(&["glob"]), value_parser = StringPattern::parse)]
     pub names: Vec<StringPattern>,
 
     /// Deprecated. Please prefix the pattern with `glob:` instead.
@@ -108,7 +110,7 @@ pub struct BranchListArgs {
     /// By default, the specified name matches exactly. Use `glob:` prefix to
     /// select branches by wildcard pattern. For details, see
     /// https://github.com/martinvonz/jj/blob/main/docs/revsets.md#string-patterns.
-    #[arg(value_parser = parse_string_pattern)]
+    #[arg(value_parser = StringPattern::parse)]
     pub names: Vec<StringPattern>,
 
     /// Show branches whose local targets are in the given revisions.
@@ -117,6 +119,23 @@ pub struct BranchListArgs {
     /// wouldn't have a local target.
     #[arg(long, short)]
     revisions: Vec<RevisionArg>,
+
+    /// Render each branch using the given template
+    ///
+    /// The template is evaluated for each local branch and each listed remote
+    /// branch, with `self` being the `RefName`, and each result is printed on
+    /// its own line. Unlike the default output, the branch targets aren't
+    /// shown.
+    ///
+    /// For the syntax, see https://github.com/martinvonz/jj/blob/main/docs/templates.md
+    #[arg(long, short = 'T')]
+    template: Option<String>,
+
+    /// Print each branch as a JSON object on its own line
+    ///
+    /// This is the same as `-T 'json(self)'`.
+    #[arg(long, conflicts_with = "template")]
+    json: bool,
 }
 
 /// Forget everything about a branch, including its local and remote
@@ -131,7 +150,7 @@ pub struct BranchForgetArgs {
     /// By default, the specified name matches exactly. Use `glob:` prefix to
     /// select branches by wildcard pattern. For details, see
     /// https://github.com/martinvonz/jj/blob/main/docs/revsets.md#string-patterns.
-    #[arg(required_unless_present_any(&["glob"]), value_parser = parse_string_pattern)]
+    #[arg(required_unless_present_any(&["glob"]), value_parser = StringPattern::parse)]
     pub names: Vec<StringPattern>,
 
     /// Deprecated. Please prefix the pattern with `glob:` instead.
@@ -628,6 +647,27 @@ fn cmd_branch_list(
     } else {
         None
     };
+    let branches_to_list = view.branches().filter(|&(name, _)| {
+        branch_names_to_list
+            .as_ref()
+            .map_or(true, |branch_names| branch_names.contains(name))
+    });
+
+    if args.template.is_some() || args.json {
+        let template_text = args.template.as_deref().unwrap_or("json(self)");
+        let template =
+            ref_name_templater::parse(template_text, workspace_command.template_aliases_map())?;
+        ui.request_pager();
+        let mut formatter = ui.stdout_formatter();
+        for (name, branch_target) in branches_to_list {
+            let listed = ListedBranch::new(name, &branch_target, args.all);
+            for ref_name in listed.ref_names() {
+                template.format(ref_name, formatter.as_mut())?;
+                writeln!(formatter)?;
+            }
+        }
+        return Ok(());
+    }
 
     let no_branches_template = workspace_command.parse_commit_template(
         &command
@@ -666,36 +706,24 @@ fn cmd_branch_list(
     let mut formatter = ui.stdout_formatter();
     let formatter = formatter.as_mut();
 
-    let branches_to_list = view.branches().filter(|&(name, _)| {
-        branch_names_to_list
-            .as_ref()
-            .map_or(true, |branch_names| branch_names.contains(name))
-    });
     for (name, branch_target) in branches_to_list {
-        let (tracking_remote_refs, untracked_remote_refs) =
-            branch_target
-                .remote_refs
-                .into_iter()
-                .partition::<Vec<_>, _>(|&(_, remote_ref)| remote_ref.is_tracking());
+        let local_target = branch_target.local_target;
+        let listed = ListedBranch::new(name, &branch_target, args.all);
 
-        if branch_target.local_target.is_present() || !tracking_remote_refs.is_empty() {
+        if listed.local.is_some() {
             write!(formatter.labeled("branch"), "{name}")?;
-            if branch_target.local_target.is_present() {
-                print_branch_target(formatter, branch_target.local_target)?;
+            if local_target.is_present() {
+                print_branch_target(formatter, local_target)?;
             } else {
                 writeln!(formatter, " (deleted)")?;
             }
         }
 
-        for &(remote, remote_ref) in &tracking_remote_refs {
-            let synced = remote_ref.target == *branch_target.local_target;
-            if !args.all && synced {
-                continue;
-            }
+        for (ref_name, remote_ref) in &listed.tracking_remotes {
+            let remote = ref_name.remote.as_deref().unwrap();
             write!(formatter, "  ")?;
             write!(formatter.labeled("branch"), "@{remote}")?;
-            let local_target = branch_target.local_target;
-            if local_target.is_present() && !synced {
+            if local_target.is_present() && !ref_name.synced {
                 let remote_added_ids = remote_ref.target.added_ids().cloned().collect_vec();
                 let local_added_ids = local_target.added_ids().cloned().collect_vec();
                 let (remote_ahead_lower, remote_ahead_upper) =
@@ -731,10 +759,11 @@ fn cmd_branch_list(
             print_branch_target(formatter, &remote_ref.target)?;
         }
 
-        if branch_target.local_target.is_absent() && !tracking_remote_refs.is_empty() {
-            let found_non_git_remote = tracking_remote_refs
-                .iter()
-                .any(|&(remote, _)| remote != git::REMOTE_NAME_FOR_LOCAL_GIT_REPO);
+        // The local branch is listed as deleted only if it's tracked
+        if listed.local.is_some() && local_target.is_absent() {
+            let found_non_git_remote = listed.tracking_remotes.iter().any(|(ref_name, _)| {
+                ref_name.remote.as_deref() != Some(git::REMOTE_NAME_FOR_LOCAL_GIT_REPO)
+            });
             if found_non_git_remote {
                 writeln!(
                     formatter.labeled("hint"),
@@ -750,13 +779,79 @@ fn cmd_branch_list(
             }
         }
 
-        if args.all {
-            for &(remote, remote_ref) in &untracked_remote_refs {
-                write!(formatter.labeled("branch"), "{name}@{remote}")?;
-                print_branch_target(formatter, &remote_ref.target)?;
-            }
+        for (ref_name, remote_ref) in &listed.untracked_remotes {
+            let remote = ref_name.remote.as_deref().unwrap();
+            write!(formatter.labeled("branch"), "{name}@{remote}")?;
+            print_branch_target(formatter, &remote_ref.target)?;
         }
     }
 
     Ok(())
 }
+
+/// Local and remote refs of a branch which are listed by `jj branch list`.
+struct ListedBranch<'a> {
+    /// Local branch, which is listed if it exists or is tracked by a remote.
+    local: Option<RefName>,
+    /// Tracking remote branches, except for the synchronized ones unless
+    /// `--all` is specified.
+    tracking_remotes: Vec<(RefName, &'a RemoteRef)>,
+    /// Untracked remote branches, which are listed only if `--all` is
+    /// specified.
+    untracked_remotes: Vec<(RefName, &'a RemoteRef)>,
+}
+
+impl<'a> ListedBranch<'a> {
+    fn new(name: &str, branch_target: &BranchTarget<'a>, all: bool) -> Self {
+        let local_target = branch_target.local_target;
+        let (tracking_remote_refs, untracked_remote_refs): (Vec<_>, Vec<_>) = branch_target
+            .remote_refs
+            .iter()
+            .partition(|&&(_, remote_ref)| remote_ref.is_tracking());
+        let local =
+            (local_target.is_present() || !tracking_remote_refs.is_empty()).then(|| RefName {
+                name: name.to_owned(),
+                remote: None,
+                conflict: local_target.has_conflict(),
+                synced: tracking_remote_refs
+                    .iter()
+                    .all(|&&(_, remote_ref)| remote_ref.target == *local_target),
+            });
+        let remote_ref_name = |remote: &str, remote_ref: &RemoteRef, synced: bool| RefName {
+            name: name.to_owned(),
+            remote: Some(remote.to_owned()),
+            conflict: remote_ref.target.has_conflict(),
+            synced,
+        };
+        let tracking_remotes = tracking_remote_refs
+            .iter()
+            .filter_map(|&&(remote, remote_ref)| {
+                let synced = remote_ref.target == *local_target;
+                (all || !synced).then(|| (remote_ref_name(remote, remote_ref, synced), remote_ref))
+            })
+            .collect();
+        let untracked_remotes = if all {
+            untracked_remote_refs
+                .iter()
+                .map(|&&(remote, remote_ref)| {
+                    (remote_ref_name(remote, remote_ref, false), remote_ref)
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        ListedBranch {
+            local,
+            tracking_remotes,
+            untracked_remotes,
+        }
+    }
+
+    /// Iterates over the listed refs in the order of the default output.
+    fn ref_names(&self) -> impl Iterator<Item = &RefName> {
+        let remotes = self.tracking_remotes.iter().chain(&self.untracked_remotes);
+        self.local
+            .iter()
+            .chain(remotes.map(|(ref_name, _)| ref_name))
+    }
+}
@@ -14,7 +14,8 @@
 
 use std::io::Write;
 
-use jj_lib::conflicts::{materialize_tree_value, MaterializedTreeValue};
+use jj_lib::conflicts::{materialize_tree_value, MaterializedTreeValue, MergeDriver};
+use jj_lib::gitattributes::TreeGitAttributes;
 use jj_lib::repo::Repo;
 use pollster::FutureExt;
 use tracing::instrument;
@@ -45,7 +46,10 @@ pub(crate) fn cmd_cat(
     let path = workspace_command.parse_file_path(&args.path)?;
     let repo = workspace_command.repo();
     let value = tree.path_value(&path);
-    let materialized = materialize_tree_value(repo.store(), &path, value).block_on()?;
+    let git_attributes = TreeGitAttributes::new(tree).for_file(&path)?;
+    let merge_driver = MergeDriver::for_path(&git_attributes, &path);
+    let materialized =
+        materialize_tree_value(repo.store(), &path, value, merge_driver).block_on()?;
     match materialized {
         MaterializedTreeValue::Absent => {
             return Err(user_error("No such path"));
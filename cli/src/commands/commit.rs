@@ -19,6 +19,7 @@ use tracing::instrument;
 
 use crate::cli_util::{join_message_paragraphs, user_error, CommandError, CommandHelper};
 use crate::description_util::{description_template_for_commit, edit_description};
+use crate::git_util::{run_commit_msg_hook, run_pre_commit_hook};
 use crate::ui::Ui;
 
 /// Update the description and create a new change on top.
@@ -43,6 +44,15 @@ pub(crate) fn cmd_commit(
     args: &CommitArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
+    // The hook may modify files in the working copy (e.g. by running a
+    // formatter), so snapshot again to include its changes in the commit.
+    let workspace_root = workspace_command.workspace_root().clone();
+    run_pre_commit_hook(
+        command.settings(),
+        workspace_command.repo().store(),
+        &workspace_root,
+    )?;
+    workspace_command.maybe_snapshot(ui)?;
 
     let commit_id = workspace_command
         .get_wc_commit_id()
@@ -93,6 +103,12 @@ new working-copy commit.
     } else {
         edit_description(tx.base_repo(), &template, command.settings())?
     };
+    let description = run_commit_msg_hook(
+        command.settings(),
+        tx.repo().store(),
+        &workspace_root,
+        description,
+    )?;
 
     let new_commit = tx
         .mut_repo()
@@ -15,10 +15,12 @@
 use std::io::{self, Read, Write};
 
 use jj_lib::object_id::ObjectId;
+use jj_lib::repo::Repo;
 use tracing::instrument;
 
 use crate::cli_util::{join_message_paragraphs, CommandError, CommandHelper, RevisionArg};
 use crate::description_util::{description_template_for_describe, edit_description};
+use crate::git_util::run_commit_msg_hook;
 use crate::ui::Ui;
 
 /// Update the change description or other metadata
@@ -82,6 +84,12 @@ pub(crate) fn cmd_describe(
     if description == *commit.description() && !args.reset_author {
         writeln!(ui.stderr(), "Nothing changed.")?;
     } else {
+        let description = run_commit_msg_hook(
+            command.settings(),
+            workspace_command.repo().store(),
+            workspace_command.workspace_root(),
+            description,
+        )?;
         let mut tx = workspace_command.start_transaction();
         let mut commit_builder = tx
             .mut_repo()
@@ -51,6 +51,7 @@ pub(crate) fn cmd_diff(
     let workspace_command = command.workspace_helper(ui)?;
     let from_tree;
     let to_tree;
+    let mut copy_records = vec![];
     if args.from.is_some() || args.to.is_some() {
         let from = workspace_command.resolve_single_rev(args.from.as_deref().unwrap_or("@"), ui)?;
         from_tree = from.tree()?;
@@ -61,7 +62,8 @@ pub(crate) fn cmd_diff(
             workspace_command.resolve_single_rev(args.revision.as_deref().unwrap_or("@"), ui)?;
         let parents = commit.parents();
         from_tree = merge_commit_trees(workspace_command.repo().as_ref(), &parents)?;
-        to_tree = commit.tree()?
+        to_tree = commit.tree()?;
+        copy_records = commit.copy_records().to_vec();
     }
     let matcher = workspace_command.matcher_from_values(&args.paths)?;
     let diff_formats = diff_formats_for(command.settings(), &args.format)?;
@@ -73,6 +75,7 @@ pub(crate) fn cmd_diff(
         &from_tree,
         &to_tree,
         matcher.as_ref(),
+        &copy_records,
         &diff_formats,
     )?;
     Ok(())
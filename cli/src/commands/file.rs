@@ -0,0 +1,108 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use jj_lib::annotate::{get_annotation_for_file, AnnotateError};
+use jj_lib::commit::Commit;
+use jj_lib::repo::Repo;
+use tracing::instrument;
+
+use crate::cli_util::{internal_error, user_error, CommandError, CommandHelper, RevisionArg};
+use crate::ui::Ui;
+
+/// Commands for working with files
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum FileCommand {
+    Annotate(FileAnnotateArgs),
+}
+
+/// Show the source change for each line of the target file
+///
+/// Annotates each line of a file with the commit that last changed it, like
+/// `git blame`. Merges are followed into all of their parents.
+#[derive(clap::Args, Clone, Debug)]
+pub struct FileAnnotateArgs {
+    /// The revision to start annotating from
+    #[arg(long, short, default_value = "@")]
+    revision: RevisionArg,
+    /// Render the prefix of each line using the given template
+    ///
+    /// The template is evaluated for the commit that introduced the line.
+    /// Defaults to the `templates.annotate_commit_summary` setting.
+    ///
+    /// For the syntax, see https://github.com/martinvonz/jj/blob/main/docs/templates.md
+    #[arg(long, short = 'T')]
+    template: Option<String>,
+    /// The file to annotate
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    path: String,
+}
+
+pub fn cmd_file(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &FileCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        FileCommand::Annotate(sub_args) => cmd_file_annotate(ui, command, sub_args),
+    }
+}
+
+#[instrument(skip_all)]
+fn cmd_file_annotate(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &FileAnnotateArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let repo = workspace_command.repo();
+    let starting_commit = workspace_command.resolve_single_rev(&args.revision, ui)?;
+    let path = workspace_command.parse_file_path(&args.path)?;
+
+    let annotation = get_annotation_for_file(repo.as_ref(), &starting_commit, &path).map_err(
+        |err| match err {
+            AnnotateError::PathNotFound | AnnotateError::NotAFile => user_error(err),
+            _ => internal_error(err),
+        },
+    )?;
+
+    let template_string = match &args.template {
+        Some(value) => value.to_string(),
+        None => command
+            .settings()
+            .config()
+            .get_string("templates.annotate_commit_summary")?,
+    };
+    let template = workspace_command.parse_commit_template(&template_string)?;
+
+    ui.request_pager();
+    let mut formatter = ui.stdout_formatter();
+    let formatter = formatter.as_mut();
+    let mut commits: HashMap<_, Commit> = HashMap::new();
+    for (commit_id, line) in annotation.lines() {
+        let commit = match commits.entry(commit_id.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(repo.store().get_commit(commit_id)?),
+        };
+        template.format(commit, formatter)?;
+        write!(formatter, ": ")?;
+        formatter.write_all(line)?;
+        if !line.ends_with(b"\n") {
+            writeln!(formatter)?;
+        }
+    }
+    Ok(())
+}
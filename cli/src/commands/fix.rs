@@ -0,0 +1,327 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use itertools::Itertools;
+use jj_lib::backend::{BackendError, CommitId, FileId, MergedTreeId, TreeValue};
+use jj_lib::commit::Commit;
+use jj_lib::merge::Merge;
+use jj_lib::merged_tree::{MergedTree, MergedTreeBuilder};
+use jj_lib::repo::Repo;
+use jj_lib::repo_path::{RepoPath, RepoPathBuf};
+use jj_lib::revset::{RevsetExpression, RevsetIteratorExt};
+use jj_lib::rewrite::merge_commit_trees;
+use jj_lib::settings::{ConfigResultExt as _, UserSettings};
+use jj_lib::store::Store;
+use tracing::instrument;
+
+use crate::cli_util::{user_error, CommandError, CommandHelper, RevisionArg};
+use crate::config::CommandNameAndArgs;
+use crate::ui::Ui;
+
+/// Update files with formatting fixes or other changes
+///
+/// Runs the tools configured in the `fix.tools` table over the files changed
+/// in the given revisions and all of their descendants, and rewrites the
+/// revisions with the output of the tools. Each tool is a command that reads
+/// the contents of a file from stdin and writes the fixed contents to stdout.
+/// It's run on the files whose paths match one of its `patterns`. If several
+/// tools match a file, they're run in alphabetical order of their names, each
+/// on the output of the previous one. If a tool fails, its output is ignored.
+///
+/// Files that weren't changed in any of the revisions aren't touched, and
+/// the working copy is only updated if the working-copy revision is rewritten.
+///
+/// For example, to format Rust files with `rustfmt`:
+///
+/// ```toml
+/// [fix.tools.rustfmt]
+/// command = ["rustfmt", "--emit", "stdout"]
+/// patterns = ["*.rs"]
+/// ```
+///
+/// The string `$path` in the command is replaced by the repository-relative
+/// path of the file being fixed.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct FixArgs {
+    /// Fix files in the given revisions and their descendants
+    #[arg(long, short, default_value = "@")]
+    source: Vec<RevisionArg>,
+    /// Fix only these paths
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    paths: Vec<String>,
+}
+
+/// A tool loaded from `[fix.tools.<name>]`.
+#[derive(Clone, Debug, serde::Deserialize)]
+struct ToolConfig {
+    /// The command to run, which gets the file contents on stdin and writes
+    /// the fixed contents to stdout.
+    command: CommandNameAndArgs,
+    /// Globs matching the repository-relative paths the tool should be run on.
+    patterns: Vec<String>,
+}
+
+/// A tool with its patterns compiled.
+struct Tool {
+    name: String,
+    command: CommandNameAndArgs,
+    patterns: Vec<glob::Pattern>,
+}
+
+impl Tool {
+    fn matches(&self, path: &RepoPath) -> bool {
+        let path = path.as_internal_file_string();
+        self.patterns.iter().any(|pattern| pattern.matches(path))
+    }
+}
+
+/// Loads the tools from `[fix.tools]`, sorted by name.
+fn get_tools_config(settings: &UserSettings) -> Result<Vec<Tool>, CommandError> {
+    const TABLE_KEY: &str = "fix.tools";
+    let Some(tools_table) = settings.config().get_table(TABLE_KEY).optional()? else {
+        return Ok(vec![]);
+    };
+    let mut tools = vec![];
+    for (name, value) in tools_table.into_iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
+        let config: ToolConfig = value
+            .try_deserialize()
+            // add config key, deserialize error is otherwise unclear
+            .map_err(|err| user_error(format!("Invalid config for {TABLE_KEY}.{name}: {err}")))?;
+        let patterns = config
+            .patterns
+            .iter()
+            .map(|pattern| {
+                glob::Pattern::new(pattern).map_err(|err| {
+                    user_error(format!(
+                        "Invalid pattern {pattern:?} in {TABLE_KEY}.{name}: {err}"
+                    ))
+                })
+            })
+            .try_collect()?;
+        tools.push(Tool {
+            name,
+            command: config.command,
+            patterns,
+        });
+    }
+    Ok(tools)
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_fix(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &FixArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let tools = get_tools_config(command.settings())?;
+    if tools.is_empty() {
+        return Err(user_error(
+            "No tools are configured. Add them to the `fix.tools` table.",
+        ));
+    }
+    let mut root_commits = vec![];
+    for revision in &args.source {
+        root_commits.push(workspace_command.parse_revset(revision, Some(ui))?);
+    }
+    let to_fix: Vec<Commit> = workspace_command
+        .evaluate_revset(RevsetExpression::union_all(&root_commits).descendants())?
+        .iter()
+        .commits(workspace_command.repo().store())
+        .try_collect()?;
+    workspace_command.check_rewritable(&to_fix)?;
+    let matcher = workspace_command.matcher_from_values(&args.paths)?;
+    let workspace_root = workspace_command.workspace_root().clone();
+
+    let mut tx = workspace_command.start_transaction();
+    let base_repo = tx.base_repo().clone();
+    let store = base_repo.store().clone();
+    let mut fixer = FileFixer {
+        ui,
+        store: &store,
+        tools: &tools,
+        workspace_root: &workspace_root,
+        fixed_files: HashMap::new(),
+    };
+    let mut rewritten: HashMap<CommitId, CommitId> = HashMap::new();
+    let mut num_fixed_commits = 0;
+    for old_commit_id in base_repo
+        .index()
+        .topo_order(&mut to_fix.iter().map(|commit| commit.id()))
+    {
+        let old_commit = store.get_commit(&old_commit_id)?;
+        let parent_tree = merge_commit_trees(base_repo.as_ref(), &old_commit.parents())?;
+        let old_tree = old_commit.tree()?;
+        // Files changed in this commit are fixed, and files inherited from a
+        // fixed parent are replaced by their fixed version.
+        let mut paths: HashSet<RepoPathBuf> = fixer
+            .fixed_files
+            .keys()
+            .map(|(path, _)| path.clone())
+            .collect();
+        for (path, diff) in parent_tree.diff(&old_tree, matcher.as_ref()) {
+            let (_, after) = diff?;
+            if let Some(Some(TreeValue::File { id, .. })) = after.as_resolved() {
+                fixer.fix_file(&path, id)?;
+            }
+            paths.insert(path);
+        }
+        let new_tree_id = fixer.replace_fixed_files(&old_tree, paths)?;
+        let new_parents = old_commit
+            .parent_ids()
+            .iter()
+            .map(|id| rewritten.get(id).unwrap_or(id).clone())
+            .collect_vec();
+        if new_tree_id == old_tree.id() && new_parents == old_commit.parent_ids() {
+            continue;
+        }
+        if new_tree_id != old_tree.id() {
+            num_fixed_commits += 1;
+        }
+        let new_commit = tx
+            .mut_repo()
+            .rewrite_commit(command.settings(), &old_commit)
+            .set_parents(new_parents)
+            .set_tree_id(new_tree_id)
+            .write()?;
+        rewritten.insert(old_commit_id, new_commit.id().clone());
+    }
+    writeln!(
+        ui.stderr(),
+        "Fixed {num_fixed_commits} commits of {} checked.",
+        to_fix.len()
+    )?;
+    tx.finish(ui, format!("fixed {num_fixed_commits} commits"))?;
+    Ok(())
+}
+
+/// Runs the tools over file contents, remembering the results.
+struct FileFixer<'a> {
+    ui: &'a Ui,
+    store: &'a Arc<Store>,
+    tools: &'a [Tool],
+    workspace_root: &'a Path,
+    /// Maps files to their fixed versions. Files that the tools didn't change
+    /// aren't included.
+    fixed_files: HashMap<(RepoPathBuf, FileId), FileId>,
+}
+
+impl FileFixer<'_> {
+    /// Runs the matching tools over a file, unless that has been done already.
+    fn fix_file(&mut self, path: &RepoPath, id: &FileId) -> Result<(), CommandError> {
+        let key = (path.to_owned(), id.clone());
+        if self.fixed_files.contains_key(&key) {
+            return Ok(());
+        }
+        let tools = self
+            .tools
+            .iter()
+            .filter(|tool| tool.matches(path))
+            .collect_vec();
+        if tools.is_empty() {
+            return Ok(());
+        }
+        let mut old_contents = vec![];
+        self.store
+            .read_file(path, id)?
+            .read_to_end(&mut old_contents)?;
+        let mut contents = old_contents.clone();
+        for tool in tools {
+            match self.run_tool(tool, path, &contents) {
+                Ok(new_contents) => contents = new_contents,
+                Err(message) => {
+                    writeln!(
+                        self.ui.warning(),
+                        "Failed to fix {} with {}: {message}",
+                        path.as_internal_file_string(),
+                        tool.name
+                    )?;
+                }
+            }
+        }
+        if contents != old_contents {
+            let new_id = self.store.write_file(path, &mut contents.as_slice())?;
+            self.fixed_files.insert(key, new_id);
+        }
+        Ok(())
+    }
+
+    fn run_tool(&self, tool: &Tool, path: &RepoPath, contents: &[u8]) -> Result<Vec<u8>, String> {
+        let (name, args) = tool.command.split_name_and_args();
+        let mut command = Command::new(name.as_ref());
+        if let CommandNameAndArgs::Structured { env, .. } = &tool.command {
+            command.envs(env);
+        }
+        command.args(
+            args.iter()
+                .map(|arg| arg.replace("$path", path.as_internal_file_string())),
+        );
+        let mut child = command
+            .current_dir(self.workspace_root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|err| err.to_string())?;
+        // Write the input from another thread so a tool that writes its output
+        // before reading all of its input can't deadlock.
+        let mut stdin = child.stdin.take().unwrap();
+        let output = std::thread::scope(|scope| {
+            scope.spawn(move || stdin.write_all(contents));
+            child.wait_with_output()
+        })
+        .map_err(|err| err.to_string())?;
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(output.status.to_string())
+        }
+    }
+
+    /// Returns the id of `tree` with the files at `paths` replaced by their
+    /// fixed versions.
+    fn replace_fixed_files(
+        &self,
+        tree: &MergedTree,
+        paths: HashSet<RepoPathBuf>,
+    ) -> Result<MergedTreeId, BackendError> {
+        let mut tree_builder = MergedTreeBuilder::new(tree.id());
+        let mut changed = false;
+        for path in paths {
+            let value = tree.path_value(&path);
+            let Some(Some(TreeValue::File { id, executable })) = value.as_resolved() else {
+                continue;
+            };
+            if let Some(new_id) = self.fixed_files.get(&(path.clone(), id.clone())) {
+                let new_value = TreeValue::File {
+                    id: new_id.clone(),
+                    executable: *executable,
+                };
+                tree_builder.set_or_remove(path, Merge::normal(new_value));
+                changed = true;
+            }
+        }
+        if changed {
+            tree_builder.write_tree(self.store)
+        } else {
+            Ok(tree.id())
+        }
+    }
+}
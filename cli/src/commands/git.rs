@@ -12,8 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
+use std::num::NonZeroU32;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::{fmt, fs, io};
@@ -21,8 +22,11 @@ use std::{fmt, fs, io};
 use clap::{ArgGroup, Subcommand};
 use itertools::Itertools;
 use jj_lib::backend::TreeValue;
+use jj_lib::commit::Commit;
+use jj_lib::default_index::DefaultIndexStore;
 use jj_lib::git::{
-    self, parse_gitmodules, GitBranchPushTargets, GitFetchError, GitFetchStats, GitPushError,
+    self, parse_gitmodules, GitBranchPushTargets, GitFetchError, GitFetchOptions, GitFetchStats,
+    GitPushError,
 };
 use jj_lib::object_id::ObjectId;
 use jj_lib::op_store::RefTarget;
@@ -39,12 +43,14 @@ use jj_lib::workspace::Workspace;
 use maplit::hashset;
 
 use crate::cli_util::{
-    parse_string_pattern, resolve_multiple_nonempty_revsets, short_change_hash, short_commit_hash,
+    internal_error, resolve_multiple_nonempty_revsets, short_change_hash, short_commit_hash,
     user_error, user_error_with_hint, user_error_with_hint_opt, user_error_with_message,
     CommandError, CommandHelper, RevisionArg, WorkspaceCommandHelper,
 };
+use crate::formatter::PlainTextFormatter;
 use crate::git_util::{
-    get_git_repo, print_failed_git_export, print_git_import_stats, with_remote_git_callbacks,
+    fetch_lfs_objects, get_git_repo, print_failed_git_export, print_git_import_stats,
+    push_lfs_objects, run_pre_push_hook, with_remote_git_callbacks,
 };
 use crate::ui::Ui;
 
@@ -112,7 +118,7 @@ pub struct GitFetchArgs {
     ///
     /// By default, the specified name matches exactly. Use `glob:` prefix to
     /// expand `*` as a glob. The other wildcard characters aren't supported.
-    #[arg(long, default_value = "glob:*", value_parser = parse_string_pattern)]
+    #[arg(long, default_value = "glob:*", value_parser = StringPattern::parse)]
     branch: Vec<StringPattern>,
     /// The remote to fetch from (only named remotes are supported, can be
     /// repeated)
@@ -121,6 +127,16 @@ pub struct GitFetchArgs {
     /// Fetch from all remotes
     #[arg(long, conflicts_with = "remotes")]
     all_remotes: bool,
+    /// Limit fetching to the specified number of commits from the tip of each
+    /// branch
+    ///
+    /// Commits whose parents aren't fetched become children of the root
+    /// commit.
+    #[arg(long)]
+    depth: Option<NonZeroU32>,
+    /// Limit fetching to commits more recent than the specified date
+    #[arg(long, value_name = "DATE")]
+    shallow_since: Option<String>,
 }
 
 /// Create a new repo backed by a clone of a Git repo
@@ -137,6 +153,30 @@ pub struct GitCloneArgs {
     /// Whether or not to colocate the Jujutsu repo with the git repo
     #[arg(long)]
     colocate: bool,
+    /// Create a shallow clone of the specified number of commits from the tip
+    /// of each branch
+    ///
+    /// Commits whose parents aren't fetched become children of the root
+    /// commit.
+    #[arg(long)]
+    depth: Option<NonZeroU32>,
+    /// Create a shallow clone of commits more recent than the specified date
+    #[arg(long, value_name = "DATE")]
+    shallow_since: Option<String>,
+    /// Create a partial clone with the specified object filter
+    ///
+    /// Only blob filters such as `blob:none` are supported. Missing file
+    /// contents are fetched from the remote when they are needed.
+    #[arg(long, value_name = "FILTER-SPEC", value_parser = parse_blob_filter)]
+    filter: Option<String>,
+}
+
+fn parse_blob_filter(filter: &str) -> Result<String, String> {
+    if filter.starts_with("blob:") {
+        Ok(filter.to_owned())
+    } else {
+        Err("Only blob filters such as `blob:none` are supported".to_owned())
+    }
 }
 
 /// Push to a Git remote
@@ -157,7 +197,7 @@ pub struct GitPushArgs {
     /// By default, the specified name matches exactly. Use `glob:` prefix to
     /// select branches by wildcard pattern. For details, see
     /// https://github.com/martinvonz/jj/blob/main/docs/revsets.md#string-patterns.
-    #[arg(long, short, value_parser = parse_string_pattern)]
+    #[arg(long, short, value_parser = StringPattern::parse)]
     branch: Vec<StringPattern>,
     /// Push all branches (including deleted branches)
     #[arg(long)]
@@ -170,6 +210,9 @@ pub struct GitPushArgs {
     revisions: Vec<RevisionArg>,
     /// Push this commit by creating a branch based on its change ID (can be
     /// repeated)
+    ///
+    /// The branch name can be customized by the `git.push-branch-prefix` or
+    /// `git.push-branch-name` config.
     #[arg(long, short)]
     change: Vec<RevisionArg>,
     /// Only display what will change on the remote
@@ -326,7 +369,13 @@ fn cmd_git_fetch(
     } else {
         args.remotes.clone()
     };
+    let fetch_options = GitFetchOptions {
+        depth: args.depth,
+        shallow_since: args.shallow_since.clone(),
+        filter: None,
+    };
     let mut tx = workspace_command.start_transaction();
+    let mut has_unshallowed_commits = false;
     for remote in &remotes {
         let stats = with_remote_git_callbacks(ui, |cb| {
             git::fetch(
@@ -334,6 +383,7 @@ fn cmd_git_fetch(
                 &git_repo,
                 remote,
                 &args.branch,
+                &fetch_options,
                 cb,
                 &command.settings().git_settings(),
             )
@@ -349,6 +399,16 @@ fn cmd_git_fetch(
                         err,
                         "Prefix the pattern with `glob:` to expand `*` as a glob",
                     )
+                } else if args
+                    .branch
+                    .iter()
+                    .any(|pattern| pattern.to_glob().is_none())
+                {
+                    user_error_with_hint(
+                        err,
+                        "Only case-sensitive `exact:`, `substring:`, and `glob:` patterns can be \
+                         used to fetch branches",
+                    )
                 } else {
                     user_error(err)
                 }
@@ -358,11 +418,46 @@ fn cmd_git_fetch(
             _ => user_error(err),
         })?;
         print_git_import_stats(ui, &stats.import_stats)?;
+        fetch_lfs_objects(ui, command.settings(), &git_repo, remote, &args.branch)?;
+        has_unshallowed_commits |= !stats.unshallowed_commits.is_empty();
     }
     tx.finish(
         ui,
         format!("fetch from git remote(s) {}", remotes.iter().join(",")),
     )?;
+    if has_unshallowed_commits {
+        reindex_unshallowed_commits(ui, command, &workspace_command)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the index after the history has been deepened. The commits at the
+/// previous shallow boundary were indexed as children of the root commit, but
+/// now have their real parents.
+fn reindex_unshallowed_commits(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    workspace_command: &WorkspaceCommandHelper,
+) -> Result<(), CommandError> {
+    // Load the repo again so the commits aren't read from the cache.
+    let workspace = command.load_workspace()?;
+    let repo_loader = workspace.repo_loader();
+    let op = workspace_command.repo().operation();
+    let index_store = repo_loader.index_store();
+    let Some(default_index_store) = index_store.as_any().downcast_ref::<DefaultIndexStore>() else {
+        return Err(user_error(format!(
+            "Cannot reindex indexes of type '{}' after deepening the history",
+            index_store.name()
+        )));
+    };
+    writeln!(
+        ui.stderr(),
+        "Rebuilding the index since the history has been deepened."
+    )?;
+    default_index_store.reinit().map_err(internal_error)?;
+    default_index_store
+        .build_index_at_operation(op, repo_loader.store())
+        .map_err(internal_error)?;
     Ok(())
 }
 
@@ -476,12 +571,18 @@ fn cmd_git_clone(
     let canonical_wc_path: PathBuf = wc_path
         .canonicalize()
         .map_err(|err| user_error_with_message(format!("Failed to create {wc_path_str}"), err))?;
+    let fetch_options = GitFetchOptions {
+        depth: args.depth,
+        shallow_since: args.shallow_since.clone(),
+        filter: args.filter.clone(),
+    };
     let clone_result = do_git_clone(
         ui,
         command,
         args.colocate,
         remote_name,
         &source,
+        &fetch_options,
         &canonical_wc_path,
     );
     if clone_result.is_err() {
@@ -533,6 +634,7 @@ fn do_git_clone(
     colocate: bool,
     remote_name: &str,
     source: &str,
+    fetch_options: &GitFetchOptions,
     wc_path: &Path,
 ) -> Result<(WorkspaceCommandHelper, GitFetchStats), CommandError> {
     let (workspace, repo) = if colocate {
@@ -557,6 +659,7 @@ fn do_git_clone(
             &git_repo,
             remote_name,
             &[StringPattern::everything()],
+            fetch_options,
             cb,
             &command.settings().git_settings(),
         )
@@ -570,8 +673,18 @@ fn do_git_clone(
         GitFetchError::InvalidBranchPattern => {
             unreachable!("we didn't provide any globs")
         }
+        GitFetchError::GitCommand(_)
+        | GitFetchError::GitCommandErrorStatus { .. }
+        | GitFetchError::ReadShallowCommits(_) => user_error(err),
     })?;
     print_git_import_stats(ui, &stats.import_stats)?;
+    fetch_lfs_objects(
+        ui,
+        command.settings(),
+        &git_repo,
+        remote_name,
+        &[StringPattern::everything()],
+    )?;
     fetch_tx.finish(ui, "fetch from git remote into empty repo")?;
     Ok((workspace_command, stats))
 }
@@ -597,6 +710,21 @@ fn cmd_git_push(
         .iter()
         .map(|change_str| workspace_command.resolve_single_rev(change_str, ui))
         .try_collect()?;
+    let change_branch_names = match command
+        .settings()
+        .config()
+        .get_string("git.push-branch-name")
+        .optional()?
+    {
+        Some(template_text) => Some(generate_change_branch_names(
+            &workspace_command,
+            &template_text,
+            &remote,
+            &args.change,
+            &change_commits,
+        )?),
+        None => None,
+    };
 
     let mut tx = workspace_command.start_transaction();
     let tx_description;
@@ -637,33 +765,42 @@ fn cmd_git_push(
             }
         }
 
-        for (change_str, commit) in std::iter::zip(args.change.iter(), change_commits) {
-            let mut branch_name = format!(
-                "{}{}",
-                command.settings().push_branch_prefix(),
-                commit.change_id().hex()
-            );
-            if !seen_branches.insert(branch_name.clone()) {
-                continue;
-            }
+        for (i, (change_str, commit)) in args.change.iter().zip(change_commits).enumerate() {
             let view = tx.base_repo().view();
-            if view.get_local_branch(&branch_name).is_absent() {
-                // A local branch with the full change ID doesn't exist already, so use the
-                // short ID if it's not ambiguous (which it shouldn't be most of the time).
-                let short_change_id = short_change_hash(commit.change_id());
-                if tx
-                    .base_workspace_helper()
-                    .resolve_single_rev(&short_change_id, ui)
-                    .is_ok()
-                {
-                    // Short change ID is not ambiguous, so update the branch name to use it.
-                    branch_name = format!(
-                        "{}{}",
-                        command.settings().push_branch_prefix(),
-                        short_change_id
-                    );
-                };
-            }
+            let branch_name = if let Some(names) = &change_branch_names {
+                let branch_name = names[i].clone();
+                if !seen_branches.insert(branch_name.clone()) {
+                    continue;
+                }
+                branch_name
+            } else {
+                let mut branch_name = format!(
+                    "{}{}",
+                    command.settings().push_branch_prefix(),
+                    commit.change_id().hex()
+                );
+                if !seen_branches.insert(branch_name.clone()) {
+                    continue;
+                }
+                if view.get_local_branch(&branch_name).is_absent() {
+                    // A local branch with the full change ID doesn't exist already, so use the
+                    // short ID if it's not ambiguous (which it shouldn't be most of the time).
+                    let short_change_id = short_change_hash(commit.change_id());
+                    if tx
+                        .base_workspace_helper()
+                        .resolve_single_rev(&short_change_id, ui)
+                        .is_ok()
+                    {
+                        // Short change ID is not ambiguous, so update the branch name to use it.
+                        branch_name = format!(
+                            "{}{}",
+                            command.settings().push_branch_prefix(),
+                            short_change_id
+                        );
+                    };
+                }
+                branch_name
+            };
             if view.get_local_branch(&branch_name).is_absent() {
                 writeln!(
                     ui.stderr(),
@@ -860,6 +997,14 @@ fn cmd_git_push(
         branch_updates,
         force_pushed_branches,
     };
+    run_pre_push_hook(
+        command.settings(),
+        &git_repo,
+        tx.base_workspace_helper().workspace_root(),
+        &remote,
+        &targets,
+    )?;
+    push_lfs_objects(ui, &git_repo, &remote, &targets)?;
     with_remote_git_callbacks(ui, |cb| {
         git::push_branches(tx.mut_repo(), &git_repo, &remote, &targets, cb)
     })
@@ -876,6 +1021,102 @@ fn cmd_git_push(
     Ok(())
 }
 
+/// Generates branch names for `jj git push --change` by the
+/// `git.push-branch-name` template.
+///
+/// If a change has already been pushed to the `remote` with a tracking branch,
+/// the existing branch name is reused so the name doesn't change when the
+/// description is edited. Collisions with existing local or remote branches
+/// are reported as errors.
+fn generate_change_branch_names(
+    workspace_command: &WorkspaceCommandHelper,
+    template_text: &str,
+    remote: &str,
+    change_args: &[RevisionArg],
+    commits: &[Commit],
+) -> Result<Vec<String>, CommandError> {
+    let repo = workspace_command.repo().as_ref();
+    let template = workspace_command.parse_commit_template(template_text)?;
+    let mut generated: HashMap<String, &Commit> = HashMap::new();
+    let mut branch_names = vec![];
+    for (change_str, commit) in std::iter::zip(change_args, commits) {
+        let branch_name = if let Some(name) = find_pushed_branch_for_change(repo, remote, commit)? {
+            name
+        } else {
+            let mut output = Vec::new();
+            template.format(commit, &mut PlainTextFormatter::new(&mut output))?;
+            let name = String::from_utf8_lossy(&output).into_owned();
+            if !git2::Reference::is_valid_name(&format!("refs/heads/{name}")) {
+                return Err(user_error_with_hint(
+                    format!(
+                        "Invalid branch name {name:?} generated for revision {}",
+                        change_str.deref()
+                    ),
+                    "Check the git.push-branch-name template",
+                ));
+            }
+            // Don't overwrite a branch for another change, either locally or on
+            // the remote.
+            let local_target = repo.view().get_local_branch(&name);
+            let remote_target = &repo.view().get_remote_branch(&name, remote).target;
+            let points_to_other_change = [local_target, remote_target]
+                .into_iter()
+                .flat_map(|target| target.added_ids())
+                .any(|id| {
+                    repo.store()
+                        .get_commit(id)
+                        .map_or(true, |target| target.change_id() != commit.change_id())
+                });
+            if local_target.has_conflict() || remote_target.has_conflict() || points_to_other_change
+            {
+                return Err(user_error_with_hint(
+                    format!(
+                        "Branch {name} generated for revision {} already points to another \
+                         change",
+                        change_str.deref()
+                    ),
+                    "Rename or delete the existing branch, or use `--branch` to push it",
+                ));
+            }
+            name
+        };
+        if let Some(other_commit) = generated.insert(branch_name.clone(), commit) {
+            if other_commit.id() != commit.id() {
+                return Err(user_error(format!(
+                    "Branch name {branch_name} was generated for multiple revisions: {} and {}",
+                    short_commit_hash(other_commit.id()),
+                    short_commit_hash(commit.id())
+                )));
+            }
+        }
+        branch_names.push(branch_name);
+    }
+    Ok(branch_names)
+}
+
+/// Returns the name of the tracking branch which was pushed to the `remote` for
+/// the change of the `commit`, and now points to the `commit`.
+fn find_pushed_branch_for_change(
+    repo: &dyn Repo,
+    remote: &str,
+    commit: &Commit,
+) -> Result<Option<String>, CommandError> {
+    for (branch_name, targets) in repo.view().local_remote_branches(remote) {
+        if targets.local_target.as_normal() != Some(commit.id())
+            || !targets.remote_ref.is_tracking()
+        {
+            continue;
+        }
+        let Some(remote_id) = targets.remote_ref.target.as_normal() else {
+            continue;
+        };
+        if repo.store().get_commit(remote_id)?.change_id() == commit.change_id() {
+            return Ok(Some(branch_name.to_owned()));
+        }
+    }
+    Ok(None)
+}
+
 fn get_default_push_remote(
     ui: &Ui,
     settings: &UserSettings,
@@ -63,6 +63,7 @@ pub(crate) fn cmd_interdiff(
         &from_tree,
         &to_tree,
         matcher.as_ref(),
+        &[],
         &diff_formats,
     )
 }
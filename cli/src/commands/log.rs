@@ -12,16 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::rc::Rc;
+
+use indexmap::IndexSet;
 use itertools::Itertools;
 use jj_lib::backend::CommitId;
+use jj_lib::matchers::{EverythingMatcher, FilesMatcher, Matcher};
 use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::revset::{self, RevsetExpression, RevsetFilterPredicate, RevsetIteratorExt};
 use jj_lib::revset_graph::{
     ReverseRevsetGraphIterator, RevsetGraphEdgeType, TopoGroupedRevsetGraphIterator,
 };
+use jj_lib::rewrite::merge_commit_trees;
 use tracing::instrument;
 
-use crate::cli_util::{CommandError, CommandHelper, LogContentFormat, RevisionArg};
+use crate::cli_util::{
+    CommandError, CommandHelper, LogContentFormat, RevisionArg, WorkspaceCommandHelper,
+};
 use crate::diff_util::{self, DiffFormatArgs};
 use crate::graphlog::{get_graphlog, Edge};
 use crate::ui::Ui;
@@ -37,6 +45,13 @@ pub(crate) struct LogArgs {
     /// Show commits modifying the given paths
     #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Follow the given files across renames
+    ///
+    /// Commits are walked from the newest, and a file that was renamed (or
+    /// copied) from another file continues to be tracked under its old name.
+    /// The paths must be file paths, not patterns.
+    #[arg(long, requires = "paths")]
+    follow: bool,
     /// Show revisions in the opposite order (older revisions first)
     #[arg(long)]
     reversed: bool,
@@ -79,22 +94,32 @@ pub(crate) fn cmd_log(
                 .try_collect()?;
             RevsetExpression::union_all(&expressions)
         };
-        if !args.paths.is_empty() {
-            let repo_paths: Vec<_> = args
-                .paths
-                .iter()
-                .map(|path_arg| workspace_command.parse_file_path(path_arg))
-                .try_collect()?;
+        // With --follow, the paths are filtered while walking the history
+        if !args.paths.is_empty() && !args.follow {
+            let fileset_expression = workspace_command.parse_file_patterns(&args.paths)?;
             expression = expression.intersection(&RevsetExpression::filter(
-                RevsetFilterPredicate::File(Some(repo_paths)),
+                RevsetFilterPredicate::File(fileset_expression),
             ));
         }
         revset::optimize(expression)
     };
     let repo = workspace_command.repo();
     let wc_commit_id = workspace_command.get_wc_commit_id();
-    let matcher = workspace_command.matcher_from_values(&args.paths)?;
-    let revset = workspace_command.evaluate_revset(revset_expression)?;
+    let (revset, matcher) = if args.follow {
+        let paths: Vec<_> = args
+            .paths
+            .iter()
+            .map(|path| workspace_command.parse_file_path(path))
+            .try_collect()?;
+        let (commit_ids, paths) = follow_renames(&workspace_command, revset_expression, paths)?;
+        let revset = workspace_command.evaluate_revset(RevsetExpression::commits(commit_ids))?;
+        let matcher: Box<dyn Matcher> = Box::new(FilesMatcher::new(paths));
+        (revset, matcher)
+    } else {
+        let matcher = workspace_command.matcher_from_values(&args.paths)?;
+        let revset = workspace_command.evaluate_revset(revset_expression)?;
+        (revset, matcher)
+    };
 
     let store = repo.store();
     let diff_formats =
@@ -227,3 +252,54 @@ pub(crate) fn cmd_log(
 
     Ok(())
 }
+
+/// Walks the commits in `expression` from the newest, and returns the commits
+/// that modified any of the `paths`, and the names the files had in them.
+fn follow_renames(
+    workspace_command: &WorkspaceCommandHelper,
+    expression: Rc<RevsetExpression>,
+    paths: Vec<RepoPathBuf>,
+) -> Result<(Vec<CommitId>, Vec<RepoPathBuf>), CommandError> {
+    let repo = workspace_command.repo();
+    let revset = workspace_command.evaluate_revset(expression)?;
+    let mut paths: IndexSet<RepoPathBuf> = paths.into_iter().collect();
+    let mut commit_ids = vec![];
+    for commit in revset.iter().commits(repo.store()) {
+        let commit = commit?;
+        let parent_tree = merge_commit_trees(repo.as_ref(), &commit.parents())?;
+        let tree = commit.tree()?;
+        let matcher = FilesMatcher::new(&paths);
+        let mut added_paths = vec![];
+        let mut modified = false;
+        for (path, diff) in parent_tree.diff(&tree, &matcher) {
+            let (before, _after) = diff?;
+            modified = true;
+            if before.is_absent() {
+                added_paths.push(path);
+            }
+        }
+        if !modified {
+            continue;
+        }
+        commit_ids.push(commit.id().clone());
+        if added_paths.is_empty() {
+            continue;
+        }
+        // Look for the sources of the added files in the whole diff
+        let entries = diff_util::diff_with_copies(
+            workspace_command,
+            &parent_tree,
+            &tree,
+            &EverythingMatcher,
+            commit.copy_records(),
+        )?;
+        for entry in entries {
+            if let Some(source) = entry.source {
+                if added_paths.contains(&entry.target) {
+                    paths.insert(source.path);
+                }
+            }
+        }
+    }
+    Ok((commit_ids, paths.into_iter().collect()))
+}
@@ -13,9 +13,11 @@
 // limitations under the License.
 
 mod abandon;
+mod absorb;
 mod backout;
 #[cfg(feature = "bench")]
 mod bench;
+mod bisect;
 mod branch;
 mod cat;
 mod checkout;
@@ -28,7 +30,9 @@ mod diff;
 mod diffedit;
 mod duplicate;
 mod edit;
+mod file;
 mod files;
+mod fix;
 mod git;
 mod init;
 mod interdiff;
@@ -39,6 +43,7 @@ mod new;
 mod next;
 mod obslog;
 mod operation;
+mod parallelize;
 mod prev;
 mod rebase;
 mod resolve;
@@ -46,11 +51,13 @@ mod restore;
 mod root;
 mod run;
 mod show;
+mod sign;
 mod sparse;
 mod split;
 mod squash;
 mod status;
 mod tag;
+mod unsign;
 mod unsquash;
 mod untrack;
 mod util;
@@ -68,11 +75,14 @@ use crate::ui::Ui;
 #[derive(clap::Parser, Clone, Debug)]
 enum Command {
     Abandon(abandon::AbandonArgs),
+    Absorb(absorb::AbsorbArgs),
     Backout(backout::BackoutArgs),
     #[cfg(feature = "bench")]
     #[command(subcommand)]
     Bench(bench::BenchCommand),
     #[command(subcommand)]
+    Bisect(bisect::BisectCommand),
+    #[command(subcommand)]
     Branch(branch::BranchCommand),
     #[command(alias = "print")]
     Cat(cat::CatArgs),
@@ -88,7 +98,10 @@ enum Command {
     Diffedit(diffedit::DiffeditArgs),
     Duplicate(duplicate::DuplicateArgs),
     Edit(edit::EditArgs),
+    #[command(subcommand)]
+    File(file::FileCommand),
     Files(files::FilesArgs),
+    Fix(fix::FixArgs),
     #[command(subcommand)]
     Git(git::GitCommand),
     Init(init::InitArgs),
@@ -111,6 +124,7 @@ enum Command {
     #[command(subcommand)]
     #[command(visible_alias = "op")]
     Operation(operation::OperationCommand),
+    Parallelize(parallelize::ParallelizeArgs),
     Prev(prev::PrevArgs),
     Rebase(rebase::RebaseArgs),
     Resolve(resolve::ResolveArgs),
@@ -121,10 +135,9 @@ enum Command {
     )]
     Revert(DummyCommandArgs),
     Root(root::RootArgs),
-    #[command(hide = true)]
-    // TODO: Flesh out.
     Run(run::RunArgs),
     Show(show::ShowArgs),
+    Sign(sign::SignArgs),
     #[command(subcommand)]
     Sparse(sparse::SparseArgs),
     Split(split::SplitArgs),
@@ -136,6 +149,7 @@ enum Command {
     Util(util::UtilCommand),
     /// Undo an operation (shortcut for `jj op undo`)
     Undo(operation::OperationUndoArgs),
+    Unsign(unsign::UnsignArgs),
     Unsquash(unsquash::UnsquashArgs),
     Untrack(untrack::UntrackArgs),
     Version(version::VersionArgs),
@@ -163,10 +177,13 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Config(sub_args) => config::cmd_config(ui, command_helper, sub_args),
         Command::Checkout(sub_args) => checkout::cmd_checkout(ui, command_helper, sub_args),
         Command::Untrack(sub_args) => untrack::cmd_untrack(ui, command_helper, sub_args),
+        Command::File(sub_args) => file::cmd_file(ui, command_helper, sub_args),
         Command::Files(sub_args) => files::cmd_files(ui, command_helper, sub_args),
+        Command::Fix(sub_args) => fix::cmd_fix(ui, command_helper, sub_args),
         Command::Cat(sub_args) => cat::cmd_cat(ui, command_helper, sub_args),
         Command::Diff(sub_args) => diff::cmd_diff(ui, command_helper, sub_args),
         Command::Show(sub_args) => show::cmd_show(ui, command_helper, sub_args),
+        Command::Sign(sub_args) => sign::cmd_sign(ui, command_helper, sub_args),
         Command::Status(sub_args) => status::cmd_status(ui, command_helper, sub_args),
         Command::Log(sub_args) => log::cmd_log(ui, command_helper, sub_args),
         Command::Interdiff(sub_args) => interdiff::cmd_interdiff(ui, command_helper, sub_args),
@@ -175,6 +192,7 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Commit(sub_args) => commit::cmd_commit(ui, command_helper, sub_args),
         Command::Duplicate(sub_args) => duplicate::cmd_duplicate(ui, command_helper, sub_args),
         Command::Abandon(sub_args) => abandon::cmd_abandon(ui, command_helper, sub_args),
+        Command::Absorb(sub_args) => absorb::cmd_absorb(ui, command_helper, sub_args),
         Command::Edit(sub_args) => edit::cmd_edit(ui, command_helper, sub_args),
         Command::Next(sub_args) => next::cmd_next(ui, command_helper, sub_args),
         Command::Prev(sub_args) => prev::cmd_prev(ui, command_helper, sub_args),
@@ -182,6 +200,7 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Move(sub_args) => r#move::cmd_move(ui, command_helper, sub_args),
         Command::Squash(sub_args) => squash::cmd_squash(ui, command_helper, sub_args),
         Command::Unsquash(sub_args) => unsquash::cmd_unsquash(ui, command_helper, sub_args),
+        Command::Unsign(sub_args) => unsign::cmd_unsign(ui, command_helper, sub_args),
         Command::Restore(sub_args) => restore::cmd_restore(ui, command_helper, sub_args),
         Command::Revert(_args) => revert(),
         Command::Root(sub_args) => root::cmd_root(ui, command_helper, sub_args),
@@ -189,9 +208,13 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Diffedit(sub_args) => diffedit::cmd_diffedit(ui, command_helper, sub_args),
         Command::Split(sub_args) => split::cmd_split(ui, command_helper, sub_args),
         Command::Merge(sub_args) => merge::cmd_merge(ui, command_helper, sub_args),
+        Command::Parallelize(sub_args) => {
+            parallelize::cmd_parallelize(ui, command_helper, sub_args)
+        }
         Command::Rebase(sub_args) => rebase::cmd_rebase(ui, command_helper, sub_args),
         Command::Backout(sub_args) => backout::cmd_backout(ui, command_helper, sub_args),
         Command::Resolve(sub_args) => resolve::cmd_resolve(ui, command_helper, sub_args),
+        Command::Bisect(sub_args) => bisect::cmd_bisect(ui, command_helper, sub_args),
         Command::Branch(sub_args) => branch::cmd_branch(ui, command_helper, sub_args),
         Command::Undo(sub_args) => operation::cmd_op_undo(ui, command_helper, sub_args),
         Command::Operation(sub_args) => operation::cmd_operation(ui, command_helper, sub_args),
@@ -163,6 +163,7 @@ fn show_predecessor_patch(
         &predecessor_tree,
         &tree,
         &EverythingMatcher,
+        &[],
         diff_formats,
     )
 }
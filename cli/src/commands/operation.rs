@@ -12,25 +12,35 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
 use std::io::Write as _;
 use std::slice;
 
 use clap::Subcommand;
-use itertools::Itertools as _;
+use indexmap::IndexMap;
+use itertools::{EitherOrBoth, Itertools as _};
+use jj_lib::backend::ChangeId;
+use jj_lib::commit::Commit;
+use jj_lib::id_prefix::IdPrefixContext;
+use jj_lib::matchers::EverythingMatcher;
 use jj_lib::object_id::ObjectId;
-use jj_lib::op_store::OperationId;
+use jj_lib::op_store::{OperationId, RefTarget, RemoteRef, RemoteRefState};
 use jj_lib::op_walk;
 use jj_lib::operation::Operation;
 use jj_lib::repo::Repo;
+use jj_lib::revset::{RevsetExpression, RevsetIteratorExt as _};
+use jj_lib::rewrite::{merge_commit_trees, rebase_to_dest_parent};
 
 use crate::cli_util::{
-    short_operation_hash, user_error, user_error_with_hint, CommandError, CommandHelper,
-    LogContentFormat,
+    short_change_hash, short_operation_hash, user_error, user_error_with_hint, CommandError,
+    CommandHelper, LogContentFormat, WorkspaceCommandHelper,
 };
+use crate::diff_util::{self, DiffFormat, DiffFormatArgs};
+use crate::formatter::Formatter;
 use crate::graphlog::{get_graphlog, Edge};
-use crate::operation_templater;
-use crate::templater::Template as _;
+use crate::templater::Template;
 use crate::ui::Ui;
+use crate::{commit_templater, operation_templater};
 
 /// Commands for working with the operation log
 ///
@@ -39,7 +49,9 @@ use crate::ui::Ui;
 #[derive(Subcommand, Clone, Debug)]
 pub enum OperationCommand {
     Abandon(OperationAbandonArgs),
+    Diff(OperationDiffArgs),
     Log(OperationLogArgs),
+    Show(OperationShowArgs),
     Undo(OperationUndoArgs),
     Restore(OperationRestoreArgs),
 }
@@ -60,6 +72,53 @@ pub struct OperationLogArgs {
     template: Option<String>,
 }
 
+/// Show changes to the repository in an operation
+///
+/// Shows the operation, followed by how it changed the commits, branches,
+/// tags, Git refs and working-copy commits, compared to its parent
+/// operation(s).
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationShowArgs {
+    /// Show repository changes in this operation, compared to its parent(s)
+    #[arg(default_value = "@")]
+    operation: String,
+    /// Show patch of modifications to changes
+    ///
+    /// If the previous version has different parents, it will be temporarily
+    /// rebased to the parents of the new version, so the diff is not
+    /// contaminated by unrelated changes.
+    #[arg(long, short = 'p')]
+    patch: bool,
+    #[command(flatten)]
+    diff_format: DiffFormatArgs,
+}
+
+/// Compare changes to the repository between two operations
+///
+/// Shows how the commits, branches, tags, Git refs and working-copy commits
+/// differ between the repository states after the two operations.
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationDiffArgs {
+    /// Show repository changes in this operation, compared to its parent(s)
+    #[arg(long, visible_alias = "op")]
+    operation: Option<String>,
+    /// Show repository changes from this operation
+    #[arg(long, conflicts_with = "operation")]
+    from: Option<String>,
+    /// Show repository changes to this operation
+    #[arg(long, conflicts_with = "operation")]
+    to: Option<String>,
+    /// Show patch of modifications to changes
+    ///
+    /// If the previous version has different parents, it will be temporarily
+    /// rebased to the parents of the new version, so the diff is not
+    /// contaminated by unrelated changes.
+    #[arg(long, short = 'p')]
+    patch: bool,
+    #[command(flatten)]
+    diff_format: DiffFormatArgs,
+}
+
 /// Create a new operation that restores the repo to an earlier state
 ///
 /// This restores the repo to the state at the specified operation, effectively
@@ -222,6 +281,388 @@ fn cmd_op_log(
     Ok(())
 }
 
+fn cmd_op_show(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationShowArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let op = workspace_command.resolve_single_op(&args.operation)?;
+    let parent_ops: Vec<Operation> = op.parents().try_collect()?;
+    let diff_formats =
+        diff_util::diff_formats_for_log(command.settings(), &args.diff_format, args.patch)?;
+
+    let repo = workspace_command.repo();
+    let template_string = command.settings().config().get_string("templates.op_log")?;
+    let template = operation_templater::parse(
+        repo.op_store().root_operation_id(),
+        Some(repo.operation().id()),
+        &template_string,
+        workspace_command.template_aliases_map(),
+    )?;
+
+    ui.request_pager();
+    let mut formatter = ui.stdout_formatter();
+    let formatter = formatter.as_mut();
+    formatter.with_label("op_log", |formatter| template.format(&op, formatter))?;
+    show_op_diff(
+        ui,
+        formatter,
+        command,
+        &workspace_command,
+        &parent_ops,
+        &op,
+        &diff_formats,
+    )
+}
+
+fn cmd_op_diff(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationDiffArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let (from_ops, to_op) = if args.from.is_some() || args.to.is_some() {
+        let from_op = workspace_command.resolve_single_op(args.from.as_deref().unwrap_or("@"))?;
+        let to_op = workspace_command.resolve_single_op(args.to.as_deref().unwrap_or("@"))?;
+        (vec![from_op], to_op)
+    } else {
+        let op = workspace_command.resolve_single_op(args.operation.as_deref().unwrap_or("@"))?;
+        let parent_ops: Vec<Operation> = op.parents().try_collect()?;
+        (parent_ops, op)
+    };
+    let diff_formats =
+        diff_util::diff_formats_for_log(command.settings(), &args.diff_format, args.patch)?;
+
+    ui.request_pager();
+    let mut formatter = ui.stdout_formatter();
+    let formatter = formatter.as_mut();
+    for (prefix, op) in from_ops
+        .iter()
+        .map(|op| ("From operation", op))
+        .chain([("  To operation", &to_op)])
+    {
+        writeln!(
+            formatter,
+            "{prefix} {}: {}",
+            short_operation_hash(op.id()),
+            op.store_operation().metadata.description
+        )?;
+    }
+    show_op_diff(
+        ui,
+        formatter,
+        command,
+        &workspace_command,
+        &from_ops,
+        &to_op,
+        &diff_formats,
+    )
+}
+
+/// The commits of a change that were added or removed between two operations.
+#[derive(Default)]
+struct ModifiedChange {
+    added_commits: Vec<Commit>,
+    removed_commits: Vec<Commit>,
+}
+
+/// Shows the differences between the repo views after `from_ops` (merged if
+/// there are several) and after `to_op`.
+fn show_op_diff(
+    ui: &Ui,
+    formatter: &mut dyn Formatter,
+    command: &CommandHelper,
+    workspace_command: &WorkspaceCommandHelper,
+    from_ops: &[Operation],
+    to_op: &Operation,
+    diff_formats: &[DiffFormat],
+) -> Result<(), CommandError> {
+    let settings = command.settings();
+    let repo_loader = workspace_command.repo().loader();
+    // Start from the state after `from_ops`, and then switch to the view of
+    // `to_op` with an index that includes the commits of both sides.
+    let mut tx = match from_ops.split_first() {
+        None => repo_loader.load_at(to_op)?.start_transaction(settings),
+        Some((first_op, other_ops)) => {
+            let mut tx = repo_loader.load_at(first_op)?.start_transaction(settings);
+            for other_op in other_ops {
+                tx.merge_operation(other_op.clone())?;
+                tx.mut_repo().rebase_descendants(settings)?;
+            }
+            tx
+        }
+    };
+    let from_view = tx.repo().view().store_view().clone();
+    let to_repo = repo_loader.load_at(to_op)?;
+    let to_view = to_repo.view().store_view();
+    tx.mut_repo().merge_index(&to_repo);
+    tx.mut_repo().set_view(to_view.clone());
+    let repo = tx.repo();
+
+    let id_prefix_context = IdPrefixContext::default();
+    let template = commit_templater::parse(
+        repo,
+        workspace_command.workspace_id(),
+        &id_prefix_context,
+        &settings.config().get_string("templates.commit_summary")?,
+        workspace_command.template_aliases_map(),
+    )?;
+    let template = template.as_ref();
+
+    let from_heads = RevsetExpression::commits(from_view.head_ids.iter().cloned().collect());
+    let to_heads = RevsetExpression::commits(to_view.head_ids.iter().cloned().collect());
+    let mut changes: IndexMap<ChangeId, ModifiedChange> = IndexMap::new();
+    for commit in to_heads
+        .ancestors()
+        .minus(&from_heads.ancestors())
+        .evaluate_programmatic(repo)?
+        .iter()
+        .commits(repo.store())
+    {
+        let commit = commit?;
+        let change = changes.entry(commit.change_id().clone()).or_default();
+        change.added_commits.push(commit);
+    }
+    for commit in from_heads
+        .ancestors()
+        .minus(&to_heads.ancestors())
+        .evaluate_programmatic(repo)?
+        .iter()
+        .commits(repo.store())
+    {
+        let commit = commit?;
+        let change = changes.entry(commit.change_id().clone()).or_default();
+        change.removed_commits.push(commit);
+    }
+    if !changes.is_empty() {
+        writeln!(formatter)?;
+        writeln!(formatter, "Changed commits:")?;
+        for (change_id, change) in &changes {
+            writeln!(formatter, "Change {}", short_change_hash(change_id))?;
+            for commit in &change.added_commits {
+                write!(formatter, "+ ")?;
+                template.format(commit, formatter)?;
+                writeln!(formatter)?;
+            }
+            for commit in &change.removed_commits {
+                write!(formatter, "- ")?;
+                template.format(commit, formatter)?;
+                writeln!(formatter)?;
+            }
+            if !diff_formats.is_empty() {
+                show_change_diff(ui, formatter, workspace_command, repo, change, diff_formats)?;
+            }
+        }
+    }
+
+    let changed_wc_commits = diff_named_refs(
+        from_view
+            .wc_commit_ids
+            .iter()
+            .map(|(id, commit_id)| (id.as_str(), commit_id))
+            .sorted(),
+        to_view
+            .wc_commit_ids
+            .iter()
+            .map(|(id, commit_id)| (id.as_str(), commit_id))
+            .sorted(),
+    );
+    if !changed_wc_commits.is_empty() {
+        writeln!(formatter)?;
+        writeln!(formatter, "Changed working-copy commits:")?;
+        for (workspace_id, (from_id, to_id)) in changed_wc_commits {
+            writeln!(formatter, "{workspace_id}@:")?;
+            for (prefix, commit_id) in [("+", to_id), ("-", from_id)] {
+                match commit_id {
+                    Some(commit_id) => {
+                        write!(formatter, "{prefix} ")?;
+                        template.format(&repo.store().get_commit(commit_id)?, formatter)?;
+                        writeln!(formatter)?;
+                    }
+                    None => writeln!(formatter, "{prefix} (absent)")?,
+                }
+            }
+        }
+    }
+
+    for (title, from_refs, to_refs) in [
+        (
+            "Changed local branches:",
+            &from_view.local_branches,
+            &to_view.local_branches,
+        ),
+        ("Changed tags:", &from_view.tags, &to_view.tags),
+    ] {
+        write_changed_ref_targets(formatter, repo, template, title, from_refs, to_refs)?;
+    }
+
+    let changed_remote_branches = diff_named_refs(
+        iter_remote_branches(&from_view.remote_views),
+        iter_remote_branches(&to_view.remote_views),
+    );
+    if !changed_remote_branches.is_empty() {
+        writeln!(formatter)?;
+        writeln!(formatter, "Changed remote branches:")?;
+        for ((name, remote_name), (from_ref, to_ref)) in changed_remote_branches {
+            writeln!(formatter, "{name}@{remote_name}:")?;
+            for (prefix, remote_ref) in [("+", to_ref), ("-", from_ref)] {
+                let remote_ref = remote_ref.unwrap_or(RemoteRef::absent_ref());
+                let state = match remote_ref.state {
+                    RemoteRefState::New => "untracked",
+                    RemoteRefState::Tracking => "tracked",
+                };
+                write_ref_target_summary(
+                    formatter,
+                    repo,
+                    template,
+                    &format!("{prefix} {state}"),
+                    &remote_ref.target,
+                )?;
+            }
+        }
+    }
+
+    let mut from_git_refs = from_view.git_refs.clone();
+    from_git_refs.insert("HEAD".to_owned(), from_view.git_head.clone());
+    let mut to_git_refs = to_view.git_refs.clone();
+    to_git_refs.insert("HEAD".to_owned(), to_view.git_head.clone());
+    write_changed_ref_targets(
+        formatter,
+        repo,
+        template,
+        "Changed git refs:",
+        &from_git_refs,
+        &to_git_refs,
+    )?;
+
+    Ok(())
+}
+
+/// Shows the patch of a change that was added or rewritten.
+fn show_change_diff(
+    ui: &Ui,
+    formatter: &mut dyn Formatter,
+    workspace_command: &WorkspaceCommandHelper,
+    repo: &dyn Repo,
+    change: &ModifiedChange,
+    diff_formats: &[DiffFormat],
+) -> Result<(), CommandError> {
+    let (from_tree, commit, copy_records) = match (&*change.removed_commits, &*change.added_commits)
+    {
+        ([predecessor], [commit]) => {
+            let from_tree = rebase_to_dest_parent(repo, predecessor, commit)?;
+            (from_tree, commit, [].as_slice())
+        }
+        ([], [commit]) => {
+            let from_tree = merge_commit_trees(repo, &commit.parents())?;
+            (from_tree, commit, commit.copy_records())
+        }
+        // Abandoned or divergent changes have no meaningful patch.
+        _ => return Ok(()),
+    };
+    diff_util::show_diff(
+        ui,
+        formatter,
+        workspace_command,
+        &from_tree,
+        &commit.tree()?,
+        &EverythingMatcher,
+        copy_records,
+        diff_formats,
+    )
+}
+
+/// The old and new values of a ref, `None` if it didn't exist.
+type RefDiff<'a, V> = (Option<&'a V>, Option<&'a V>);
+
+/// Returns the refs that differ between the two sorted iterators, with their
+/// old and new values.
+fn diff_named_refs<'a, K: Ord, V: PartialEq + 'a>(
+    from_refs: impl IntoIterator<Item = (K, &'a V)>,
+    to_refs: impl IntoIterator<Item = (K, &'a V)>,
+) -> Vec<(K, RefDiff<'a, V>)> {
+    from_refs
+        .into_iter()
+        .merge_join_by(to_refs, |(from_name, _), (to_name, _)| {
+            from_name.cmp(to_name)
+        })
+        .filter_map(|entry| match entry {
+            EitherOrBoth::Both((name, from_ref), (_, to_ref)) => {
+                (from_ref != to_ref).then_some((name, (Some(from_ref), Some(to_ref))))
+            }
+            EitherOrBoth::Left((name, from_ref)) => Some((name, (Some(from_ref), None))),
+            EitherOrBoth::Right((name, to_ref)) => Some((name, (None, Some(to_ref)))),
+        })
+        .collect()
+}
+
+fn iter_remote_branches(
+    remote_views: &BTreeMap<String, jj_lib::op_store::RemoteView>,
+) -> impl Iterator<Item = ((&str, &str), &RemoteRef)> {
+    remote_views
+        .iter()
+        .flat_map(|(remote_name, remote_view)| {
+            remote_view
+                .branches
+                .iter()
+                .map(move |(name, remote_ref)| ((name.as_str(), remote_name.as_str()), remote_ref))
+        })
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+}
+
+fn write_changed_ref_targets(
+    formatter: &mut dyn Formatter,
+    repo: &dyn Repo,
+    template: &dyn Template<Commit>,
+    title: &str,
+    from_refs: &BTreeMap<String, RefTarget>,
+    to_refs: &BTreeMap<String, RefTarget>,
+) -> Result<(), CommandError> {
+    let changed_refs = diff_named_refs(from_refs, to_refs);
+    if changed_refs.is_empty() {
+        return Ok(());
+    }
+    writeln!(formatter)?;
+    writeln!(formatter, "{title}")?;
+    for (name, (from_target, to_target)) in changed_refs {
+        writeln!(formatter, "{name}:")?;
+        for (prefix, target) in [("+", to_target), ("-", from_target)] {
+            let target = target.unwrap_or(RefTarget::absent_ref());
+            write_ref_target_summary(formatter, repo, template, prefix, target)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_ref_target_summary(
+    formatter: &mut dyn Formatter,
+    repo: &dyn Repo,
+    template: &dyn Template<Commit>,
+    prefix: &str,
+    target: &RefTarget,
+) -> Result<(), CommandError> {
+    let mut write_commit = |prefix: &str, commit_id| -> Result<(), CommandError> {
+        write!(formatter, "{prefix} ")?;
+        template.format(&repo.store().get_commit(commit_id)?, formatter)?;
+        writeln!(formatter)?;
+        Ok(())
+    };
+    if target.is_absent() {
+        writeln!(formatter, "{prefix} (absent)")?;
+    } else if target.has_conflict() {
+        for commit_id in target.added_ids() {
+            write_commit(&format!("{prefix} (added)"), commit_id)?;
+        }
+        for commit_id in target.removed_ids() {
+            write_commit(&format!("{prefix} (removed)"), commit_id)?;
+        }
+    } else {
+        write_commit(prefix, target.as_normal().unwrap())?;
+    }
+    Ok(())
+}
+
 /// Restore only the portions of the view specified by the `what` argument
 fn view_with_desired_portions_restored(
     view_being_restored: &jj_lib::op_store::View,
@@ -397,7 +838,9 @@ pub fn cmd_operation(
 ) -> Result<(), CommandError> {
     match subcommand {
         OperationCommand::Abandon(args) => cmd_op_abandon(ui, command, args),
+        OperationCommand::Diff(args) => cmd_op_diff(ui, command, args),
         OperationCommand::Log(args) => cmd_op_log(ui, command, args),
+        OperationCommand::Show(args) => cmd_op_show(ui, command, args),
         OperationCommand::Restore(args) => cmd_op_restore(ui, command, args),
         OperationCommand::Undo(args) => cmd_op_undo(ui, command, args),
     }
@@ -0,0 +1,155 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use indexmap::IndexSet;
+use itertools::Itertools;
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::repo::Repo;
+use jj_lib::revset::{RevsetExpression, RevsetIteratorExt};
+use jj_lib::rewrite::rebase_commit;
+use tracing::instrument;
+
+use crate::cli_util::{
+    resolve_multiple_nonempty_revsets, user_error, CommandError, CommandHelper, RevisionArg,
+};
+use crate::ui::Ui;
+
+/// Parallelize revisions by making them siblings
+///
+/// The revisions are reparented onto the parents of the roots of the set, and
+/// each of them keeps its own changes. Revisions that also have parents from
+/// outside the set keep those parents. The children of the heads of the set
+/// are rebased onto all of the new siblings, so they keep the same contents.
+/// Other children of the set keep their parent. For example,
+/// `jj parallelize A::B` would transform your history like this:
+///
+/// ```text
+/// C           C
+/// |           |\
+/// B           | \
+/// |    =>     A  B
+/// A           | /
+/// |           |/
+/// O           O
+/// ```
+///
+/// The revisions must be connected, i.e. every revision between two of them
+/// must be part of the set.
+#[derive(clap::Args, Clone, Debug)]
+#[command(verbatim_doc_comment)]
+pub(crate) struct ParallelizeArgs {
+    /// Revisions to parallelize
+    revisions: Vec<RevisionArg>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_parallelize(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &ParallelizeArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let target_commits: IndexSet<Commit> =
+        resolve_multiple_nonempty_revsets(&args.revisions, &workspace_command, ui)?;
+    if target_commits.len() < 2 {
+        writeln!(ui.stderr(), "Nothing changed.")?;
+        return Ok(());
+    }
+    let target_expression = RevsetExpression::commits(
+        target_commits
+            .iter()
+            .map(|commit| commit.id().clone())
+            .collect(),
+    );
+    let num_connected = workspace_command
+        .evaluate_revset(target_expression.connected())?
+        .iter()
+        .count();
+    if num_connected != target_commits.len() {
+        return Err(user_error(
+            "Cannot parallelize since the target revisions are not connected.",
+        ));
+    }
+    let repo = workspace_command.repo();
+    let new_parents: Vec<Commit> = workspace_command
+        .evaluate_revset(target_expression.roots().parents())?
+        .iter()
+        .commits(repo.store())
+        .try_collect()?;
+    let head_ids: HashSet<CommitId> = workspace_command
+        .evaluate_revset(target_expression.heads())?
+        .iter()
+        .collect();
+    let child_commits: Vec<Commit> = workspace_command
+        .evaluate_revset(target_expression.children().minus(&target_expression))?
+        .iter()
+        .commits(repo.store())
+        .try_collect()?;
+    workspace_command.check_rewritable(target_commits.iter().chain(&child_commits))?;
+
+    let mut tx = workspace_command.start_transaction();
+    let base_repo = tx.base_repo().clone();
+    let store = base_repo.store();
+    let target_ids: HashSet<CommitId> = target_commits
+        .iter()
+        .map(|commit| commit.id().clone())
+        .collect();
+    let mut new_target_commits: HashMap<CommitId, Commit> = HashMap::new();
+    let mut new_siblings = vec![];
+    for old_id in base_repo.index().topo_order(&mut target_ids.iter()) {
+        let old_commit = store.get_commit(&old_id)?;
+        let new_commit_parents = new_parents
+            .iter()
+            .cloned()
+            .chain(old_commit.parents().into_iter().filter(|parent| {
+                !target_ids.contains(parent.id()) && !new_parents.contains(parent)
+            }))
+            .collect_vec();
+        let new_commit = rebase_commit(
+            command.settings(),
+            tx.mut_repo(),
+            &old_commit,
+            &new_commit_parents,
+        )?;
+        new_siblings.push(new_commit.clone());
+        new_target_commits.insert(old_id, new_commit);
+    }
+    // The children of the heads depend on all of the new siblings instead.
+    // Other parents are kept, or replaced by their new version if they're in
+    // the set.
+    for child in &child_commits {
+        let mut new_child_parents: IndexSet<Commit> = IndexSet::new();
+        for parent in child.parents() {
+            if head_ids.contains(parent.id()) {
+                new_child_parents.extend(new_siblings.iter().cloned());
+            } else if let Some(new_parent) = new_target_commits.get(parent.id()) {
+                new_child_parents.insert(new_parent.clone());
+            } else {
+                new_child_parents.insert(parent);
+            }
+        }
+        let new_child_parents = new_child_parents.into_iter().collect_vec();
+        rebase_commit(command.settings(), tx.mut_repo(), child, &new_child_parents)?;
+    }
+    let num_rebased = tx.mut_repo().rebase_descendants(command.settings())?;
+    if num_rebased > 0 {
+        writeln!(ui.stderr(), "Rebased {num_rebased} descendant commits")?;
+    }
+    tx.finish(ui, format!("parallelize {} commits", target_commits.len()))?;
+    Ok(())
+}
@@ -14,16 +14,41 @@
 
 //! This file contains the internal implementation of `run`.
 
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+
+use itertools::Itertools;
+use jj_lib::backend::MergedTreeId;
+use jj_lib::commit::Commit;
+use jj_lib::local_working_copy::TreeState;
+use jj_lib::lock::FileLock;
+use jj_lib::object_id::ObjectId;
+use jj_lib::repo::Repo;
+use jj_lib::store::Store;
+use jj_lib::working_copy::SnapshotOptions;
+use tracing::instrument;
+
 use crate::cli_util::{
-    resolve_multiple_nonempty_revsets, user_error, CommandError, CommandHelper, RevisionArg,
+    internal_error_with_message, resolve_multiple_nonempty_revsets, user_error, CommandError,
+    CommandHelper, RevisionArg,
 };
 use crate::ui::Ui;
 
 /// Run a command across a set of revisions.
 ///
+/// Each revision is checked out into a temporary working copy in the `.jj`
+/// directory, where the command is run. Any changes the command makes to the
+/// files are amended into the revision, and descendants are rebased on top of
+/// the result. Revisions for which the command fails are left unchanged.
 ///
-/// All recorded state will be persisted in the `.jj` directory, so occasionally
-/// a `jj run --clean` is needed to clean up disk space.
+/// The temporary working copies are reused between invocations, and ignored
+/// files (such as build outputs) are kept in them, so occasionally a
+/// `jj run --clean` is needed to clean up disk space. Each working copy is
+/// locked while in use, so concurrent invocations use different ones.
 ///
 /// # Example
 ///
@@ -45,22 +70,241 @@ pub struct RunArgs {
     /// How many processes should run in parallel, uses by default all cores.
     #[arg(long, short)]
     jobs: Option<usize>,
+    /// Remove the temporary working copies (including any ignored files in
+    /// them) before running the command. Working copies in use by other
+    /// invocations are left alone.
+    #[arg(long)]
+    clean: bool,
+}
+
+/// The result of running the command in one revision.
+struct RunOutcome {
+    commit: Commit,
+    exit_status: ExitStatus,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    new_tree_id: MergedTreeId,
 }
 
+#[instrument(skip_all)]
 pub fn cmd_run(ui: &mut Ui, command: &CommandHelper, args: &RunArgs) -> Result<(), CommandError> {
-    let workspace_command = command.workspace_helper(ui)?;
-    let _resolved_commits =
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let resolved_commits =
         resolve_multiple_nonempty_revsets(&args.revisions, &workspace_command, ui)?;
+    workspace_command.check_rewritable(resolved_commits.iter())?;
     // Jobs are resolved in this order:
     // 1. Commandline argument iff > 0.
     // 2. the amount of cores available.
     // 3. a single job, if all of the above fails.
-    let _jobs = match args.jobs {
+    let jobs = match args.jobs {
         Some(0) => return Err(user_error("must pass at least one job")),
         Some(jobs) => Some(jobs),
         None => std::thread::available_parallelism().map(|t| t.into()).ok(),
     }
     // Fallback to a single user-visible job.
     .unwrap_or(1usize);
-    Err(user_error("This is a stub, do not use"))
+
+    let run_dir = workspace_command.workspace_root().join(".jj").join("run");
+    std::fs::create_dir_all(&run_dir).map_err(|err| {
+        internal_error_with_message("Failed to set up a temporary working copy", err)
+    })?;
+    // The locks are held until the command has been run in all revisions.
+    let slots = lock_free_slots(&run_dir, jobs.min(resolved_commits.len()));
+    if args.clean {
+        remove_unused_working_copies(&run_dir, &slots).map_err(|err| {
+            internal_error_with_message("Failed to remove the temporary working copies", err)
+        })?;
+    }
+    let store = workspace_command.repo().store().clone();
+    let tree_states: Vec<_> = slots
+        .iter()
+        .map(|(slot, _lock)| load_tree_state(&store, &run_dir.join(slot.to_string())))
+        .try_collect()?;
+    let base_ignores = workspace_command.base_ignores();
+    let max_new_file_size = command.settings().max_new_file_size()?;
+    let snapshot_options = || SnapshotOptions {
+        base_ignores: base_ignores.clone(),
+        fsmonitor_kind: None,
+        progress: None,
+        max_new_file_size,
+    };
+
+    // The revset is in reverse topological order. Start with the ancestors so
+    // incremental tools (like build systems) have less work to do.
+    let queue: Mutex<VecDeque<(usize, Commit)>> =
+        Mutex::new(resolved_commits.into_iter().rev().enumerate().collect());
+    let (sender, receiver) = mpsc::channel();
+    let mut outcomes = vec![];
+    std::thread::scope(|scope| -> Result<(), CommandError> {
+        for mut tree_state in tree_states {
+            let sender = sender.clone();
+            let queue = &queue;
+            let snapshot_options = &snapshot_options;
+            let shell_command = args.shell_command.as_str();
+            scope.spawn(move || loop {
+                let Some((index, commit)) = queue.lock().unwrap().pop_front() else {
+                    break;
+                };
+                let outcome =
+                    run_in_tree_state(&mut tree_state, commit, shell_command, snapshot_options);
+                if sender.send((index, outcome)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+        // Print the outcomes in a deterministic order, as soon as all previous
+        // ones are available.
+        let mut pending = HashMap::new();
+        for (index, outcome) in receiver {
+            pending.insert(index, outcome?);
+            while let Some(outcome) = pending.remove(&outcomes.len()) {
+                write!(ui.stderr(), "Ran command on ")?;
+                workspace_command
+                    .write_commit_summary(ui.stderr_formatter().as_mut(), &outcome.commit)?;
+                writeln!(ui.stderr())?;
+                ui.stdout().write_all(&outcome.stdout)?;
+                ui.stderr().write_all(&outcome.stderr)?;
+                outcomes.push(outcome);
+            }
+        }
+        Ok(())
+    })?;
+
+    let mut tx = workspace_command.start_transaction();
+    let mut num_rewritten = 0;
+    let mut failed_commits = vec![];
+    for outcome in &outcomes {
+        if !outcome.exit_status.success() {
+            failed_commits.push(&outcome.commit);
+        } else if outcome.new_tree_id != *outcome.commit.tree_id() {
+            tx.mut_repo()
+                .rewrite_commit(command.settings(), &outcome.commit)
+                .set_tree_id(outcome.new_tree_id.clone())
+                .write()?;
+            num_rewritten += 1;
+        }
+    }
+    if num_rewritten > 0 {
+        writeln!(ui.stderr(), "Rewrote {num_rewritten} commits")?;
+    }
+    if !failed_commits.is_empty() {
+        writeln!(ui.stderr(), "The command failed on these commits:")?;
+        for commit in &failed_commits {
+            write!(ui.stderr(), "  ")?;
+            tx.write_commit_summary(ui.stderr_formatter().as_mut(), commit)?;
+            writeln!(ui.stderr())?;
+        }
+    }
+    tx.finish(ui, format!("run command '{}'", args.shell_command))?;
+    if failed_commits.is_empty() {
+        Ok(())
+    } else {
+        Err(user_error(format!(
+            "Command failed on {} commits, which were left unchanged",
+            failed_commits.len()
+        )))
+    }
+}
+
+fn slot_lock_path(run_dir: &Path, slot: usize) -> PathBuf {
+    run_dir.join(format!("{slot}.lock"))
+}
+
+/// Locks the first `count` temporary working copies which aren't in use by
+/// another invocation.
+fn lock_free_slots(run_dir: &Path, count: usize) -> Vec<(usize, FileLock)> {
+    (0..)
+        .filter_map(|slot| Some((slot, FileLock::try_lock(slot_lock_path(run_dir, slot))?)))
+        .take(count)
+        .collect()
+}
+
+/// Removes the temporary working copies, except for the ones locked by other
+/// invocations.
+fn remove_unused_working_copies(
+    run_dir: &Path,
+    locked_slots: &[(usize, FileLock)],
+) -> io::Result<()> {
+    for entry in std::fs::read_dir(run_dir)? {
+        let path = entry?.path();
+        let Some(slot) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse().ok())
+        else {
+            continue;
+        };
+        let _lock = if locked_slots.iter().any(|(locked, _)| *locked == slot) {
+            None
+        } else if let Some(lock) = FileLock::try_lock(slot_lock_path(run_dir, slot)) {
+            Some(lock)
+        } else {
+            continue;
+        };
+        std::fs::remove_dir_all(&path)?;
+    }
+    Ok(())
+}
+
+/// Loads the temporary working copy in `dir`, creating it if it doesn't exist
+/// yet.
+fn load_tree_state(store: &Arc<Store>, dir: &Path) -> Result<TreeState, CommandError> {
+    let wc_dir = dir.join("working_copy");
+    let state_dir = dir.join("state");
+    let message = "Failed to set up a temporary working copy";
+    std::fs::create_dir_all(&wc_dir).map_err(|err| internal_error_with_message(message, err))?;
+    std::fs::create_dir_all(&state_dir).map_err(|err| internal_error_with_message(message, err))?;
+    TreeState::load(store.clone(), wc_dir, state_dir)
+        .map_err(|err| internal_error_with_message(message, err))
+}
+
+/// Checks out `commit` in the temporary working copy, runs the command there,
+/// and snapshots the result.
+fn run_in_tree_state(
+    tree_state: &mut TreeState,
+    commit: Commit,
+    shell_command: &str,
+    snapshot_options: impl Fn() -> SnapshotOptions<'static>,
+) -> Result<RunOutcome, CommandError> {
+    // Snapshot first so any leftovers from an interrupted run are discarded by
+    // the checkout below.
+    tree_state.snapshot(snapshot_options())?;
+    tree_state
+        .check_out(&commit.tree()?)
+        .map_err(|err| internal_error_with_message("Failed to check out the commit", err))?;
+    let output = shell_command_for(shell_command)
+        .current_dir(tree_state.working_copy_path())
+        .env("JJ_COMMIT_ID", commit.id().hex())
+        .env("JJ_CHANGE_ID", commit.change_id().hex())
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| user_error(format!("Failed to run '{shell_command}': {err}")))?;
+    tracing::info!(?output.status, commit_id = commit.id().hex(), "Command exited");
+    tree_state.snapshot(snapshot_options())?;
+    tree_state
+        .save()
+        .map_err(|err| internal_error_with_message("Failed to save the working copy state", err))?;
+    let new_tree_id = tree_state.current_tree_id().clone();
+    Ok(RunOutcome {
+        commit,
+        exit_status: output.status,
+        stdout: output.stdout,
+        stderr: output.stderr,
+        new_tree_id,
+    })
+}
+
+#[cfg(unix)]
+pub(crate) fn shell_command_for(shell_command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(shell_command);
+    cmd
+}
+
+#[cfg(windows)]
+pub(crate) fn shell_command_for(shell_command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(shell_command);
+    cmd
 }
@@ -0,0 +1,126 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use indexmap::IndexSet;
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::repo::Repo;
+use jj_lib::settings::UserSettings;
+use jj_lib::signing::SignBehavior;
+use tracing::instrument;
+
+use crate::cli_util::{
+    resolve_multiple_nonempty_revsets, user_error_with_hint, CommandError, CommandHelper,
+    RevisionArg, WorkspaceCommandTransaction,
+};
+use crate::ui::Ui;
+
+/// Cryptographically sign revisions
+///
+/// Rewrites the given revisions to add a signature made with the configured
+/// `signing.backend`, and rebases their descendants. Revisions that are already
+/// signed are signed again.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct SignArgs {
+    /// The revision(s) to sign
+    #[arg(long, short, default_value = "@")]
+    revisions: Vec<RevisionArg>,
+    /// The key to sign with, overriding `signing.key`
+    ///
+    /// The format depends on the signing backend.
+    #[arg(long)]
+    key: Option<String>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_sign(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &SignArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    if !workspace_command.repo().store().signer().can_sign() {
+        return Err(user_error_with_hint(
+            "No signing backend is configured",
+            "Set `signing.backend` to the backend to sign with",
+        ));
+    }
+    let to_sign = resolve_multiple_nonempty_revsets(&args.revisions, &workspace_command, ui)?;
+    workspace_command.check_rewritable(to_sign.iter())?;
+
+    let mut tx = workspace_command.start_transaction();
+    let key = args
+        .key
+        .clone()
+        .or_else(|| command.settings().sign_settings().key);
+    let signed = rewrite_signatures(
+        &mut tx,
+        command.settings(),
+        &to_sign,
+        SignBehavior::Force,
+        key,
+    )?;
+    let num_rebased = tx.mut_repo().rebase_descendants(command.settings())?;
+    writeln!(ui.stderr(), "Signed {} commits:", signed.len())?;
+    for commit in &signed {
+        write!(ui.stderr(), "  ")?;
+        tx.write_commit_summary(ui.stderr_formatter().as_mut(), commit)?;
+        writeln!(ui.stderr())?;
+    }
+    if num_rebased > 0 {
+        writeln!(ui.stderr(), "Rebased {num_rebased} descendant commits")?;
+    }
+    tx.finish(ui, format!("sign {} commits", signed.len()))?;
+    Ok(())
+}
+
+/// Rewrites `commits` in topological order with the given signing behavior,
+/// so that none of them has to be rebased onto the rewritten others
+/// afterwards. Returns the rewritten commits.
+pub(crate) fn rewrite_signatures(
+    tx: &mut WorkspaceCommandTransaction,
+    settings: &UserSettings,
+    commits: &IndexSet<Commit>,
+    behavior: SignBehavior,
+    key: Option<String>,
+) -> Result<Vec<Commit>, CommandError> {
+    let base_repo = tx.base_repo().clone();
+    let store = base_repo.store();
+    let mut_repo = tx.mut_repo();
+    let mut rewritten: HashMap<CommitId, CommitId> = HashMap::new();
+    let mut new_commits = vec![];
+    for old_commit_id in base_repo
+        .index()
+        .topo_order(&mut commits.iter().map(|c| c.id()))
+    {
+        let old_commit = store.get_commit(&old_commit_id)?;
+        let new_parents = old_commit
+            .parent_ids()
+            .iter()
+            .map(|id| rewritten.get(id).unwrap_or(id).clone())
+            .collect();
+        let new_commit = mut_repo
+            .rewrite_commit(settings, &old_commit)
+            .set_parents(new_parents)
+            .set_sign_behavior(behavior)
+            .set_sign_key(key.clone())
+            .write()?;
+        rewritten.insert(old_commit_id, new_commit.id().clone());
+        new_commits.push(new_commit);
+    }
+    Ok(new_commits)
+}
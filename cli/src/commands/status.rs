@@ -58,11 +58,14 @@ pub(crate) fn cmd_status(
             formatter.write_str("The working copy is clean\n")?;
         } else {
             formatter.write_str("Working copy changes:\n")?;
-            diff_util::show_diff_summary(
-                formatter,
+            let tree_diff = diff_util::diff_with_copies(
                 &workspace_command,
-                parent_tree.diff_stream(&tree, &EverythingMatcher),
+                &parent_tree,
+                &tree,
+                &EverythingMatcher,
+                wc_commit.copy_records(),
             )?;
+            diff_util::show_diff_summary(formatter, &workspace_command, tree_diff)?;
         }
 
         let conflicts = wc_commit.tree()?.conflicts().collect_vec();
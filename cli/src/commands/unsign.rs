@@ -0,0 +1,75 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+
+use indexmap::IndexSet;
+use jj_lib::commit::Commit;
+use jj_lib::signing::SignBehavior;
+use tracing::instrument;
+
+use crate::cli_util::{
+    resolve_multiple_nonempty_revsets, CommandError, CommandHelper, RevisionArg,
+};
+use crate::commands::sign::rewrite_signatures;
+use crate::ui::Ui;
+
+/// Drop the cryptographic signatures of revisions
+///
+/// Rewrites the given revisions without their signatures, and rebases their
+/// descendants. Revisions that aren't signed are left unchanged.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct UnsignArgs {
+    /// The revision(s) to unsign
+    #[arg(long, short, default_value = "@")]
+    revisions: Vec<RevisionArg>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_unsign(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &UnsignArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let to_unsign: IndexSet<Commit> =
+        resolve_multiple_nonempty_revsets(&args.revisions, &workspace_command, ui)?
+            .into_iter()
+            .filter(|commit| commit.is_signed())
+            .collect();
+    workspace_command.check_rewritable(to_unsign.iter())?;
+
+    let mut tx = workspace_command.start_transaction();
+    let unsigned = rewrite_signatures(
+        &mut tx,
+        command.settings(),
+        &to_unsign,
+        SignBehavior::Drop,
+        None,
+    )?;
+    let num_rebased = tx.mut_repo().rebase_descendants(command.settings())?;
+    if !unsigned.is_empty() {
+        writeln!(ui.stderr(), "Unsigned {} commits:", unsigned.len())?;
+        for commit in &unsigned {
+            write!(ui.stderr(), "  ")?;
+            tx.write_commit_summary(ui.stderr_formatter().as_mut(), commit)?;
+            writeln!(ui.stderr())?;
+        }
+    }
+    if num_rebased > 0 {
+        writeln!(ui.stderr(), "Rebased {num_rebased} descendant commits")?;
+    }
+    tx.finish(ui, format!("unsign {} commits", unsigned.len()))?;
+    Ok(())
+}
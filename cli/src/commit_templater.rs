@@ -25,6 +25,7 @@ use jj_lib::id_prefix::IdPrefixContext;
 use jj_lib::object_id::ObjectId as _;
 use jj_lib::op_store::{RefTarget, WorkspaceId};
 use jj_lib::repo::Repo;
+use jj_lib::signing::{SigStatus, Verification};
 use jj_lib::{git, rewrite};
 use once_cell::unsync::OnceCell;
 
@@ -36,8 +37,8 @@ use crate::template_parser::{
     self, FunctionCallNode, TemplateAliasesMap, TemplateParseError, TemplateParseResult,
 };
 use crate::templater::{
-    self, IntoTemplate, PlainTextFormattedProperty, Template, TemplateFunction, TemplateProperty,
-    TemplatePropertyFn,
+    self, IntoJsonProperty, IntoTemplate, JsonSerialize, PlainTextFormattedProperty, Template,
+    TemplateFunction, TemplateProperty, TemplatePropertyFn,
 };
 use crate::text_util;
 
@@ -98,6 +99,9 @@ impl<'repo> TemplateLanguage<'repo> for CommitTemplateLanguage<'repo, '_> {
             CommitTemplatePropertyKind::ShortestIdPrefix(property) => {
                 build_shortest_id_prefix_method(self, build_ctx, property, function)
             }
+            CommitTemplatePropertyKind::CryptographicSignature(property) => {
+                build_cryptographic_signature_method(self, build_ctx, property, function)
+            }
         }
     }
 }
@@ -146,6 +150,13 @@ impl<'repo> CommitTemplateLanguage<'repo, '_> {
     ) -> CommitTemplatePropertyKind<'repo> {
         CommitTemplatePropertyKind::ShortestIdPrefix(Box::new(property))
     }
+
+    fn wrap_cryptographic_signature(
+        &self,
+        property: impl TemplateProperty<Commit, Output = CryptographicSignature> + 'repo,
+    ) -> CommitTemplatePropertyKind<'repo> {
+        CommitTemplatePropertyKind::CryptographicSignature(Box::new(property))
+    }
 }
 
 enum CommitTemplatePropertyKind<'repo> {
@@ -156,6 +167,9 @@ enum CommitTemplatePropertyKind<'repo> {
     RefNameList(Box<dyn TemplateProperty<Commit, Output = Vec<RefName>> + 'repo>),
     CommitOrChangeId(Box<dyn TemplateProperty<Commit, Output = CommitOrChangeId> + 'repo>),
     ShortestIdPrefix(Box<dyn TemplateProperty<Commit, Output = ShortestIdPrefix> + 'repo>),
+    CryptographicSignature(
+        Box<dyn TemplateProperty<Commit, Output = CryptographicSignature> + 'repo>,
+    ),
 }
 
 impl<'repo> IntoTemplateProperty<'repo, Commit> for CommitTemplatePropertyKind<'repo> {
@@ -172,6 +186,11 @@ impl<'repo> IntoTemplateProperty<'repo, Commit> for CommitTemplatePropertyKind<'
             }
             CommitTemplatePropertyKind::CommitOrChangeId(_) => None,
             CommitTemplatePropertyKind::ShortestIdPrefix(_) => None,
+            CommitTemplatePropertyKind::CryptographicSignature(property) => {
+                Some(Box::new(TemplateFunction::new(property, |sig| {
+                    !matches!(sig, CryptographicSignature::Unsigned)
+                })))
+            }
         }
     }
 
@@ -207,6 +226,32 @@ impl<'repo> IntoTemplateProperty<'repo, Commit> for CommitTemplatePropertyKind<'
             CommitTemplatePropertyKind::ShortestIdPrefix(property) => {
                 Some(property.into_template())
             }
+            CommitTemplatePropertyKind::CryptographicSignature(property) => {
+                Some(property.into_template())
+            }
+        }
+    }
+
+    fn try_into_json(
+        self,
+    ) -> Option<Box<dyn TemplateProperty<Commit, Output = serde_json::Value> + 'repo>> {
+        match self {
+            CommitTemplatePropertyKind::Core(property) => property.try_into_json(),
+            CommitTemplatePropertyKind::Commit(property) => Some(property.into_json_property()),
+            CommitTemplatePropertyKind::CommitList(property) => Some(property.into_json_property()),
+            CommitTemplatePropertyKind::RefName(property) => Some(property.into_json_property()),
+            CommitTemplatePropertyKind::RefNameList(property) => {
+                Some(property.into_json_property())
+            }
+            CommitTemplatePropertyKind::CommitOrChangeId(property) => {
+                Some(property.into_json_property())
+            }
+            CommitTemplatePropertyKind::ShortestIdPrefix(property) => {
+                Some(property.into_json_property())
+            }
+            CommitTemplatePropertyKind::CryptographicSignature(property) => {
+                Some(property.into_json_property())
+            }
         }
     }
 }
@@ -247,6 +292,9 @@ fn build_commit_keyword<'repo>(
     // maybe we can add an abstraction that takes "Fn(&Commit) -> O" and returns
     // "TemplateProperty<Commit, Output = O>".
     let property = TemplatePropertyFn(|commit: &Commit| commit.clone());
+    if name == "self" {
+        return Ok(language.wrap_commit(property));
+    }
     build_commit_keyword_opt(language, property, name)
         .ok_or_else(|| TemplateParseError::no_such_keyword(name, span))
 }
@@ -378,11 +426,37 @@ fn build_commit_keyword_opt<'repo>(
         "root" => language.wrap_boolean(wrap_fn(property, move |commit| {
             commit.id() == repo.store().root_commit_id()
         })),
+        "signature" => {
+            language.wrap_cryptographic_signature(wrap_fn(property, |commit| {
+                match commit.verification() {
+                    Ok(Some(verification)) => CryptographicSignature::Verified(verification),
+                    Ok(None) => CryptographicSignature::Unsigned,
+                    Err(_) => CryptographicSignature::Invalid,
+                }
+            }))
+        }
         _ => return None,
     };
     Some(property)
 }
 
+impl JsonSerialize for Commit {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "commit_id": CommitOrChangeId::Commit(self.id().clone()).to_json(),
+            "parents": self
+                .parent_ids()
+                .iter()
+                .map(|id| CommitOrChangeId::Commit(id.clone()).to_json())
+                .collect_vec(),
+            "change_id": CommitOrChangeId::Change(self.change_id().clone()).to_json(),
+            "description": self.description(),
+            "author": self.author().to_json(),
+            "committer": self.committer().to_json(),
+        })
+    }
+}
+
 // TODO: return Vec<String>
 fn extract_working_copies(repo: &dyn Repo, commit: &Commit) -> String {
     let wc_commit_ids = repo.view().wc_commit_ids();
@@ -400,24 +474,24 @@ fn extract_working_copies(repo: &dyn Repo, commit: &Commit) -> String {
 
 /// Branch or tag name with metadata.
 #[derive(Clone, Debug, Eq, PartialEq)]
-struct RefName {
+pub struct RefName {
     /// Local name.
-    name: String,
+    pub name: String,
     /// Remote name if this is a remote or Git-tracking ref.
-    remote: Option<String>,
+    pub remote: Option<String>,
     /// Ref target has conflicts.
-    conflict: bool,
+    pub conflict: bool,
     /// Local ref is synchronized with all tracking remotes, or tracking remote
     /// ref is synchronized with the local.
-    synced: bool,
+    pub synced: bool,
 }
 
 impl RefName {
-    fn is_local(&self) -> bool {
+    pub fn is_local(&self) -> bool {
         self.remote.is_none()
     }
 
-    fn is_remote(&self) -> bool {
+    pub fn is_remote(&self) -> bool {
         self.remote.is_some()
     }
 }
@@ -440,6 +514,17 @@ impl Template<()> for RefName {
     }
 }
 
+impl JsonSerialize for RefName {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "remote": self.remote,
+            "conflict": self.conflict,
+            "synced": self.synced,
+        })
+    }
+}
+
 impl Template<()> for Vec<RefName> {
     fn format(&self, _: &(), formatter: &mut dyn Formatter) -> io::Result<()> {
         templater::format_joined(&(), formatter, self, " ")
@@ -604,6 +689,12 @@ impl Template<()> for CommitOrChangeId {
     }
 }
 
+impl JsonSerialize for CommitOrChangeId {
+    fn to_json(&self) -> serde_json::Value {
+        self.hex().into()
+    }
+}
+
 fn build_commit_or_change_id_method<'repo>(
     language: &CommitTemplateLanguage<'repo, '_>,
     build_ctx: &BuildContext<CommitTemplatePropertyKind<'repo>>,
@@ -660,6 +751,15 @@ impl Template<()> for ShortestIdPrefix {
     }
 }
 
+impl JsonSerialize for ShortestIdPrefix {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "prefix": self.prefix,
+            "rest": self.rest,
+        })
+    }
+}
+
 impl ShortestIdPrefix {
     fn to_upper(&self) -> Self {
         Self {
@@ -710,6 +810,111 @@ fn build_shortest_id_prefix_method<'repo>(
     Ok(property)
 }
 
+/// Cryptographic signature of a commit and the result of its verification.
+#[derive(Clone, Debug)]
+enum CryptographicSignature {
+    Unsigned,
+    /// The signature couldn't be verified, e.g. because no backend can read it
+    /// or the backend failed.
+    Invalid,
+    Verified(Verification),
+}
+
+impl CryptographicSignature {
+    fn status(&self) -> &'static str {
+        match self {
+            CryptographicSignature::Unsigned => "",
+            CryptographicSignature::Invalid => "invalid",
+            CryptographicSignature::Verified(verification) => match verification.status {
+                SigStatus::Good => "good",
+                SigStatus::Unknown => "unknown",
+                SigStatus::Bad => "bad",
+            },
+        }
+    }
+
+    fn key(&self) -> String {
+        match self {
+            CryptographicSignature::Verified(verification) => {
+                verification.key.clone().unwrap_or_default()
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn display(&self) -> String {
+        match self {
+            CryptographicSignature::Verified(verification) => {
+                verification.display.clone().unwrap_or_default()
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+impl Template<()> for CryptographicSignature {
+    fn format(&self, _: &(), formatter: &mut dyn Formatter) -> io::Result<()> {
+        if let CryptographicSignature::Unsigned = self {
+            return Ok(());
+        }
+        let status = self.status();
+        write!(formatter.labeled(status), "{status} signature")?;
+        let display = self.display();
+        if !display.is_empty() {
+            write!(formatter, " by {display}")?;
+        }
+        let key = self.key();
+        if !key.is_empty() {
+            write!(formatter, " (key {key})")?;
+        }
+        Ok(())
+    }
+}
+
+impl JsonSerialize for CryptographicSignature {
+    fn to_json(&self) -> serde_json::Value {
+        if let CryptographicSignature::Unsigned = self {
+            return serde_json::Value::Null;
+        }
+        serde_json::json!({
+            "status": self.status(),
+            "key": self.key(),
+            "display": self.display(),
+        })
+    }
+}
+
+fn build_cryptographic_signature_method<'repo>(
+    language: &CommitTemplateLanguage<'repo, '_>,
+    _build_ctx: &BuildContext<CommitTemplatePropertyKind<'repo>>,
+    self_property: impl TemplateProperty<Commit, Output = CryptographicSignature> + 'repo,
+    function: &FunctionCallNode,
+) -> TemplateParseResult<CommitTemplatePropertyKind<'repo>> {
+    let property = match function.name {
+        "status" => {
+            template_parser::expect_no_arguments(function)?;
+            language.wrap_string(TemplateFunction::new(self_property, |sig| {
+                sig.status().to_owned()
+            }))
+        }
+        "key" => {
+            template_parser::expect_no_arguments(function)?;
+            language.wrap_string(TemplateFunction::new(self_property, |sig| sig.key()))
+        }
+        "display" => {
+            template_parser::expect_no_arguments(function)?;
+            language.wrap_string(TemplateFunction::new(self_property, |sig| sig.display()))
+        }
+        _ => {
+            return Err(TemplateParseError::no_such_method(
+                "CryptographicSignature",
+                function,
+            ))
+        }
+    };
+    Ok(property)
+}
+
 pub fn parse<'repo>(
     repo: &'repo dyn Repo,
     workspace_id: &WorkspaceId,
@@ -109,6 +109,7 @@ pub fn description_template_for_commit(
         from_tree,
         to_tree,
         &EverythingMatcher,
+        &[],
         &[DiffFormat::Summary],
     )?;
     let mut template_chunks = Vec::new();
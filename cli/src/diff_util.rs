@@ -13,20 +13,22 @@
 // limitations under the License.
 
 use std::cmp::max;
-use std::collections::VecDeque;
-use std::io;
+use std::collections::{HashSet, VecDeque};
 use std::ops::Range;
+use std::{io, mem};
 
 use futures::{try_join, Stream, StreamExt};
 use itertools::Itertools;
-use jj_lib::backend::{BackendResult, TreeValue};
+use jj_lib::backend::{BackendError, BackendResult, CopyRecord, TreeValue};
 use jj_lib::commit::Commit;
-use jj_lib::conflicts::{materialize_tree_value, MaterializedTreeValue};
+use jj_lib::conflicts::{materialize_tree_value, MaterializedTreeValue, MergeDriver};
+use jj_lib::copies::{self, CopiesTreeDiffEntry, CopyDetectionOptions, CopyOperation, CopySource};
 use jj_lib::diff::{Diff, DiffHunk};
 use jj_lib::files::DiffLine;
+use jj_lib::gitattributes::{GitAttributeValue, TreeGitAttributes};
 use jj_lib::matchers::Matcher;
 use jj_lib::merge::MergedTreeValue;
-use jj_lib::merged_tree::{MergedTree, TreeDiffStream};
+use jj_lib::merged_tree::MergedTree;
 use jj_lib::object_id::ObjectId;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::{RepoPath, RepoPathBuf};
@@ -39,14 +41,14 @@ use unicode_width::UnicodeWidthStr as _;
 
 use crate::cli_util::{CommandError, WorkspaceCommandHelper};
 use crate::formatter::Formatter;
-use crate::merge_tools::{self, ExternalMergeTool, MergeTool};
+use crate::merge_tools::{self, DiffToolMode, ExternalMergeTool, MergeTool};
 use crate::text_util;
 use crate::ui::Ui;
 
 #[derive(clap::Args, Clone, Debug)]
 #[command(next_help_heading = "Diff Formatting Options")]
 #[command(group(clap::ArgGroup::new("short-format").args(&["summary", "stat", "types"])))]
-#[command(group(clap::ArgGroup::new("long-format").args(&["git", "color_words", "tool"])))]
+#[command(group(clap::ArgGroup::new("long-format").args(&["git", "color_words", "side_by_side", "tool"])))]
 pub struct DiffFormatArgs {
     /// For each path, show only whether it was modified, added, or deleted
     #[arg(long, short)]
@@ -69,6 +71,9 @@ pub struct DiffFormatArgs {
     /// Show a word-level diff with changes indicated only by color
     #[arg(long)]
     pub color_words: bool,
+    /// Show the old and new contents side by side
+    #[arg(long)]
+    pub side_by_side: bool,
     /// Generate diff by external command
     #[arg(long)]
     pub tool: Option<String>,
@@ -81,6 +86,7 @@ pub enum DiffFormat {
     Types,
     Git,
     ColorWords,
+    SideBySide,
     Tool(Box<ExternalMergeTool>),
 }
 
@@ -122,6 +128,7 @@ fn diff_formats_from_args(
         (args.types, DiffFormat::Types),
         (args.git, DiffFormat::Git),
         (args.color_words, DiffFormat::ColorWords),
+        (args.side_by_side, DiffFormat::SideBySide),
         (args.stat, DiffFormat::Stat),
     ]
     .into_iter()
@@ -165,6 +172,7 @@ fn default_diff_format(settings: &UserSettings) -> Result<DiffFormat, config::Co
         "types" => Ok(DiffFormat::Types),
         "git" => Ok(DiffFormat::Git),
         "color-words" => Ok(DiffFormat::ColorWords),
+        "side-by-side" => Ok(DiffFormat::SideBySide),
         "stat" => Ok(DiffFormat::Stat),
         _ => Err(config::ConfigError::Message(format!(
             "invalid diff format: {name}"
@@ -172,6 +180,7 @@ fn default_diff_format(settings: &UserSettings) -> Result<DiffFormat, config::Co
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn show_diff(
     ui: &Ui,
     formatter: &mut dyn Formatter,
@@ -179,38 +188,132 @@ pub fn show_diff(
     from_tree: &MergedTree,
     to_tree: &MergedTree,
     matcher: &dyn Matcher,
+    copy_records: &[CopyRecord],
     formats: &[DiffFormat],
 ) -> Result<(), CommandError> {
+    // Copy detection may compare the contents of many files, so the diff is
+    // computed at most once and shared by all formats.
+    let mut diff_entries = None;
+    let mut tree_diff = || -> Result<Vec<CopiesTreeDiffEntry>, CommandError> {
+        if diff_entries.is_none() {
+            let entries: Vec<_> =
+                diff_with_copies(workspace_command, from_tree, to_tree, matcher, copy_records)?
+                    .into_iter()
+                    .map(|entry| {
+                        let values = entry.values?;
+                        Ok::<_, BackendError>((entry.target, entry.source, values))
+                    })
+                    .try_collect()?;
+            diff_entries = Some(entries);
+        }
+        let entries = diff_entries.iter().flatten();
+        Ok(entries
+            .map(|(target, source, values)| CopiesTreeDiffEntry {
+                target: target.clone(),
+                source: source.clone(),
+                values: Ok(values.clone()),
+            })
+            .collect())
+    };
+    let git_attributes = DiffGitAttributes::new(from_tree, to_tree);
     for format in formats {
         match format {
             DiffFormat::Summary => {
-                let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                show_diff_summary(formatter, workspace_command, tree_diff)?;
+                show_diff_summary(formatter, workspace_command, tree_diff()?)?;
             }
             DiffFormat::Stat => {
-                let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                show_diff_stat(ui, formatter, workspace_command, tree_diff)?;
+                show_diff_stat(
+                    ui,
+                    formatter,
+                    workspace_command,
+                    &git_attributes,
+                    tree_diff()?,
+                )?;
             }
             DiffFormat::Types => {
-                let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                show_types(formatter, workspace_command, tree_diff)?;
+                show_types(formatter, workspace_command, tree_diff()?)?;
             }
             DiffFormat::Git => {
-                let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                show_git_diff(formatter, workspace_command, tree_diff)?;
+                show_git_diff(formatter, workspace_command, &git_attributes, tree_diff()?)?;
             }
             DiffFormat::ColorWords => {
-                let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                show_color_words_diff(formatter, workspace_command, tree_diff)?;
+                show_color_words_diff(formatter, workspace_command, &git_attributes, tree_diff()?)?;
             }
-            DiffFormat::Tool(tool) => {
-                merge_tools::generate_diff(ui, formatter.raw(), from_tree, to_tree, matcher, tool)?;
+            DiffFormat::SideBySide => {
+                show_side_by_side_diff(
+                    ui,
+                    formatter,
+                    workspace_command,
+                    &git_attributes,
+                    tree_diff()?,
+                )?;
             }
+            DiffFormat::Tool(tool) => match tool.diff_invocation_mode {
+                DiffToolMode::Dir => {
+                    let writer = formatter.raw();
+                    merge_tools::generate_diff(ui, writer, from_tree, to_tree, matcher, tool)?;
+                }
+                DiffToolMode::FileByFile => {
+                    show_file_by_file_tool_diff(
+                        ui,
+                        formatter,
+                        workspace_command,
+                        &git_attributes,
+                        tree_diff()?,
+                        tool,
+                    )?;
+                }
+            },
         }
     }
     Ok(())
 }
 
+/// Returns the rename detection options configured by `ui.diff.renames`.
+pub fn copy_detection_options(
+    settings: &UserSettings,
+) -> Result<CopyDetectionOptions, config::ConfigError> {
+    let config = settings.config();
+    let detect_renames = config.get_bool("ui.diff.renames").optional()?;
+    Ok(CopyDetectionOptions {
+        detect_renames: detect_renames.unwrap_or(true),
+        ..Default::default()
+    })
+}
+
+/// Computes the diff between the trees, pairing up copied and renamed files.
+///
+/// Renames are detected unless disabled by the `ui.diff.renames` setting. The
+/// `copy_records` are those recorded in the commit whose tree is `to_tree`.
+pub fn diff_with_copies(
+    workspace_command: &WorkspaceCommandHelper,
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    matcher: &dyn Matcher,
+    copy_records: &[CopyRecord],
+) -> Result<Vec<CopiesTreeDiffEntry>, CommandError> {
+    let options = copy_detection_options(workspace_command.settings())?;
+    let entries = copies::diff_with_copies(from_tree, to_tree, matcher, copy_records, &options)?;
+    Ok(entries)
+}
+
+/// Formats the path of a diff entry, which is `source => target` if the file
+/// was copied or renamed.
+fn format_entry_path(
+    workspace_command: &WorkspaceCommandHelper,
+    target: &RepoPath,
+    source: Option<&CopySource>,
+) -> String {
+    let ui_target = workspace_command.format_file_path(target);
+    match source {
+        Some(source) => {
+            let ui_source = workspace_command.format_file_path(&source.path);
+            format!("{ui_source} => {ui_target}")
+        }
+        None => ui_target,
+    }
+}
+
 pub fn show_patch(
     ui: &Ui,
     formatter: &mut dyn Formatter,
@@ -229,16 +332,40 @@ pub fn show_patch(
         &from_tree,
         &to_tree,
         matcher,
+        commit.copy_records(),
         formats,
     )
 }
 
+const SKIPPED_CONTEXT_LINE: &str = "    ...\n";
+
 fn show_color_words_diff_hunks(
     left: &[u8],
     right: &[u8],
     formatter: &mut dyn Formatter,
 ) -> io::Result<()> {
-    const SKIPPED_CONTEXT_LINE: &str = "    ...\n";
+    let skipped_context =
+        show_diff_lines_with_context(left, right, formatter, show_color_words_diff_line)?;
+
+    // If the last diff line doesn't end with newline, add it.
+    let no_hunk = left.is_empty() && right.is_empty();
+    let any_last_newline = left.ends_with(b"\n") || right.ends_with(b"\n");
+    if !skipped_context && !no_hunk && !any_last_newline {
+        formatter.write_str("\n")?;
+    }
+
+    Ok(())
+}
+
+/// Shows the changed lines and up to 3 lines of context around them, writing
+/// each line with `show_line`. Returns true if the trailing context was
+/// skipped.
+fn show_diff_lines_with_context(
+    left: &[u8],
+    right: &[u8],
+    formatter: &mut dyn Formatter,
+    mut show_line: impl FnMut(&mut dyn Formatter, &DiffLine) -> io::Result<()>,
+) -> io::Result<bool> {
     let num_context_lines = 3;
     let mut context = VecDeque::new();
     // Have we printed "..." for any skipped context?
@@ -257,7 +384,7 @@ fn show_color_words_diff_hunks(
                 }
             } else if context.len() > num_context_lines * 2 + 1 {
                 for line in context.drain(..num_context_lines) {
-                    show_color_words_diff_line(formatter, &line)?;
+                    show_line(formatter, &line)?;
                 }
                 start_skipping_context = true;
             }
@@ -269,10 +396,10 @@ fn show_color_words_diff_hunks(
             }
         } else {
             for line in &context {
-                show_color_words_diff_line(formatter, line)?;
+                show_line(formatter, line)?;
             }
             context.clear();
-            show_color_words_diff_line(formatter, &diff_line)?;
+            show_line(formatter, &diff_line)?;
             context_before = false;
             skipped_context = false;
         }
@@ -284,21 +411,13 @@ fn show_color_words_diff_hunks(
             context_before = true;
         }
         for line in &context {
-            show_color_words_diff_line(formatter, line)?;
+            show_line(formatter, line)?;
         }
         if context_before {
             formatter.write_str(SKIPPED_CONTEXT_LINE)?;
         }
     }
-
-    // If the last diff line doesn't end with newline, add it.
-    let no_hunk = left.is_empty() && right.is_empty();
-    let any_last_newline = left.ends_with(b"\n") || right.ends_with(b"\n");
-    if !skipped_context && !no_hunk && !any_last_newline {
-        formatter.write_str("\n")?;
-    }
-
-    Ok(())
+    Ok(skipped_context)
 }
 
 fn show_color_words_diff_line(
@@ -346,6 +465,143 @@ fn show_color_words_diff_line(
     Ok(())
 }
 
+/// Width of a line number column, including the space after the number.
+const SIDE_BY_SIDE_LINE_NUMBER_WIDTH: usize = 5;
+/// Separator between the left and right sides.
+const SIDE_BY_SIDE_SEPARATOR: &str = " │ ";
+/// The content of each side is never narrower than this, even if the terminal
+/// is. It must be wide enough to fit any character.
+const MIN_SIDE_BY_SIDE_CONTENT_WIDTH: usize = 10;
+
+type SideBySideRow = Vec<(DiffTokenType, String)>;
+
+fn show_side_by_side_diff_hunks(
+    left: &[u8],
+    right: &[u8],
+    width: usize,
+    formatter: &mut dyn Formatter,
+) -> io::Result<()> {
+    let content_width = max(
+        width.saturating_sub(2 * SIDE_BY_SIDE_LINE_NUMBER_WIDTH + SIDE_BY_SIDE_SEPARATOR.width())
+            / 2,
+        MIN_SIDE_BY_SIDE_CONTENT_WIDTH,
+    );
+    show_diff_lines_with_context(left, right, formatter, |formatter, diff_line| {
+        show_side_by_side_diff_line(formatter, diff_line, content_width)
+    })?;
+    Ok(())
+}
+
+fn show_side_by_side_diff_line(
+    formatter: &mut dyn Formatter,
+    diff_line: &DiffLine,
+    content_width: usize,
+) -> io::Result<()> {
+    let mut left_tokens = vec![];
+    let mut right_tokens = vec![];
+    for hunk in &diff_line.hunks {
+        match hunk {
+            DiffHunk::Matching(data) => {
+                left_tokens.push((DiffTokenType::Matching, *data));
+                right_tokens.push((DiffTokenType::Matching, *data));
+            }
+            DiffHunk::Different(data) => {
+                left_tokens.push((DiffTokenType::Different, data[0]));
+                right_tokens.push((DiffTokenType::Different, data[1]));
+            }
+        }
+    }
+    let left_rows = wrap_side_by_side_tokens(&left_tokens, content_width);
+    let right_rows = wrap_side_by_side_tokens(&right_tokens, content_width);
+    let num_rows = max(left_rows.len(), right_rows.len());
+    let no_row = (vec![], 0);
+    for i in 0..num_rows {
+        let (left_row, left_width) = left_rows.get(i).unwrap_or(&no_row);
+        let (right_row, _) = right_rows.get(i).unwrap_or(&no_row);
+        if i == 0 && diff_line.has_left_content {
+            write!(
+                formatter.labeled("removed"),
+                "{:>4}",
+                diff_line.left_line_number
+            )?;
+            formatter.write_str(" ")?;
+        } else {
+            formatter.write_str("     ")?;
+        }
+        show_side_by_side_row(formatter, left_row, "removed")?;
+        write!(formatter, "{:1$}", "", content_width - left_width)?;
+        // Don't leave trailing whitespace if the right side is empty
+        if i == 0 && diff_line.has_right_content {
+            formatter.write_str(SIDE_BY_SIDE_SEPARATOR)?;
+            write!(
+                formatter.labeled("added"),
+                "{:>4}",
+                diff_line.right_line_number
+            )?;
+        } else if !right_row.is_empty() {
+            formatter.write_str(SIDE_BY_SIDE_SEPARATOR)?;
+            formatter.write_str("    ")?;
+        } else {
+            formatter.write_str(SIDE_BY_SIDE_SEPARATOR.trim_end())?;
+        }
+        if !right_row.is_empty() {
+            formatter.write_str(" ")?;
+            show_side_by_side_row(formatter, right_row, "added")?;
+        }
+        formatter.write_str("\n")?;
+    }
+    Ok(())
+}
+
+/// Splits the tokens of one side of a line into rows that fit in `width`
+/// columns, returning each row with its width. There is always at least one
+/// row.
+///
+/// Tabs are expanded and the trailing newline is dropped.
+fn wrap_side_by_side_tokens(
+    tokens: &[(DiffTokenType, &[u8])],
+    width: usize,
+) -> Vec<(SideBySideRow, usize)> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut row_width = 0;
+    for (token_type, data) in tokens {
+        let data = data.strip_suffix(b"\n").unwrap_or(data);
+        let text = String::from_utf8_lossy(data).replace('\t', "    ");
+        let mut rest = text.as_str();
+        while !rest.is_empty() {
+            let (head, tail, head_width) = text_util::split_at_width(rest, width - row_width);
+            if !head.is_empty() {
+                row.push((*token_type, head.to_owned()));
+                row_width += head_width;
+            }
+            rest = tail;
+            if !rest.is_empty() {
+                rows.push((mem::take(&mut row), row_width));
+                row_width = 0;
+            }
+        }
+    }
+    rows.push((row, row_width));
+    rows
+}
+
+fn show_side_by_side_row(
+    formatter: &mut dyn Formatter,
+    row: &[(DiffTokenType, String)],
+    label: &str,
+) -> io::Result<()> {
+    for (token_type, text) in row {
+        match token_type {
+            DiffTokenType::Matching => formatter.write_str(text)?,
+            DiffTokenType::Different => {
+                formatter.with_label(label, |formatter| formatter.write_str(text))?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn diff_content(path: &RepoPath, value: MaterializedTreeValue) -> Result<Vec<u8>, CommandError> {
     match value {
         MaterializedTreeValue::Absent => Ok(vec![]),
@@ -387,15 +643,78 @@ fn basic_diff_file_type(value: &MaterializedTreeValue) -> &'static str {
 pub fn show_color_words_diff(
     formatter: &mut dyn Formatter,
     workspace_command: &WorkspaceCommandHelper,
-    tree_diff: TreeDiffStream,
+    git_attributes: &DiffGitAttributes,
+    tree_diff: Vec<CopiesTreeDiffEntry>,
+) -> Result<(), CommandError> {
+    show_file_by_file_diff(
+        formatter,
+        workspace_command,
+        git_attributes,
+        tree_diff,
+        show_color_words_diff_hunks,
+    )
+}
+
+pub fn show_side_by_side_diff(
+    ui: &Ui,
+    formatter: &mut dyn Formatter,
+    workspace_command: &WorkspaceCommandHelper,
+    git_attributes: &DiffGitAttributes,
+    tree_diff: Vec<CopiesTreeDiffEntry>,
+) -> Result<(), CommandError> {
+    // 4 characters padding for the graph
+    let width = usize::from(ui.term_width().unwrap_or(80)).saturating_sub(4);
+    show_file_by_file_diff(
+        formatter,
+        workspace_command,
+        git_attributes,
+        tree_diff,
+        |left, right, formatter| show_side_by_side_diff_hunks(left, right, width, formatter),
+    )
+}
+
+/// Shows a header describing each changed file, followed by its contents
+/// rendered by `show_hunks`. The contents of binary files aren't shown.
+fn show_file_by_file_diff(
+    formatter: &mut dyn Formatter,
+    workspace_command: &WorkspaceCommandHelper,
+    git_attributes: &DiffGitAttributes,
+    tree_diff: Vec<CopiesTreeDiffEntry>,
+    show_hunks: impl Fn(&[u8], &[u8], &mut dyn Formatter) -> io::Result<()>,
 ) -> Result<(), CommandError> {
     formatter.push_label("diff")?;
-    let mut diff_stream = materialized_diff_stream(workspace_command.repo().store(), tree_diff);
+    let mut diff_stream =
+        materialized_diff_stream(workspace_command.repo().store(), git_attributes, tree_diff)?;
     async {
-        while let Some((path, diff)) = diff_stream.next().await {
+        while let Some((path, copy_source, diff)) = diff_stream.next().await {
             let ui_path = workspace_command.format_file_path(&path);
             let (left_value, right_value) = diff?;
-            if left_value.is_absent() {
+            let source_path = copy_source
+                .as_ref()
+                .map_or(path.as_ref(), |source| source.path.as_ref());
+            let binary = git_attributes.is_binary(source_path, &path)?;
+            let show_hunks = |left: &[u8], right: &[u8], formatter: &mut dyn Formatter| {
+                if binary {
+                    writeln!(formatter.labeled("binary"), "    (binary)")
+                } else {
+                    show_hunks(left, right, formatter)
+                }
+            };
+            if let Some(source) = &copy_source {
+                let operation = match source.operation {
+                    CopyOperation::Copy => "Copied",
+                    CopyOperation::Rename => "Renamed",
+                };
+                let description = basic_diff_file_type(&right_value);
+                let ui_source = workspace_command.format_file_path(&source.path);
+                writeln!(
+                    formatter.labeled("header"),
+                    "{operation} {description} {ui_source} => {ui_path}:"
+                )?;
+                let left_content = diff_content(&source.path, left_value)?;
+                let right_content = diff_content(&path, right_value)?;
+                show_hunks(&left_content, &right_content, formatter)?;
+            } else if left_value.is_absent() {
                 let description = basic_diff_file_type(&right_value);
                 writeln!(
                     formatter.labeled("header"),
@@ -405,7 +724,7 @@ pub fn show_color_words_diff(
                 if right_content.is_empty() {
                     writeln!(formatter.labeled("empty"), "    (empty)")?;
                 } else {
-                    show_color_words_diff_hunks(&[], &right_content, formatter)?;
+                    show_hunks(&[], &right_content, formatter)?;
                 }
             } else if right_value.is_present() {
                 let description = match (&left_value, &right_value) {
@@ -458,7 +777,7 @@ pub fn show_color_words_diff(
                 let left_content = diff_content(&path, left_value)?;
                 let right_content = diff_content(&path, right_value)?;
                 writeln!(formatter.labeled("header"), "{description} {ui_path}:")?;
-                show_color_words_diff_hunks(&left_content, &right_content, formatter)?;
+                show_hunks(&left_content, &right_content, formatter)?;
             } else {
                 let description = basic_diff_file_type(&left_value);
                 writeln!(
@@ -469,7 +788,7 @@ pub fn show_color_words_diff(
                 if left_content.is_empty() {
                     writeln!(formatter.labeled("empty"), "    (empty)")?;
                 } else {
-                    show_color_words_diff_hunks(&left_content, &[], formatter)?;
+                    show_hunks(&left_content, &[], formatter)?;
                 }
             }
         }
@@ -480,6 +799,62 @@ pub fn show_color_words_diff(
     Ok(())
 }
 
+/// Generates the diff of each file by invoking the external `tool` once per
+/// file, in path order.
+pub fn show_file_by_file_tool_diff(
+    ui: &Ui,
+    formatter: &mut dyn Formatter,
+    workspace_command: &WorkspaceCommandHelper,
+    git_attributes: &DiffGitAttributes,
+    tree_diff: Vec<CopiesTreeDiffEntry>,
+    tool: &ExternalMergeTool,
+) -> Result<(), CommandError> {
+    let mut diff_stream =
+        materialized_diff_stream(workspace_command.repo().store(), git_attributes, tree_diff)?;
+    // Diff tools conventionally exit with 1 if the inputs differ, which is
+    // expected here. Other failures are reported once, not for every file.
+    let mut failed_status = None;
+    async {
+        while let Some((path, copy_source, diff)) = diff_stream.next().await {
+            let (left_value, right_value) = diff?;
+            let source_path = copy_source
+                .as_ref()
+                .map_or(path.as_ref(), |source| source.path.as_ref());
+            let ui_path = workspace_command.format_file_path(&path);
+            if git_attributes.is_binary(source_path, &path)? {
+                // The tool isn't invoked for files whose textual diff is
+                // disabled, as in the builtin formats.
+                let ui_source = workspace_command.format_file_path(source_path);
+                writeln!(formatter, "Binary files {ui_source} and {ui_path} differ")?;
+                continue;
+            }
+            let left_content = diff_content(source_path, left_value)?;
+            let right_content = diff_content(&path, right_value)?;
+            let exit_status = merge_tools::generate_file_diff(
+                ui,
+                formatter.raw(),
+                &ui_path,
+                (source_path, &left_content),
+                (&path, &right_content),
+                tool,
+            )?;
+            if !exit_status.success() && exit_status.code() != Some(1) {
+                failed_status.get_or_insert(exit_status);
+            }
+        }
+        Ok::<(), CommandError>(())
+    }
+    .block_on()?;
+    if let Some(exit_status) = failed_status {
+        writeln!(
+            ui.warning(),
+            "{}",
+            merge_tools::format_tool_aborted(&exit_status)
+        )?;
+    }
+    Ok(())
+}
+
 struct GitDiffPart {
     mode: String,
     hash: String,
@@ -542,23 +917,147 @@ fn git_diff_part(
     })
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum DiffLineType {
     Context,
     Removed,
     Added,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DiffTokenType {
+    Matching,
+    Different,
+}
+
+type DiffTokenVec<'content> = Vec<(DiffTokenType, &'content [u8])>;
+
+struct UnifiedDiffLine<'content> {
+    line_type: DiffLineType,
+    /// Whether the line is part of a block that was moved elsewhere in the
+    /// diff.
+    moved: bool,
+    tokens: DiffTokenVec<'content>,
+}
+
 struct UnifiedDiffHunk<'content> {
     left_line_range: Range<usize>,
     right_line_range: Range<usize>,
-    lines: Vec<(DiffLineType, &'content [u8])>,
+    lines: Vec<UnifiedDiffLine<'content>>,
+}
+
+impl<'content> UnifiedDiffHunk<'content> {
+    fn push_line(&mut self, line_type: DiffLineType, tokens: DiffTokenVec<'content>) {
+        self.lines.push(UnifiedDiffLine {
+            line_type,
+            moved: false,
+            tokens,
+        });
+    }
+}
+
+/// Options for rendering the hunks of the Git diff format.
+#[derive(Clone, Debug, Default)]
+struct UnifiedDiffOptions {
+    /// Whether to highlight the changed words within modified lines.
+    highlight_words: bool,
+    /// Whether to highlight blocks of lines that were moved within the diff.
+    color_moved: bool,
+}
+
+impl UnifiedDiffOptions {
+    fn from_settings(settings: &UserSettings) -> Result<Self, config::ConfigError> {
+        let config = settings.config();
+        Ok(UnifiedDiffOptions {
+            highlight_words: config
+                .get_bool("ui.diff.highlight-words")
+                .optional()?
+                .unwrap_or(false),
+            color_moved: config
+                .get_bool("ui.diff.color-moved")
+                .optional()?
+                .unwrap_or(false),
+        })
+    }
+}
+
+/// Blocks of moved lines with fewer alphanumeric characters than this aren't
+/// highlighted, so that lines like `}` aren't considered moved.
+const MIN_MOVED_BLOCK_ALNUM_CHARS: usize = 20;
+
+fn split_lines(content: &[u8]) -> Vec<&[u8]> {
+    diff::find_line_ranges(content)
+        .into_iter()
+        .map(|range| &content[range])
+        .collect()
+}
+
+/// Splits the tokens into lines. A token spanning multiple lines is split at
+/// the newlines.
+fn split_tokens_into_lines(tokens: DiffTokenVec) -> Vec<DiffTokenVec> {
+    let mut lines = vec![];
+    let mut current_line = vec![];
+    for (token_type, content) in tokens {
+        for part in split_lines(content) {
+            current_line.push((token_type, part));
+            if part.ends_with(b"\n") {
+                lines.push(mem::take(&mut current_line));
+            }
+        }
+    }
+    if !current_line.is_empty() {
+        lines.push(current_line);
+    }
+    lines
+}
+
+/// Returns the lines of the left and right sides of a changed region, with the
+/// changed words marked as different if `highlight_words` is enabled.
+fn changed_region_lines<'content>(
+    left_content: &'content [u8],
+    right_content: &'content [u8],
+    highlight_words: bool,
+) -> (Vec<DiffTokenVec<'content>>, Vec<DiffTokenVec<'content>>) {
+    if !highlight_words || left_content.is_empty() || right_content.is_empty() {
+        let to_lines = |content| {
+            split_lines(content)
+                .into_iter()
+                .map(|line| vec![(DiffTokenType::Matching, line)])
+                .collect_vec()
+        };
+        return (to_lines(left_content), to_lines(right_content));
+    }
+    let mut diff = Diff::for_tokenizer(&[left_content, right_content], &diff::find_word_ranges);
+    diff.refine_changed_regions(&diff::find_nonword_ranges);
+    let mut left_tokens = vec![];
+    let mut right_tokens = vec![];
+    for hunk in diff.hunks() {
+        match hunk {
+            DiffHunk::Matching(content) => {
+                left_tokens.push((DiffTokenType::Matching, content));
+                right_tokens.push((DiffTokenType::Matching, content));
+            }
+            DiffHunk::Different(contents) => {
+                if !contents[0].is_empty() {
+                    left_tokens.push((DiffTokenType::Different, contents[0]));
+                }
+                if !contents[1].is_empty() {
+                    right_tokens.push((DiffTokenType::Different, contents[1]));
+                }
+            }
+        }
+    }
+    (
+        split_tokens_into_lines(left_tokens),
+        split_tokens_into_lines(right_tokens),
+    )
 }
 
 fn unified_diff_hunks<'content>(
     left_content: &'content [u8],
     right_content: &'content [u8],
     num_context_lines: usize,
+    options: &UnifiedDiffOptions,
 ) -> Vec<UnifiedDiffHunk<'content>> {
     let mut hunks = vec![];
     let mut current_hunk = UnifiedDiffHunk {
@@ -581,7 +1080,10 @@ fn unified_diff_hunks<'content>(
                 current_hunk.left_line_range.end += num_after_lines;
                 current_hunk.right_line_range.end += num_after_lines;
                 for line in lines.iter().take(num_after_lines) {
-                    current_hunk.lines.push((DiffLineType::Context, line));
+                    current_hunk.push_line(
+                        DiffLineType::Context,
+                        vec![(DiffTokenType::Matching, *line)],
+                    );
                 }
                 let num_skip_lines = lines
                     .len()
@@ -603,24 +1105,23 @@ fn unified_diff_hunks<'content>(
                 current_hunk.left_line_range.end += num_before_lines;
                 current_hunk.right_line_range.end += num_before_lines;
                 for line in lines.iter().skip(num_after_lines + num_skip_lines) {
-                    current_hunk.lines.push((DiffLineType::Context, line));
+                    current_hunk.push_line(
+                        DiffLineType::Context,
+                        vec![(DiffTokenType::Matching, *line)],
+                    );
                 }
             }
             DiffHunk::Different(content) => {
                 show_context_after = true;
-                let left_lines = content[0].split_inclusive(|b| *b == b'\n').collect_vec();
-                let right_lines = content[1].split_inclusive(|b| *b == b'\n').collect_vec();
-                if !left_lines.is_empty() {
-                    current_hunk.left_line_range.end += left_lines.len();
-                    for line in left_lines {
-                        current_hunk.lines.push((DiffLineType::Removed, line));
-                    }
+                let (left_lines, right_lines) =
+                    changed_region_lines(content[0], content[1], options.highlight_words);
+                current_hunk.left_line_range.end += left_lines.len();
+                for tokens in left_lines {
+                    current_hunk.push_line(DiffLineType::Removed, tokens);
                 }
-                if !right_lines.is_empty() {
-                    current_hunk.right_line_range.end += right_lines.len();
-                    for line in right_lines {
-                        current_hunk.lines.push((DiffLineType::Added, line));
-                    }
+                current_hunk.right_line_range.end += right_lines.len();
+                for tokens in right_lines {
+                    current_hunk.push_line(DiffLineType::Added, tokens);
                 }
             }
         }
@@ -628,19 +1129,96 @@ fn unified_diff_hunks<'content>(
     if !current_hunk
         .lines
         .iter()
-        .all(|(diff_type, _line)| *diff_type == DiffLineType::Context)
+        .all(|line| line.line_type == DiffLineType::Context)
     {
         hunks.push(current_hunk);
     }
     hunks
 }
 
+/// Marks removed lines that were added elsewhere, and added lines that were
+/// removed elsewhere, as moved. The `hunks` of all files in the diff are
+/// searched, so lines moved between files are marked too.
+///
+/// Only blocks of consecutive such lines with enough alphanumeric characters
+/// are marked.
+fn mark_moved_lines(hunks: &mut [Vec<UnifiedDiffHunk>]) {
+    fn line_content(line: &UnifiedDiffLine) -> Vec<u8> {
+        line.tokens
+            .iter()
+            .flat_map(|(_, part)| *part)
+            .copied()
+            .collect()
+    }
+    let mut removed_lines = HashSet::new();
+    let mut added_lines = HashSet::new();
+    for line in hunks.iter().flatten().flat_map(|hunk| &hunk.lines) {
+        match line.line_type {
+            DiffLineType::Context => {}
+            DiffLineType::Removed => {
+                removed_lines.insert(line_content(line));
+            }
+            DiffLineType::Added => {
+                added_lines.insert(line_content(line));
+            }
+        }
+    }
+    for hunk in hunks.iter_mut().flatten() {
+        let mut block_start = 0;
+        while block_start < hunk.lines.len() {
+            let is_candidate = |line: &UnifiedDiffLine, line_type: DiffLineType| {
+                line.line_type == line_type
+                    && match line_type {
+                        DiffLineType::Context => false,
+                        DiffLineType::Removed => added_lines.contains(&line_content(line)),
+                        DiffLineType::Added => removed_lines.contains(&line_content(line)),
+                    }
+            };
+            let line_type = hunk.lines[block_start].line_type;
+            let block_len = hunk.lines[block_start..]
+                .iter()
+                .take_while(|line| is_candidate(line, line_type))
+                .count();
+            if block_len == 0 {
+                block_start += 1;
+                continue;
+            }
+            let block = &mut hunk.lines[block_start..block_start + block_len];
+            let num_alnum_chars: usize = block
+                .iter()
+                .flat_map(|line| &line.tokens)
+                .map(|(_, part)| part.iter().filter(|b| b.is_ascii_alphanumeric()).count())
+                .sum();
+            if num_alnum_chars >= MIN_MOVED_BLOCK_ALNUM_CHARS {
+                for line in block {
+                    line.moved = true;
+                }
+            }
+            block_start += block_len;
+        }
+    }
+}
+
+fn show_diff_line_tokens(
+    formatter: &mut dyn Formatter,
+    tokens: &[(DiffTokenType, &[u8])],
+) -> io::Result<()> {
+    for (token_type, content) in tokens {
+        match token_type {
+            DiffTokenType::Matching => formatter.write_all(content)?,
+            DiffTokenType::Different => {
+                formatter.with_label("token", |formatter| formatter.write_all(content))?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn show_unified_diff_hunks(
     formatter: &mut dyn Formatter,
-    left_content: &[u8],
-    right_content: &[u8],
+    hunks: &[UnifiedDiffHunk],
 ) -> Result<(), CommandError> {
-    for hunk in unified_diff_hunks(left_content, right_content, 3) {
+    for hunk in hunks {
         writeln!(
             formatter.labeled("hunk_header"),
             "@@ -{},{} +{},{} @@",
@@ -649,28 +1227,32 @@ fn show_unified_diff_hunks(
             hunk.right_line_range.start,
             hunk.right_line_range.len()
         )?;
-        for (line_type, content) in hunk.lines {
-            match line_type {
-                DiffLineType::Context => {
-                    formatter.with_label("context", |formatter| {
-                        formatter.write_str(" ")?;
-                        formatter.write_all(content)
-                    })?;
-                }
-                DiffLineType::Removed => {
-                    formatter.with_label("removed", |formatter| {
-                        formatter.write_str("-")?;
-                        formatter.write_all(content)
-                    })?;
-                }
-                DiffLineType::Added => {
-                    formatter.with_label("added", |formatter| {
-                        formatter.write_str("+")?;
-                        formatter.write_all(content)
-                    })?;
+        for line in &hunk.lines {
+            let (label, prefix) = match line.line_type {
+                DiffLineType::Context => ("context", " "),
+                DiffLineType::Removed => ("removed", "-"),
+                DiffLineType::Added => ("added", "+"),
+            };
+            formatter.with_label(label, |formatter| {
+                if line.moved {
+                    // The changed words aren't meaningful in a moved line.
+                    formatter.with_label("moved", |formatter| {
+                        formatter.write_str(prefix)?;
+                        for (_, content) in &line.tokens {
+                            formatter.write_all(content)?;
+                        }
+                        Ok(())
+                    })
+                } else {
+                    formatter.write_str(prefix)?;
+                    show_diff_line_tokens(formatter, &line.tokens)
                 }
-            }
-            if !content.ends_with(b"\n") {
+            })?;
+            let ends_with_newline = line
+                .tokens
+                .last()
+                .map_or(true, |(_, content)| content.ends_with(b"\n"));
+            if !ends_with_newline {
                 formatter.write_str("\n\\ No newline at end of file\n")?;
             }
         }
@@ -678,93 +1260,322 @@ fn show_unified_diff_hunks(
     Ok(())
 }
 
+/// The .gitattributes files in effect on both sides of a diff.
+pub struct DiffGitAttributes {
+    from: TreeGitAttributes,
+    to: TreeGitAttributes,
+}
+
+impl DiffGitAttributes {
+    pub fn new(from_tree: &MergedTree, to_tree: &MergedTree) -> Self {
+        DiffGitAttributes {
+            from: TreeGitAttributes::new(from_tree.clone()),
+            to: TreeGitAttributes::new(to_tree.clone()),
+        }
+    }
+
+    /// Returns how conflicts at `source_path` in the "from" tree and at
+    /// `target_path` in the "to" tree are materialized.
+    fn merge_drivers(
+        &self,
+        source_path: &RepoPath,
+        target_path: &RepoPath,
+    ) -> BackendResult<(MergeDriver, MergeDriver)> {
+        let before = self.from.for_file(source_path)?;
+        let after = self.to.for_file(target_path)?;
+        Ok((
+            MergeDriver::for_path(&before, source_path),
+            MergeDriver::for_path(&after, target_path),
+        ))
+    }
+
+    /// Returns whether textual diffs are disabled by the `diff` attribute on
+    /// either side.
+    fn is_binary(&self, source_path: &RepoPath, target_path: &RepoPath) -> BackendResult<bool> {
+        let diff_unset =
+            |attributes: &TreeGitAttributes, path: &RepoPath| -> BackendResult<bool> {
+                let value = attributes.for_file(path)?;
+                Ok(value.get(path.as_internal_file_string(), "diff")
+                    == Some(&GitAttributeValue::Unset))
+            };
+        Ok(diff_unset(&self.from, source_path)? || diff_unset(&self.to, target_path)?)
+    }
+}
+
+/// Path, copy source, and materialized values before and after.
+type MaterializedDiffEntry = (
+    RepoPathBuf,
+    Option<CopySource>,
+    BackendResult<(MaterializedTreeValue, MaterializedTreeValue)>,
+);
+
 fn materialized_diff_stream<'a>(
     store: &'a Store,
-    tree_diff: TreeDiffStream<'a>,
-) -> impl Stream<
-    Item = (
-        RepoPathBuf,
-        BackendResult<(MaterializedTreeValue, MaterializedTreeValue)>,
-    ),
-> + 'a {
-    tree_diff
-        .map(|(path, diff)| async {
-            match diff {
-                Err(err) => (path, Err(err)),
+    git_attributes: &'a DiffGitAttributes,
+    tree_diff: Vec<CopiesTreeDiffEntry>,
+) -> BackendResult<impl Stream<Item = MaterializedDiffEntry> + 'a> {
+    // Let the backend fetch the contents in one go instead of one file at a
+    // time.
+    let file_ids = tree_diff
+        .iter()
+        .filter_map(|entry| entry.values.as_ref().ok())
+        .flat_map(|(before, after)| before.iter().chain(after.iter()).flatten())
+        .filter_map(|value| match value {
+            TreeValue::File { id, .. } => Some(id.clone()),
+            _ => None,
+        })
+        .collect_vec();
+    store.prefetch_files(&file_ids)?;
+    let stream = futures::stream::iter(tree_diff)
+        .map(|entry| async {
+            let CopiesTreeDiffEntry {
+                target,
+                source,
+                values,
+            } = entry;
+            match values {
+                Err(err) => (target, source, Err(err)),
                 Ok((before, after)) => {
-                    let before_future = materialize_tree_value(store, &path, before);
-                    let after_future = materialize_tree_value(store, &path, after);
+                    let source_path = source.as_ref().map_or(&target, |source| &source.path);
+                    let (before_driver, after_driver) =
+                        match git_attributes.merge_drivers(source_path, &target) {
+                            Ok(drivers) => drivers,
+                            Err(err) => return (target, source, Err(err)),
+                        };
+                    let before_future =
+                        materialize_tree_value(store, source_path, before, before_driver);
+                    let after_future = materialize_tree_value(store, &target, after, after_driver);
                     let values = try_join!(before_future, after_future);
-                    (path, values)
+                    (target, source, values)
                 }
             }
         })
-        .buffered((store.concurrency() / 2).max(1))
+        .buffered((store.concurrency() / 2).max(1));
+    Ok(stream)
 }
 
 pub fn show_git_diff(
     formatter: &mut dyn Formatter,
     workspace_command: &WorkspaceCommandHelper,
-    tree_diff: TreeDiffStream,
+    git_attributes: &DiffGitAttributes,
+    tree_diff: Vec<CopiesTreeDiffEntry>,
 ) -> Result<(), CommandError> {
+    let options = UnifiedDiffOptions::from_settings(workspace_command.settings())?;
     formatter.push_label("diff")?;
 
-    let mut diff_stream = materialized_diff_stream(workspace_command.repo().store(), tree_diff);
+    // Lines can be moved between files, so all files have to be read before
+    // moved lines are detected.
+    let mut pending_files = vec![];
+    let mut diff_stream =
+        materialized_diff_stream(workspace_command.repo().store(), git_attributes, tree_diff)?;
     async {
-        while let Some((path, diff)) = diff_stream.next().await {
-            let path_string = path.as_internal_file_string();
+        while let Some((path, copy_source, diff)) = diff_stream.next().await {
             let (left_value, right_value) = diff?;
-            if left_value.is_absent() {
-                let right_part = git_diff_part(&path, right_value)?;
-                formatter.with_label("file_header", |formatter| {
-                    writeln!(formatter, "diff --git a/{path_string} b/{path_string}")?;
-                    writeln!(formatter, "new file mode {}", &right_part.mode)?;
-                    writeln!(formatter, "index 0000000000..{}", &right_part.hash)?;
-                    writeln!(formatter, "--- /dev/null")?;
-                    writeln!(formatter, "+++ b/{path_string}")
-                })?;
-                show_unified_diff_hunks(formatter, &[], &right_part.content)?;
-            } else if right_value.is_present() {
-                let left_part = git_diff_part(&path, left_value)?;
-                let right_part = git_diff_part(&path, right_value)?;
-                formatter.with_label("file_header", |formatter| {
-                    writeln!(formatter, "diff --git a/{path_string} b/{path_string}")?;
-                    if left_part.mode != right_part.mode {
-                        writeln!(formatter, "old mode {}", &left_part.mode)?;
-                        writeln!(formatter, "new mode {}", &right_part.mode)?;
-                        if left_part.hash != right_part.hash {
-                            writeln!(formatter, "index {}...{}", &left_part.hash, right_part.hash)?;
-                        }
-                    } else if left_part.hash != right_part.hash {
+            let file =
+                GitDiffFile::new(git_attributes, path, copy_source, left_value, right_value)?;
+            if options.color_moved {
+                pending_files.push(file);
+            } else {
+                show_git_diff_file(formatter, &file, &file.hunks(&options))?;
+            }
+        }
+        Ok::<(), CommandError>(())
+    }
+    .block_on()?;
+    let mut hunks = pending_files
+        .iter()
+        .map(|file| file.hunks(&options))
+        .collect_vec();
+    mark_moved_lines(&mut hunks);
+    for (file, hunks) in pending_files.iter().zip(&hunks) {
+        show_git_diff_file(formatter, file, hunks)?;
+    }
+    formatter.pop_label()?;
+    Ok(())
+}
+
+/// A file in the Git diff format, with the contents of both sides read.
+struct GitDiffFile {
+    path: RepoPathBuf,
+    copy_source: Option<CopySource>,
+    /// The left side, or `None` if the file was added.
+    left: Option<GitDiffPart>,
+    /// The right side, or `None` if the file was removed.
+    right: Option<GitDiffPart>,
+    binary: bool,
+}
+
+impl GitDiffFile {
+    fn new(
+        git_attributes: &DiffGitAttributes,
+        path: RepoPathBuf,
+        copy_source: Option<CopySource>,
+        left_value: MaterializedTreeValue,
+        right_value: MaterializedTreeValue,
+    ) -> Result<Self, CommandError> {
+        let source_path = copy_source
+            .as_ref()
+            .map_or(path.as_ref(), |source| source.path.as_ref());
+        let binary = git_attributes.is_binary(source_path, &path)?;
+        let left = if left_value.is_absent() {
+            None
+        } else {
+            Some(git_diff_part(source_path, left_value)?)
+        };
+        let right = if right_value.is_absent() {
+            None
+        } else {
+            Some(git_diff_part(&path, right_value)?)
+        };
+        Ok(GitDiffFile {
+            path,
+            copy_source,
+            left,
+            right,
+            binary,
+        })
+    }
+
+    fn hunks(&self, options: &UnifiedDiffOptions) -> Vec<UnifiedDiffHunk<'_>> {
+        if self.binary {
+            return vec![];
+        }
+        let left_content = self.left.as_ref().map_or(&[][..], |part| &part.content);
+        let right_content = self.right.as_ref().map_or(&[][..], |part| &part.content);
+        unified_diff_hunks(left_content, right_content, 3, options)
+    }
+}
+
+fn show_git_diff_file(
+    formatter: &mut dyn Formatter,
+    file: &GitDiffFile,
+    hunks: &[UnifiedDiffHunk],
+) -> Result<(), CommandError> {
+    let path_string = file.path.as_internal_file_string();
+    let binary = file.binary;
+    match (&file.copy_source, &file.left, &file.right) {
+        (Some(source), Some(left_part), Some(right_part)) => {
+            let source_string = source.path.as_internal_file_string();
+            let operation = match source.operation {
+                CopyOperation::Copy => "copy",
+                CopyOperation::Rename => "rename",
+            };
+            formatter.with_label("file_header", |formatter| {
+                writeln!(formatter, "diff --git a/{source_string} b/{path_string}")?;
+                if left_part.mode != right_part.mode {
+                    writeln!(formatter, "old mode {}", &left_part.mode)?;
+                    writeln!(formatter, "new mode {}", &right_part.mode)?;
+                }
+                writeln!(formatter, "similarity index {}%", source.similarity)?;
+                writeln!(formatter, "{operation} from {source_string}")?;
+                writeln!(formatter, "{operation} to {path_string}")?;
+                if left_part.hash != right_part.hash {
+                    if left_part.mode == right_part.mode {
                         writeln!(
                             formatter,
                             "index {}...{} {}",
                             &left_part.hash, right_part.hash, left_part.mode
                         )?;
+                    } else {
+                        writeln!(formatter, "index {}...{}", &left_part.hash, right_part.hash)?;
                     }
-                    if left_part.content != right_part.content {
-                        writeln!(formatter, "--- a/{path_string}")?;
-                        writeln!(formatter, "+++ b/{path_string}")?;
-                    }
-                    Ok(())
-                })?;
-                show_unified_diff_hunks(formatter, &left_part.content, &right_part.content)?;
+                }
+                if left_part.content != right_part.content && !binary {
+                    writeln!(formatter, "--- a/{source_string}")?;
+                    writeln!(formatter, "+++ b/{path_string}")?;
+                }
+                Ok(())
+            })?;
+            if binary {
+                if left_part.content != right_part.content {
+                    writeln!(
+                        formatter,
+                        "Binary files a/{source_string} and b/{path_string} differ"
+                    )?;
+                }
+            } else {
+                show_unified_diff_hunks(formatter, hunks)?;
+            }
+        }
+        (_, None, Some(right_part)) => {
+            formatter.with_label("file_header", |formatter| {
+                writeln!(formatter, "diff --git a/{path_string} b/{path_string}")?;
+                writeln!(formatter, "new file mode {}", &right_part.mode)?;
+                writeln!(formatter, "index 0000000000..{}", &right_part.hash)?;
+                if binary {
+                    return Ok(());
+                }
+                writeln!(formatter, "--- /dev/null")?;
+                writeln!(formatter, "+++ b/{path_string}")
+            })?;
+            if binary {
+                if !right_part.content.is_empty() {
+                    writeln!(
+                        formatter,
+                        "Binary files /dev/null and b/{path_string} differ"
+                    )?;
+                }
             } else {
-                let left_part = git_diff_part(&path, left_value)?;
-                formatter.with_label("file_header", |formatter| {
-                    writeln!(formatter, "diff --git a/{path_string} b/{path_string}")?;
-                    writeln!(formatter, "deleted file mode {}", &left_part.mode)?;
-                    writeln!(formatter, "index {}..0000000000", &left_part.hash)?;
+                show_unified_diff_hunks(formatter, hunks)?;
+            }
+        }
+        (_, Some(left_part), Some(right_part)) => {
+            formatter.with_label("file_header", |formatter| {
+                writeln!(formatter, "diff --git a/{path_string} b/{path_string}")?;
+                if left_part.mode != right_part.mode {
+                    writeln!(formatter, "old mode {}", &left_part.mode)?;
+                    writeln!(formatter, "new mode {}", &right_part.mode)?;
+                    if left_part.hash != right_part.hash {
+                        writeln!(formatter, "index {}...{}", &left_part.hash, right_part.hash)?;
+                    }
+                } else if left_part.hash != right_part.hash {
+                    writeln!(
+                        formatter,
+                        "index {}...{} {}",
+                        &left_part.hash, right_part.hash, left_part.mode
+                    )?;
+                }
+                if left_part.content != right_part.content && !binary {
                     writeln!(formatter, "--- a/{path_string}")?;
-                    writeln!(formatter, "+++ /dev/null")
-                })?;
-                show_unified_diff_hunks(formatter, &left_part.content, &[])?;
+                    writeln!(formatter, "+++ b/{path_string}")?;
+                }
+                Ok(())
+            })?;
+            if binary {
+                if left_part.content != right_part.content {
+                    writeln!(
+                        formatter,
+                        "Binary files a/{path_string} and b/{path_string} differ"
+                    )?;
+                }
+            } else {
+                show_unified_diff_hunks(formatter, hunks)?;
             }
         }
-        Ok::<(), CommandError>(())
+        (_, Some(left_part), None) => {
+            formatter.with_label("file_header", |formatter| {
+                writeln!(formatter, "diff --git a/{path_string} b/{path_string}")?;
+                writeln!(formatter, "deleted file mode {}", &left_part.mode)?;
+                writeln!(formatter, "index {}..0000000000", &left_part.hash)?;
+                if binary {
+                    return Ok(());
+                }
+                writeln!(formatter, "--- a/{path_string}")?;
+                writeln!(formatter, "+++ /dev/null")
+            })?;
+            if binary {
+                if !left_part.content.is_empty() {
+                    writeln!(
+                        formatter,
+                        "Binary files a/{path_string} and /dev/null differ"
+                    )?;
+                }
+            } else {
+                show_unified_diff_hunks(formatter, hunks)?;
+            }
+        }
+        (_, None, None) => panic!("Absent path {path_string:?} on both sides of diff"),
     }
-    .block_on()?;
-    formatter.pop_label()?;
     Ok(())
 }
 
@@ -772,35 +1583,43 @@ pub fn show_git_diff(
 pub fn show_diff_summary(
     formatter: &mut dyn Formatter,
     workspace_command: &WorkspaceCommandHelper,
-    mut tree_diff: TreeDiffStream,
+    tree_diff: Vec<CopiesTreeDiffEntry>,
 ) -> io::Result<()> {
     formatter.with_label("diff", |formatter| -> io::Result<()> {
-        async {
-            while let Some((repo_path, diff)) = tree_diff.next().await {
-                let (before, after) = diff.unwrap();
-                if before.is_present() && after.is_present() {
-                    writeln!(
-                        formatter.labeled("modified"),
-                        "M {}",
-                        workspace_command.format_file_path(&repo_path)
-                    )?;
-                } else if before.is_absent() {
-                    writeln!(
-                        formatter.labeled("added"),
-                        "A {}",
-                        workspace_command.format_file_path(&repo_path)
-                    )?;
-                } else {
-                    writeln!(
-                        formatter.labeled("removed"),
-                        "D {}", // `R` could be interpreted as "renamed"
-                        workspace_command.format_file_path(&repo_path)
-                    )?;
-                }
+        for entry in tree_diff {
+            let repo_path = &entry.target;
+            let (before, after) = entry.values.unwrap();
+            if let Some(source) = &entry.source {
+                let (label, operation) = match source.operation {
+                    CopyOperation::Copy => ("copied", 'C'),
+                    CopyOperation::Rename => ("renamed", 'R'),
+                };
+                writeln!(
+                    formatter.labeled(label),
+                    "{operation} {}",
+                    format_entry_path(workspace_command, repo_path, Some(source))
+                )?;
+            } else if before.is_present() && after.is_present() {
+                writeln!(
+                    formatter.labeled("modified"),
+                    "M {}",
+                    workspace_command.format_file_path(repo_path)
+                )?;
+            } else if before.is_absent() {
+                writeln!(
+                    formatter.labeled("added"),
+                    "A {}",
+                    workspace_command.format_file_path(repo_path)
+                )?;
+            } else {
+                writeln!(
+                    formatter.labeled("removed"),
+                    "D {}", // `R` means "renamed"
+                    workspace_command.format_file_path(repo_path)
+                )?;
             }
-            Ok(())
         }
-        .block_on()
+        Ok(())
     })
 }
 
@@ -811,12 +1630,13 @@ struct DiffStat {
 }
 
 fn get_diff_stat(path: String, left_content: &[u8], right_content: &[u8]) -> DiffStat {
-    let hunks = unified_diff_hunks(left_content, right_content, 0);
+    let options = UnifiedDiffOptions::default();
+    let hunks = unified_diff_hunks(left_content, right_content, 0, &options);
     let mut added = 0;
     let mut removed = 0;
     for hunk in hunks {
-        for (line_type, _content) in hunk.lines {
-            match line_type {
+        for line in hunk.lines {
+            match line.line_type {
                 DiffLineType::Context => {}
                 DiffLineType::Removed => removed += 1,
                 DiffLineType::Added => added += 1,
@@ -834,21 +1654,31 @@ pub fn show_diff_stat(
     ui: &Ui,
     formatter: &mut dyn Formatter,
     workspace_command: &WorkspaceCommandHelper,
-    tree_diff: TreeDiffStream,
+    git_attributes: &DiffGitAttributes,
+    tree_diff: Vec<CopiesTreeDiffEntry>,
 ) -> Result<(), CommandError> {
     let mut stats: Vec<DiffStat> = vec![];
     let mut max_path_width = 0;
     let mut max_diffs = 0;
 
-    let mut diff_stream = materialized_diff_stream(workspace_command.repo().store(), tree_diff);
+    let mut diff_stream =
+        materialized_diff_stream(workspace_command.repo().store(), git_attributes, tree_diff)?;
     async {
-        while let Some((repo_path, diff)) = diff_stream.next().await {
+        while let Some((repo_path, copy_source, diff)) = diff_stream.next().await {
             let (left, right) = diff?;
-            let path = workspace_command.format_file_path(&repo_path);
-            let left_content = diff_content(&repo_path, left)?;
+            let path = format_entry_path(workspace_command, &repo_path, copy_source.as_ref());
+            let source_path = copy_source
+                .as_ref()
+                .map_or(&repo_path, |source| &source.path);
+            let left_content = diff_content(source_path, left)?;
             let right_content = diff_content(&repo_path, right)?;
             max_path_width = max(max_path_width, path.width());
-            let stat = get_diff_stat(path, &left_content, &right_content);
+            // Lines of binary files aren't counted
+            let stat = if git_attributes.is_binary(source_path, &repo_path)? {
+                get_diff_stat(path, &[], &[])
+            } else {
+                get_diff_stat(path, &left_content, &right_content)
+            };
             max_diffs = max(max_diffs, stat.added + stat.removed);
             stats.push(stat);
         }
@@ -910,23 +1740,20 @@ pub fn show_diff_stat(
 pub fn show_types(
     formatter: &mut dyn Formatter,
     workspace_command: &WorkspaceCommandHelper,
-    mut tree_diff: TreeDiffStream,
+    tree_diff: Vec<CopiesTreeDiffEntry>,
 ) -> io::Result<()> {
     formatter.with_label("diff", |formatter| {
-        async {
-            while let Some((repo_path, diff)) = tree_diff.next().await {
-                let (before, after) = diff.unwrap();
-                writeln!(
-                    formatter.labeled("modified"),
-                    "{}{} {}",
-                    diff_summary_char(&before),
-                    diff_summary_char(&after),
-                    workspace_command.format_file_path(&repo_path)
-                )?;
-            }
-            Ok(())
+        for entry in tree_diff {
+            let (before, after) = entry.values.unwrap();
+            writeln!(
+                formatter.labeled("modified"),
+                "{}{} {}",
+                diff_summary_char(&before),
+                diff_summary_char(&after),
+                format_entry_path(workspace_command, &entry.target, entry.source.as_ref())
+            )?;
         }
-        .block_on()
+        Ok(())
     })
 }
 
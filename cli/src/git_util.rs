@@ -14,17 +14,26 @@
 
 //! Git utilities shared by various commands.
 
+use std::ffi::OsStr;
+use std::fmt::Write as _;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::Stdio;
+use std::process::{Command, Stdio};
 use std::sync::Mutex;
 use std::time::Instant;
+use std::{fs, io};
 
-use jj_lib::git::{self, FailedRefExport, FailedRefExportReason, GitImportStats};
+use jj_lib::git::{
+    self, FailedRefExport, FailedRefExportReason, GitBranchPushTargets, GitImportStats,
+};
 use jj_lib::git_backend::GitBackend;
+use jj_lib::git_lfs::{self, LfsError, LfsRequest, LfsRequestBody, LfsResponse, LfsTransport};
+use jj_lib::object_id::ObjectId;
+use jj_lib::settings::{ConfigResultExt as _, UserSettings};
 use jj_lib::store::Store;
+use jj_lib::str_util::StringPattern;
 
-use crate::cli_util::{user_error, CommandError};
+use crate::cli_util::{user_error, user_error_with_message, CommandError};
 use crate::progress::Progress;
 use crate::ui::Ui;
 
@@ -199,3 +208,389 @@ pub fn expand_git_path(path_str: &str) -> PathBuf {
     }
     PathBuf::from(path_str)
 }
+
+/// Git hooks which can be run by jj if `git.run-hooks` is enabled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum GitHook {
+    /// Run by `jj commit` before the description is edited.
+    PreCommit,
+    /// Run with a file containing the new description.
+    CommitMsg,
+    /// Run by `jj git push` before pushing.
+    PrePush,
+}
+
+impl GitHook {
+    fn name(self) -> &'static str {
+        match self {
+            GitHook::PreCommit => "pre-commit",
+            GitHook::CommitMsg => "commit-msg",
+            GitHook::PrePush => "pre-push",
+        }
+    }
+}
+
+fn git_hooks_enabled(settings: &UserSettings) -> Result<bool, CommandError> {
+    let enabled = settings
+        .config()
+        .get_bool("git.run-hooks")
+        .optional()?
+        .unwrap_or(false);
+    Ok(enabled)
+}
+
+/// Runs the `hook` of the Git repo with the given arguments and standard
+/// input. Does nothing if the hook doesn't exist. Returns an error if the hook
+/// failed.
+fn run_git_hook(
+    git_repo: &git2::Repository,
+    workspace_root: &Path,
+    hook: GitHook,
+    args: &[&OsStr],
+    stdin: &[u8],
+) -> Result<(), CommandError> {
+    // Like Git, run hooks in the root of the working tree. The Git repo of a
+    // non-colocated workspace has no working tree, so use the workspace root.
+    let hooks_dir = match git_repo.config()?.get_path("core.hooksPath") {
+        Ok(path) => workspace_root.join(expand_git_path(path.to_str().unwrap_or_default())),
+        Err(_) => git_repo.path().join("hooks"),
+    };
+    let hook_path = hooks_dir.join(hook.name());
+    if !is_executable_file(&hook_path) {
+        return Ok(());
+    }
+    tracing::info!(?hook_path, "running git hook");
+    // Like Git, redirect the hook's stdout to stderr so it doesn't get mixed
+    // up with the command output.
+    let mut child = Command::new(&hook_path)
+        .args(args)
+        .current_dir(workspace_root)
+        .stdin(Stdio::piped())
+        .stdout(io::stderr())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|err| {
+            user_error_with_message(format!("Failed to run the {} hook", hook.name()), err)
+        })?;
+    // The hook may exit without reading the input.
+    child.stdin.take().unwrap().write_all(stdin).ok();
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(user_error(format!(
+            "The {} hook failed ({status})",
+            hook.name()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable_file(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt as _;
+    fs::metadata(path).map_or(false, |metadata| {
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    })
+}
+
+#[cfg(not(unix))]
+fn is_executable_file(path: &Path) -> bool {
+    path.is_file()
+}
+
+fn open_git_repo_for_hooks(
+    settings: &UserSettings,
+    store: &Store,
+) -> Result<Option<git2::Repository>, CommandError> {
+    if !git_hooks_enabled(settings)? {
+        return Ok(None);
+    }
+    match store.backend_impl().downcast_ref::<GitBackend>() {
+        None => Ok(None),
+        Some(git_backend) => Ok(Some(git_backend.open_git_repo()?)),
+    }
+}
+
+/// Runs the `pre-commit` hook if enabled.
+pub fn run_pre_commit_hook(
+    settings: &UserSettings,
+    store: &Store,
+    workspace_root: &Path,
+) -> Result<(), CommandError> {
+    if let Some(git_repo) = open_git_repo_for_hooks(settings, store)? {
+        run_git_hook(&git_repo, workspace_root, GitHook::PreCommit, &[], &[])?;
+    }
+    Ok(())
+}
+
+/// Runs the `commit-msg` hook if enabled, and returns the description which
+/// may have been edited by the hook.
+pub fn run_commit_msg_hook(
+    settings: &UserSettings,
+    store: &Store,
+    workspace_root: &Path,
+    description: String,
+) -> Result<String, CommandError> {
+    let Some(git_repo) = open_git_repo_for_hooks(settings, store)? else {
+        return Ok(description);
+    };
+    let mut file = tempfile::Builder::new()
+        .prefix("COMMIT_EDITMSG-")
+        .tempfile()
+        .map_err(|err| user_error_with_message("Failed to create description file", err))?;
+    file.write_all(description.as_bytes())
+        .map_err(|err| user_error_with_message("Failed to write description file", err))?;
+    let path = file.into_temp_path();
+    run_git_hook(
+        &git_repo,
+        workspace_root,
+        GitHook::CommitMsg,
+        &[path.as_os_str()],
+        &[],
+    )?;
+    fs::read_to_string(&path)
+        .map_err(|err| user_error_with_message("Failed to read description file", err))
+}
+
+/// Runs the `pre-push` hook if enabled. The hook receives the ref updates in
+/// the standard input.
+pub fn run_pre_push_hook(
+    settings: &UserSettings,
+    git_repo: &git2::Repository,
+    workspace_root: &Path,
+    remote_name: &str,
+    targets: &GitBranchPushTargets,
+) -> Result<(), CommandError> {
+    if !git_hooks_enabled(settings)? {
+        return Ok(());
+    }
+    let remote = git_repo.find_remote(remote_name)?;
+    let remote_url = remote.url().unwrap_or(remote_name);
+    let null_hash = "0".repeat(40);
+    let mut stdin = String::new();
+    for (branch_name, update) in &targets.branch_updates {
+        let ref_name = format!("refs/heads/{branch_name}");
+        let (local_ref, local_hash) = match &update.new_target {
+            Some(id) => (ref_name.as_str(), id.hex()),
+            None => ("(delete)", null_hash.clone()),
+        };
+        let remote_hash = update
+            .old_target
+            .as_ref()
+            .map_or_else(|| null_hash.clone(), |id| id.hex());
+        stdin.push_str(&format!(
+            "{local_ref} {local_hash} {ref_name} {remote_hash}\n"
+        ));
+    }
+    run_git_hook(
+        git_repo,
+        workspace_root,
+        GitHook::PrePush,
+        &[OsStr::new(remote_name), OsStr::new(remote_url)],
+        stdin.as_bytes(),
+    )
+}
+
+/// Downloads the Git LFS objects referenced by the remote-tracking branches of
+/// `remote_name` matching `branch_names`, unless disabled by the
+/// `git.fetch-lfs-objects` setting. A failure is reported as a warning since
+/// the pointer files will be checked out instead of the objects.
+pub fn fetch_lfs_objects(
+    ui: &Ui,
+    settings: &UserSettings,
+    git_repo: &git2::Repository,
+    remote_name: &str,
+    branch_names: &[StringPattern],
+) -> Result<(), CommandError> {
+    let enabled = settings
+        .config()
+        .get_bool("git.fetch-lfs-objects")
+        .optional()?
+        .unwrap_or(true);
+    if !enabled {
+        return Ok(());
+    }
+    match git_lfs::fetch_objects(git_repo, remote_name, branch_names, &CommandLfsTransport) {
+        Ok(0) => {}
+        Ok(count) => writeln!(ui.stderr(), "Downloaded {count} Git LFS objects")?,
+        Err(err) => writeln!(
+            ui.warning(),
+            "Failed to download Git LFS objects from {remote_name}: {err}"
+        )?,
+    }
+    Ok(())
+}
+
+/// Uploads the Git LFS objects referenced by the commits to be pushed.
+pub fn push_lfs_objects(
+    ui: &Ui,
+    git_repo: &git2::Repository,
+    remote_name: &str,
+    targets: &GitBranchPushTargets,
+) -> Result<(), CommandError> {
+    let new_heads: Vec<_> = targets
+        .branch_updates
+        .iter()
+        .filter_map(|(_, update)| update.new_target.as_ref())
+        .map(|id| git2::Oid::from_bytes(id.as_bytes()))
+        .collect::<Result<_, _>>()?;
+    let count = git_lfs::push_objects(git_repo, remote_name, &new_heads, &CommandLfsTransport)
+        .map_err(|err| user_error_with_message("Failed to upload Git LFS objects", err))?;
+    if count > 0 {
+        writeln!(ui.stderr(), "Uploaded {count} Git LFS objects")?;
+    }
+    Ok(())
+}
+
+/// Git LFS transport which runs `curl`, `ssh`, and `git credential`.
+struct CommandLfsTransport;
+
+impl CommandLfsTransport {
+    /// Runs the command with the given standard input, and returns its output.
+    /// Returns an error with the standard error if the command failed.
+    fn run(
+        program: &str,
+        command: &mut Command,
+        input: &[u8],
+    ) -> Result<Result<Vec<u8>, String>, LfsError> {
+        let to_error = |source| LfsError::Command {
+            program: program.to_owned(),
+            source,
+        };
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(to_error)?;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input)
+            .map_err(to_error)?;
+        let output = child.wait_with_output().map_err(to_error)?;
+        if output.status.success() {
+            return Ok(Ok(output.stdout));
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.trim();
+        if message.is_empty() {
+            Ok(Err(output.status.to_string()))
+        } else {
+            Ok(Err(message.to_owned()))
+        }
+    }
+}
+
+impl LfsTransport for CommandLfsTransport {
+    /// Runs curl with the options passed on stdin, so credentials don't show up
+    /// in the process list.
+    fn send(&self, request: &LfsRequest) -> Result<LfsResponse, LfsError> {
+        let mut config = String::new();
+        for option in ["silent", "show-error", "location"] {
+            writeln!(config, "{option}").unwrap();
+        }
+        // Don't let the server redirect us to other protocols
+        writeln!(config, "proto = \"=http,https\"").unwrap();
+        writeln!(config, "proto-redir = \"=http,https\"").unwrap();
+        // Don't wait for "100 Continue" before sending request bodies
+        writeln!(config, "header = \"Expect:\"").unwrap();
+        // The status code is always three digits, and is printed after the body
+        writeln!(config, "write-out = \"%{{http_code}}\"").unwrap();
+        writeln!(config, "url = {}", curl_config_quote(request.url)).unwrap();
+        for header in &request.headers {
+            writeln!(config, "header = {}", curl_config_quote(header)).unwrap();
+        }
+        if let Some(user) = request.user {
+            writeln!(config, "user = {}", curl_config_quote(user)).unwrap();
+        }
+        // Paths are passed as arguments since they may not be valid UTF-8
+        let mut curl = Command::new("curl");
+        curl.arg("--config").arg("-");
+        match &request.body {
+            LfsRequestBody::None => {}
+            LfsRequestBody::Data(data) => {
+                let data = String::from_utf8_lossy(data);
+                writeln!(config, "data-binary = {}", curl_config_quote(&data)).unwrap();
+            }
+            LfsRequestBody::File(path) => {
+                curl.arg("--upload-file").arg(path);
+            }
+        }
+        if let Some(path) = request.output {
+            curl.arg("--output").arg(path);
+        }
+        let mut body = Self::run("curl", &mut curl, config.as_bytes())?.map_err(|message| {
+            LfsError::Request {
+                url: request.url.to_owned(),
+                message: message.trim_start_matches("curl: ").to_owned(),
+            }
+        })?;
+        let status = body
+            .len()
+            .checked_sub(3)
+            .and_then(|start| {
+                let status = std::str::from_utf8(&body[start..]).ok()?.parse().ok()?;
+                body.truncate(start);
+                Some(status)
+            })
+            .ok_or_else(|| LfsError::Request {
+                url: request.url.to_owned(),
+                message: "curl didn't report the status code".to_owned(),
+            })?;
+        Ok(LfsResponse { status, body })
+    }
+
+    fn run_ssh(
+        &self,
+        destination: &str,
+        port: Option<&str>,
+        command: &str,
+    ) -> Result<Vec<u8>, LfsError> {
+        let mut ssh = Command::new("ssh");
+        if let Some(port) = port {
+            ssh.arg("-p").arg(port);
+        }
+        ssh.arg("--").arg(destination).arg(command);
+        Self::run("ssh", &mut ssh, b"")?.map_err(LfsError::SshAuthentication)
+    }
+
+    fn run_git_credential(
+        &self,
+        git_dir: &Path,
+        url: &str,
+        action: &str,
+        input: &str,
+    ) -> Result<String, LfsError> {
+        let mut git = Command::new("git");
+        git.arg("--git-dir=.") // turn off discovery
+            .args(["credential", action])
+            .current_dir(git_dir);
+        let output = Self::run("git", &mut git, input.as_bytes())?.map_err(|message| {
+            LfsError::Credential {
+                url: url.to_owned(),
+                message,
+            }
+        })?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+}
+
+/// Quotes a string for use in a curl config file.
+fn curl_config_quote(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -27,6 +27,7 @@ pub mod graphlog;
 pub mod merge_tools;
 pub mod operation_templater;
 mod progress;
+pub mod ref_name_templater;
 pub mod template_builder;
 pub mod template_parser;
 pub mod templater;
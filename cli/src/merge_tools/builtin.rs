@@ -2,13 +2,14 @@ use std::borrow::Cow;
 use std::path::Path;
 use std::sync::Arc;
 
-use futures::{StreamExt, TryStreamExt};
+use futures::StreamExt;
 use itertools::Itertools;
 use jj_lib::backend::{BackendError, FileId, MergedTreeId, TreeValue};
+use jj_lib::copies::{self, CopyDetectionOptions};
 use jj_lib::diff::{find_line_ranges, Diff, DiffHunk};
 use jj_lib::files::{self, ContentHunk, MergeResult};
 use jj_lib::matchers::Matcher;
-use jj_lib::merge::Merge;
+use jj_lib::merge::{Merge, MergedTreeValue};
 use jj_lib::merged_tree::{MergedTree, MergedTreeBuilder};
 use jj_lib::object_id::ObjectId;
 use jj_lib::repo_path::{RepoPath, RepoPathBuf};
@@ -63,6 +64,29 @@ pub struct FileInfo {
     contents: FileContents,
 }
 
+/// A file that differs between the left and the right trees.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChangedFile {
+    /// Path of the file in the right tree.
+    pub path: RepoPathBuf,
+    /// Path of the file in the left tree if the file was renamed.
+    pub old_path: Option<RepoPathBuf>,
+}
+
+impl ChangedFile {
+    pub fn new(path: RepoPathBuf) -> Self {
+        ChangedFile {
+            path,
+            old_path: None,
+        }
+    }
+
+    /// Path of the file in the left tree.
+    pub fn left_path(&self) -> &RepoPath {
+        self.old_path.as_ref().unwrap_or(&self.path)
+    }
+}
+
 /// File modes according to the Git file mode conventions. used for display
 /// purposes and equality comparison.
 ///
@@ -232,28 +256,64 @@ fn make_diff_sections(
     Ok(sections)
 }
 
+fn is_empty_contents(contents: &FileContents) -> bool {
+    match contents {
+        FileContents::Absent => true,
+        FileContents::Text { num_bytes, .. } | FileContents::Binary { num_bytes, .. } => {
+            *num_bytes == 0
+        }
+    }
+}
+
+/// Returns true if the mode change should be presented as a selectable item.
+///
+/// Addition and removal of a file is usually selected by its content lines,
+/// but an empty file has no lines to select. The mode change item also serves
+/// as the creation/deletion item in that case.
+fn should_render_mode_section(
+    (left_mode, left_contents): (scm_record::FileMode, &FileContents),
+    (right_mode, right_contents): (scm_record::FileMode, &FileContents),
+) -> bool {
+    let absent = scm_record::FileMode::absent();
+    if left_mode == right_mode {
+        false
+    } else if left_mode != absent && right_mode != absent {
+        true
+    } else {
+        is_empty_contents(left_contents) && is_empty_contents(right_contents)
+    }
+}
+
 pub fn make_diff_files(
     store: &Arc<Store>,
     left_tree: &MergedTree,
     right_tree: &MergedTree,
-    changed_files: &[RepoPathBuf],
+    changed_files: &[ChangedFile],
 ) -> Result<Vec<scm_record::File<'static>>, BuiltinToolError> {
     let mut files = Vec::new();
-    for changed_path in changed_files {
+    for changed_file in changed_files {
         let FileInfo {
             file_mode: left_file_mode,
             contents: left_contents,
-        } = read_file_contents(store, left_tree, changed_path)?;
+        } = read_file_contents(store, left_tree, changed_file.left_path())?;
         let FileInfo {
             file_mode: right_file_mode,
             contents: right_contents,
-        } = read_file_contents(store, right_tree, changed_path)?;
+        } = read_file_contents(store, right_tree, &changed_file.path)?;
 
         let mut sections = Vec::new();
-        if left_file_mode != right_file_mode
-            && left_file_mode != scm_record::FileMode::absent()
-            && right_file_mode != scm_record::FileMode::absent()
-        {
+        if changed_file.old_path.is_some() {
+            // The rename is selected by the item that creates the file at the
+            // new path. The lines select the contents either way.
+            sections.push(scm_record::Section::FileMode {
+                is_checked: false,
+                before: scm_record::FileMode::absent(),
+                after: right_file_mode,
+            });
+        } else if should_render_mode_section(
+            (left_file_mode, &left_contents),
+            (right_file_mode, &right_contents),
+        ) {
             sections.push(scm_record::Section::FileMode {
                 is_checked: false,
                 before: left_file_mode,
@@ -349,8 +409,11 @@ pub fn make_diff_files(
         }
 
         files.push(scm_record::File {
-            old_path: None,
-            path: Cow::Owned(changed_path.to_fs_path(Path::new(""))),
+            old_path: changed_file
+                .old_path
+                .as_ref()
+                .map(|old_path| Cow::Owned(old_path.to_fs_path(Path::new("")))),
+            path: Cow::Owned(changed_file.path.to_fs_path(Path::new(""))),
             file_mode: Some(left_file_mode),
             sections,
         });
@@ -358,11 +421,73 @@ pub fn make_diff_files(
     Ok(files)
 }
 
+/// Returns the file mode selected by the user, or `None` if the file has no
+/// selectable mode change.
+fn get_selected_file_mode(file: &scm_record::File) -> Option<scm_record::FileMode> {
+    file.sections.iter().find_map(|section| match section {
+        scm_record::Section::FileMode {
+            is_checked,
+            before,
+            after,
+        } => Some(if *is_checked { *after } else { *before }),
+        _ => None,
+    })
+}
+
+fn to_file_mode(value: &MergedTreeValue) -> scm_record::FileMode {
+    match value.as_resolved() {
+        Some(Some(TreeValue::File {
+            id: _,
+            executable: true,
+        })) => scm_record::FileMode(mode::EXECUTABLE),
+        Some(Some(TreeValue::File {
+            id: _,
+            executable: false,
+        })) => scm_record::FileMode(mode::NORMAL),
+        Some(Some(TreeValue::Symlink(_))) => scm_record::FileMode(mode::SYMLINK),
+        _ => scm_record::FileMode::absent(),
+    }
+}
+
+/// Updates the executable bit of the file `value` to match the `file_mode`.
+fn with_file_mode(value: MergedTreeValue, file_mode: scm_record::FileMode) -> MergedTreeValue {
+    if file_mode == scm_record::FileMode::absent() {
+        return Merge::absent();
+    }
+    match value.into_resolved() {
+        Ok(Some(TreeValue::File { id, executable: _ })) => Merge::normal(TreeValue::File {
+            id,
+            executable: file_mode == scm_record::FileMode(mode::EXECUTABLE),
+        }),
+        Ok(value) => Merge::resolved(value),
+        Err(value) => value,
+    }
+}
+
+fn write_contents_with_mode(
+    store: &Store,
+    path: &RepoPath,
+    contents: &str,
+    file_mode: scm_record::FileMode,
+) -> Result<MergedTreeValue, BackendError> {
+    let value = if file_mode == scm_record::FileMode(mode::SYMLINK) {
+        let id = store.write_symlink(path, contents)?;
+        TreeValue::Symlink(id)
+    } else {
+        let id = store.write_file(path, &mut contents.as_bytes())?;
+        TreeValue::File {
+            id,
+            executable: file_mode == scm_record::FileMode(mode::EXECUTABLE),
+        }
+    };
+    Ok(Merge::normal(value))
+}
+
 pub fn apply_diff_builtin(
     store: Arc<Store>,
     left_tree: &MergedTree,
     right_tree: &MergedTree,
-    changed_files: Vec<RepoPathBuf>,
+    changed_files: Vec<ChangedFile>,
     files: &[scm_record::File],
 ) -> Result<MergedTreeId, BackendError> {
     let mut tree_builder = MergedTreeBuilder::new(left_tree.id().clone());
@@ -371,32 +496,60 @@ pub fn apply_diff_builtin(
         files.len(),
         "result had a different number of files"
     );
-    for (path, file) in changed_files.into_iter().zip(files) {
+    for (changed_file, file) in changed_files.into_iter().zip(files) {
+        let ChangedFile { path, old_path } = changed_file;
+        if let Some(old_path) = old_path {
+            apply_rename(
+                &store,
+                left_tree,
+                right_tree,
+                (old_path, path),
+                file,
+                &mut tree_builder,
+            )?;
+            continue;
+        }
+        let selected_mode = get_selected_file_mode(file);
         let (selected, _unselected) = file.get_selected_contents();
         match selected {
-            scm_record::SelectedContents::Absent => {
-                tree_builder.set_or_remove(path, Merge::absent());
-            }
+            scm_record::SelectedContents::Absent => match selected_mode {
+                // Creation or deletion of an empty file is selected by its mode.
+                Some(file_mode) if file_mode != scm_record::FileMode::absent() => {
+                    let value = write_contents_with_mode(&store, &path, "", file_mode)?;
+                    tree_builder.set_or_remove(path, value);
+                }
+                _ => {
+                    tree_builder.set_or_remove(path, Merge::absent());
+                }
+            },
             scm_record::SelectedContents::Unchanged => {
-                // Do nothing.
+                if let Some(file_mode) = selected_mode {
+                    let value = with_file_mode(left_tree.path_value(&path), file_mode);
+                    tree_builder.set_or_remove(path, value);
+                }
             }
             scm_record::SelectedContents::Binary {
                 old_description: _,
                 new_description: _,
             } => {
-                let value = right_tree.path_value(&path);
+                let mut value = right_tree.path_value(&path);
+                if let Some(file_mode) = selected_mode {
+                    value = with_file_mode(value, file_mode);
+                }
                 tree_builder.set_or_remove(path, value);
             }
             scm_record::SelectedContents::Present { contents } => {
-                let file_id = store.write_file(&path, &mut contents.as_bytes())?;
-                tree_builder.set_or_remove(
-                    path,
-                    Merge::normal(TreeValue::File {
-                        id: file_id,
-                        executable: file.get_file_mode()
-                            == Some(scm_record::FileMode(mode::EXECUTABLE)),
-                    }),
-                )
+                let file_mode = selected_mode.unwrap_or_else(|| {
+                    // The mode of an added or removed file isn't selectable.
+                    let left_mode = to_file_mode(&left_tree.path_value(&path));
+                    if left_mode != scm_record::FileMode::absent() {
+                        left_mode
+                    } else {
+                        to_file_mode(&right_tree.path_value(&path))
+                    }
+                });
+                let value = write_contents_with_mode(&store, &path, &contents, file_mode)?;
+                tree_builder.set_or_remove(path, value);
             }
         }
     }
@@ -405,26 +558,90 @@ pub fn apply_diff_builtin(
     Ok(tree_id)
 }
 
+/// Applies the selection of a file renamed from `old_path` to `path`. The file
+/// is moved if the rename item is selected, and the selected contents are
+/// written to wherever the file ends up.
+fn apply_rename(
+    store: &Store,
+    left_tree: &MergedTree,
+    right_tree: &MergedTree,
+    (old_path, path): (RepoPathBuf, RepoPathBuf),
+    file: &scm_record::File,
+    tree_builder: &mut MergedTreeBuilder,
+) -> Result<(), BackendError> {
+    let is_renamed = get_selected_file_mode(file)
+        .is_some_and(|file_mode| file_mode != scm_record::FileMode::absent());
+    let (dest_path, file_mode) = if is_renamed {
+        (&path, to_file_mode(&right_tree.path_value(&path)))
+    } else {
+        (&old_path, to_file_mode(&left_tree.path_value(&old_path)))
+    };
+    let (selected, _unselected) = file.get_selected_contents();
+    let value = match selected {
+        scm_record::SelectedContents::Absent => {
+            write_contents_with_mode(store, dest_path, "", file_mode)?
+        }
+        scm_record::SelectedContents::Unchanged => {
+            with_file_mode(left_tree.path_value(&old_path), file_mode)
+        }
+        scm_record::SelectedContents::Binary {
+            old_description: _,
+            new_description: _,
+        } => with_file_mode(right_tree.path_value(&path), file_mode),
+        scm_record::SelectedContents::Present { contents } => {
+            write_contents_with_mode(store, dest_path, &contents, file_mode)?
+        }
+    };
+    if is_renamed {
+        tree_builder.set_or_remove(old_path, Merge::absent());
+        tree_builder.set_or_remove(path, value);
+    } else {
+        tree_builder.set_or_remove(old_path, value);
+    }
+    Ok(())
+}
+
+// TODO: Editing the contents of the right side inline and searching across
+// files need support in scm-record, which can only select existing lines.
 pub fn edit_diff_builtin(
     left_tree: &MergedTree,
     right_tree: &MergedTree,
     matcher: &dyn Matcher,
+    copy_options: &CopyDetectionOptions,
+) -> Result<MergedTreeId, BuiltinToolError> {
+    let mut input = scm_record::helpers::CrosstermInput;
+    edit_diff_builtin_with_input(left_tree, right_tree, matcher, copy_options, &mut input)
+}
+
+fn edit_diff_builtin_with_input(
+    left_tree: &MergedTree,
+    right_tree: &MergedTree,
+    matcher: &dyn Matcher,
+    copy_options: &CopyDetectionOptions,
+    input: &mut dyn scm_record::RecordInput,
 ) -> Result<MergedTreeId, BuiltinToolError> {
     let store = left_tree.store().clone();
-    let changed_files: Vec<_> = left_tree
+    let entries = left_tree
         .diff_stream(right_tree, matcher)
-        .map(|(path, diff)| diff.map(|_| path))
-        .try_collect()
-        .block_on()?;
+        .collect::<Vec<_>>()
+        .block_on();
+    let changed_files: Vec<_> = copies::detect_copies(left_tree, entries, &[], copy_options)?
+        .into_iter()
+        .map(|entry| {
+            entry.values.map(|_| ChangedFile {
+                path: entry.target,
+                old_path: entry.source.map(|source| source.path),
+            })
+        })
+        .try_collect()?;
     let files = make_diff_files(&store, left_tree, right_tree, &changed_files)?;
-    let mut input = scm_record::helpers::CrosstermInput;
     let recorder = scm_record::Recorder::new(
         scm_record::RecordState {
             is_read_only: false,
             files,
             commits: Default::default(),
         },
-        &mut input,
+        input,
     );
     let result = recorder.run().map_err(BuiltinToolError::Record)?;
     let tree_id = apply_diff_builtin(store, left_tree, right_tree, changed_files, &result.files)
@@ -542,7 +759,7 @@ pub fn edit_merge_builtin(
         tree.store().clone(),
         tree,
         tree,
-        vec![path.to_owned()],
+        vec![ChangedFile::new(path.to_owned())],
         &[file],
     )
     .map_err(BuiltinToolError::BackendError)
@@ -551,9 +768,11 @@ pub fn edit_merge_builtin(
 #[cfg(test)]
 mod tests {
     use jj_lib::conflicts::extract_as_single_hunk;
-    use jj_lib::merge::MergedTreeValue;
+    use jj_lib::matchers::EverythingMatcher;
     use jj_lib::repo::Repo;
     use pollster::FutureExt;
+    use scm_record::helpers::TestingInput;
+    use scm_record::{Event, TestingScreenshot};
     use testutils::TestRepo;
 
     use super::*;
@@ -586,9 +805,9 @@ mod tests {
         );
 
         let changed_files = vec![
-            unchanged.to_owned(),
-            changed_path.to_owned(),
-            added_path.to_owned(),
+            ChangedFile::new(unchanged.to_owned()),
+            ChangedFile::new(changed_path.to_owned()),
+            ChangedFile::new(added_path.to_owned()),
         ];
         let files = make_diff_files(store, &left_tree, &right_tree, &changed_files).unwrap();
         insta::assert_debug_snapshot!(files, @r###"
@@ -716,6 +935,320 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_edit_diff_builtin_file_modes() {
+        let test_repo = TestRepo::init();
+        let store = test_repo.repo.store();
+
+        let exec_path = RepoPath::from_internal_string("exec");
+        let empty_added_path = RepoPath::from_internal_string("empty_added");
+        let empty_removed_path = RepoPath::from_internal_string("empty_removed");
+        let symlink_path = RepoPath::from_internal_string("symlink");
+        let left_tree = {
+            let mut tree_builder = store.tree_builder(store.empty_tree_id().clone());
+            testutils::write_normal_file(&mut tree_builder, exec_path, "content\n");
+            testutils::write_normal_file(&mut tree_builder, empty_removed_path, "");
+            testutils::write_symlink(&mut tree_builder, symlink_path, "target");
+            let id = tree_builder.write_tree();
+            MergedTree::legacy(store.get_tree(RepoPath::root(), &id).unwrap())
+        };
+        let right_tree = {
+            let mut tree_builder = store.tree_builder(store.empty_tree_id().clone());
+            testutils::write_executable_file(&mut tree_builder, exec_path, "content\n");
+            testutils::write_normal_file(&mut tree_builder, empty_added_path, "");
+            testutils::write_symlink(&mut tree_builder, symlink_path, "new target");
+            let id = tree_builder.write_tree();
+            MergedTree::legacy(store.get_tree(RepoPath::root(), &id).unwrap())
+        };
+
+        let changed_files = vec![
+            ChangedFile::new(empty_added_path.to_owned()),
+            ChangedFile::new(empty_removed_path.to_owned()),
+            ChangedFile::new(exec_path.to_owned()),
+            ChangedFile::new(symlink_path.to_owned()),
+        ];
+        let files = make_diff_files(store, &left_tree, &right_tree, &changed_files).unwrap();
+        insta::assert_debug_snapshot!(files, @r###"
+        [
+            File {
+                old_path: None,
+                path: "empty_added",
+                file_mode: Some(
+                    FileMode(
+                        0,
+                    ),
+                ),
+                sections: [
+                    FileMode {
+                        is_checked: false,
+                        before: FileMode(
+                            0,
+                        ),
+                        after: FileMode(
+                            33188,
+                        ),
+                    },
+                    Changed {
+                        lines: [],
+                    },
+                ],
+            },
+            File {
+                old_path: None,
+                path: "empty_removed",
+                file_mode: Some(
+                    FileMode(
+                        33188,
+                    ),
+                ),
+                sections: [
+                    FileMode {
+                        is_checked: false,
+                        before: FileMode(
+                            33188,
+                        ),
+                        after: FileMode(
+                            0,
+                        ),
+                    },
+                    Changed {
+                        lines: [],
+                    },
+                ],
+            },
+            File {
+                old_path: None,
+                path: "exec",
+                file_mode: Some(
+                    FileMode(
+                        33188,
+                    ),
+                ),
+                sections: [
+                    FileMode {
+                        is_checked: false,
+                        before: FileMode(
+                            33188,
+                        ),
+                        after: FileMode(
+                            33261,
+                        ),
+                    },
+                    Unchanged {
+                        lines: [
+                            "content\n",
+                        ],
+                    },
+                ],
+            },
+            File {
+                old_path: None,
+                path: "symlink",
+                file_mode: Some(
+                    FileMode(
+                        40960,
+                    ),
+                ),
+                sections: [
+                    Changed {
+                        lines: [
+                            SectionChangedLine {
+                                is_checked: false,
+                                change_type: Removed,
+                                line: "target",
+                            },
+                            SectionChangedLine {
+                                is_checked: false,
+                                change_type: Added,
+                                line: "new target",
+                            },
+                        ],
+                    },
+                ],
+            },
+        ]
+        "###);
+
+        let no_changes_tree_id = apply_diff_builtin(
+            store.clone(),
+            &left_tree,
+            &right_tree,
+            changed_files.clone(),
+            &files,
+        )
+        .unwrap();
+        assert_eq!(no_changes_tree_id, left_tree.id());
+
+        let mut files = files;
+        for file in files.iter_mut() {
+            file.toggle_all();
+        }
+        let all_changes_tree_id = apply_diff_builtin(
+            store.clone(),
+            &left_tree,
+            &right_tree,
+            changed_files,
+            &files,
+        )
+        .unwrap();
+        assert_eq!(all_changes_tree_id, right_tree.id());
+    }
+
+    #[test]
+    fn test_edit_diff_builtin_scripted() {
+        let test_repo = TestRepo::init();
+        let store = test_repo.repo.store();
+
+        let file_path = RepoPath::from_internal_string("file");
+        let left_tree =
+            testutils::create_tree(&test_repo.repo, &[(file_path, "line1\nline2\nline3\n")]);
+        let right_tree = testutils::create_tree(
+            &test_repo.repo,
+            &[(file_path, "line1\nchanged1\nchanged2\nline3\n")],
+        );
+
+        // Select a single added line inside the hunk
+        let screenshot = TestingScreenshot::default();
+        let mut input = TestingInput::new(
+            50,
+            10,
+            [
+                Event::ExpandAll,
+                Event::FocusNext,
+                Event::FocusNext,
+                Event::FocusNext,
+                Event::ToggleItem,
+                screenshot.event(),
+                Event::QuitAccept,
+            ],
+        );
+        let tree_id = edit_diff_builtin_with_input(
+            &left_tree,
+            &right_tree,
+            &EverythingMatcher,
+            &CopyDetectionOptions::default(),
+            &mut input,
+        )
+        .unwrap();
+        insta::assert_snapshot!(screenshot.to_string(), @r###"
+        "[File] [Edit] [Select] [View]                     "
+        "[~] file                                       [-]"
+        "        1 line1⏎                                  "
+        "  [~] Section 1/1                              [-]"
+        "    [ ] - line2⏎                                  "
+        "    (×) + changed1⏎                               "
+        "    [ ] + changed2⏎                               "
+        "        3 line3⏎                                  "
+        "                                                  "
+        "                                                  "
+        "###);
+        insta::assert_snapshot!(testutils::dump_tree(store, &tree_id), @r###"
+        tree eaeded0ccad4123745d5
+          file "file" (551c8925f0640eb7dc81): "line1\nline2\nchanged1\nline3\n"
+        "###);
+
+        // Cancelled edit is an error
+        let mut input = TestingInput::new(50, 10, [Event::QuitCancel]);
+        let result = edit_diff_builtin_with_input(
+            &left_tree,
+            &right_tree,
+            &EverythingMatcher,
+            &CopyDetectionOptions::default(),
+            &mut input,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_edit_diff_builtin_renames() {
+        let test_repo = TestRepo::init();
+        let store = test_repo.repo.store();
+
+        let old_path = RepoPath::from_internal_string("old");
+        let new_path = RepoPath::from_internal_string("new");
+        let left_tree =
+            testutils::create_tree(&test_repo.repo, &[(old_path, "line1\nline2\nline3\n")]);
+        let right_tree =
+            testutils::create_tree(&test_repo.repo, &[(new_path, "line1\nline2\nchanged\n")]);
+
+        // Select the rename but not the changed lines
+        let screenshot = TestingScreenshot::default();
+        let mut input = TestingInput::new(
+            50,
+            8,
+            [
+                Event::ExpandAll,
+                Event::FocusNext,
+                Event::ToggleItem,
+                screenshot.event(),
+                Event::QuitAccept,
+            ],
+        );
+        let tree_id = edit_diff_builtin_with_input(
+            &left_tree,
+            &right_tree,
+            &EverythingMatcher,
+            &CopyDetectionOptions::default(),
+            &mut input,
+        )
+        .unwrap();
+        insta::assert_snapshot!(screenshot.to_string(), @r###"
+        "[File] [Edit] [Select] [View]                     "
+        "[~] old => new                                 [-]"
+        "  (×) File mode changed from 0 to 100644          "
+        "        1 line1⏎                                  "
+        "        2 line2⏎                                  "
+        "  [ ] Section 2/2                              [-]"
+        "    [ ] - line3⏎                                  "
+        "    [ ] + changed⏎                                "
+        "###);
+        insta::assert_snapshot!(testutils::dump_tree(store, &tree_id), @r###"
+        tree 913774b601464ddc652d
+          file "new" (066aff7e033eb6a54543): "line1\nline2\nline3\n"
+        "###);
+
+        // Select the changed lines but not the rename
+        let mut input = TestingInput::new(
+            50,
+            8,
+            [
+                Event::ExpandAll,
+                Event::FocusNext,
+                Event::FocusNext,
+                Event::ToggleItem,
+                Event::QuitAccept,
+            ],
+        );
+        let tree_id = edit_diff_builtin_with_input(
+            &left_tree,
+            &right_tree,
+            &EverythingMatcher,
+            &CopyDetectionOptions::default(),
+            &mut input,
+        )
+        .unwrap();
+        insta::assert_snapshot!(testutils::dump_tree(store, &tree_id), @r###"
+        tree c29e19438c579bfc4ecf
+          file "old" (5215e0292bf9de66af24): "line1\nline2\nchanged\n"
+        "###);
+
+        // Renames aren't paired up if detection is disabled
+        let copy_options = CopyDetectionOptions {
+            detect_renames: false,
+            ..Default::default()
+        };
+        let mut input = TestingInput::new(50, 8, [Event::ToggleAll, Event::QuitAccept]);
+        let tree_id = edit_diff_builtin_with_input(
+            &left_tree,
+            &right_tree,
+            &EverythingMatcher,
+            &copy_options,
+            &mut input,
+        )
+        .unwrap();
+        assert_eq!(tree_id, right_tree.id());
+    }
+
     #[test]
     fn test_make_merge_sections() {
         let test_repo = TestRepo::init();
@@ -9,7 +9,7 @@ use config::ConfigError;
 use futures::StreamExt;
 use itertools::Itertools;
 use jj_lib::backend::{FileId, MergedTreeId, TreeValue};
-use jj_lib::conflicts::{self, materialize_merge_result};
+use jj_lib::conflicts::{self, materialize_merge_result, MergeDriver};
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::local_working_copy::{TreeState, TreeStateError};
 use jj_lib::matchers::Matcher;
@@ -36,8 +36,12 @@ pub struct ExternalMergeTool {
     /// if not specified in the config.
     pub program: String,
     /// Arguments to pass to the program when generating diffs.
-    /// `$left` and `$right` are replaced with the corresponding directories.
+    /// `$left` and `$right` are replaced with the corresponding directories,
+    /// or files if `diff_invocation_mode` is `FileByFile`.
     pub diff_args: Vec<String>,
+    /// Whether to invoke the diff tool once for the whole diff, or once for
+    /// each changed file.
+    pub diff_invocation_mode: DiffToolMode,
     /// Arguments to pass to the program when editing diffs.
     /// `$left` and `$right` are replaced with the corresponding directories.
     pub edit_args: Vec<String>,
@@ -57,11 +61,23 @@ pub struct ExternalMergeTool {
     pub merge_tool_edits_conflict_markers: bool,
 }
 
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffToolMode {
+    /// Invoke the diff tool on directories containing the changed files.
+    #[default]
+    Dir,
+    /// Invoke the diff tool on each changed file. `$path` in the arguments is
+    /// replaced with the path of the file.
+    FileByFile,
+}
+
 impl Default for ExternalMergeTool {
     fn default() -> Self {
         Self {
             program: String::new(),
             diff_args: ["$left", "$right"].map(ToOwned::to_owned).to_vec(),
+            diff_invocation_mode: DiffToolMode::Dir,
             edit_args: ["$left", "$right"].map(ToOwned::to_owned).to_vec(),
             merge_args: vec![],
             merge_tool_edits_conflict_markers: false,
@@ -367,6 +383,7 @@ pub fn run_mergetool_external(
             &file_merge,
             tree.store(),
             repo_path,
+            MergeDriver::Text,
             output_file_contents.as_slice(),
         )
         .block_on()?
@@ -546,11 +563,66 @@ pub fn generate_diff(
         .map_err(ExternalToolError::SetUpDir)?;
     set_readonly_recursively(diff_wc.right_working_copy_path())
         .map_err(ExternalToolError::SetUpDir)?;
-    // TODO: Add support for tools without directory diff functionality?
     // TODO: Somehow propagate --color to the external command?
     let patterns = diff_wc.to_command_variables();
+    let exit_status = invoke_external_diff(ui, writer, tool, &patterns)?;
+    if !exit_status.success() {
+        writeln!(ui.warning(), "{}", format_tool_aborted(&exit_status)).ok();
+    }
+    Ok(())
+}
+
+/// Generates textual diff of a single file by the specified `tool`, and writes
+/// into `writer`.
+///
+/// The contents are written to temporary files named after the file, so tools
+/// can detect the language from the file extension. `path` is substituted for
+/// `$path` in the arguments.
+///
+/// The exit status of the tool is returned instead of reported, since the
+/// caller invokes the tool once per file.
+pub fn generate_file_diff(
+    ui: &Ui,
+    writer: &mut dyn Write,
+    path: &str,
+    left: (&RepoPath, &[u8]),
+    right: (&RepoPath, &[u8]),
+    tool: &ExternalMergeTool,
+) -> Result<ExitStatus, DiffGenerateError> {
+    let temp_dir = new_utf8_temp_dir("jj-diff-").map_err(ExternalToolError::SetUpDir)?;
+    let write_file = |side: &str, (repo_path, content): (&RepoPath, &[u8])| {
+        let file_name = repo_path
+            .components()
+            .next_back()
+            .map_or("file", |name| name.as_str());
+        let dir = temp_dir.path().join(side);
+        std::fs::create_dir(&dir)?;
+        let file_path = dir.join(file_name);
+        std::fs::write(&file_path, content)?;
+        set_readonly_recursively(&file_path)?;
+        Ok(file_path
+            .into_os_string()
+            .into_string()
+            .expect("temp_dir should be valid utf-8"))
+    };
+    let left_path = write_file("left", left).map_err(ExternalToolError::SetUpDir)?;
+    let right_path = write_file("right", right).map_err(ExternalToolError::SetUpDir)?;
+    let patterns = maplit::hashmap! {
+        "left" => left_path.as_str(),
+        "right" => right_path.as_str(),
+        "path" => path,
+    };
+    invoke_external_diff(ui, writer, tool, &patterns)
+}
+
+fn invoke_external_diff(
+    ui: &Ui,
+    writer: &mut dyn Write,
+    tool: &ExternalMergeTool,
+    patterns: &HashMap<&str, &str>,
+) -> Result<ExitStatus, DiffGenerateError> {
     let mut cmd = Command::new(&tool.program);
-    cmd.args(interpolate_variables(&tool.diff_args, &patterns));
+    cmd.args(interpolate_variables(&tool.diff_args, patterns));
     tracing::info!(?cmd, "Invoking the external diff generator:");
     let mut child = cmd
         .stdin(Stdio::null())
@@ -566,13 +638,10 @@ pub fn generate_diff(
     // will exit with 1 if inputs are different.
     let exit_status = child.wait().map_err(ExternalToolError::Io)?;
     tracing::info!(?cmd, ?exit_status, "The external diff generator exited:");
-    if !exit_status.success() {
-        writeln!(ui.warning(), "{}", format_tool_aborted(&exit_status)).ok();
-    }
-    Ok(())
+    Ok(exit_status)
 }
 
-fn format_tool_aborted(exit_status: &ExitStatus) -> String {
+pub fn format_tool_aborted(exit_status: &ExitStatus) -> String {
     let code = exit_status
         .code()
         .map(|c| c.to_string())
@@ -31,8 +31,11 @@ use thiserror::Error;
 
 use self::builtin::{edit_diff_builtin, edit_merge_builtin, BuiltinToolError};
 use self::external::{edit_diff_external, DiffCheckoutError, ExternalToolError};
-pub use self::external::{generate_diff, ExternalMergeTool};
+pub use self::external::{
+    format_tool_aborted, generate_diff, generate_file_diff, DiffToolMode, ExternalMergeTool,
+};
 use crate::config::CommandNameAndArgs;
+use crate::diff_util;
 use crate::ui::Ui;
 
 const BUILTIN_EDITOR_NAME: &str = ":builtin";
@@ -140,7 +143,9 @@ pub fn edit_diff(
     let editor = get_diff_editor_from_settings(ui, settings)?;
     match editor {
         MergeTool::Builtin => {
-            let tree_id = edit_diff_builtin(left_tree, right_tree, matcher).map_err(Box::new)?;
+            let copy_options = diff_util::copy_detection_options(settings)?;
+            let tree_id = edit_diff_builtin(left_tree, right_tree, matcher, &copy_options)
+                .map_err(Box::new)?;
             Ok(tree_id)
         }
         MergeTool::External(editor) => edit_diff_external(
@@ -282,6 +287,7 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                diff_invocation_mode: Dir,
                 edit_args: [
                     "$left",
                     "$right",
@@ -302,6 +308,7 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                diff_invocation_mode: Dir,
                 edit_args: [
                     "-l",
                     "$left",
@@ -324,6 +331,7 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                diff_invocation_mode: Dir,
                 edit_args: [
                     "--diff",
                     "$left",
@@ -350,6 +358,7 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                diff_invocation_mode: Dir,
                 edit_args: [
                     "--edit",
                     "args",
@@ -377,6 +386,7 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                diff_invocation_mode: Dir,
                 edit_args: [
                     "$left",
                     "$right",
@@ -396,6 +406,7 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                diff_invocation_mode: Dir,
                 edit_args: [
                     "$left",
                     "$right",
@@ -439,6 +450,7 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                diff_invocation_mode: Dir,
                 edit_args: [
                     "$left",
                     "$right",
@@ -466,6 +478,7 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                diff_invocation_mode: Dir,
                 edit_args: [
                     "$left",
                     "$right",
@@ -496,6 +509,7 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                diff_invocation_mode: Dir,
                 edit_args: [
                     "$left",
                     "$right",
@@ -27,8 +27,8 @@ use crate::template_parser::{
     self, FunctionCallNode, TemplateAliasesMap, TemplateParseError, TemplateParseResult,
 };
 use crate::templater::{
-    IntoTemplate, PlainTextFormattedProperty, Template, TemplateFunction, TemplateProperty,
-    TemplatePropertyFn, TimestampRange,
+    IntoJsonProperty, IntoTemplate, JsonSerialize, PlainTextFormattedProperty, Template,
+    TemplateFunction, TemplateProperty, TemplatePropertyFn, TimestampRange,
 };
 
 struct OperationTemplateLanguage<'b> {
@@ -56,6 +56,9 @@ impl TemplateLanguage<'static> for OperationTemplateLanguage<'_> {
             OperationTemplatePropertyKind::Core(property) => {
                 template_builder::build_core_method(self, build_ctx, property, function)
             }
+            OperationTemplatePropertyKind::Operation(property) => {
+                build_operation_method(self, build_ctx, property, function)
+            }
             OperationTemplatePropertyKind::OperationId(property) => {
                 build_operation_id_method(self, build_ctx, property, function)
             }
@@ -64,6 +67,13 @@ impl TemplateLanguage<'static> for OperationTemplateLanguage<'_> {
 }
 
 impl OperationTemplateLanguage<'_> {
+    fn wrap_operation(
+        &self,
+        property: impl TemplateProperty<Operation, Output = Operation> + 'static,
+    ) -> OperationTemplatePropertyKind {
+        OperationTemplatePropertyKind::Operation(Box::new(property))
+    }
+
     fn wrap_operation_id(
         &self,
         property: impl TemplateProperty<Operation, Output = OperationId> + 'static,
@@ -74,6 +84,7 @@ impl OperationTemplateLanguage<'_> {
 
 enum OperationTemplatePropertyKind {
     Core(CoreTemplatePropertyKind<'static, Operation>),
+    Operation(Box<dyn TemplateProperty<Operation, Output = Operation>>),
     OperationId(Box<dyn TemplateProperty<Operation, Output = OperationId>>),
 }
 
@@ -81,6 +92,7 @@ impl IntoTemplateProperty<'static, Operation> for OperationTemplatePropertyKind
     fn try_into_boolean(self) -> Option<Box<dyn TemplateProperty<Operation, Output = bool>>> {
         match self {
             OperationTemplatePropertyKind::Core(property) => property.try_into_boolean(),
+            OperationTemplatePropertyKind::Operation(_) => None,
             OperationTemplatePropertyKind::OperationId(_) => None,
         }
     }
@@ -105,9 +117,24 @@ impl IntoTemplateProperty<'static, Operation> for OperationTemplatePropertyKind
     fn try_into_template(self) -> Option<Box<dyn Template<Operation>>> {
         match self {
             OperationTemplatePropertyKind::Core(property) => property.try_into_template(),
+            OperationTemplatePropertyKind::Operation(_) => None,
             OperationTemplatePropertyKind::OperationId(property) => Some(property.into_template()),
         }
     }
+
+    fn try_into_json(
+        self,
+    ) -> Option<Box<dyn TemplateProperty<Operation, Output = serde_json::Value>>> {
+        match self {
+            OperationTemplatePropertyKind::Core(property) => property.try_into_json(),
+            OperationTemplatePropertyKind::Operation(property) => {
+                Some(property.into_json_property())
+            }
+            OperationTemplatePropertyKind::OperationId(property) => {
+                Some(property.into_json_property())
+            }
+        }
+    }
 }
 
 fn build_operation_keyword(
@@ -115,25 +142,59 @@ fn build_operation_keyword(
     name: &str,
     span: pest::Span,
 ) -> TemplateParseResult<OperationTemplatePropertyKind> {
-    fn wrap_fn<O, F: Fn(&Operation) -> O>(f: F) -> TemplatePropertyFn<F> {
-        TemplatePropertyFn(f)
+    // Operation object is lightweight (a few Arc + OperationId)
+    let property = TemplatePropertyFn(|op: &Operation| op.clone());
+    if name == "self" {
+        return Ok(language.wrap_operation(property));
+    }
+    build_operation_keyword_opt(language, property, name)
+        .ok_or_else(|| TemplateParseError::no_such_keyword(name, span))
+}
+
+fn build_operation_method(
+    language: &OperationTemplateLanguage,
+    _build_ctx: &BuildContext<OperationTemplatePropertyKind>,
+    self_property: impl TemplateProperty<Operation, Output = Operation> + 'static,
+    function: &FunctionCallNode,
+) -> TemplateParseResult<OperationTemplatePropertyKind> {
+    if let Some(property) = build_operation_keyword_opt(language, self_property, function.name) {
+        template_parser::expect_no_arguments(function)?;
+        Ok(property)
+    } else {
+        Err(TemplateParseError::no_such_method("Operation", function))
+    }
+}
+
+fn build_operation_keyword_opt(
+    language: &OperationTemplateLanguage,
+    property: impl TemplateProperty<Operation, Output = Operation> + 'static,
+    name: &str,
+) -> Option<OperationTemplatePropertyKind> {
+    fn wrap_fn<O>(
+        property: impl TemplateProperty<Operation, Output = Operation>,
+        f: impl Fn(&Operation) -> O,
+    ) -> impl TemplateProperty<Operation, Output = O> {
+        TemplateFunction::new(property, move |op| f(&op))
     }
     fn wrap_metadata_fn<O>(
-        f: impl Fn(&OperationMetadata) -> O + 'static,
+        property: impl TemplateProperty<Operation, Output = Operation>,
+        f: impl Fn(&OperationMetadata) -> O,
     ) -> impl TemplateProperty<Operation, Output = O> {
-        wrap_fn(move |op| f(&op.store_operation().metadata))
+        wrap_fn(property, move |op| f(&op.store_operation().metadata))
     }
 
     let property = match name {
         "current_operation" => {
             let current_op_id = language.current_op_id.cloned();
-            language.wrap_boolean(wrap_fn(move |op| Some(op.id()) == current_op_id.as_ref()))
-        }
-        "description" => {
-            language.wrap_string(wrap_metadata_fn(|metadata| metadata.description.clone()))
+            language.wrap_boolean(wrap_fn(property, move |op| {
+                Some(op.id()) == current_op_id.as_ref()
+            }))
         }
-        "id" => language.wrap_operation_id(wrap_fn(|op| op.id().clone())),
-        "tags" => language.wrap_string(wrap_metadata_fn(|metadata| {
+        "description" => language.wrap_string(wrap_metadata_fn(property, |metadata| {
+            metadata.description.clone()
+        })),
+        "id" => language.wrap_operation_id(wrap_fn(property, |op| op.id().clone())),
+        "tags" => language.wrap_string(wrap_metadata_fn(property, |metadata| {
             // TODO: introduce map type
             metadata
                 .tags
@@ -141,21 +202,48 @@ fn build_operation_keyword(
                 .map(|(key, value)| format!("{key}: {value}"))
                 .join("\n")
         })),
-        "time" => language.wrap_timestamp_range(wrap_metadata_fn(|metadata| TimestampRange {
-            start: metadata.start_time.clone(),
-            end: metadata.end_time.clone(),
-        })),
-        "user" => language.wrap_string(wrap_metadata_fn(|metadata| {
+        "time" => {
+            language.wrap_timestamp_range(wrap_metadata_fn(property, |metadata| TimestampRange {
+                start: metadata.start_time.clone(),
+                end: metadata.end_time.clone(),
+            }))
+        }
+        "user" => language.wrap_string(wrap_metadata_fn(property, |metadata| {
             // TODO: introduce dedicated type and provide accessors?
             format!("{}@{}", metadata.username, metadata.hostname)
         })),
         "root" => {
             let root_op_id = language.root_op_id.clone();
-            language.wrap_boolean(wrap_fn(move |op| op.id() == &root_op_id))
+            language.wrap_boolean(wrap_fn(property, move |op| op.id() == &root_op_id))
         }
-        _ => return Err(TemplateParseError::no_such_keyword(name, span)),
+        _ => return None,
     };
-    Ok(property)
+    Some(property)
+}
+
+impl JsonSerialize for Operation {
+    fn to_json(&self) -> serde_json::Value {
+        let metadata = &self.store_operation().metadata;
+        serde_json::json!({
+            "id": self.id().to_json(),
+            "parents": self.parent_ids().iter().map(|id| id.to_json()).collect_vec(),
+            "time": TimestampRange {
+                start: metadata.start_time.clone(),
+                end: metadata.end_time.clone(),
+            }
+            .to_json(),
+            "description": metadata.description,
+            "hostname": metadata.hostname,
+            "username": metadata.username,
+            "tags": metadata.tags,
+        })
+    }
+}
+
+impl JsonSerialize for OperationId {
+    fn to_json(&self) -> serde_json::Value {
+        self.hex().into()
+    }
 }
 
 impl Template<()> for OperationId {
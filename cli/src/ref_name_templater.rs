@@ -0,0 +1,166 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::commit_templater::RefName;
+use crate::template_builder::{
+    self, BuildContext, CoreTemplatePropertyKind, IntoTemplateProperty, TemplateLanguage,
+};
+use crate::template_parser::{
+    self, FunctionCallNode, TemplateAliasesMap, TemplateParseError, TemplateParseResult,
+};
+use crate::templater::{
+    IntoJsonProperty, IntoTemplate, PlainTextFormattedProperty, Template, TemplateFunction,
+    TemplateProperty, TemplatePropertyFn,
+};
+
+struct RefNameTemplateLanguage;
+
+impl TemplateLanguage<'static> for RefNameTemplateLanguage {
+    type Context = RefName;
+    type Property = RefNameTemplatePropertyKind;
+
+    template_builder::impl_core_wrap_property_fns!('static, RefNameTemplatePropertyKind::Core);
+
+    fn build_keyword(&self, name: &str, span: pest::Span) -> TemplateParseResult<Self::Property> {
+        build_ref_name_keyword(self, name, span)
+    }
+
+    fn build_method(
+        &self,
+        build_ctx: &BuildContext<Self::Property>,
+        property: Self::Property,
+        function: &FunctionCallNode,
+    ) -> TemplateParseResult<Self::Property> {
+        match property {
+            RefNameTemplatePropertyKind::Core(property) => {
+                template_builder::build_core_method(self, build_ctx, property, function)
+            }
+            RefNameTemplatePropertyKind::RefName(property) => {
+                build_ref_name_method(self, build_ctx, property, function)
+            }
+        }
+    }
+}
+
+impl RefNameTemplateLanguage {
+    fn wrap_ref_name(
+        &self,
+        property: impl TemplateProperty<RefName, Output = RefName> + 'static,
+    ) -> RefNameTemplatePropertyKind {
+        RefNameTemplatePropertyKind::RefName(Box::new(property))
+    }
+}
+
+enum RefNameTemplatePropertyKind {
+    Core(CoreTemplatePropertyKind<'static, RefName>),
+    RefName(Box<dyn TemplateProperty<RefName, Output = RefName>>),
+}
+
+impl IntoTemplateProperty<'static, RefName> for RefNameTemplatePropertyKind {
+    fn try_into_boolean(self) -> Option<Box<dyn TemplateProperty<RefName, Output = bool>>> {
+        match self {
+            RefNameTemplatePropertyKind::Core(property) => property.try_into_boolean(),
+            RefNameTemplatePropertyKind::RefName(_) => None,
+        }
+    }
+
+    fn try_into_integer(self) -> Option<Box<dyn TemplateProperty<RefName, Output = i64>>> {
+        match self {
+            RefNameTemplatePropertyKind::Core(property) => property.try_into_integer(),
+            _ => None,
+        }
+    }
+
+    fn try_into_plain_text(self) -> Option<Box<dyn TemplateProperty<RefName, Output = String>>> {
+        match self {
+            RefNameTemplatePropertyKind::Core(property) => property.try_into_plain_text(),
+            _ => {
+                let template = self.try_into_template()?;
+                Some(Box::new(PlainTextFormattedProperty::new(template)))
+            }
+        }
+    }
+
+    fn try_into_template(self) -> Option<Box<dyn Template<RefName>>> {
+        match self {
+            RefNameTemplatePropertyKind::Core(property) => property.try_into_template(),
+            RefNameTemplatePropertyKind::RefName(property) => Some(property.into_template()),
+        }
+    }
+
+    fn try_into_json(
+        self,
+    ) -> Option<Box<dyn TemplateProperty<RefName, Output = serde_json::Value>>> {
+        match self {
+            RefNameTemplatePropertyKind::Core(property) => property.try_into_json(),
+            RefNameTemplatePropertyKind::RefName(property) => Some(property.into_json_property()),
+        }
+    }
+}
+
+fn build_ref_name_keyword(
+    language: &RefNameTemplateLanguage,
+    name: &str,
+    span: pest::Span,
+) -> TemplateParseResult<RefNameTemplatePropertyKind> {
+    let property = TemplatePropertyFn(|ref_name: &RefName| ref_name.clone());
+    if name == "self" {
+        return Ok(language.wrap_ref_name(property));
+    }
+    build_ref_name_keyword_opt(language, property, name)
+        .ok_or_else(|| TemplateParseError::no_such_keyword(name, span))
+}
+
+fn build_ref_name_method(
+    language: &RefNameTemplateLanguage,
+    _build_ctx: &BuildContext<RefNameTemplatePropertyKind>,
+    self_property: impl TemplateProperty<RefName, Output = RefName> + 'static,
+    function: &FunctionCallNode,
+) -> TemplateParseResult<RefNameTemplatePropertyKind> {
+    if let Some(property) = build_ref_name_keyword_opt(language, self_property, function.name) {
+        template_parser::expect_no_arguments(function)?;
+        Ok(property)
+    } else {
+        Err(TemplateParseError::no_such_method("RefName", function))
+    }
+}
+
+fn build_ref_name_keyword_opt(
+    language: &RefNameTemplateLanguage,
+    property: impl TemplateProperty<RefName, Output = RefName> + 'static,
+    name: &str,
+) -> Option<RefNameTemplatePropertyKind> {
+    let property = match name {
+        "name" => language.wrap_string(TemplateFunction::new(property, |ref_name| ref_name.name)),
+        "remote" => language.wrap_string(TemplateFunction::new(property, |ref_name| {
+            ref_name.remote.unwrap_or_default()
+        })),
+        "conflict" => language.wrap_boolean(TemplateFunction::new(property, |ref_name| {
+            ref_name.conflict
+        })),
+        "synced" => {
+            language.wrap_boolean(TemplateFunction::new(property, |ref_name| ref_name.synced))
+        }
+        _ => return None,
+    };
+    Some(property)
+}
+
+pub fn parse(
+    template_text: &str,
+    aliases_map: &TemplateAliasesMap,
+) -> TemplateParseResult<Box<dyn Template<RefName>>> {
+    let node = template_parser::parse(template_text, aliases_map)?;
+    template_builder::build(&RefNameTemplateLanguage, &node)
+}
@@ -22,9 +22,10 @@ use crate::template_parser::{
     TemplateParseResult,
 };
 use crate::templater::{
-    ConcatTemplate, ConditionalTemplate, IntoTemplate, LabelTemplate, ListPropertyTemplate,
-    ListTemplate, Literal, PlainTextFormattedProperty, PropertyPlaceholder, ReformatTemplate,
-    SeparateTemplate, Template, TemplateFunction, TemplateProperty, TimestampRange,
+    ConcatTemplate, ConditionalTemplate, IntoJsonProperty, IntoTemplate, LabelTemplate,
+    ListPropertyTemplate, ListTemplate, Literal, PlainTextFormattedProperty, PropertyPlaceholder,
+    ReformatTemplate, SeparateTemplate, Template, TemplateFunction, TemplateProperty,
+    TimestampRange,
 };
 use crate::{text_util, time_util};
 
@@ -129,7 +130,8 @@ macro_rules! impl_wrap_property_fns {
     };
 }
 
-pub(crate) use {impl_core_wrap_property_fns, impl_wrap_property_fns};
+pub(crate) use impl_core_wrap_property_fns;
+pub(crate) use impl_wrap_property_fns;
 
 /// Provides access to basic template property types.
 pub trait IntoTemplateProperty<'a, C> {
@@ -138,6 +140,8 @@ pub trait IntoTemplateProperty<'a, C> {
 
     fn try_into_plain_text(self) -> Option<Box<dyn TemplateProperty<C, Output = String> + 'a>>;
     fn try_into_template(self) -> Option<Box<dyn Template<C> + 'a>>;
+    fn try_into_json(self)
+        -> Option<Box<dyn TemplateProperty<C, Output = serde_json::Value> + 'a>>;
 }
 
 pub enum CoreTemplatePropertyKind<'a, I> {
@@ -208,6 +212,26 @@ impl<'a, I: 'a> IntoTemplateProperty<'a, I> for CoreTemplatePropertyKind<'a, I>
             CoreTemplatePropertyKind::ListTemplate(template) => Some(template.into_template()),
         }
     }
+
+    fn try_into_json(
+        self,
+    ) -> Option<Box<dyn TemplateProperty<I, Output = serde_json::Value> + 'a>> {
+        match self {
+            CoreTemplatePropertyKind::String(property) => Some(property.into_json_property()),
+            CoreTemplatePropertyKind::StringList(property) => Some(property.into_json_property()),
+            CoreTemplatePropertyKind::Boolean(property) => Some(property.into_json_property()),
+            CoreTemplatePropertyKind::Integer(property) => Some(property.into_json_property()),
+            CoreTemplatePropertyKind::Signature(property) => Some(property.into_json_property()),
+            CoreTemplatePropertyKind::Timestamp(property) => Some(property.into_json_property()),
+            CoreTemplatePropertyKind::TimestampRange(property) => {
+                Some(property.into_json_property())
+            }
+            // Formatted output has no defined structure. Use plain-text
+            // string if needed.
+            CoreTemplatePropertyKind::Template(_) => None,
+            CoreTemplatePropertyKind::ListTemplate(_) => None,
+        }
+    }
 }
 
 /// Opaque struct that represents a template value.
@@ -254,6 +278,15 @@ impl<P> Expression<P> {
         self.property.try_into_plain_text()
     }
 
+    pub fn try_into_json<'a, C: 'a>(
+        self,
+    ) -> Option<Box<dyn TemplateProperty<C, Output = serde_json::Value> + 'a>>
+    where
+        P: IntoTemplateProperty<'a, C>,
+    {
+        self.property.try_into_json()
+    }
+
     pub fn try_into_template<'a, C: 'a>(self) -> Option<Box<dyn Template<C> + 'a>>
     where
         P: IntoTemplateProperty<'a, C>,
@@ -433,11 +466,24 @@ fn build_string_method<'a, L: TemplateLanguage<'a>>(
             template_parser::expect_no_arguments(function)?;
             language.wrap_string(TemplateFunction::new(self_property, |s| s.to_lowercase()))
         }
+        "slug" => {
+            template_parser::expect_no_arguments(function)?;
+            language.wrap_string(TemplateFunction::new(self_property, |s| slugify(&s)))
+        }
         _ => return Err(TemplateParseError::no_such_method("String", function)),
     };
     Ok(property)
 }
 
+/// Converts the string to lowercase alphanumeric words separated by `-`, which
+/// can be used in a branch name.
+fn slugify(s: &str) -> String {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .join("-")
+}
+
 fn build_boolean_method<'a, L: TemplateLanguage<'a>>(
     _language: &L,
     _build_ctx: &BuildContext<L::Property>,
@@ -735,6 +781,11 @@ fn build_global_function<'a, L: TemplateLanguage<'a>>(
             let template = ConditionalTemplate::new(condition, true_template, false_template);
             language.wrap_template(Box::new(template))
         }
+        "json" => {
+            let [value_node] = template_parser::expect_exact_arguments(function)?;
+            let value = expect_json_expression(language, build_ctx, value_node)?;
+            language.wrap_string(TemplateFunction::new(value, |value| value.to_string()))
+        }
         "concat" => {
             let contents = function
                 .args
@@ -850,6 +901,16 @@ pub fn expect_plain_text_expression<'a, L: TemplateLanguage<'a>>(
         .ok_or_else(|| TemplateParseError::expected_type("Template", node.span))
 }
 
+pub fn expect_json_expression<'a, L: TemplateLanguage<'a>>(
+    language: &L,
+    build_ctx: &BuildContext<L::Property>,
+    node: &ExpressionNode,
+) -> TemplateParseResult<Box<dyn TemplateProperty<L::Context, Output = serde_json::Value> + 'a>> {
+    build_expression(language, build_ctx, node)?
+        .try_into_json()
+        .ok_or_else(|| TemplateParseError::expected_type("Serializable", node.span))
+}
+
 pub fn expect_template_expression<'a, L: TemplateLanguage<'a>>(
     language: &L,
     build_ctx: &BuildContext<L::Property>,
@@ -1355,6 +1416,12 @@ mod tests {
         // ranges with end > start are empty
         insta::assert_snapshot!(env.render_ok(r#""abcdef".substr(4, 2)"#), @"");
         insta::assert_snapshot!(env.render_ok(r#""abcdef".substr(-2, -4)"#), @"");
+
+        insta::assert_snapshot!(env.render_ok(r#""".slug()"#), @"");
+        insta::assert_snapshot!(
+            env.render_ok(r#""Fix the `foo()` bug!\n\nDetails".slug()"#),
+            @"fix-the-foo-bug-details");
+        insta::assert_snapshot!(env.render_ok(r#""--föö_bar v2.0 ".slug()"#), @"föö-bar-v2-0");
     }
 
     #[test]
@@ -1745,4 +1812,81 @@ mod tests {
             env.render_ok(r#"separate(hidden, "X", "Y", "Z")"#),
             @"XfalseYfalseZ");
     }
+
+    #[test]
+    fn test_json_function() {
+        let mut env = TestTemplateEnv::default();
+        env.add_keyword("description", |language| {
+            language.wrap_string(Literal("line 1\n\"quoted\"\n".to_owned()))
+        });
+        env.add_keyword("tags", |language| {
+            language.wrap_string_list(Literal(vec!["a".to_owned(), "b".to_owned()]))
+        });
+        env.add_keyword("empty", |language| language.wrap_boolean(Literal(true)));
+        env.add_keyword("size", |language| language.wrap_integer(Literal(-1)));
+        env.add_keyword("author", |language| {
+            language.wrap_signature(Literal(Signature {
+                name: "Test User".to_owned(),
+                email: "test.user@example.com".to_owned(),
+                timestamp: new_timestamp(1234567890123, 120),
+            }))
+        });
+        env.add_keyword("t0", |language| {
+            language.wrap_timestamp(Literal(new_timestamp(0, 0)))
+        });
+        env.add_keyword("range", |language| {
+            language.wrap_timestamp_range(Literal(TimestampRange {
+                start: new_timestamp(0, 0),
+                end: new_timestamp(1000, -60),
+            }))
+        });
+
+        insta::assert_snapshot!(
+            env.render_ok(r#"json(description)"#),
+            @r###""line 1\n\"quoted\"\n""###);
+        insta::assert_snapshot!(env.render_ok(r#"json("")"#), @r###""""###);
+        insta::assert_snapshot!(env.render_ok(r#"json(tags)"#), @r###"["a","b"]"###);
+        insta::assert_snapshot!(env.render_ok(r#"json(empty)"#), @"true");
+        insta::assert_snapshot!(env.render_ok(r#"json(size)"#), @"-1");
+        insta::assert_snapshot!(
+            env.render_ok(r#"json(author)"#),
+            @r###"{"email":"test.user@example.com","name":"Test User","timestamp":"2009-02-14T01:31:30.123+02:00"}"###);
+        insta::assert_snapshot!(
+            env.render_ok(r#"json(t0)"#),
+            @r###""1970-01-01T00:00:00+00:00""###);
+        insta::assert_snapshot!(
+            env.render_ok(r#"json(range)"#),
+            @r###"{"end":"1969-12-31T23:00:01-01:00","start":"1970-01-01T00:00:00+00:00"}"###);
+
+        // Label is not applied to the serialized value
+        insta::assert_snapshot!(
+            env.render_ok(r#"json(description.first_line())"#),
+            @r###""line 1""###);
+
+        // Template types have no defined structure
+        insta::assert_snapshot!(env.parse_err(r#"json(label("a", "b"))"#), @r###"
+         --> 1:6
+          |
+        1 | json(label("a", "b"))
+          |      ^-------------^
+          |
+          = Expected expression of type "Serializable"
+        "###);
+        insta::assert_snapshot!(env.parse_err(r#"json(tags.map(|x| x))"#), @r###"
+         --> 1:6
+          |
+        1 | json(tags.map(|x| x))
+          |      ^-------------^
+          |
+          = Expected expression of type "Serializable"
+        "###);
+        insta::assert_snapshot!(env.parse_err(r#"json()"#), @r###"
+         --> 1:6
+          |
+        1 | json()
+          |      ^
+          |
+          = Function "json": Expected 1 arguments
+        "###);
+    }
 }
@@ -43,6 +43,15 @@ pub trait IntoTemplate<'a, C> {
     fn into_template(self) -> Box<dyn Template<C> + 'a>;
 }
 
+/// Template value that has a stable JSON representation.
+pub trait JsonSerialize {
+    fn to_json(&self) -> serde_json::Value;
+}
+
+pub trait IntoJsonProperty<'a, C> {
+    fn into_json_property(self) -> Box<dyn TemplateProperty<C, Output = serde_json::Value> + 'a>;
+}
+
 impl<C, T: Template<C> + ?Sized> Template<C> for &T {
     fn format(&self, context: &C, formatter: &mut dyn Formatter) -> io::Result<()> {
         <T as Template<C>>::format(self, context, formatter)
@@ -136,6 +145,58 @@ impl Template<()> for i64 {
     }
 }
 
+impl JsonSerialize for String {
+    fn to_json(&self) -> serde_json::Value {
+        self.as_str().into()
+    }
+}
+
+impl JsonSerialize for bool {
+    fn to_json(&self) -> serde_json::Value {
+        (*self).into()
+    }
+}
+
+impl JsonSerialize for i64 {
+    fn to_json(&self) -> serde_json::Value {
+        (*self).into()
+    }
+}
+
+impl<T: JsonSerialize> JsonSerialize for Vec<T> {
+    fn to_json(&self) -> serde_json::Value {
+        self.iter().map(|item| item.to_json()).collect()
+    }
+}
+
+impl JsonSerialize for Signature {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "email": self.email,
+            "timestamp": self.timestamp.to_json(),
+        })
+    }
+}
+
+impl JsonSerialize for Timestamp {
+    fn to_json(&self) -> serde_json::Value {
+        // Serialized as RFC 3339 string with the original time zone offset
+        time_util::datetime_from_timestamp(self).map_or(serde_json::Value::Null, |datetime| {
+            datetime.to_rfc3339().into()
+        })
+    }
+}
+
+impl JsonSerialize for TimestampRange {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "start": self.start.to_json(),
+            "end": self.end.to_json(),
+        })
+    }
+}
+
 pub struct LabelTemplate<T, L> {
     content: T,
     labels: L,
@@ -363,6 +424,15 @@ where
     }
 }
 
+impl<'a, C: 'a, O> IntoJsonProperty<'a, C> for Box<dyn TemplateProperty<C, Output = O> + 'a>
+where
+    O: JsonSerialize + 'a,
+{
+    fn into_json_property(self) -> Box<dyn TemplateProperty<C, Output = serde_json::Value> + 'a> {
+        Box::new(TemplateFunction::new(self, |value| value.to_json()))
+    }
+}
+
 /// Adapter to turn template back to string property.
 pub struct PlainTextFormattedProperty<T> {
     template: T,
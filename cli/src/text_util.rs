@@ -98,6 +98,24 @@ fn skip_start_pos(text: &str, width: usize) -> (usize, usize) {
     (text.len(), acc_width)
 }
 
+/// Splits `text` so that the first part fits in `max_width`, returning
+/// `(head, tail, head_width)`.
+///
+/// 0-width characters following the last character of the `head` are included
+/// in the `head`.
+pub fn split_at_width(text: &str, max_width: usize) -> (&str, &str, usize) {
+    let mut acc_width = 0;
+    for (i, c) in text.char_indices() {
+        let new_width = acc_width + c.width().unwrap_or(0);
+        if new_width > max_width {
+            let (head, tail) = text.split_at(i);
+            return (head, tail, acc_width);
+        }
+        acc_width = new_width;
+    }
+    (text, "", acc_width)
+}
+
 /// Removes leading 0-width characters.
 fn trim_start_zero_width_chars(text: &str) -> &str {
     text.trim_start_matches(|c: char| c.width().unwrap_or(0) == 0)
@@ -286,6 +304,21 @@ mod tests {
         String::from_utf8(output).unwrap()
     }
 
+    #[test]
+    fn test_split_at_width() {
+        assert_eq!(split_at_width("", 0), ("", "", 0));
+        assert_eq!(split_at_width("abc", 0), ("", "abc", 0));
+        assert_eq!(split_at_width("abc", 2), ("ab", "c", 2));
+        assert_eq!(split_at_width("abc", 5), ("abc", "", 3));
+
+        // Wide characters aren't split
+        assert_eq!(split_at_width("一二三", 3), ("一", "二三", 2));
+        assert_eq!(split_at_width("一二三", 4), ("一二", "三", 4));
+
+        // Decomposed characters stay with their base character
+        assert_eq!(split_at_width("e\u{301}f", 1), ("e\u{301}", "f", 1));
+    }
+
     #[test]
     fn test_elide_start() {
         // Empty string
@@ -36,6 +36,11 @@ struct Args {
 
 fn files_recursively(dir: &Path) -> HashSet<String> {
     let mut files = HashSet::new();
+    if dir.is_file() {
+        // Invoked on a single file
+        files.insert(dir.file_name().unwrap().to_str().unwrap().to_string());
+        return files;
+    }
     for dir_entry in std::fs::read_dir(dir).unwrap() {
         let dir_entry = dir_entry.unwrap();
         let base_name = dir_entry.file_name().to_str().unwrap().to_string();
@@ -60,6 +65,7 @@ fn main() {
         match parts.as_slice() {
             [""] => {}
             ["fail"] => exit(1),
+            ["fail", code] => exit(code.parse().unwrap()),
             ["files-before", ..] => {
                 let expected = parts[1..].iter().copied().map(str::to_string).collect();
                 let actual = files_recursively(&args.before);
@@ -0,0 +1,63 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{Read, Write};
+use std::process::exit;
+
+use clap::Parser;
+
+/// A fake code formatter, useful for testing
+///
+/// Reads the input from stdin and writes the output to stdout.
+#[derive(Parser, Debug)]
+#[clap()]
+struct Args {
+    /// Convert the input to uppercase
+    #[arg(long)]
+    uppercase: bool,
+    /// Reverse the order of the lines of the input
+    #[arg(long)]
+    reverse: bool,
+    /// Append this text to the output
+    #[arg(long)]
+    append: Option<String>,
+    /// Exit with a failure after writing the output
+    #[arg(long)]
+    fail: bool,
+}
+
+fn main() {
+    let args: Args = Args::parse();
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input).unwrap();
+    let mut output = if args.uppercase {
+        input.to_uppercase()
+    } else {
+        input
+    };
+    if args.reverse {
+        output = output
+            .split_inclusive('\n')
+            .rev()
+            .collect::<Vec<_>>()
+            .concat();
+    }
+    if let Some(text) = &args.append {
+        output.push_str(text);
+    }
+    std::io::stdout().write_all(output.as_bytes()).unwrap();
+    if args.fail {
+        exit(1);
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+pub mod common;
+
+fn get_diffs(test_env: &TestEnvironment, repo_path: &Path) -> String {
+    let template = r#"description.first_line() ++ "\n""#;
+    test_env.jj_cmd_success(
+        repo_path,
+        &[
+            "log",
+            "--no-graph",
+            "-r",
+            "~root()",
+            "-T",
+            template,
+            "--git",
+        ],
+    )
+}
+
+#[test]
+fn test_absorb_simple() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "a"]);
+    std::fs::write(repo_path.join("file"), "a1\na2\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "b"]);
+    std::fs::write(repo_path.join("file"), "a1\na2\nb1\nb2\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "c"]);
+
+    // Modify a line from each commit, and insert a line between lines from
+    // different commits, which is ambiguous
+    std::fs::write(repo_path.join("file"), "A1\na2\nnew\nb1\nB2\n").unwrap();
+    std::fs::write(repo_path.join("added"), "added\n").unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["absorb"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Skipping added: added files can't be absorbed
+    Absorbed changes into these revisions:
+      kkmpptxz ec05e0f8 b
+      qpvuntsm e5320a41 a
+    Rebased 1 descendant commits
+    Working copy now at: zsuskuln 2153e291 c
+    Parent commit      : kkmpptxz ec05e0f8 b
+    "###);
+    insta::assert_snapshot!(get_diffs(&test_env, &repo_path), @r###"
+    c
+    diff --git a/added b/added
+    new file mode 100644
+    index 0000000000..d5f7fc3f74
+    --- /dev/null
+    +++ b/added
+    @@ -1,0 +1,1 @@
+    +added
+    diff --git a/file b/file
+    index 11e2000b7c...e2e7405823 100644
+    --- a/file
+    +++ b/file
+    @@ -1,4 +1,5 @@
+     A1
+     a2
+    +new
+     b1
+     B2
+    b
+    diff --git a/file b/file
+    index cea9f517ac...11e2000b7c 100644
+    --- a/file
+    +++ b/file
+    @@ -1,2 +1,4 @@
+     A1
+     a2
+    +b1
+    +B2
+    a
+    diff --git a/file b/file
+    new file mode 100644
+    index 0000000000..cea9f517ac
+    --- /dev/null
+    +++ b/file
+    @@ -1,0 +1,2 @@
+    +A1
+    +a2
+    "###);
+
+    // Nothing left to absorb
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["absorb"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Skipping added: added files can't be absorbed
+    Nothing changed.
+    "###);
+}
+
+#[test]
+fn test_absorb_immutable_and_paths() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "a"]);
+    std::fs::write(repo_path.join("file1"), "a1\na2\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "a1\na2\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "b"]);
+    std::fs::write(repo_path.join("file1"), "a1\na2\nb1\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "a1\na2\nb1\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "c"]);
+    std::fs::write(repo_path.join("file1"), "A1\na2\nB1\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "A1\na2\nB1\n").unwrap();
+    test_env.add_config(r#"revset-aliases."immutable_heads()" = "description(a)""#);
+
+    // Lines from immutable commits stay, and only the given paths are absorbed
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["absorb", "file1"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Absorbed changes into these revisions:
+      kkmpptxz f3523855 b
+    Rebased 1 descendant commits
+    Working copy now at: zsuskuln afee910e c
+    Parent commit      : kkmpptxz f3523855 b
+    "###);
+    insta::assert_snapshot!(get_diffs(&test_env, &repo_path), @r###"
+    c
+    diff --git a/file1 b/file1
+    index 8fc0b7989d...65f6182a49 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,3 +1,3 @@
+    -a1
+    +A1
+     a2
+     B1
+    diff --git a/file2 b/file2
+    index 4281c5570d...65f6182a49 100644
+    --- a/file2
+    +++ b/file2
+    @@ -1,3 +1,3 @@
+    -a1
+    +A1
+     a2
+    -b1
+    +B1
+    b
+    diff --git a/file1 b/file1
+    index 0016606ee0...8fc0b7989d 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,2 +1,3 @@
+     a1
+     a2
+    +B1
+    diff --git a/file2 b/file2
+    index 0016606ee0...4281c5570d 100644
+    --- a/file2
+    +++ b/file2
+    @@ -1,2 +1,3 @@
+     a1
+     a2
+    +b1
+    a
+    diff --git a/file1 b/file1
+    new file mode 100644
+    index 0000000000..0016606ee0
+    --- /dev/null
+    +++ b/file1
+    @@ -1,0 +1,2 @@
+    +a1
+    +a2
+    diff --git a/file2 b/file2
+    new file mode 100644
+    index 0000000000..0016606ee0
+    --- /dev/null
+    +++ b/file2
+    @@ -1,0 +1,2 @@
+    +a1
+    +a2
+    "###);
+
+    // The source must be mutable
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["absorb", "--from", "description(a)"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Commit 110795593a8f is immutable
+    Hint: Configure the set of immutable commits via `revset-aliases.immutable_heads()`.
+    "###);
+}
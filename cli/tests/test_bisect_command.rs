@@ -0,0 +1,135 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+pub mod common;
+
+/// Creates a linear history of commits described "1" to "6", where commit "n"
+/// sets the contents of `file` to "n".
+fn create_commits(test_env: &TestEnvironment, repo_path: &Path) {
+    for i in 1..=6 {
+        test_env.jj_cmd_ok(repo_path, &["new", "-m", &i.to_string()]);
+        std::fs::write(repo_path.join("file"), i.to_string()).unwrap();
+    }
+    test_env.jj_cmd_ok(repo_path, &["new"]);
+}
+
+#[test]
+fn test_bisect_manual() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    create_commits(&test_env, &repo_path);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["bisect", "start", "--bad", "@-"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Mark at least one good and one bad revision to start bisecting");
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["bisect", "good", "-r", "description(1)"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Now testing: zsuskuln 85c3d7cf 3
+    Working copy now at: znkkpsqq 1c45a93f (empty) (no description set)
+    Parent commit      : zsuskuln 85c3d7cf 3
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    // The revision being tested is marked by default
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["bisect", "bad"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Now testing: kkmpptxz 55ea69f1 2
+    Working copy now at: kpqxywon b0f21928 (empty) (no description set)
+    Parent commit      : kkmpptxz 55ea69f1 2
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["bisect", "skip"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    The first bad revision is one of:
+      zsuskuln 85c3d7cf 3
+      kkmpptxz 55ea69f1 2
+    "###);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["bisect", "good"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"The first bad revision is: zsuskuln 85c3d7cf 3");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["bisect", "start"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: A bisection is already in progress
+    Hint: Use `jj bisect reset` to stop it first
+    "###);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["bisect", "reset"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Stopped bisecting");
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["bisect", "good"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No bisection in progress
+    Hint: Start one with `jj bisect start`
+    "###);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_bisect_run() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    create_commits(&test_env, &repo_path);
+
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "bisect",
+            "start",
+            "--edit",
+            "--good",
+            "description(1)",
+            "--bad",
+            "description(6)",
+        ],
+    );
+    // Commit "3" can't be tested
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "bisect",
+            "run",
+            "--command",
+            "n=$(cat file); [ $n -eq 3 ] && exit 125; [ $n -lt 4 ]",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Running 'n=$(cat file); [ $n -eq 3 ] && exit 125; [ $n -lt 4 ]'
+    The revision zsuskuln 85c3d7cf 3 is skipped
+    Now testing: mzvwutvl 41cfbbd1 4
+    Working copy now at: mzvwutvl 41cfbbd1 4
+    Parent commit      : zsuskuln 85c3d7cf 3
+    Added 0 files, modified 1 files, removed 0 files
+    Running 'n=$(cat file); [ $n -eq 3 ] && exit 125; [ $n -lt 4 ]'
+    The revision mzvwutvl 41cfbbd1 4 is bad
+    Now testing: kkmpptxz 55ea69f1 2
+    Working copy now at: kkmpptxz 55ea69f1 2
+    Parent commit      : rlvkpnrz e8487ca1 1
+    Added 0 files, modified 1 files, removed 0 files
+    Running 'n=$(cat file); [ $n -eq 3 ] && exit 125; [ $n -lt 4 ]'
+    The revision kkmpptxz 55ea69f1 2 is good
+    The first bad revision is one of:
+      mzvwutvl 41cfbbd1 4
+      zsuskuln 85c3d7cf 3
+    "###);
+}
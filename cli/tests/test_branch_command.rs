@@ -1008,6 +1008,29 @@ fn test_branch_list() {
       @origin (ahead by 1 commits, behind by 1 commits): qpsqxpyq 38ef8af7 (empty) remote-unsync
     remote-untrack@origin: vmortlor 71a16b05 (empty) remote-untrack
     "###);
+
+    let template = r#"self ++ " " ++ if(synced, "synced", "unsynced")"#;
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(&local_path, &["branch", "list", "-T", template]), @r###"
+    local-only synced
+    remote-delete* unsynced
+    remote-delete@origin unsynced
+    remote-sync synced
+    remote-unsync* unsynced
+    remote-unsync@origin unsynced
+    "###);
+
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(&local_path, &["branch", "list", "--all", "--json"]), @r###"
+    {"conflict":false,"name":"local-only","remote":null,"synced":true}
+    {"conflict":false,"name":"remote-delete","remote":null,"synced":false}
+    {"conflict":false,"name":"remote-delete","remote":"origin","synced":false}
+    {"conflict":false,"name":"remote-sync","remote":null,"synced":true}
+    {"conflict":false,"name":"remote-sync","remote":"origin","synced":true}
+    {"conflict":false,"name":"remote-unsync","remote":null,"synced":false}
+    {"conflict":false,"name":"remote-unsync","remote":"origin","synced":false}
+    {"conflict":false,"name":"remote-untrack","remote":"origin","synced":false}
+    "###);
 }
 
 #[test]
@@ -1126,6 +1149,15 @@ fn test_branch_list_filtered() {
     remote-keep: nlwprzpn 911e9120 (empty) remote-keep
     "###);
 
+    // Regex and case-insensitive patterns can also be used.
+    insta::assert_snapshot!(query(&["regex:^remote-(keep|delete)$"]), @r###"
+    remote-delete (deleted)
+      @origin: yxusvupt dad5f298 (empty) remote-delete
+      (this branch will be *deleted permanently* on the remote on the next `jj git push`. Use `jj branch forget` to prevent this)
+    remote-keep: nlwprzpn 911e9120 (empty) remote-keep
+    "###);
+    insta::assert_snapshot!(query(&["exact-i:LOCAL-KEEP"]), @"local-keep: kpqxywon c7b4c09c (empty) local-keep");
+
     // Unmatched name pattern shouldn't be an error. A warning can be added later.
     insta::assert_snapshot!(query(&["local-keep", "glob:push-*"]), @r###"
     local-keep: kpqxywon c7b4c09c (empty) local-keep
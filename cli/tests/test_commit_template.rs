@@ -123,6 +123,48 @@ fn test_log_author_timestamp_utc() {
     "###);
 }
 
+#[test]
+fn test_log_json() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first\n\n\"quoted\""]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "main"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "second"]);
+
+    // One JSON object per line
+    let template = r#"json(self) ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "--no-graph", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    {"author":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:10+07:00"},"change_id":"zsuskulnrvyrovkzqrwmxqlsskqntxvp","commit_id":"821054c5dc0378a5e8a0a66ff5e3d9237eaf33aa","committer":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:10+07:00"},"description":"second\n","parents":["18a10ac932c368e150874c7cbc8f26d8ef44e60a"]}
+    {"author":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:07+07:00"},"change_id":"qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu","commit_id":"18a10ac932c368e150874c7cbc8f26d8ef44e60a","committer":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:08+07:00"},"description":"first\n\n\"quoted\"\n","parents":["0000000000000000000000000000000000000000"]}
+    {"author":{"email":"","name":"","timestamp":"1970-01-01T00:00:00+00:00"},"change_id":"zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz","commit_id":"0000000000000000000000000000000000000000","committer":{"email":"","name":"","timestamp":"1970-01-01T00:00:00+00:00"},"description":"","parents":[]}
+    "###);
+
+    let template = r#"json(branches) ++ "\n""#;
+    let stdout =
+        test_env.jj_cmd_success(&repo_path, &["log", "--no-graph", "-r@-", "-T", template]);
+    insta::assert_snapshot!(
+        stdout,
+        @r###"[{"conflict":false,"name":"main","remote":null,"synced":true}]"###);
+
+    let template = r#"json(parents.map(|c| c.change_id())) ++ "\n""#;
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["log", "--no-graph", "-r@", "-T", template]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse template:  --> 1:6
+      |
+    1 | json(parents.map(|c| c.change_id())) ++ "\n"
+      |      ^----------------------------^
+      |
+      = Expected expression of type "Serializable"
+    "###);
+
+    let template = r#"json(self.parents()) ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["show", "-T", template, "--summary", "@"]);
+    insta::assert_snapshot!(stdout, @r###"[{"author":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:07+07:00"},"change_id":"qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu","commit_id":"18a10ac932c368e150874c7cbc8f26d8ef44e60a","committer":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:08+07:00"},"description":"first\n\n\"quoted\"\n","parents":["0000000000000000000000000000000000000000"]}]"###);
+}
+
 #[test]
 fn test_log_default() {
     let test_env = TestEnvironment::default();
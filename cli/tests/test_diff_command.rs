@@ -32,38 +32,26 @@ fn test_diff_basic() {
 
     let stdout = test_env.jj_cmd_success(&repo_path, &["diff"]);
     insta::assert_snapshot!(stdout, @r###"
-    Removed regular file file1:
-       1     : foo
     Modified regular file file2:
        1    1: foo
             2: bar
-    Added regular file file3:
-            1: foo
+    Renamed regular file file1 => file3:
     "###);
 
     let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
     insta::assert_snapshot!(stdout, @r###"
-    D file1
     M file2
-    A file3
+    R file1 => file3
     "###);
 
     let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--types"]);
     insta::assert_snapshot!(stdout, @r###"
-    F- file1
     FF file2
-    -F file3
+    FF file1 => file3
     "###);
 
     let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git"]);
     insta::assert_snapshot!(stdout, @r###"
-    diff --git a/file1 b/file1
-    deleted file mode 100644
-    index 257cc5642c..0000000000
-    --- a/file1
-    +++ /dev/null
-    @@ -1,1 +1,0 @@
-    -foo
     diff --git a/file2 b/file2
     index 257cc5642c...3bd1f0e297 100644
     --- a/file2
@@ -71,27 +59,16 @@ fn test_diff_basic() {
     @@ -1,1 +1,2 @@
      foo
     +bar
-    diff --git a/file3 b/file3
-    new file mode 100644
-    index 0000000000..257cc5642c
-    --- /dev/null
-    +++ b/file3
-    @@ -1,0 +1,1 @@
-    +foo
+    diff --git a/file1 b/file3
+    similarity index 100%
+    rename from file1
+    rename to file3
     "###);
 
     let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "--git"]);
     insta::assert_snapshot!(stdout, @r###"
-    D file1
     M file2
-    A file3
-    diff --git a/file1 b/file1
-    deleted file mode 100644
-    index 257cc5642c..0000000000
-    --- a/file1
-    +++ /dev/null
-    @@ -1,1 +1,0 @@
-    -foo
+    R file1 => file3
     diff --git a/file2 b/file2
     index 257cc5642c...3bd1f0e297 100644
     --- a/file2
@@ -99,21 +76,17 @@ fn test_diff_basic() {
     @@ -1,1 +1,2 @@
      foo
     +bar
-    diff --git a/file3 b/file3
-    new file mode 100644
-    index 0000000000..257cc5642c
-    --- /dev/null
-    +++ b/file3
-    @@ -1,0 +1,1 @@
-    +foo
+    diff --git a/file1 b/file3
+    similarity index 100%
+    rename from file1
+    rename to file3
     "###);
 
     let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--stat"]);
     insta::assert_snapshot!(stdout, @r###"
-    file1 | 1 -
-    file2 | 1 +
-    file3 | 1 +
-    3 files changed, 2 insertions(+), 1 deletion(-)
+    file2          | 1 +
+    file1 => file3 | 0
+    2 files changed, 1 insertion(+), 0 deletions(-)
     "###);
 }
 
@@ -372,6 +345,86 @@ fn test_diff_relative_paths() {
     "###);
 }
 
+#[test]
+fn test_diff_fileset() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::write(repo_path.join("a.rs"), "").unwrap();
+    std::fs::write(repo_path.join("b.rs"), "").unwrap();
+    std::fs::write(repo_path.join("c.txt"), "").unwrap();
+    std::fs::write(repo_path.join("dir").join("d.rs"), "").unwrap();
+    std::fs::write(repo_path.join("a&b"), "").unwrap();
+    std::fs::write(repo_path.join("my file.txt"), "").unwrap();
+
+    // Glob doesn't match across directories
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "glob:*.rs"]);
+    insta::assert_snapshot!(stdout, @r###"
+    A a.rs
+    A b.rs
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "glob:**/*.rs ~ b.rs"]);
+    #[cfg(unix)]
+    insta::assert_snapshot!(stdout, @r###"
+    A a.rs
+    A dir/d.rs
+    "###);
+    #[cfg(windows)]
+    insta::assert_snapshot!(stdout, @r###"
+    A a.rs
+    A dir\d.rs
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "~(c.txt | dir)"]);
+    insta::assert_snapshot!(stdout, @r###"
+    A a&b
+    A a.rs
+    A b.rs
+    A my file.txt
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "root-file:dir", "c.txt"]);
+    insta::assert_snapshot!(stdout, @"A c.txt");
+    let stdout = test_env.jj_cmd_success(&repo_path.join("dir"), &["diff", "-s", "root:a.rs"]);
+    #[cfg(unix)]
+    insta::assert_snapshot!(stdout, @"A ../a.rs");
+    #[cfg(windows)]
+    insta::assert_snapshot!(stdout, @"A ..\\a.rs");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["diff", "-s", "bad:a.rs"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse fileset:  --> 1:1
+      |
+    1 | bad:a.rs
+      | ^------^
+      |
+      = Invalid file pattern kind "bad:"
+    "###);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["diff", "-s", "a.rs |"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse fileset:  --> 1:7
+      |
+    1 | a.rs |
+      |       ^---
+      |
+      = expected `~` or <primary>
+    "###);
+
+    // A path which isn't a valid expression doesn't have to be quoted, but a
+    // path containing operator characters does
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "my file.txt"]);
+    insta::assert_snapshot!(stdout, @"A my file.txt");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "a&b"]);
+    insta::assert_snapshot!(stdout, @"");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", r#""a&b""#]);
+    insta::assert_snapshot!(stdout, @"A a&b");
+
+    // Path arguments are literal paths if filesets are disabled
+    test_env.add_config("ui.allow-filesets = false");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "a&b", "glob:*.rs"]);
+    insta::assert_snapshot!(stdout, @"A a&b");
+}
+
 #[test]
 fn test_diff_missing_newline() {
     let test_env = TestEnvironment::default();
@@ -638,6 +691,60 @@ fn test_diff_skipped_context() {
     "###);
 }
 
+#[test]
+fn test_diff_side_by_side() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\nb\nc\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file1"), "a\nB\nc\nd\n").unwrap();
+    std::fs::remove_file(repo_path.join("file2")).unwrap();
+    std::fs::write(
+        repo_path.join("file3"),
+        "a line that is too long to fit\n\t一二三四五六七八九十\n",
+    )
+    .unwrap();
+
+    test_env.add_env_var("COLUMNS", "40");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--side-by-side"]);
+    insta::assert_snapshot!(stdout, @r###"
+    Modified regular file file1:
+       1 a           │    1 a
+       2 b           │    2 B
+       3 c           │    3 c
+                     │    4 d
+    Removed regular file file2:
+       1 foo         │
+    Added regular file file3:
+                     │    1 a line that
+                     │       is too lon
+                     │      g to fit
+                     │    2     一二三
+                     │      四五六七八
+                     │      九十
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "diff",
+            "--config-toml=ui.diff.format='side-by-side'",
+            "--color=always",
+            "file1",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    [38;5;3mModified regular file file1:[39m
+    [38;5;1m   1[39m a           │ [38;5;2m   1[39m a
+    [38;5;1m   2[39m [38;5;1mb[39m           │ [38;5;2m   2[39m [38;5;2mB[39m
+    [38;5;1m   3[39m c           │ [38;5;2m   3[39m c
+                     │ [38;5;2m   4[39m [38;5;2md[39m
+    "###);
+}
+
 #[test]
 fn test_diff_external_tool() {
     let mut test_env = TestEnvironment::default();
@@ -757,6 +864,82 @@ fn test_diff_external_tool() {
 }
 
 #[cfg(unix)]
+#[test]
+fn test_diff_external_file_by_file_tool() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::remove_file(repo_path.join("file1")).unwrap();
+    std::fs::write(repo_path.join("file2"), "foo\nbar\n").unwrap();
+    std::fs::write(repo_path.join("file3"), "baz\n").unwrap();
+
+    let edit_script = test_env.set_up_fake_diff_editor();
+    std::fs::write(
+        &edit_script,
+        "print ==\0print-files-before\0print --\0print-files-after",
+    )
+    .unwrap();
+    let config = "--config-toml=merge-tools.fake-diff-editor.diff-invocation-mode='file-by-file'";
+
+    // The tool is invoked once per file, in path order
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(&repo_path, &["diff", "--tool=fake-diff-editor", config]), @r###"
+    ==
+    file1
+    --
+    file1
+    ==
+    file2
+    --
+    file2
+    ==
+    file3
+    --
+    file3
+    "###);
+
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(
+            &repo_path,
+            &["log", "-p", "-r@", "--tool=fake-diff-editor", config, "file2"],
+        ), @r###"
+    @  rlvkpnrz test.user@example.com 2001-02-03 04:05:09.000 +07:00 a480edf8
+    │  (no description set)
+    ~  ==
+       file2
+       --
+       file2
+    "###);
+
+    // Exit code 1 means that the files differ
+    std::fs::write(&edit_script, "print-files-before\0fail").unwrap();
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["diff", "--tool=fake-diff-editor", config]);
+    insta::assert_snapshot!(stdout, @r###"
+    file1
+    file2
+    file3
+    "###);
+    insta::assert_snapshot!(stderr, @"");
+
+    // Other failures are reported once
+    std::fs::write(&edit_script, "print-files-before\0fail 2").unwrap();
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["diff", "--tool=fake-diff-editor", config]);
+    insta::assert_snapshot!(stdout, @r###"
+    file1
+    file2
+    file3
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Tool exited with a non-zero code (run with --verbose to see the exact invocation). Exit code: 2.
+    "###);
+}
+
 #[test]
 fn test_diff_external_tool_symlink() {
     let mut test_env = TestEnvironment::default();
@@ -941,3 +1124,261 @@ fn test_diff_stat_long_name_or_stat() {
     2 files changed, 20 insertions(+), 0 deletions(-)
     "###);
 }
+
+#[test]
+fn test_diff_renames() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\nb\nc\nd\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "same\n").unwrap();
+    std::fs::write(repo_path.join("file3"), "removed\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::remove_file(repo_path.join("file1")).unwrap();
+    std::fs::write(repo_path.join("renamed1"), "a\nb\nc\nd\ne\n").unwrap();
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::rename(repo_path.join("file2"), repo_path.join("dir").join("file2")).unwrap();
+    std::fs::remove_file(repo_path.join("file3")).unwrap();
+    std::fs::write(repo_path.join("file4"), "added\n").unwrap();
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--summary"]);
+    insta::assert_snapshot!(stdout, @r###"
+    R file2 => dir/file2
+    D file3
+    A file4
+    R file1 => renamed1
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--stat"]);
+    insta::assert_snapshot!(stdout, @r###"
+    file2 => dir/file2 | 0
+    file3              | 1 -
+    file4              | 1 +
+    file1 => renamed1  | 1 +
+    4 files changed, 2 insertions(+), 1 deletion(-)
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--types"]);
+    insta::assert_snapshot!(stdout, @r###"
+    FF file2 => dir/file2
+    F- file3
+    -F file4
+    FF file1 => renamed1
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git"]);
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file2 b/dir/file2
+    similarity index 100%
+    rename from file2
+    rename to dir/file2
+    diff --git a/file3 b/file3
+    deleted file mode 100644
+    index 2c3f0b3406..0000000000
+    --- a/file3
+    +++ /dev/null
+    @@ -1,1 +1,0 @@
+    -removed
+    diff --git a/file4 b/file4
+    new file mode 100644
+    index 0000000000..d5f7fc3f74
+    --- /dev/null
+    +++ b/file4
+    @@ -1,0 +1,1 @@
+    +added
+    diff --git a/file1 b/renamed1
+    similarity index 88%
+    rename from file1
+    rename to renamed1
+    index d68dd4031d...9405325339 100644
+    --- a/file1
+    +++ b/renamed1
+    @@ -2,3 +2,4 @@
+     b
+     c
+     d
+    +e
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff"]);
+    insta::assert_snapshot!(stdout, @r###"
+    Renamed regular file file2 => dir/file2:
+    Removed regular file file3:
+       1     : removed
+    Added regular file file4:
+            1: added
+    Renamed regular file file1 => renamed1:
+       1    1: a
+       2    2: b
+       3    3: c
+       4    4: d
+            5: e
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["status"]);
+    insta::assert_snapshot!(stdout, @r###"
+    Working copy changes:
+    R file2 => dir/file2
+    D file3
+    A file4
+    R file1 => renamed1
+    Working copy : rlvkpnrz b4974992 (no description set)
+    Parent commit: qpvuntsm 74d9c8bc (no description set)
+    "###);
+
+    // Renames can be disabled
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["diff", "--summary", "--config-toml=ui.diff.renames=false"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    A dir/file2
+    D file1
+    D file2
+    D file3
+    A file4
+    A renamed1
+    "###);
+
+    // The source isn't matched, so the target is shown as added
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--summary", "renamed1"]);
+    insta::assert_snapshot!(stdout, @"A renamed1");
+}
+
+#[test]
+fn test_diff_git_highlight_words() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file"), "a\nfoo bar baz\nb\nc\nd\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file"), "a\nfoo qux baz\nb\nc\nd\nnew\n").unwrap();
+
+    // Off by default
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git", "--color=always"]);
+    insta::assert_snapshot!(stdout, @r###"
+    [1mdiff --git a/file b/file[0m
+    [1mindex ee2bf125ef...ae0e06634f 100644[0m
+    [1m--- a/file[0m
+    [1m+++ b/file[0m
+    [38;5;6m@@ -1,5 +1,6 @@[39m
+     a
+    [38;5;1m-foo bar baz[39m
+    [38;5;2m+foo qux baz[39m
+     b
+     c
+     d
+    [38;5;2m+new[39m
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "diff",
+            "--git",
+            "--color=always",
+            "--config-toml=ui.diff.highlight-words=true",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    [1mdiff --git a/file b/file[0m
+    [1mindex ee2bf125ef...ae0e06634f 100644[0m
+    [1m--- a/file[0m
+    [1m+++ b/file[0m
+    [38;5;6m@@ -1,5 +1,6 @@[39m
+     a
+    [38;5;1m-foo [4mbar[24m baz[39m
+    [38;5;2m+foo [4mqux[24m baz[39m
+     b
+     c
+     d
+    [38;5;2m+new[39m
+    "###);
+}
+
+#[test]
+fn test_diff_git_color_moved() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    let moved = "fn moved_function() {\n    do_something();\n}\n";
+    let short = "}\n";
+    std::fs::write(
+        repo_path.join("file"),
+        format!("{moved}a\nb\nc\nd\ne\n{short}f\n"),
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(
+        repo_path.join("file"),
+        format!("a\nb\nc\nd\ne\nf\n{short}{moved}"),
+    )
+    .unwrap();
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "diff",
+            "--git",
+            "--color=always",
+            "--config-toml=ui.diff.color-moved=true",
+        ],
+    );
+    // The removed `}` alone is too short to be highlighted as moved
+    insta::assert_snapshot!(stdout, @r###"
+    [1mdiff --git a/file b/file[0m
+    [1mindex 1a71dda864...f380d6fa14 100644[0m
+    [1m--- a/file[0m
+    [1m+++ b/file[0m
+    [38;5;6m@@ -1,10 +1,10 @@[39m
+    [1m[38;5;5m-fn moved_function() {[0m
+    [1m[38;5;5m-    do_something();[0m
+    [1m[38;5;5m-}[0m
+     a
+     b
+     c
+     d
+     e
+    [38;5;1m-}[39m
+     f
+    [1m[38;5;6m+}[0m
+    [1m[38;5;6m+fn moved_function() {[0m
+    [1m[38;5;6m+    do_something();[0m
+    [1m[38;5;6m+}[0m
+    "###);
+
+    // Lines moved to another file are highlighted too
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file"), format!("a\nb\nc\nd\ne\nf\n{short}")).unwrap();
+    std::fs::write(repo_path.join("other"), format!("g\n{moved}")).unwrap();
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "diff",
+            "--git",
+            "--color=always",
+            "--config-toml=ui.diff.color-moved=true",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    [1mdiff --git a/file b/file[0m
+    [1mindex f380d6fa14...accda8e4fa 100644[0m
+    [1m--- a/file[0m
+    [1m+++ b/file[0m
+    [38;5;6m@@ -5,6 +5,3 @@[39m
+     e
+     f
+     }
+    [1m[38;5;5m-fn moved_function() {[0m
+    [1m[38;5;5m-    do_something();[0m
+    [1m[38;5;5m-}[0m
+    [1mdiff --git a/other b/other[0m
+    [1mnew file mode 100644[0m
+    [1mindex 0000000000..0938276ee0[0m
+    [1m--- /dev/null[0m
+    [1m+++ b/other[0m
+    [38;5;6m@@ -1,0 +1,4 @@[39m
+    [38;5;2m+g[39m
+    [1m[38;5;6m+fn moved_function() {[0m
+    [1m[38;5;6m+    do_something();[0m
+    [1m[38;5;6m+}[0m
+    "###);
+}
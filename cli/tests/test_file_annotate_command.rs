@@ -0,0 +1,125 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+
+pub mod common;
+
+#[test]
+fn test_annotate_linear() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "initial"]);
+    std::fs::write(repo_path.join("file.txt"), "line1\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "next"]);
+    std::fs::write(
+        repo_path.join("file.txt"),
+        "line1\nnew text from new commit\n",
+    )
+    .unwrap();
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "annotate", "file.txt"]);
+    insta::assert_snapshot!(stdout, @r###"
+    qpvuntsm 618394d4 test.user@example.com 2001-02-03 04:05:09.000 +07:00: line1
+    kkmpptxz 20581758 test.user@example.com 2001-02-03 04:05:10.000 +07:00: new text from new commit
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "file",
+            "annotate",
+            "file.txt",
+            "-r",
+            "@-",
+            "-T",
+            "description.first_line()",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    initial: line1
+    "###);
+}
+
+#[test]
+fn test_annotate_merge() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "initial"]);
+    std::fs::write(repo_path.join("file.txt"), "line1\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "initial"]);
+
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "commit1"]);
+    std::fs::write(
+        repo_path.join("file.txt"),
+        "line1\nnew text from new commit 1\n",
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "commit1"]);
+
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "commit2", "initial"]);
+    std::fs::write(
+        repo_path.join("file.txt"),
+        "new text from new commit 2\nline1\n",
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "commit2"]);
+
+    // Create a merge commit that keeps the lines from both sides, and adds one
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "merged", "commit1", "commit2"]);
+    std::fs::write(
+        repo_path.join("file.txt"),
+        "new text from new commit 2\nline1\nnew text from new commit 1\nmerged\n",
+    )
+    .unwrap();
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "file",
+            "annotate",
+            "file.txt",
+            "-T",
+            "description.first_line()",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    commit2: new text from new commit 2
+    initial: line1
+    commit1: new text from new commit 1
+    merged: merged
+    "###);
+}
+
+#[test]
+fn test_annotate_errors() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::write(repo_path.join("dir").join("file"), "contents\n").unwrap();
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["file", "annotate", "nonexistent"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No such path
+    "###);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["file", "annotate", "dir"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Path exists but is not a file
+    "###);
+}
@@ -0,0 +1,151 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::{Path, PathBuf};
+
+use crate::common::TestEnvironment;
+
+pub mod common;
+
+/// Sets up a repo with a `fix.tools.fake` tool that runs the fake formatter
+/// with the given arguments on files matching `patterns`.
+fn init_with_fake_formatter(args: &[&str], patterns: &[&str]) -> (TestEnvironment, PathBuf) {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    let formatter_path = assert_cmd::cargo::cargo_bin("fake-formatter");
+    assert!(formatter_path.is_file());
+    let command = [formatter_path.to_str().unwrap()]
+        .iter()
+        .chain(args)
+        .map(|arg| format!("{arg:?}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let patterns = patterns
+        .iter()
+        .map(|pattern| format!("{pattern:?}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    test_env.add_config(&format!(
+        r#"
+        [fix.tools.fake]
+        command = [{command}]
+        patterns = [{patterns}]
+        "#
+    ));
+    (test_env, repo_path)
+}
+
+fn get_file(test_env: &TestEnvironment, repo_path: &Path, rev: &str, file: &str) -> String {
+    test_env.jj_cmd_success(repo_path, &["cat", "-r", rev, file])
+}
+
+#[test]
+fn test_fix_no_tools() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["fix"]);
+    insta::assert_snapshot!(stderr, @"Error: No tools are configured. Add them to the `fix.tools` table.");
+}
+
+#[test]
+fn test_fix_stack() {
+    let (test_env, repo_path) = init_with_fake_formatter(&["--uppercase"], &["*.txt"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "a"]);
+    std::fs::write(repo_path.join("file.txt"), "a\n").unwrap();
+    std::fs::write(repo_path.join("other"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "b"]);
+    std::fs::write(repo_path.join("file.txt"), "a\nb\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "c"]);
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::write(repo_path.join("dir").join("nested.txt"), "c\n").unwrap();
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["fix", "-s", "description(a)"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Fixed 3 commits of 3 checked.
+    Working copy now at: zsuskuln 00a7d563 c
+    Parent commit      : kkmpptxz ba7fe709 b
+    Added 0 files, modified 2 files, removed 0 files
+    "###);
+    insta::assert_snapshot!(get_file(&test_env, &repo_path, "description(a)", "file.txt"), @"A");
+    insta::assert_snapshot!(get_file(&test_env, &repo_path, "description(a)", "other"), @"a");
+    insta::assert_snapshot!(get_file(&test_env, &repo_path, "description(b)", "file.txt"), @r###"
+    A
+    B
+    "###);
+    // The working copy is updated
+    insta::assert_snapshot!(std::fs::read_to_string(repo_path.join("dir/nested.txt")).unwrap(), @"C");
+    insta::assert_snapshot!(std::fs::read_to_string(repo_path.join("file.txt")).unwrap(), @r###"
+    A
+    B
+    "###);
+
+    // Files that weren't changed in the revisions aren't touched
+    std::fs::write(repo_path.join("file.txt"), "unformatted\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["fix"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Fixed 0 commits of 1 checked.
+    Nothing changed.
+    "###);
+    insta::assert_snapshot!(get_file(&test_env, &repo_path, "@", "file.txt"), @"unformatted");
+}
+
+#[test]
+fn test_fix_paths_and_substitution() {
+    let (test_env, repo_path) = init_with_fake_formatter(&["--append", "$path\n"], &["*"]);
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "a\n").unwrap();
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["fix", "file1"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Fixed 1 commits of 1 checked.
+    Working copy now at: qpvuntsm 1e0dce28 (no description set)
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    insta::assert_snapshot!(get_file(&test_env, &repo_path, "@", "file1"), @r###"
+    a
+    file1
+    "###);
+    insta::assert_snapshot!(get_file(&test_env, &repo_path, "@", "file2"), @"a");
+}
+
+#[test]
+fn test_fix_failing_tool() {
+    let (test_env, repo_path) = init_with_fake_formatter(&["--uppercase", "--fail"], &["*"]);
+    std::fs::write(repo_path.join("file"), "a\n").unwrap();
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["fix"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Failed to fix file with fake: exit status: 1
+    Fixed 0 commits of 1 checked.
+    Nothing changed.
+    "###);
+    insta::assert_snapshot!(get_file(&test_env, &repo_path, "@", "file"), @"a");
+}
+
+#[test]
+fn test_fix_immutable() {
+    let (test_env, repo_path) = init_with_fake_formatter(&["--uppercase"], &["*"]);
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["fix", "-s", "root()"]);
+    insta::assert_snapshot!(stderr, @"Error: The root commit 000000000000 is immutable");
+}
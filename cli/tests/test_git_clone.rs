@@ -361,6 +361,246 @@ fn test_git_clone_remote_default_branch() {
     "###);
 }
 
+/// Creates a linear history of `main` with a file changed in each commit.
+fn set_up_linear_git_repo(git_repo: &git2::Repository, num_commits: i64) {
+    let mut parent = None;
+    for i in 1..=num_commits {
+        let signature =
+            git2::Signature::new("Some One", "some.one@example.com", &git2::Time::new(i, 0))
+                .unwrap();
+        let mut tree_builder = git_repo.treebuilder(None).unwrap();
+        let file_oid = git_repo.blob(format!("content {i}\n").as_bytes()).unwrap();
+        tree_builder
+            .insert("file", file_oid, git2::FileMode::Blob.into())
+            .unwrap();
+        let tree_oid = tree_builder.write().unwrap();
+        let tree = git_repo.find_tree(tree_oid).unwrap();
+        let parents = parent.iter().collect::<Vec<_>>();
+        let oid = git_repo
+            .commit(
+                None,
+                &signature,
+                &signature,
+                &format!("commit {i}"),
+                &tree,
+                &parents,
+            )
+            .unwrap();
+        parent = Some(git_repo.find_commit(oid).unwrap());
+    }
+    git_repo
+        .reference("refs/heads/main", parent.unwrap().id(), true, "")
+        .unwrap();
+    git_repo.set_head("refs/heads/main").unwrap();
+}
+
+#[test]
+fn test_git_clone_shallow() {
+    let test_env = TestEnvironment::default();
+    let git_repo_path = test_env.env_root().join("source");
+    let git_repo = git2::Repository::init(&git_repo_path).unwrap();
+    set_up_linear_git_repo(&git_repo, 4);
+    let source_url = format!("file://{}", git_repo_path.display());
+
+    // The oldest fetched commit becomes a child of the root commit
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["git", "clone", "--depth=2", &source_url, "clone"],
+    );
+    let clone_path = test_env.env_root().join("clone");
+    let template = r#"description.first_line() ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(&clone_path, &["log", "-r=::main", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  commit 4
+    ◉  commit 3
+    ◉
+    "###);
+    assert_eq!(
+        std::fs::read_to_string(clone_path.join("file")).unwrap(),
+        "content 4\n"
+    );
+
+    // Deepen the history
+    set_up_linear_git_repo(&git_repo, 5);
+    test_env.jj_cmd_ok(&clone_path, &["git", "fetch", "--depth=3"]);
+    let stdout = test_env.jj_cmd_success(&clone_path, &["log", "-r=::main", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  commit 5
+    ◉  commit 4
+    ◉  commit 3
+    ◉
+    "###);
+
+    // Invalid date is reported by git
+    let stderr = test_env.jj_cmd_failure(
+        test_env.env_root(),
+        &["git", "clone", "--shallow-since=bogus", &source_url, "bad"],
+    );
+    assert!(
+        stderr.contains("git command exited with an error"),
+        "{stderr}"
+    );
+    assert!(!test_env.env_root().join("bad").exists());
+}
+
+#[test]
+fn test_git_clone_shallow_deepen() {
+    let test_env = TestEnvironment::default();
+    let git_repo_path = test_env.env_root().join("source");
+    let git_repo = git2::Repository::init(&git_repo_path).unwrap();
+    set_up_linear_git_repo(&git_repo, 4);
+    let source_url = format!("file://{}", git_repo_path.display());
+
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["git", "clone", "--depth=1", &source_url, "clone"],
+    );
+    let clone_path = test_env.env_root().join("clone");
+    let template = r#"description.first_line() ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(&clone_path, &["log", "-r=::main", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  commit 4
+    ◉
+    "###);
+
+    // The commit at the previous shallow boundary is reindexed with its parents
+    let (stdout, stderr) = test_env.jj_cmd_ok(&clone_path, &["git", "fetch", "--depth=3"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Nothing changed.
+    Rebuilding the index since the history has been deepened.
+    "###);
+    let stdout = test_env.jj_cmd_success(&clone_path, &["log", "-r=::main", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  commit 4
+    ◉  commit 3
+    ◉  commit 2
+    ◉
+    "###);
+    let stdout = test_env.jj_cmd_success(&clone_path, &["log", "-r=root()+", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  commit 2
+    │
+    ~
+    "###);
+}
+
+#[test]
+fn test_git_clone_partial() {
+    let test_env = TestEnvironment::default();
+    let git_repo_path = test_env.env_root().join("source");
+    let git_repo = git2::Repository::init(&git_repo_path).unwrap();
+    set_up_linear_git_repo(&git_repo, 3);
+    git_repo
+        .config()
+        .unwrap()
+        .set_bool("uploadpack.allowFilter", true)
+        .unwrap();
+    let source_url = format!("file://{}", git_repo_path.display());
+
+    let stderr = test_env.jj_cmd_cli_error(
+        test_env.env_root(),
+        &["git", "clone", "--filter=tree:0", &source_url, "bad"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value 'tree:0' for '--filter <FILTER-SPEC>': Only blob filters such as `blob:none` are supported
+
+    For more information, try '--help'.
+    "###);
+
+    // The blobs of the checked-out commit are fetched on demand
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["git", "clone", "--filter=blob:none", &source_url, "clone"],
+    );
+    let clone_path = test_env.env_root().join("clone");
+    assert_eq!(
+        std::fs::read_to_string(clone_path.join("file")).unwrap(),
+        "content 3\n"
+    );
+    let stdout = test_env.jj_cmd_success(&clone_path, &["diff", "--git", "-r=main-"]);
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file b/file
+    index a0054e4928...4d4bc1c77f 100644
+    --- a/file
+    +++ b/file
+    @@ -1,1 +1,1 @@
+    -content 1
+    +content 2
+    "###);
+}
+
+#[test]
+fn test_git_clone_partial_batched_fetch() {
+    let test_env = TestEnvironment::default();
+    let git_repo_path = test_env.env_root().join("source");
+    let git_repo = git2::Repository::init(&git_repo_path).unwrap();
+    git_repo
+        .config()
+        .unwrap()
+        .set_bool("uploadpack.allowFilter", true)
+        .unwrap();
+    let signature = git2::Signature::now("Some One", "some.one@example.com").unwrap();
+    let mut parents = vec![];
+    for content in ["old", "new"] {
+        let mut tree_builder = git_repo.treebuilder(None).unwrap();
+        for name in ["file1", "file2", "file3"] {
+            let blob_id = git_repo.blob(format!("{name} {content}\n").as_bytes());
+            tree_builder
+                .insert(name, blob_id.unwrap(), git2::FileMode::Blob.into())
+                .unwrap();
+        }
+        let tree = git_repo.find_tree(tree_builder.write().unwrap()).unwrap();
+        let parent_refs = parents.iter().collect::<Vec<_>>();
+        let oid = git_repo
+            .commit(None, &signature, &signature, content, &tree, &parent_refs)
+            .unwrap();
+        parents = vec![git_repo.find_commit(oid).unwrap()];
+    }
+    git_repo
+        .reference("refs/heads/main", parents[0].id(), true, "")
+        .unwrap();
+    git_repo.set_head("refs/heads/main").unwrap();
+    let source_url = format!("file://{}", git_repo_path.display());
+
+    // Each fetch of missing blobs adds a pack file
+    let pack_dir = test_env
+        .env_root()
+        .join("clone/.jj/repo/store/git/objects/pack");
+    let count_packs = || {
+        std::fs::read_dir(&pack_dir)
+            .unwrap()
+            .filter(|entry| {
+                let path = entry.as_ref().unwrap().path();
+                path.extension().is_some_and(|ext| ext == "pack")
+            })
+            .count()
+    };
+
+    // The blobs of the checked-out files are fetched at once
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["git", "clone", "--filter=blob:none", &source_url, "clone"],
+    );
+    let clone_path = test_env.env_root().join("clone");
+    assert_eq!(
+        std::fs::read_to_string(clone_path.join("file3")).unwrap(),
+        "file3 new\n"
+    );
+    // One pack from the clone and one from the checkout
+    assert_eq!(count_packs(), 2);
+
+    // So are the blobs needed by a diff
+    let stdout = test_env.jj_cmd_success(&clone_path, &["diff", "--stat", "-r=main"]);
+    insta::assert_snapshot!(stdout, @r###"
+    file1 | 2 +-
+    file2 | 2 +-
+    file3 | 2 +-
+    3 files changed, 3 insertions(+), 3 deletions(-)
+    "###);
+    assert_eq!(count_packs(), 3);
+}
+
 fn get_branch_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
     test_env.jj_cmd_success(repo_path, &["branch", "list", "--all"])
 }
@@ -770,6 +770,16 @@ fn test_git_fetch_some_of_many_branches() {
       + nxrpswuq 01d11519 descr_for_b
       @origin (behind by 1 commits): nxrpswuq 01d11519 descr_for_b
     "###);
+
+    // Regex and case-insensitive patterns can't be translated to refspecs
+    let stderr = test_env.jj_cmd_failure(
+        &target_jj_repo_path,
+        &["git", "fetch", "--branch", "regex:^a"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Invalid branch pattern provided. Patterns may not contain the characters `:`, `^`, `?`, `[`, `]`
+    Hint: Only case-sensitive `exact:`, `substring:`, and `glob:` patterns can be used to fetch branches
+    "###);
 }
 
 // See `test_undo_restore_commands.rs` for fetch-undo-push and fetch-undo-fetch
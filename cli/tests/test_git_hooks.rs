@@ -0,0 +1,266 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::{Path, PathBuf};
+
+use crate::common::TestEnvironment;
+
+pub mod common;
+
+fn set_up() -> (TestEnvironment, PathBuf) {
+    let test_env = TestEnvironment::default();
+    git2::Repository::init_bare(test_env.env_root().join("origin")).unwrap();
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["git", "clone", "--colocate", "origin", "repo"],
+    );
+    let repo_path = test_env.env_root().join("repo");
+    test_env.add_config("git.run-hooks = true");
+    (test_env, repo_path)
+}
+
+fn write_hook(repo_path: &Path, name: &str, script: &str) {
+    let hooks_dir = repo_path.join(".git").join("hooks");
+    std::fs::create_dir_all(&hooks_dir).unwrap();
+    let hook_path = hooks_dir.join(name);
+    std::fs::write(&hook_path, format!("#!/bin/sh\n{script}")).unwrap();
+    std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn get_log_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
+    let template = r#"description ++ branches"#;
+    test_env.jj_cmd_success(repo_path, &["log", "-T", template])
+}
+
+#[test]
+fn test_git_hooks_disabled() {
+    let (test_env, repo_path) = set_up();
+    test_env.add_config("git.run-hooks = false");
+    write_hook(&repo_path, "commit-msg", "exit 1");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  first
+    ◉
+    "###);
+}
+
+#[test]
+fn test_git_hooks_commit_msg() {
+    let (test_env, repo_path) = set_up();
+    write_hook(
+        &repo_path,
+        "commit-msg",
+        r#"
+if grep -q WIP "$1"; then
+    echo "WIP commits are not allowed"
+    exit 1
+fi
+echo "Signed-off-by: Test User" >> "$1"
+"#,
+    );
+
+    // The hook can edit the description
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  first
+    │  Signed-off-by: Test User
+    ◉
+    "###);
+
+    // A failing hook aborts the command
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["describe", "-m", "WIP"]);
+    insta::assert_snapshot!(stderr, @r###"
+    WIP commits are not allowed
+    Error: The commit-msg hook failed (exit status: 1)
+    "###);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["commit", "-m", "WIP"]);
+    insta::assert_snapshot!(stderr, @r###"
+    WIP commits are not allowed
+    Error: The commit-msg hook failed (exit status: 1)
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  first
+    │  Signed-off-by: Test User
+    ◉
+    "###);
+
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "second"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @
+    ◉  second
+    │  Signed-off-by: Test User
+    ◉
+    "###);
+}
+
+#[test]
+fn test_git_hooks_pre_commit() {
+    let (test_env, repo_path) = set_up();
+    write_hook(
+        &repo_path,
+        "pre-commit",
+        r#"
+if [ -e forbidden ]; then
+    echo "forbidden file exists"
+    exit 1
+fi
+"#,
+    );
+
+    // The hook is run in the workspace root
+    std::fs::write(repo_path.join("forbidden"), "").unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["commit", "-m", "first"]);
+    insta::assert_snapshot!(stderr, @r###"
+    forbidden file exists
+    Error: The pre-commit hook failed (exit status: 1)
+    "###);
+
+    std::fs::remove_file(repo_path.join("forbidden")).unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @
+    ◉  first
+    ◉
+    "###);
+}
+
+#[test]
+fn test_git_hooks_pre_commit_modifies_files() {
+    let (test_env, repo_path) = set_up();
+    write_hook(&repo_path, "pre-commit", "echo formatted > file\n");
+
+    // Changes made by the hook are included in the commit
+    std::fs::write(repo_path.join("file"), "unformatted\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "-r", "@-", "file"]);
+    insta::assert_snapshot!(stdout, @"formatted");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--summary"]);
+    insta::assert_snapshot!(stdout, @"");
+}
+
+#[test]
+fn test_git_hooks_non_colocated() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.add_config("git.run-hooks = true");
+    let hooks_dir = repo_path.join(".jj/repo/store/git/hooks");
+    std::fs::create_dir_all(&hooks_dir).unwrap();
+    let hook_path = hooks_dir.join("pre-commit");
+    std::fs::write(
+        &hook_path,
+        "#!/bin/sh
+if [ -e forbidden ]; then
+    echo \"forbidden file exists\"
+    exit 1
+fi
+",
+    )
+    .unwrap();
+    std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    // The hook is run in the workspace root, not in the Git repo
+    std::fs::create_dir(repo_path.join("subdir")).unwrap();
+    std::fs::write(repo_path.join("forbidden"), "").unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path.join("subdir"), &["commit", "-m", "first"]);
+    insta::assert_snapshot!(stderr, @r###"
+    forbidden file exists
+    Error: The pre-commit hook failed (exit status: 1)
+    "###);
+
+    std::fs::remove_file(repo_path.join("forbidden")).unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @
+    ◉  first
+    ◉
+    "###);
+}
+
+#[test]
+fn test_git_hooks_invalid_config() {
+    let (test_env, repo_path) = set_up();
+    test_env.add_config("git.run-hooks = 'bad'");
+    write_hook(&repo_path, "commit-msg", "exit 1");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["describe", "-m", "first"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Config error: invalid type: string "bad", expected a boolean
+    For help, see https://github.com/martinvonz/jj/blob/main/docs/config.md.
+    "###);
+}
+
+#[test]
+fn test_git_hooks_pre_push() {
+    let (test_env, repo_path) = set_up();
+    write_hook(
+        &repo_path,
+        "pre-push",
+        r#"
+echo "$1 $2" > ../pre-push-input
+cat >> ../pre-push-input
+if [ -e ../reject ]; then
+    echo "push rejected"
+    exit 1
+fi
+"#,
+    );
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "branch1"]);
+    let commit_id1 = test_env.jj_cmd_success(&repo_path, &["log", "-r@", "-T=commit_id"]);
+    let commit_id1 = commit_id1.lines().next().unwrap().trim_start_matches("@  ");
+
+    // A failing hook aborts the push
+    std::fs::write(test_env.env_root().join("reject"), "").unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["git", "push", "--all"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Add branch branch1 to 69542c1984c1
+    push rejected
+    Error: The pre-push hook failed (exit status: 1)
+    "###);
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(&repo_path, &["branch", "list", "--all"]), @r###"
+    branch1: qpvuntsm 69542c19 (empty) first
+      @git: qpvuntsm 69542c19 (empty) first
+    "###);
+    let input = std::fs::read_to_string(test_env.env_root().join("pre-push-input")).unwrap();
+    assert_eq!(
+        input,
+        format!(
+            "origin {origin}\nrefs/heads/branch1 {commit_id1} refs/heads/branch1 {null}\n",
+            origin = test_env.env_root().join("origin").display(),
+            null = "0".repeat(40),
+        )
+    );
+
+    // Deleting the branch
+    std::fs::remove_file(test_env.env_root().join("reject")).unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["git", "push", "--all"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "delete", "branch1"]);
+    test_env.jj_cmd_ok(&repo_path, &["git", "push", "--deleted"]);
+    let input = std::fs::read_to_string(test_env.env_root().join("pre-push-input")).unwrap();
+    assert_eq!(
+        input,
+        format!(
+            "origin {origin}\n(delete) {null} refs/heads/branch1 {commit_id1}\n",
+            origin = test_env.env_root().join("origin").display(),
+            null = "0".repeat(40),
+        )
+    );
+}
@@ -0,0 +1,597 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::common::TestEnvironment;
+
+pub mod common;
+
+const POINTER: &str = "version https://git-lfs.github.com/spec/v1
+oid sha256:43e2396ec094bd64464552be37c3fa586e33ae07c88294054606a64283825d12
+size 40
+";
+
+/// A minimal Git LFS server implementing the batch API and the basic transfer
+/// adapter.
+struct LfsServer {
+    url: String,
+    objects: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl LfsServer {
+    fn start() -> Self {
+        LfsServer::start_with_auth(None)
+    }
+
+    /// Starts a server which requires the given `Authorization` header for
+    /// batch requests.
+    fn start_with_auth(authorization: Option<&str>) -> Self {
+        let authorization = authorization.map(str::to_owned);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let objects = Arc::new(Mutex::new(HashMap::new()));
+        let requests = Arc::new(Mutex::new(vec![]));
+        let server = LfsServer {
+            url: url.clone(),
+            objects: objects.clone(),
+            requests: requests.clone(),
+        };
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                handle_request(
+                    stream.unwrap(),
+                    &url,
+                    authorization.as_deref(),
+                    &objects,
+                    &requests,
+                );
+            }
+        });
+        server
+    }
+
+    fn take_requests(&self) -> Vec<String> {
+        std::mem::take(&mut self.requests.lock().unwrap())
+    }
+}
+
+fn handle_request(
+    stream: TcpStream,
+    url: &str,
+    required_authorization: Option<&str>,
+    objects: &Mutex<HashMap<String, Vec<u8>>>,
+    requests: &Mutex<Vec<String>>,
+) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut content_length = 0;
+    let mut authorization = None;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line.trim().is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').unwrap();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.trim().parse().unwrap();
+        } else if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value.trim().to_owned());
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap(), parts.next().unwrap());
+    let (status, response) = match (method, path) {
+        ("POST", "/objects/batch") => {
+            let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let operation = request["operation"].as_str().unwrap();
+            if required_authorization.is_some()
+                && authorization.as_deref() != required_authorization
+            {
+                requests
+                    .lock()
+                    .unwrap()
+                    .push(format!("batch {operation} unauthorized"));
+                let response = serde_json::json!({"message": "Credentials needed"});
+                write_response(
+                    stream,
+                    "401 Unauthorized",
+                    &response.to_string().into_bytes(),
+                );
+                return;
+            }
+            requests.lock().unwrap().push(format!("batch {operation}"));
+            let objects = objects.lock().unwrap();
+            let response_objects = request["objects"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|object| {
+                    let oid = object["oid"].as_str().unwrap();
+                    let href = format!("{url}/objects/{oid}");
+                    match (operation, objects.contains_key(oid)) {
+                        ("download", true) => serde_json::json!({
+                            "oid": oid,
+                            "actions": {"download": {"href": href}},
+                        }),
+                        ("download", false) => serde_json::json!({
+                            "oid": oid,
+                            "error": {"code": 404, "message": "Object does not exist"},
+                        }),
+                        (_, true) => serde_json::json!({"oid": oid}),
+                        (_, false) => serde_json::json!({
+                            "oid": oid,
+                            "actions": {
+                                "upload": {"href": href, "header": {"X-Upload": "yes"}},
+                            },
+                        }),
+                    }
+                })
+                .collect::<Vec<_>>();
+            let response = serde_json::json!({"transfer": "basic", "objects": response_objects});
+            ("200 OK", response.to_string().into_bytes())
+        }
+        ("GET", path) => {
+            let oid = path.strip_prefix("/objects/").unwrap();
+            requests
+                .lock()
+                .unwrap()
+                .push(format!("download {}", &oid[..12]));
+            match objects.lock().unwrap().get(oid) {
+                Some(contents) => ("200 OK", contents.clone()),
+                None => ("404 Not Found", vec![]),
+            }
+        }
+        ("PUT", path) => {
+            let oid = path.strip_prefix("/objects/").unwrap();
+            requests
+                .lock()
+                .unwrap()
+                .push(format!("upload {}", &oid[..12]));
+            objects.lock().unwrap().insert(oid.to_owned(), body);
+            ("200 OK", vec![])
+        }
+        _ => ("400 Bad Request", vec![]),
+    };
+    write_response(stream, status, &response);
+}
+
+fn write_response(mut stream: TcpStream, status: &str, body: &[u8]) {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )
+    .unwrap();
+    stream.write_all(body).unwrap();
+}
+
+/// Skips the test if curl, which is used for the transfers, isn't installed.
+macro_rules! curl_guard {
+    () => {
+        if std::process::Command::new("curl")
+            .arg("--version")
+            .output()
+            .is_err()
+        {
+            eprintln!("Skipping because curl is not installed");
+            return;
+        }
+    };
+}
+
+fn set_up(test_env: &TestEnvironment, server: &LfsServer) {
+    let git_repo = git2::Repository::init_bare(test_env.env_root().join("origin")).unwrap();
+    git_repo.set_head("refs/heads/main").unwrap();
+    std::fs::write(
+        test_env.home_dir().join(".gitconfig"),
+        format!("[lfs]\n\turl = {}\n", server.url),
+    )
+    .unwrap();
+}
+
+fn lfs_object_path(repo_path: &Path, oid: &str) -> std::path::PathBuf {
+    repo_path
+        .join(".jj/repo/store/git/lfs/objects")
+        .join(&oid[..2])
+        .join(&oid[2..4])
+        .join(oid)
+}
+
+#[test]
+fn test_git_lfs_push_and_clone() {
+    curl_guard!();
+    let test_env = TestEnvironment::default();
+    let server = LfsServer::start();
+    set_up(&test_env, &server);
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "clone", "origin", "repo1"]);
+    let repo1_path = test_env.env_root().join("repo1");
+
+    // Files with the filter=lfs attribute are stored as pointers, regardless of
+    // their size
+    std::fs::write(
+        repo1_path.join(".gitattributes"),
+        "*.bin filter=lfs -text\n",
+    )
+    .unwrap();
+    std::fs::write(
+        repo1_path.join("large.bin"),
+        "large file contents exceeding the limit\n",
+    )
+    .unwrap();
+    std::fs::write(repo1_path.join("small.txt"), "small file contents\n").unwrap();
+    test_env.add_config(r#"snapshot.max-new-file-size = "30""#);
+    test_env.jj_cmd_ok(&repo1_path, &["describe", "-m", "add large file"]);
+    let stdout = test_env.jj_cmd_success(&repo1_path, &["cat", "large.bin"]);
+    insta::assert_snapshot!(stdout, @r###"
+    version https://git-lfs.github.com/spec/v1
+    oid sha256:43e2396ec094bd64464552be37c3fa586e33ae07c88294054606a64283825d12
+    size 40
+    "###);
+    assert_eq!(stdout, POINTER);
+    let oid = "43e2396ec094bd64464552be37c3fa586e33ae07c88294054606a64283825d12";
+    assert_eq!(
+        std::fs::read_to_string(lfs_object_path(&repo1_path, oid)).unwrap(),
+        "large file contents exceeding the limit\n"
+    );
+    // The working copy still contains the real contents
+    assert_eq!(
+        std::fs::read_to_string(repo1_path.join("large.bin")).unwrap(),
+        "large file contents exceeding the limit\n"
+    );
+    let stdout = test_env.jj_cmd_success(&repo1_path, &["diff", "--summary"]);
+    insta::assert_snapshot!(stdout, @r###"
+    A .gitattributes
+    A large.bin
+    A small.txt
+    "###);
+
+    // Pushing uploads the objects the server doesn't have yet
+    test_env.jj_cmd_ok(&repo1_path, &["branch", "create", "main"]);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo1_path, &["git", "push", "-b", "main"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Add branch main to 4a8b4262df7b
+    Uploaded 1 Git LFS objects
+    "###);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @r###"
+    batch upload
+    upload 43e2396ec094
+    "###);
+    test_env.jj_cmd_ok(&repo1_path, &["new", "main", "-m", "unrelated change"]);
+    std::fs::write(repo1_path.join("small.txt"), "modified\n").unwrap();
+    test_env.jj_cmd_ok(&repo1_path, &["branch", "set", "main"]);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo1_path, &["git", "push", "-b", "main"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Move branch main from 4a8b4262df7b to 40af51f051f1
+    "###);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @"batch upload");
+
+    // Cloning downloads the objects and checks out the real contents
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(test_env.env_root(), &["git", "clone", "origin", "repo2"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Fetching into new repo in "$TEST_ENV/repo2"
+    Downloaded 1 Git LFS objects
+    Working copy now at: uuzqqzqu 6dce7964 (empty) (no description set)
+    Parent commit      : wpnqvtlp 40af51f0 main | unrelated change
+    Added 3 files, modified 0 files, removed 0 files
+    "###);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @r###"
+    batch download
+    download 43e2396ec094
+    "###);
+    let repo2_path = test_env.env_root().join("repo2");
+    assert_eq!(
+        std::fs::read_to_string(repo2_path.join("large.bin")).unwrap(),
+        "large file contents exceeding the limit\n"
+    );
+    let stdout = test_env.jj_cmd_success(&repo2_path, &["status"]);
+    insta::assert_snapshot!(stdout, @r###"
+    The working copy is clean
+    Working copy : uuzqqzqu 6dce7964 (empty) (no description set)
+    Parent commit: wpnqvtlp 40af51f0 main | unrelated change
+    "###);
+
+    // Fetching again doesn't download anything
+    test_env.jj_cmd_ok(&repo2_path, &["git", "fetch"]);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @"");
+
+    // Only the files changed since the previous fetch are searched for pointers
+    test_env.jj_cmd_ok(&repo1_path, &["new", "main", "-m", "another large file"]);
+    std::fs::write(repo1_path.join("other.bin"), "other large file\n").unwrap();
+    test_env.jj_cmd_ok(&repo1_path, &["branch", "set", "main"]);
+    test_env.jj_cmd_ok(&repo1_path, &["git", "push", "-b", "main"]);
+    server.take_requests();
+    std::fs::remove_file(lfs_object_path(&repo2_path, oid)).unwrap();
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo2_path, &["git", "fetch"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Downloaded 1 Git LFS objects
+    "###);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @r###"
+    batch download
+    download 8576598f46ab
+    "###);
+}
+
+#[test]
+fn test_git_lfs_fetch_selected_objects() {
+    curl_guard!();
+    let test_env = TestEnvironment::default();
+    let server = LfsServer::start();
+    set_up(&test_env, &server);
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "clone", "origin", "repo1"]);
+    let repo1_path = test_env.env_root().join("repo1");
+    std::fs::write(repo1_path.join(".gitattributes"), "*.bin filter=lfs\n").unwrap();
+    std::fs::write(
+        repo1_path.join("large.bin"),
+        "large file contents exceeding the limit\n",
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(&repo1_path, &["describe", "-m", "add large file"]);
+    test_env.jj_cmd_ok(&repo1_path, &["branch", "create", "main"]);
+    test_env.jj_cmd_ok(&repo1_path, &["new", "main", "-m", "another large file"]);
+    std::fs::write(repo1_path.join("other.bin"), "other large file\n").unwrap();
+    test_env.jj_cmd_ok(&repo1_path, &["branch", "create", "other"]);
+    test_env.jj_cmd_ok(&repo1_path, &["git", "push", "--all"]);
+    server.take_requests();
+
+    // Downloads can be turned off
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &[
+            "git",
+            "clone",
+            "origin",
+            "repo2",
+            "--config-toml=git.fetch-lfs-objects=false",
+        ],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Fetching into new repo in "$TEST_ENV/repo2"
+    Working copy now at: spxsnpux b3719257 (empty) (no description set)
+    Parent commit      : rwyuurxx f3da9741 main | add large file
+    Added 2 files, modified 0 files, removed 0 files
+    "###);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @"");
+    let repo2_path = test_env.env_root().join("repo2");
+
+    // Only the objects of the fetched branches are downloaded
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo2_path, &["git", "fetch", "--branch", "main"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Downloaded 1 Git LFS objects
+    Nothing changed.
+    "###);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @r###"
+    batch download
+    download 43e2396ec094
+    "###);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo2_path, &["git", "fetch"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Downloaded 1 Git LFS objects
+    Nothing changed.
+    "###);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @r###"
+    batch download
+    download 8576598f46ab
+    "###);
+}
+
+#[test]
+fn test_git_lfs_missing_object() {
+    curl_guard!();
+    let test_env = TestEnvironment::default();
+    let server = LfsServer::start();
+    set_up(&test_env, &server);
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "clone", "origin", "repo1"]);
+    let repo1_path = test_env.env_root().join("repo1");
+    std::fs::write(repo1_path.join(".gitattributes"), "*.bin filter=lfs\n").unwrap();
+    std::fs::write(
+        repo1_path.join("large.bin"),
+        "large file contents exceeding the limit\n",
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(&repo1_path, &["describe", "-m", "add large file"]);
+    test_env.jj_cmd_ok(&repo1_path, &["branch", "create", "main"]);
+    test_env.jj_cmd_ok(&repo1_path, &["git", "push", "-b", "main"]);
+    let objects = std::mem::take(&mut *server.objects.lock().unwrap());
+
+    // If the object can't be downloaded, the pointer is checked out
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(test_env.env_root(), &["git", "clone", "origin", "repo2"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Fetching into new repo in "$TEST_ENV/repo2"
+    Failed to download Git LFS objects from origin: Failed to transfer Git LFS object 43e2396ec094bd64464552be37c3fa586e33ae07c88294054606a64283825d12: Object does not exist (error code 404)
+    Working copy now at: zxsnswpr e2224477 (empty) (no description set)
+    Parent commit      : rwyuurxx f3da9741 main | add large file
+    Added 2 files, modified 0 files, removed 0 files
+    "###);
+    let repo2_path = test_env.env_root().join("repo2");
+    assert_eq!(
+        std::fs::read_to_string(repo2_path.join("large.bin")).unwrap(),
+        POINTER
+    );
+    // The pointer isn't mistaken for modified contents
+    let stdout = test_env.jj_cmd_success(&repo2_path, &["diff", "--summary"]);
+    insta::assert_snapshot!(stdout, @"");
+
+    // The download is retried by the next fetch even if the branch hasn't moved
+    server.take_requests();
+    *server.objects.lock().unwrap() = objects;
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo2_path, &["git", "fetch"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Downloaded 1 Git LFS objects
+    Nothing changed.
+    "###);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @r###"
+    batch download
+    download 43e2396ec094
+    "###);
+    server.objects.lock().unwrap().clear();
+    let oid = "43e2396ec094bd64464552be37c3fa586e33ae07c88294054606a64283825d12";
+    std::fs::remove_file(lfs_object_path(&repo2_path, oid)).unwrap();
+
+    // Pushing fails if the server needs an object we don't have
+    test_env.jj_cmd_ok(&repo2_path, &["new", "main"]);
+    std::fs::write(repo2_path.join("other.bin"), "other\n").unwrap();
+    test_env.jj_cmd_ok(&repo2_path, &["describe", "-m", "add other file"]);
+    test_env.jj_cmd_ok(&repo2_path, &["branch", "create", "other"]);
+    let stderr = test_env.jj_cmd_failure(&repo2_path, &["git", "push", "-b", "other"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Add branch other to 8dd849abb432
+    Error: Failed to upload Git LFS objects
+    Caused by: Failed to transfer Git LFS object 43e2396ec094bd64464552be37c3fa586e33ae07c88294054606a64283825d12: The object is not available locally
+    "###);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_git_lfs_credential_helper() {
+    curl_guard!();
+    let test_env = TestEnvironment::default();
+    // "user:secret" in base64
+    let server = LfsServer::start_with_auth(Some("Basic dXNlcjpzZWNyZXQ="));
+    set_up(&test_env, &server);
+    let home_dir = test_env.home_dir();
+    let mut git_config = std::fs::OpenOptions::new()
+        .append(true)
+        .open(home_dir.join(".gitconfig"))
+        .unwrap();
+    writeln!(
+        git_config,
+        r#"[credential]
+	helper = "!f() {{ echo \"$1\" >> \"$HOME/credential-log\"; if [ \"$1\" = get ]; then echo username=user; echo password=$(cat \"$HOME/password\"); fi; }}; f""#
+    )
+    .unwrap();
+    std::fs::write(home_dir.join("password"), "wrong").unwrap();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "clone", "origin", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join(".gitattributes"), "*.bin filter=lfs\n").unwrap();
+    std::fs::write(
+        repo_path.join("large.bin"),
+        "large file contents exceeding the limit\n",
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "add large file"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "main"]);
+
+    // Rejected credentials are reported to the credential helper
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["git", "push", "-b", "main"]);
+    insta::assert_snapshot!(stderr.replace(&server.url, "$URL"), @r###"
+    Branch changes to push to origin:
+      Add branch main to f3da97418756
+    Error: Failed to upload Git LFS objects
+    Caused by: Git LFS request to $URL/objects/batch failed: Credentials needed (HTTP status 401)
+    "###);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @r###"
+    batch upload unauthorized
+    batch upload unauthorized
+    "###);
+    let log = std::fs::read_to_string(home_dir.join("credential-log")).unwrap();
+    insta::assert_snapshot!(log, @r###"
+    get
+    erase
+    "###);
+    std::fs::remove_file(home_dir.join("credential-log")).unwrap();
+
+    // The credentials are requested when the server asks for them
+    std::fs::write(home_dir.join("password"), "secret").unwrap();
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["git", "push", "-b", "main"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Add branch main to f3da97418756
+    Uploaded 1 Git LFS objects
+    "###);
+    insta::assert_snapshot!(server.take_requests().join("\n"), @r###"
+    batch upload unauthorized
+    batch upload
+    upload 43e2396ec094
+    "###);
+    let log = std::fs::read_to_string(home_dir.join("credential-log")).unwrap();
+    insta::assert_snapshot!(log, @r###"
+    get
+    store
+    "###);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_git_lfs_ssh_authentication() {
+    curl_guard!();
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let mut test_env = TestEnvironment::default();
+    let server = LfsServer::start_with_auth(Some("RemoteAuth token"));
+    git2::Repository::init_bare(test_env.env_root().join("origin"))
+        .unwrap()
+        .set_head("refs/heads/main")
+        .unwrap();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "clone", "origin", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // The LFS endpoint and the credentials are provided by git-lfs-authenticate
+    let bin_dir = test_env.env_root().join("bin");
+    std::fs::create_dir(&bin_dir).unwrap();
+    let ssh_path = bin_dir.join("ssh");
+    std::fs::write(
+        &ssh_path,
+        format!(
+            r#"#!/bin/sh
+echo "$@" >> "$HOME/ssh-log"
+echo '{{"href": "{}", "header": {{"Authorization": "RemoteAuth token"}}}}'
+"#,
+            server.url
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&ssh_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    test_env.add_env_var("PATH", &format!("{}:/usr/bin:/bin", bin_dir.display()));
+    let git_repo = git2::Repository::open(repo_path.join(".jj/repo/store/git")).unwrap();
+    git_repo
+        .remote_set_url("origin", "git@example.com:foo/bar.git")
+        .unwrap();
+
+    std::fs::write(repo_path.join(".gitattributes"), "*.bin filter=lfs\n").unwrap();
+    std::fs::write(
+        repo_path.join("large.bin"),
+        "large file contents exceeding the limit\n",
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "add large file"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "main"]);
+    // The objects are uploaded before pushing the commits, which fails since
+    // there's no SSH server
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["git", "push", "-b", "main"]);
+    assert!(stderr.contains("Uploaded 1 Git LFS objects"), "{stderr}");
+    insta::assert_snapshot!(server.take_requests().join("\n"), @r###"
+    batch upload
+    upload 43e2396ec094
+    "###);
+    let log = std::fs::read_to_string(test_env.home_dir().join("ssh-log")).unwrap();
+    insta::assert_snapshot!(log, @r###"
+    -- git@example.com git-lfs-authenticate 'foo/bar.git' upload
+    "###);
+}
@@ -434,6 +434,86 @@ fn test_git_push_changes() {
     "###);
 }
 
+#[test]
+fn test_git_push_changes_with_name_template() {
+    let (test_env, workspace_root) = set_up();
+    test_env.add_config(
+        r#"git.push-branch-name = 'author.username() ++ "/" ++ description.first_line().slug()'"#,
+    );
+    test_env.jj_cmd_ok(&workspace_root, &["describe", "-m", "Fix the foo() bug"]);
+    std::fs::write(workspace_root.join("file"), "contents").unwrap();
+    test_env.jj_cmd_ok(&workspace_root, &["new", "-m", "Add bar"]);
+    std::fs::write(workspace_root.join("file"), "modified").unwrap();
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_root, &["git", "push", "-c=@-"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Creating branch test.user/fix-the-foo-bug for revision @-
+    Branch changes to push to origin:
+      Add branch test.user/fix-the-foo-bug to e23113215682
+    "###);
+
+    // The branch name doesn't change when the description is edited
+    test_env.jj_cmd_ok(
+        &workspace_root,
+        &["describe", "@-", "-m", "Fix the baz() bug"],
+    );
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_root, &["git", "push", "-c=@-"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Force branch test.user/fix-the-foo-bug from e23113215682 to 8b289b601335
+    "###);
+
+    // Collision between generated names
+    test_env.jj_cmd_ok(&workspace_root, &["describe", "-m", "Add qux"]);
+    test_env.jj_cmd_ok(&workspace_root, &["new", "root()", "-m", "Add qux!"]);
+    let stderr = test_env.jj_cmd_failure(
+        &workspace_root,
+        &[
+            "git",
+            "push",
+            "-c=@",
+            r#"-c=children("test.user/fix-the-foo-bug")"#,
+        ],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Branch name test.user/add-qux was generated for multiple revisions: a1b2b17a7da2 and 11fa2c22ae1e
+    "###);
+
+    // Collision with an existing branch for another change
+    test_env.jj_cmd_ok(&workspace_root, &["describe", "-m", "Fix the foo() bug"]);
+    let stderr = test_env.jj_cmd_failure(&workspace_root, &["git", "push", "-c=@"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Branch test.user/fix-the-foo-bug generated for revision @ already points to another change
+    Hint: Rename or delete the existing branch, or use `--branch` to push it
+    "###);
+
+    // Invalid branch name
+    test_env.jj_cmd_ok(&workspace_root, &["describe", "-m", ""]);
+    let stderr = test_env.jj_cmd_failure(&workspace_root, &["git", "push", "-c=@"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Invalid branch name "test.user/" generated for revision @
+    Hint: Check the git.push-branch-name template
+    "###);
+
+    // Collision with a remote branch for another change
+    test_env.jj_cmd_ok(
+        &workspace_root,
+        &["branch", "untrack", "test.user/fix-the-foo-bug@origin"],
+    );
+    test_env.jj_cmd_ok(
+        &workspace_root,
+        &["branch", "delete", "test.user/fix-the-foo-bug"],
+    );
+    test_env.jj_cmd_ok(&workspace_root, &["describe", "-m", "Fix the foo() bug"]);
+    let stderr = test_env.jj_cmd_failure(&workspace_root, &["git", "push", "-c=@"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Branch test.user/fix-the-foo-bug generated for revision @ already points to another change
+    Hint: Rename or delete the existing branch, or use `--branch` to push it
+    "###);
+}
+
 #[test]
 fn test_git_push_revisions() {
     let (test_env, workspace_root) = set_up();
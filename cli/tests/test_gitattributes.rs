@@ -0,0 +1,230 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+pub mod common;
+
+fn create_commit(
+    test_env: &TestEnvironment,
+    repo_path: &Path,
+    name: &str,
+    parents: &[&str],
+    files: &[(&str, &str)],
+) {
+    if parents.is_empty() {
+        test_env.jj_cmd_ok(repo_path, &["new", "root()", "-m", name]);
+    } else {
+        let mut args = vec!["new", "-m", name];
+        args.extend(parents);
+        test_env.jj_cmd_ok(repo_path, &args);
+    }
+    for (name, content) in files {
+        std::fs::write(repo_path.join(name), content).unwrap();
+    }
+    test_env.jj_cmd_ok(repo_path, &["branch", "create", name]);
+}
+
+#[test]
+fn test_gitattributes_eol() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(
+        repo_path.join(".gitattributes"),
+        "*.txt text eol=crlf\n*.lf eol=lf\n*.bin -text\n",
+    )
+    .unwrap();
+    std::fs::write(repo_path.join("file.txt"), "a\r\nb\n").unwrap();
+    std::fs::write(repo_path.join("file.lf"), "a\r\nb\r\n").unwrap();
+    std::fs::write(repo_path.join("file.bin"), "a\r\nb\r\n").unwrap();
+
+    // Line endings are normalized to LF in the repo
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "file.txt"]);
+    assert_eq!(stdout, "a\nb\n");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "file.lf"]);
+    assert_eq!(stdout, "a\nb\n");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "file.bin"]);
+    assert_eq!(stdout, "a\r\nb\r\n");
+
+    // Files are written with the configured line endings on checkout
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "eol"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "eol"]);
+    let read = |name: &str| std::fs::read(repo_path.join(name)).unwrap();
+    assert_eq!(read("file.txt"), b"a\r\nb\r\n");
+    assert_eq!(read("file.lf"), b"a\nb\n");
+    assert_eq!(read("file.bin"), b"a\r\nb\r\n");
+
+    // The converted files are unchanged when snapshotted again
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @"");
+}
+
+#[test]
+fn test_gitattributes_diff_binary() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(
+        repo_path.join(".gitattributes"),
+        "*.bin binary\n*.dat -diff\n",
+    )
+    .unwrap();
+    std::fs::write(repo_path.join("file.bin"), "a\n").unwrap();
+    std::fs::write(repo_path.join("file.dat"), "a\n").unwrap();
+    std::fs::write(repo_path.join("file.txt"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file.bin"), "b\n").unwrap();
+    std::fs::write(repo_path.join("file.dat"), "b\n").unwrap();
+    std::fs::write(repo_path.join("file.txt"), "b\n").unwrap();
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff"]);
+    insta::assert_snapshot!(stdout, @r###"
+    Modified regular file file.bin:
+        (binary)
+    Modified regular file file.dat:
+        (binary)
+    Modified regular file file.txt:
+       1    1: ab
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git"]);
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file.bin b/file.bin
+    index 7898192261...6178079822 100644
+    Binary files a/file.bin and b/file.bin differ
+    diff --git a/file.dat b/file.dat
+    index 7898192261...6178079822 100644
+    Binary files a/file.dat and b/file.dat differ
+    diff --git a/file.txt b/file.txt
+    index 7898192261...6178079822 100644
+    --- a/file.txt
+    +++ b/file.txt
+    @@ -1,1 +1,1 @@
+    -a
+    +b
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--stat"]);
+    insta::assert_snapshot!(stdout, @r###"
+    file.bin | 0
+    file.dat | 0
+    file.txt | 2 +-
+    3 files changed, 1 insertion(+), 1 deletion(-)
+    "###);
+
+    // The contents of binary files aren't passed to a file-by-file diff tool
+    let edit_script = test_env.set_up_fake_diff_editor();
+    std::fs::write(
+        edit_script,
+        "print-files-before\0print --\0print-files-after",
+    )
+    .unwrap();
+    let config = "--config-toml=merge-tools.fake-diff-editor.diff-invocation-mode='file-by-file'";
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--tool=fake-diff-editor", config]);
+    insta::assert_snapshot!(stdout, @r###"
+    Binary files file.bin and file.bin differ
+    Binary files file.dat and file.dat differ
+    file.txt
+    --
+    file.txt
+    "###);
+
+    // Added and removed files are also shown as binary
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git", "-r", "@-"]);
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/.gitattributes b/.gitattributes
+    new file mode 100644
+    index 0000000000..962157b636
+    --- /dev/null
+    +++ b/.gitattributes
+    @@ -1,0 +1,2 @@
+    +*.bin binary
+    +*.dat -diff
+    diff --git a/file.bin b/file.bin
+    new file mode 100644
+    index 0000000000..7898192261
+    Binary files /dev/null and b/file.bin differ
+    diff --git a/file.dat b/file.dat
+    new file mode 100644
+    index 0000000000..7898192261
+    Binary files /dev/null and b/file.dat differ
+    diff --git a/file.txt b/file.txt
+    new file mode 100644
+    index 0000000000..7898192261
+    --- /dev/null
+    +++ b/file.txt
+    @@ -1,0 +1,1 @@
+    +a
+    "###);
+}
+
+#[test]
+fn test_gitattributes_merge_binary() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(
+        &test_env,
+        &repo_path,
+        "base",
+        &[],
+        &[
+            (".gitattributes", "*.bin merge=binary\n"),
+            ("file.bin", "base\n"),
+        ],
+    );
+    create_commit(
+        &test_env,
+        &repo_path,
+        "a",
+        &["base"],
+        &[("file.bin", "a\n")],
+    );
+    create_commit(
+        &test_env,
+        &repo_path,
+        "b",
+        &["base"],
+        &[("file.bin", "b\n")],
+    );
+    create_commit(&test_env, &repo_path, "conflict", &["a", "b"], &[]);
+
+    // The first side is materialized without conflict markers
+    let stdout = test_env.jj_cmd_success(&repo_path, &["resolve", "--list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    file.bin    2-sided conflict
+    "###);
+    assert_eq!(std::fs::read(repo_path.join("file.bin")).unwrap(), b"a\n");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "file.bin"]);
+    assert_eq!(stdout, "a\n");
+
+    // Any change to the file resolves the conflict, even if it looks like it
+    // has conflict markers
+    std::fs::write(
+        repo_path.join("file.bin"),
+        "<<<<<<<\n+++++++\na\n+++++++\nb\n>>>>>>>\n",
+    )
+    .unwrap();
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["resolve", "--list"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No conflicts found at this revision
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "file.bin"]);
+    assert_eq!(stdout, "<<<<<<<\n+++++++\na\n+++++++\nb\n>>>>>>>\n");
+}
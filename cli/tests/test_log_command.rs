@@ -861,6 +861,27 @@ fn test_log_filtered_by_path() {
     M file1
     A file2
     "###);
+
+    // Fileset expressions can be used in both path arguments and file().
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "-T", "description", "--no-graph", "~file1"],
+    );
+    insta::assert_snapshot!(stdout, @"second");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "-T",
+            "description",
+            "--no-graph",
+            r#"-rfile(glob:"file*" ~ file2)"#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    second
+    first
+    "###);
 }
 
 #[test]
@@ -1357,3 +1378,122 @@ fn test_log_word_wrap() {
        merge
     "###);
 }
+
+#[test]
+fn test_log_follow() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    let template = r#"description ++ "\n""#;
+
+    std::fs::write(repo_path.join("file1"), "a\nb\nc\n").unwrap();
+    std::fs::write(repo_path.join("other"), "other\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "add file1"]);
+    std::fs::write(repo_path.join("other"), "other 2\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "modify other"]);
+    std::fs::write(repo_path.join("file1"), "a\nb\nc\nd\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "modify file1"]);
+    std::fs::remove_file(repo_path.join("file1")).unwrap();
+    std::fs::write(repo_path.join("file2"), "a\nb\nc\nd\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "rename file1 to file2"]);
+    std::fs::write(repo_path.join("file2"), "a\nb\nc\nd\ne\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "modify file2"]);
+
+    // Without --follow, the history stops at the rename
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "-r", "::", "--no-graph", "-T", template, "file2"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    modify file2
+
+    rename file1 to file2
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "-r::",
+            "--no-graph",
+            "-T",
+            template,
+            "--follow",
+            "-s",
+            "file2",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    modify file2
+
+    M file2
+    rename file1 to file2
+
+    R file1 => file2
+    modify file1
+
+    M file1
+    add file1
+
+    A file1
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log", "-r", "::", "-T", template, "--follow", "--git", "file2",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    @  modify file2
+    │
+    │  diff --git a/file2 b/file2
+    │  index d68dd4031d...9405325339 100644
+    │  --- a/file2
+    │  +++ b/file2
+    │  @@ -2,3 +2,4 @@
+    │   b
+    │   c
+    │   d
+    │  +e
+    ◉  rename file1 to file2
+    │
+    │  diff --git a/file1 b/file2
+    │  similarity index 100%
+    │  rename from file1
+    │  rename to file2
+    ◉  modify file1
+    ╷
+    ╷  diff --git a/file1 b/file1
+    ╷  index de980441c3...d68dd4031d 100644
+    ╷  --- a/file1
+    ╷  +++ b/file1
+    ╷  @@ -1,3 +1,4 @@
+    ╷   a
+    ╷   b
+    ╷   c
+    ╷  +d
+    ◉  add file1
+    │
+    ~  diff --git a/file1 b/file1
+       new file mode 100644
+       index 0000000000..de980441c3
+       --- /dev/null
+       +++ b/file1
+       @@ -1,0 +1,3 @@
+       +a
+       +b
+       +c
+    "###);
+
+    // Paths are required
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["log", "--follow"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: the following required arguments were not provided:
+      <PATHS>...
+
+    Usage: jj log --follow <PATHS>...
+
+    For more information, try '--help'.
+    "###);
+}
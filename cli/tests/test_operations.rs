@@ -193,6 +193,28 @@ fn test_op_log_template() {
     "###);
 }
 
+#[test]
+fn test_op_log_json() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    let template = r#"json(self) ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "log", "--no-graph", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    {"description":"add workspace 'default'","hostname":"host.example.com","id":"27143b59c6904046f6be83ad6fe145d819944f9abbd7247ea9c57848d1d2c678ea8265598a156fe8aeef31d24d958bf6cfa0c2eb3afef40bdae2c5e98d73d0ee","parents":["0e8aee02e24230c99d6d90d469c582a60fdb2ae8329341bbdb09f4a0beceba1ce7c84fc9ba6c7657d6d275b392b89b825502475ad2501be1ddebd4a09b07668c"],"tags":{},"time":{"end":"2001-02-03T04:05:07+07:00","start":"2001-02-03T04:05:07+07:00"},"username":"test-username"}
+    {"description":"initialize repo","hostname":"host.example.com","id":"0e8aee02e24230c99d6d90d469c582a60fdb2ae8329341bbdb09f4a0beceba1ce7c84fc9ba6c7657d6d275b392b89b825502475ad2501be1ddebd4a09b07668c","parents":["00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000"],"tags":{},"time":{"end":"2001-02-03T04:05:07+07:00","start":"2001-02-03T04:05:07+07:00"},"username":"test-username"}
+    {"description":"","hostname":"","id":"00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","parents":[],"tags":{},"time":{"end":"1970-01-01T00:00:00+00:00","start":"1970-01-01T00:00:00+00:00"},"username":""}
+    "###);
+
+    let template = r#"json(self.id()) ++ " " ++ json(time) ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["op", "log", "--no-graph", "--limit=1", "-T", template],
+    );
+    insta::assert_snapshot!(stdout, @r###""27143b59c6904046f6be83ad6fe145d819944f9abbd7247ea9c57848d1d2c678ea8265598a156fe8aeef31d24d958bf6cfa0c2eb3afef40bdae2c5e98d73d0ee" {"end":"2001-02-03T04:05:07+07:00","start":"2001-02-03T04:05:07+07:00"}"###);
+}
+
 #[test]
 fn test_op_log_builtin_templates() {
     let test_env = TestEnvironment::default();
@@ -443,6 +465,238 @@ fn test_op_abandon_without_updating_working_copy() {
     "###);
 }
 
+#[test]
+fn test_op_show() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // The root operation has nothing to compare against
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "show", "@--"]);
+    insta::assert_snapshot!(stdout, @"000000000000 root()");
+
+    // Initializing the repo adds the working-copy commit
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "show", "@-"]);
+    insta::assert_snapshot!(stdout, @r###"
+    0e8aee02e242 test-username@host.example.com 2001-02-03 04:05:07.000 +07:00 - 2001-02-03 04:05:07.000 +07:00
+    initialize repo
+
+    Changed commits:
+    Change zzzzzzzzzzzz
+    + zzzzzzzz 00000000 (empty) (no description set)
+    "###);
+
+    std::fs::write(repo_path.join("file"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "-r@-", "main"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "show"]);
+    insta::assert_snapshot!(stdout, @r###"
+    bcd104bdddd2 test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    create branch main pointing to commit d97acf9c99333408859e8c989e515efdadf7bdc3
+    args: jj branch create -r@- main
+
+    Changed local branches:
+    main:
+    + qpvuntsm d97acf9c main | first
+    - (absent)
+    "###);
+
+    // Rewriting a commit shows both versions, and the patch between them
+    std::fs::write(repo_path.join("file"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["squash"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "show", "-p", "--git"]);
+    insta::assert_snapshot!(stdout, @r###"
+    aa8503c03da3 test-username@host.example.com 2001-02-03 04:05:13.000 +07:00 - 2001-02-03 04:05:13.000 +07:00
+    squash commit 249ec9f00a6c7dfc4ee29b9a6378181c4c83b029
+    args: jj squash
+
+    Changed commits:
+    Change yqosqzytrlsw
+    + yqosqzyt bec15a0a (empty) (no description set)
+    Change qpvuntsmwlqt
+    + qpvuntsm bc16cf93 main | first
+    - qpvuntsm hidden d97acf9c first
+    diff --git a/file b/file
+    index 7898192261...6178079822 100644
+    --- a/file
+    +++ b/file
+    @@ -1,1 +1,1 @@
+    -a
+    +b
+    Change zsuskulnrvyr
+    - zsuskuln hidden 249ec9f0 (no description set)
+
+    Changed working-copy commits:
+    default@:
+    + yqosqzyt bec15a0a (empty) (no description set)
+    - zsuskuln hidden 249ec9f0 (no description set)
+
+    Changed local branches:
+    main:
+    + qpvuntsm bc16cf93 main | first
+    - qpvuntsm hidden d97acf9c first
+    "###);
+}
+
+#[test]
+fn test_op_diff() {
+    let test_env = TestEnvironment::default();
+    let git_repo_path = test_env.env_root().join("git-repo");
+    let git_repo = git2::Repository::init(&git_repo_path).unwrap();
+    let signature =
+        git2::Signature::new("Some One", "some.one@example.com", &git2::Time::new(0, 0)).unwrap();
+    let tree_id = git_repo.treebuilder(None).unwrap().write().unwrap();
+    let tree = git_repo.find_tree(tree_id).unwrap();
+    git_repo
+        .commit(
+            Some("refs/heads/main"),
+            &signature,
+            &signature,
+            "remote commit",
+            &tree,
+            &[],
+        )
+        .unwrap();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["git", "remote", "add", "origin", "../git-repo"],
+    );
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "log", "--no-graph", "-T=id.short()"]);
+    let base_op_id = stdout[..12].to_owned();
+
+    test_env.jj_cmd_ok(&repo_path, &["git", "fetch"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "diff"]);
+    insta::assert_snapshot!(stdout, @r###"
+    From operation 27143b59c690: add workspace 'default'
+      To operation 80aa86018194: fetch from git remote(s) origin
+
+    Changed commits:
+    Change luzzuoykymmo
+    + luzzuoyk ab29b767 main@origin | (empty) remote commit
+
+    Changed remote branches:
+    main@origin:
+    + untracked luzzuoyk ab29b767 main@origin | (empty) remote commit
+    - untracked (absent)
+
+    Changed git refs:
+    refs/remotes/origin/main:
+    + luzzuoyk ab29b767 main@origin | (empty) remote commit
+    - (absent)
+    "###);
+
+    test_env.jj_cmd_ok(&repo_path, &["new", "main@origin", "-m", "on main"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "track", "main@origin"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "set", "main", "-r@"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "diff", "--op", "@-"]);
+    insta::assert_snapshot!(stdout, @r###"
+    From operation 32ea6399005e: new empty commit
+      To operation 5d77476c03c7: track remote branch main@origin
+
+    Changed local branches:
+    main:
+    + luzzuoyk ab29b767 main | (empty) remote commit
+    - (absent)
+
+    Changed remote branches:
+    main@origin:
+    + tracked luzzuoyk ab29b767 main | (empty) remote commit
+    - untracked luzzuoyk ab29b767 main | (empty) remote commit
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "diff"]);
+    insta::assert_snapshot!(stdout, @r###"
+    From operation 5d77476c03c7: track remote branch main@origin
+      To operation 7f3166ac3edb: point branch main to commit 17174b156621ce4d695aa07b51952d4528a889b4
+
+    Changed local branches:
+    main:
+    + royxmykx 17174b15 main* | (empty) on main
+    - luzzuoyk ab29b767 main@origin | (empty) remote commit
+    "###);
+
+    // Compare across several operations, in both directions
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "diff", "--from", &base_op_id]);
+    insta::assert_snapshot!(stdout, @r###"
+    From operation 27143b59c690: add workspace 'default'
+      To operation 7f3166ac3edb: point branch main to commit 17174b156621ce4d695aa07b51952d4528a889b4
+
+    Changed commits:
+    Change royxmykxtrkr
+    + royxmykx 17174b15 main* | (empty) on main
+    Change luzzuoykymmo
+    + luzzuoyk ab29b767 main@origin | (empty) remote commit
+    Change qpvuntsmwlqt
+    - qpvuntsm hidden 230dd059 (empty) (no description set)
+
+    Changed working-copy commits:
+    default@:
+    + royxmykx 17174b15 main* | (empty) on main
+    - qpvuntsm hidden 230dd059 (empty) (no description set)
+
+    Changed local branches:
+    main:
+    + royxmykx 17174b15 main* | (empty) on main
+    - (absent)
+
+    Changed remote branches:
+    main@origin:
+    + tracked luzzuoyk ab29b767 main@origin | (empty) remote commit
+    - untracked (absent)
+
+    Changed git refs:
+    refs/remotes/origin/main:
+    + luzzuoyk ab29b767 main@origin | (empty) remote commit
+    - (absent)
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "diff", "--to", &base_op_id]);
+    insta::assert_snapshot!(stdout, @r###"
+    From operation 7f3166ac3edb: point branch main to commit 17174b156621ce4d695aa07b51952d4528a889b4
+      To operation 27143b59c690: add workspace 'default'
+
+    Changed commits:
+    Change qpvuntsmwlqt
+    + qpvuntsm 230dd059 (empty) (no description set)
+    Change royxmykxtrkr
+    - royxmykx hidden 17174b15 (empty) on main
+    Change luzzuoykymmo
+    - luzzuoyk hidden ab29b767 (empty) remote commit
+
+    Changed working-copy commits:
+    default@:
+    + qpvuntsm 230dd059 (empty) (no description set)
+    - royxmykx hidden 17174b15 (empty) on main
+
+    Changed local branches:
+    main:
+    + (absent)
+    - royxmykx hidden 17174b15 (empty) on main
+
+    Changed remote branches:
+    main@origin:
+    + untracked (absent)
+    - tracked luzzuoyk hidden ab29b767 (empty) remote commit
+
+    Changed git refs:
+    refs/remotes/origin/main:
+    + (absent)
+    - luzzuoyk hidden ab29b767 (empty) remote commit
+    "###);
+
+    let stderr = test_env.jj_cmd_cli_error(
+        &repo_path,
+        &["op", "diff", "--op", "@", "--from", &base_op_id],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--operation <OPERATION>' cannot be used with '--from <FROM>'
+
+    Usage: jj operation diff --operation <OPERATION>
+
+    For more information, try '--help'.
+    "###);
+}
+
 fn get_log_output(test_env: &TestEnvironment, repo_path: &Path, op_id: &str) -> String {
     test_env.jj_cmd_success(
         repo_path,
@@ -0,0 +1,240 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+pub mod common;
+
+#[test]
+fn test_parallelize_no_descendants() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+
+    for n in 1..6 {
+        test_env.jj_cmd_ok(&workspace_path, &["commit", &format!("-m{n}")]);
+    }
+    test_env.jj_cmd_ok(&workspace_path, &["describe", "-m=6"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_path), @r###"
+    @  b911505e443e 6
+    ◉  2e00cb15c7b6 5
+    ◉  9df3c87db1a2 4
+    ◉  9f5b59fa4622 3
+    ◉  d826910d21fb 2
+    ◉  dc0e5d6135ce 1
+    ◉  000000000000
+    "###);
+
+    test_env.jj_cmd_ok(&workspace_path, &["parallelize", "description(1)::"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_path), @r###"
+    @  6c7b60a45eb6 6
+    │ ◉  296f48966777 5
+    ├─╯
+    │ ◉  524062469789 4
+    ├─╯
+    │ ◉  a9334ecaa379 3
+    ├─╯
+    │ ◉  3a7b37ebe843 2
+    ├─╯
+    │ ◉  761e67df44b7 1
+    ├─╯
+    ◉  000000000000
+    "###);
+}
+
+#[test]
+fn test_parallelize_with_descendants() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+
+    for n in 1..4 {
+        std::fs::write(workspace_path.join(format!("file{n}")), "").unwrap();
+        test_env.jj_cmd_ok(&workspace_path, &["commit", &format!("-m{n}")]);
+    }
+    test_env.jj_cmd_ok(&workspace_path, &["describe", "-m=4"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_path), @r###"
+    @  58ea8e6c8d85 4
+    ◉  4229173cf26b 3
+    ◉  d75bc4a4d0f2 2
+    ◉  46c69ebe62bf 1
+    ◉  000000000000
+    "###);
+
+    // The child of the set keeps its contents, and the siblings keep their own
+    // changes.
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &workspace_path,
+        &["parallelize", "description(1)::description(2)"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Rebased 1 descendant commits
+    Working copy now at: zsuskuln 275079a2 (empty) 4
+    Parent commit      : kkmpptxz 851df988 3
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_path), @r###"
+    @  275079a26ed0 4
+    ◉    851df9888450 3
+    ├─╮
+    │ ◉  6c374e486860 2
+    ◉ │  021ffed7b403 1
+    ├─╯
+    ◉  000000000000
+    "###);
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["diff", "-s", "-r=description(2)"]);
+    insta::assert_snapshot!(stdout, @"A file2");
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["diff", "-s", "-r=description(3)"]);
+    insta::assert_snapshot!(stdout, @"A file3");
+}
+
+#[test]
+fn test_parallelize_with_child_of_middle_commit() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+
+    for n in 1..4 {
+        std::fs::write(workspace_path.join(format!("file{n}")), "").unwrap();
+        test_env.jj_cmd_ok(&workspace_path, &["commit", &format!("-m{n}")]);
+    }
+    test_env.jj_cmd_ok(&workspace_path, &["describe", "-m=4"]);
+    test_env.jj_cmd_ok(&workspace_path, &["new", "description(2)", "-m=x"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_path), @r###"
+    @  9f7b4c7a912c x
+    │ ◉  58ea8e6c8d85 4
+    │ ◉  4229173cf26b 3
+    ├─╯
+    ◉  d75bc4a4d0f2 2
+    ◉  46c69ebe62bf 1
+    ◉  000000000000
+    "###);
+
+    // Only the child of the head depends on all of the new siblings. The child
+    // of the middle commit keeps its parent.
+    test_env.jj_cmd_ok(
+        &workspace_path,
+        &["parallelize", "description(1)::description(3)"],
+    );
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_path), @r###"
+    ◉      3f0f1d9aa5e6 4
+    ├─┬─╮
+    │ │ ◉  fa62146e9649 3
+    ◉ │ │  7b1b91c67e04 1
+    ├───╯
+    │ │ @  53422fc938e3 x
+    │ ├─╯
+    │ ◉  7d9f13d4352f 2
+    ├─╯
+    ◉  000000000000
+    "###);
+}
+
+#[test]
+fn test_parallelize_merge_with_outside_parent() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&workspace_path, &["commit", "-m=0"]);
+    test_env.jj_cmd_ok(&workspace_path, &["commit", "-m=1"]);
+    test_env.jj_cmd_ok(&workspace_path, &["new", "description(0)", "-m=x"]);
+    test_env.jj_cmd_ok(
+        &workspace_path,
+        &["new", "description(1)", "description(x)", "-m=2"],
+    );
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_path), @r###"
+    @    028724d78ff5 2
+    ├─╮
+    │ ◉  5452c6c28f1d x
+    ◉ │  a915696cf0ad 1
+    ├─╯
+    ◉  a56846756248 0
+    ◉  000000000000
+    "###);
+
+    // The merge keeps its parent from outside the set, and the other target
+    // doesn't get it.
+    test_env.jj_cmd_ok(
+        &workspace_path,
+        &["parallelize", "description(1)::description(2)"],
+    );
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_path), @r###"
+    @    8e14c170d429 2
+    ├─╮
+    │ ◉  5452c6c28f1d x
+    ├─╯
+    │ ◉  43d31385df1b 1
+    ├─╯
+    ◉  a56846756248 0
+    ◉  000000000000
+    "###);
+}
+
+#[test]
+fn test_parallelize_single_revision() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&workspace_path, &["commit", "-m=1"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_path, &["parallelize", "@-"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Nothing changed.");
+}
+
+#[test]
+fn test_parallelize_disconnected_target_commits() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+
+    for n in 1..3 {
+        test_env.jj_cmd_ok(&workspace_path, &["commit", &format!("-m{n}")]);
+    }
+    test_env.jj_cmd_ok(&workspace_path, &["describe", "-m=3"]);
+
+    let stderr = test_env.jj_cmd_failure(
+        &workspace_path,
+        &["parallelize", "description(1)", "description(3)"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Cannot parallelize since the target revisions are not connected.
+    "###);
+}
+
+#[test]
+fn test_parallelize_immutable_commits() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+
+    for n in 1..3 {
+        test_env.jj_cmd_ok(&workspace_path, &["commit", &format!("-m{n}")]);
+    }
+    test_env.add_config(r#"revset-aliases."immutable_heads()" = "description(1)""#);
+
+    let stderr = test_env.jj_cmd_failure(&workspace_path, &["parallelize", "description(1)::"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Commit dc0e5d6135ce is immutable
+    Hint: Configure the set of immutable commits via `revset-aliases.immutable_heads()`.
+    "###);
+}
+
+fn get_log_output(test_env: &TestEnvironment, cwd: &Path) -> String {
+    let template = r#"separate(" ", commit_id.short(), local_branches, description)"#;
+    test_env.jj_cmd_success(cwd, &["log", "-T", template])
+}
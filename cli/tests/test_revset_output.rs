@@ -128,7 +128,13 @@ fn test_bad_function_call() {
     1 | file(a, not:a-string)
       |         ^----------^
       |
-      = Invalid arguments to revset function "file": Expected function argument of type string
+      = Invalid fileset expression
+     --> 1:1
+      |
+    1 | not:a-string
+      | ^----------^
+      |
+      = Invalid file pattern kind "not:"
     "###);
 
     let stderr = test_env.jj_cmd_failure(&repo_path, &["log", "-r", r#"file(a, "../out")"#]);
@@ -138,6 +144,12 @@ fn test_bad_function_call() {
     1 | file(a, "../out")
       |         ^------^
       |
+      = Invalid fileset expression
+     --> 1:1
+      |
+    1 | ../out
+      | ^----^
+      |
       = Invalid file pattern: Path "../out" is not in the repo
     "###);
 
@@ -151,6 +163,42 @@ fn test_bad_function_call() {
       = Invalid arguments to revset function "branches": Invalid string pattern kind "bad"
     "###);
 
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["log", "-r", "author_date(2001-02-03)"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse revset:  --> 1:13
+      |
+    1 | author_date(2001-02-03)
+      |             ^--------^
+      |
+      = Invalid arguments to revset function "author_date": Expected function argument of date pattern
+    "###);
+
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["log", "-r", r#"committer_date(since:"2001-02-03")"#],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse revset:  --> 1:16
+      |
+    1 | committer_date(since:"2001-02-03")
+      |                ^----------------^
+      |
+      = Invalid arguments to revset function "committer_date": Invalid date pattern kind "since"
+    "###);
+
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["log", "-r", r#"committer_date(after:"someday")"#],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse revset:  --> 1:16
+      |
+    1 | committer_date(after:"someday")
+      |                ^-------------^
+      |
+      = Invalid arguments to revset function "committer_date": Invalid date "someday"
+    "###);
+
     let stderr = test_env.jj_cmd_failure(&repo_path, &["log", "-r", "root()::whatever()"]);
     insta::assert_snapshot!(stderr, @r###"
     Error: Failed to parse revset:  --> 1:9
@@ -241,7 +289,7 @@ fn test_function_name_hint() {
       | ^-----^
       |
       = Revset function "author_" doesn't exist
-    Hint: Did you mean "author", "my_author"?
+    Hint: Did you mean "author", "author_date", "my_author"?
     "###);
 }
 
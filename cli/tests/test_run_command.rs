@@ -0,0 +1,176 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use jj_lib::lock::FileLock;
+
+use crate::common::TestEnvironment;
+
+pub mod common;
+
+fn create_commit(test_env: &TestEnvironment, repo_path: &Path, name: &str, parents: &[&str]) {
+    if parents.is_empty() {
+        test_env.jj_cmd_ok(repo_path, &["new", "root()", "-m", name]);
+    } else {
+        let mut args = vec!["new", "-m", name];
+        args.extend(parents);
+        test_env.jj_cmd_ok(repo_path, &args);
+    }
+    std::fs::write(repo_path.join(format!("{name}.txt")), format!("{name}\n")).unwrap();
+    test_env.jj_cmd_ok(repo_path, &["branch", "create", name]);
+}
+
+fn get_log_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
+    test_env.jj_cmd_success(repo_path, &["log", "-T", "branches"])
+}
+
+#[test]
+fn test_run_invalid_jobs() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["run", "true", "-j", "0"]);
+    insta::assert_snapshot!(stderr, @"Error: must pass at least one job");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_rewrites_and_rebases() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(&test_env, &repo_path, "a", &[]);
+    create_commit(&test_env, &repo_path, "b", &["a"]);
+    create_commit(&test_env, &repo_path, "c", &["b"]);
+
+    let upcase = "for f in *.txt; do tr a-z A-Z < $f > $f.tmp && mv $f.tmp $f; done";
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["run", upcase, "-r", "a::b", "-j", "2"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Ran command on rlvkpnrz 694d850a a | a
+    Ran command on zsuskuln 351216fd b | b
+    Rewrote 2 commits
+    Rebased 2 descendant commits
+    Working copy now at: royxmykx aeb39c23 c | c
+    Parent commit      : zsuskuln 2bb43c19 b | b
+    Added 0 files, modified 2 files, removed 0 files
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  c
+    ◉  b
+    ◉  a
+    ◉
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "-r", "c", "a.txt"]);
+    insta::assert_snapshot!(stdout, @"A");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "-r", "c", "b.txt"]);
+    insta::assert_snapshot!(stdout, @"B");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "-r", "c", "c.txt"]);
+    insta::assert_snapshot!(stdout, @"c");
+
+    // The temporary working copies are reused, but can be cleaned up
+    assert!(repo_path.join(".jj").join("run").join("0").exists());
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["run", "true", "-r", "c", "--clean"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Ran command on royxmykx aeb39c23 c | c
+    Nothing changed.
+    "###);
+    assert!(!repo_path.join(".jj").join("run").join("1").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_output_and_failure() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(&test_env, &repo_path, "a", &[]);
+    create_commit(&test_env, &repo_path, "b", &["a"]);
+
+    // The command's output is forwarded, and commits where it fails are left
+    // unchanged
+    let assert = test_env
+        .jj_cmd(
+            &repo_path,
+            &[
+                "run",
+                "cat *.txt; echo modified > a.txt; test ! -f b.txt",
+                "-r",
+                "a::b",
+                "-j",
+                "1",
+            ],
+        )
+        .assert()
+        .code(1);
+    let stdout = test_env.normalize_output(&common::get_stdout_string(&assert));
+    let stderr = test_env.normalize_output(&common::get_stderr_string(&assert));
+    insta::assert_snapshot!(stdout, @r###"
+    a
+    a
+    b
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Ran command on rlvkpnrz 694d850a a | a
+    Ran command on zsuskuln 351216fd b | b
+    Rewrote 1 commits
+    The command failed on these commits:
+      zsuskuln 351216fd b | b
+    Rebased 1 descendant commits
+    Working copy now at: zsuskuln 8ee04621 b | b
+    Parent commit      : rlvkpnrz 4a7c4a5c a | a
+    Added 0 files, modified 1 files, removed 0 files
+    Error: Command failed on 1 commits, which were left unchanged
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "-r", "b", "a.txt"]);
+    insta::assert_snapshot!(stdout, @"modified");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_skips_locked_working_copies() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    let run_dir = repo_path.join(".jj").join("run");
+
+    create_commit(&test_env, &repo_path, "a", &[]);
+
+    // A working copy in use by another invocation is neither used nor cleaned
+    std::fs::create_dir_all(run_dir.join("0")).unwrap();
+    let lock = FileLock::lock(run_dir.join("0.lock"));
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["run", "true", "--clean"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Ran command on rlvkpnrz 694d850a a | a
+    Nothing changed.
+    "###);
+    assert!(run_dir.join("0").exists());
+    assert!(run_dir.join("1").exists());
+    assert!(!run_dir.join("1.lock").exists());
+
+    // Once it's released, it's used again
+    drop(lock);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["run", "true", "--clean"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Ran command on rlvkpnrz 694d850a a | a
+    Nothing changed.
+    "###);
+    assert!(run_dir.join("0").exists());
+    assert!(!run_dir.join("1").exists());
+}
@@ -0,0 +1,130 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+pub mod common;
+
+fn get_log_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
+    let template = r#"description.first_line() ++ " " ++ if(signature, signature, "unsigned")"#;
+    test_env.jj_cmd_success(repo_path, &["log", "-T", template])
+}
+
+#[test]
+fn test_sign_not_configured() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["sign"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No signing backend is configured
+    Hint: Set `signing.backend` to the backend to sign with
+    "###);
+}
+
+#[test]
+fn test_sign_and_unsign() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(
+        r#"
+        signing.backend = "test"
+        signing.key = "impeccable"
+        "#,
+    );
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "a"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "b"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "c"]);
+
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  c unsigned
+    ◉  b unsigned
+    ◉  a unsigned
+    ◉   unsigned
+    "###);
+
+    // Sign a range of commits, which are rewritten without being rebased onto
+    // each other again
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["sign", "-r", "@--::@-"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Signed 2 commits:
+      qpvuntsm b057e183 (empty) a
+      kkmpptxz b9ad830f (empty) b
+    Rebased 1 descendant commits
+    Working copy now at: zsuskuln 4c243e6b (empty) c
+    Parent commit      : kkmpptxz b9ad830f (empty) b
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  c unsigned
+    ◉  b good signature (key impeccable)
+    ◉  a good signature (key impeccable)
+    ◉   unsigned
+    "###);
+
+    // The key can be overridden
+    test_env.jj_cmd_ok(&repo_path, &["sign", "-r", "@", "--key", "other"]);
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "-r",
+            "@",
+            "--no-graph",
+            "-T",
+            r#"signature.status() ++ " " ++ signature.key() ++ "\n""#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    good other
+    "###);
+
+    // Unsigned commits in the revset are left alone
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["unsign", "-r", "all()"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Unsigned 3 commits:
+      qpvuntsm 5deb62ec (empty) a
+      kkmpptxz 3fc82336 (empty) b
+      zsuskuln c7aeb51c (empty) c
+    Working copy now at: zsuskuln c7aeb51c (empty) c
+    Parent commit      : kkmpptxz 3fc82336 (empty) b
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  c unsigned
+    ◉  b unsigned
+    ◉  a unsigned
+    ◉   unsigned
+    "###);
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["unsign", "-r", "all()"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Nothing changed.
+    "###);
+}
+
+#[test]
+fn test_sign_immutable() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"signing.backend = "test""#);
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["sign", "-r", "root()"]);
+    insta::assert_snapshot!(stderr, @"Error: The root commit 000000000000 is immutable");
+}
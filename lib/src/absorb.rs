@@ -0,0 +1,289 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Moves the changes in a commit into the ancestors that last modified the
+//! same lines.
+//!
+//! Each hunk of the diff between the source commit and its parents is
+//! attributed with the help of [`crate::annotate`]. A hunk is moved into a
+//! destination commit only if all lines it replaces (or, for pure insertions,
+//! the lines surrounding it) were last modified by that commit.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::annotate::{annotate_contents, AnnotateError};
+use crate::backend::{BackendError, CommitId, FileId, TreeValue};
+use crate::commit::Commit;
+use crate::diff::{find_line_ranges, Diff, DiffHunk};
+use crate::matchers::Matcher;
+use crate::merge::Merge;
+use crate::merged_tree::{MergedTree, MergedTreeBuilder};
+use crate::repo::{MutableRepo, Repo};
+use crate::repo_path::{RepoPath, RepoPathBuf};
+use crate::rewrite::merge_commit_trees;
+use crate::settings::UserSettings;
+use crate::tree::TreeMergeError;
+
+/// Error while absorbing changes.
+#[derive(Debug, Error)]
+pub enum AbsorbError {
+    /// Failed to attribute the lines of a file.
+    #[error(transparent)]
+    Annotate(#[from] AnnotateError),
+    /// Failed to read or write objects.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Failed to merge trees.
+    #[error(transparent)]
+    TreeMerge(#[from] TreeMergeError),
+    /// Failed to read file contents.
+    #[error("Failed to read file contents: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// The commit to move changes out of.
+#[derive(Clone, Debug)]
+pub struct AbsorbSource {
+    commit: Commit,
+    parent_tree: MergedTree,
+}
+
+impl AbsorbSource {
+    /// Creates a source from the changes `commit` makes on top of its
+    /// parents.
+    pub fn from_commit(repo: &dyn Repo, commit: Commit) -> Result<Self, TreeMergeError> {
+        let parent_tree = merge_commit_trees(repo, &commit.parents())?;
+        Ok(AbsorbSource {
+            commit,
+            parent_tree,
+        })
+    }
+
+    /// The source commit.
+    pub fn commit(&self) -> &Commit {
+        &self.commit
+    }
+}
+
+/// The hunks selected for each destination commit.
+pub struct SelectedTrees {
+    /// Trees with the selected hunks applied to the source's parent tree,
+    /// keyed by destination commit.
+    pub target_commits: HashMap<CommitId, MergedTreeBuilder>,
+    /// Changed paths that can't be absorbed, with the reason why.
+    pub skipped_paths: Vec<(RepoPathBuf, String)>,
+}
+
+/// Attributes each hunk of the changes in `source` to one of the
+/// `destinations`.
+///
+/// The destinations should be ancestors of the source. Hunks that can't be
+/// attributed to a single destination aren't selected.
+pub fn split_hunks_to_trees(
+    repo: &dyn Repo,
+    source: &AbsorbSource,
+    destinations: &HashSet<CommitId>,
+    matcher: &dyn Matcher,
+) -> Result<SelectedTrees, AbsorbError> {
+    let mut selected_trees = SelectedTrees {
+        target_commits: HashMap::new(),
+        skipped_paths: vec![],
+    };
+    let left_tree = &source.parent_tree;
+    let right_tree = source.commit.tree()?;
+    for (path, diff) in left_tree.diff(&right_tree, matcher) {
+        let (left_value, right_value) = diff?;
+        let (left_id, executable, right_id) =
+            match (left_value.as_resolved(), right_value.as_resolved()) {
+                (
+                    Some(Some(TreeValue::File {
+                        id: left_id,
+                        executable,
+                    })),
+                    Some(Some(TreeValue::File { id: right_id, .. })),
+                ) => (left_id, *executable, right_id),
+                (Some(None), _) => {
+                    let reason = "added files can't be absorbed".to_owned();
+                    selected_trees.skipped_paths.push((path, reason));
+                    continue;
+                }
+                (_, Some(None)) => {
+                    let reason = "deleted files can't be absorbed".to_owned();
+                    selected_trees.skipped_paths.push((path, reason));
+                    continue;
+                }
+                _ => {
+                    let reason = "only changes to regular files can be absorbed".to_owned();
+                    selected_trees.skipped_paths.push((path, reason));
+                    continue;
+                }
+            };
+        if left_id == right_id {
+            continue;
+        }
+        let left_contents = read_file_contents(repo, &path, left_id)?;
+        let right_contents = read_file_contents(repo, &path, right_id)?;
+        let origins = annotate_contents(
+            repo,
+            &source.commit,
+            &left_contents,
+            &path,
+            Some(destinations),
+        )?;
+        let hunks = select_hunks(&left_contents, &right_contents, &origins, destinations);
+        for destination in hunks.iter().filter_map(|(_, _, id)| *id).unique() {
+            let mut contents = vec![];
+            for (left, right, id) in &hunks {
+                if *id == Some(destination) {
+                    contents.extend_from_slice(right);
+                } else {
+                    contents.extend_from_slice(left);
+                }
+            }
+            let id = repo.store().write_file(&path, &mut contents.as_slice())?;
+            selected_trees
+                .target_commits
+                .entry(destination.clone())
+                .or_insert_with(|| MergedTreeBuilder::new(left_tree.id()))
+                .set_or_remove(
+                    path.clone(),
+                    Merge::normal(TreeValue::File { id, executable }),
+                );
+        }
+    }
+    Ok(selected_trees)
+}
+
+fn read_file_contents(
+    repo: &dyn Repo,
+    path: &RepoPath,
+    id: &FileId,
+) -> Result<Vec<u8>, AbsorbError> {
+    let mut contents = vec![];
+    repo.store()
+        .read_file(path, id)?
+        .read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+/// Splits the diff between `left` and `right` into `(left, right, destination)`
+/// hunks. Unchanged content is included as hunks with identical sides and no
+/// destination.
+fn select_hunks<'a, 'b>(
+    left: &'a [u8],
+    right: &'a [u8],
+    origins: &'b [Option<CommitId>],
+    destinations: &HashSet<CommitId>,
+) -> Vec<(&'a [u8], &'a [u8], Option<&'b CommitId>)> {
+    let diff = Diff::for_tokenizer(&[left, right], &find_line_ranges);
+    let mut left_line = 0;
+    let mut hunks = vec![];
+    for hunk in diff.hunks() {
+        match hunk {
+            DiffHunk::Matching(content) => {
+                left_line += find_line_ranges(content).len();
+                hunks.push((content, content, None));
+            }
+            DiffHunk::Different(contents) => {
+                let num_left_lines = find_line_ranges(contents[0]).len();
+                // A pure insertion is attributed by the lines around it.
+                let attributed_lines = if num_left_lines == 0 {
+                    left_line.saturating_sub(1)..(left_line + 1).min(origins.len())
+                } else {
+                    left_line..left_line + num_left_lines
+                };
+                let destination = unique_origin(&origins[attributed_lines])
+                    .filter(|id| destinations.contains(id));
+                left_line += num_left_lines;
+                hunks.push((contents[0], contents[1], destination));
+            }
+        }
+    }
+    hunks
+}
+
+/// Returns the commit all `origins` agree on, if any.
+fn unique_origin(origins: &[Option<CommitId>]) -> Option<&CommitId> {
+    let (first, rest) = origins.split_first()?;
+    let first = first.as_ref()?;
+    rest.iter()
+        .all(|origin| origin.as_ref() == Some(first))
+        .then_some(first)
+}
+
+/// Result of [`absorb_hunks`].
+#[derive(Debug)]
+pub struct AbsorbStats {
+    /// The rewritten destination commits.
+    pub rewritten_destinations: Vec<Commit>,
+    /// The number of descendant commits that were rebased, including the
+    /// source commit.
+    pub num_rebased: usize,
+}
+
+/// Applies the `selected_trees` to their destination commits, and rebases all
+/// descendants, which removes the absorbed hunks from the source.
+pub fn absorb_hunks(
+    settings: &UserSettings,
+    mut_repo: &mut MutableRepo,
+    source: &AbsorbSource,
+    selected_trees: HashMap<CommitId, MergedTreeBuilder>,
+) -> Result<AbsorbStats, AbsorbError> {
+    let store = mut_repo.store().clone();
+    let mut selected_trees = selected_trees;
+    let destination_ids = selected_trees.keys().cloned().collect_vec();
+    // Parents are rewritten before their children so the children can be
+    // rebased onto them directly.
+    let sorted_ids = mut_repo.index().topo_order(&mut destination_ids.iter());
+    let mut rewritten: HashMap<CommitId, Commit> = HashMap::new();
+    let mut rewritten_destinations = vec![];
+    for old_id in sorted_ids {
+        let old_commit = store.get_commit(&old_id)?;
+        let tree_builder = selected_trees.remove(&old_id).unwrap();
+        let selected_tree = store.get_root_tree(&tree_builder.write_tree(&store)?)?;
+        let new_parents = old_commit
+            .parents()
+            .into_iter()
+            .map(|parent| rewritten.get(parent.id()).cloned().unwrap_or(parent))
+            .collect_vec();
+        let mut new_tree = old_commit.tree()?;
+        if new_parents != old_commit.parents() {
+            let old_base_tree = merge_commit_trees(mut_repo, &old_commit.parents())?;
+            let new_base_tree = merge_commit_trees(mut_repo, &new_parents)?;
+            new_tree = new_base_tree.merge(&old_base_tree, &new_tree)?;
+        }
+        let new_tree = new_tree.merge(&source.parent_tree, &selected_tree)?;
+        let new_commit = mut_repo
+            .rewrite_commit(settings, &old_commit)
+            .set_parents(
+                new_parents
+                    .iter()
+                    .map(|parent| parent.id().clone())
+                    .collect(),
+            )
+            .set_tree_id(new_tree.id())
+            .write()?;
+        rewritten.insert(old_id, new_commit.clone());
+        rewritten_destinations.push(new_commit);
+    }
+    let num_rebased = mut_repo.rebase_descendants(settings)?;
+    Ok(AbsorbStats {
+        rewritten_destinations,
+        num_rebased,
+    })
+}
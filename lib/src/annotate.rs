@@ -0,0 +1,259 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Line-by-line attribution ("blame") of a file to the commits that introduced
+//! each line.
+//!
+//! Starting from a commit, the file's history is walked towards the root. Each
+//! line that still needs to be attributed is followed into the parents whose
+//! version of the file contains it unchanged. A line that doesn't exist in any
+//! parent was introduced by the commit itself.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use pollster::FutureExt;
+use thiserror::Error;
+
+use crate::backend::{BackendError, CommitId};
+use crate::commit::Commit;
+use crate::conflicts::{materialize_tree_value, MaterializedTreeValue, MergeDriver};
+use crate::diff::{find_line_ranges, Diff, DiffHunk};
+use crate::repo::Repo;
+use crate::repo_path::RepoPath;
+use crate::revset::{RevsetEvaluationError, RevsetExpression};
+
+/// The commit that introduced each line of a file.
+#[derive(Clone, Debug)]
+pub struct FileAnnotation {
+    lines: Vec<(CommitId, Vec<u8>)>,
+}
+
+impl FileAnnotation {
+    /// Returns the lines of the file, including their line terminators, each
+    /// with the id of the commit that introduced it.
+    pub fn lines(&self) -> impl Iterator<Item = (&CommitId, &[u8])> {
+        self.lines
+            .iter()
+            .map(|(commit_id, line)| (commit_id, line.as_slice()))
+    }
+}
+
+/// Error while annotating a file.
+#[derive(Debug, Error)]
+pub enum AnnotateError {
+    /// The path doesn't exist in the starting commit.
+    #[error("No such path")]
+    PathNotFound,
+    /// The path exists in the starting commit, but isn't a file.
+    #[error("Path exists but is not a file")]
+    NotAFile,
+    /// Failed to read the history.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Failed to walk the history.
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+    /// Failed to read the file contents.
+    #[error("Failed to read file contents: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Lines of one version of the file that haven't been attributed yet.
+struct PendingLines {
+    contents: Vec<u8>,
+    /// Maps line numbers in `contents` to line numbers in the annotated file.
+    /// Lines that are identical in different versions of the file may be
+    /// matched to the same line in a common ancestor.
+    line_map: HashMap<usize, Vec<usize>>,
+}
+
+/// Reads the contents of the file at `path` in `commit`. Returns `None` if
+/// there's no file at the path. Conflicts are materialized with conflict
+/// markers.
+fn get_file_contents(
+    repo: &dyn Repo,
+    commit: &Commit,
+    path: &RepoPath,
+) -> Result<Option<Vec<u8>>, AnnotateError> {
+    let tree = commit.tree()?;
+    let value = tree.path_value(path);
+    match materialize_tree_value(repo.store(), path, value, MergeDriver::Text).block_on()? {
+        MaterializedTreeValue::File { mut reader, .. } => {
+            let mut contents = vec![];
+            reader.read_to_end(&mut contents)?;
+            Ok(Some(contents))
+        }
+        MaterializedTreeValue::Conflict { contents, .. } => Ok(Some(contents)),
+        MaterializedTreeValue::Absent
+        | MaterializedTreeValue::Symlink { .. }
+        | MaterializedTreeValue::Tree(_)
+        | MaterializedTreeValue::GitSubmodule(_) => Ok(None),
+    }
+}
+
+/// Returns pairs of line numbers `(child_line, parent_line)` of the lines that
+/// are unchanged between the two versions of the file.
+fn matching_lines(child: &[u8], parent: &[u8]) -> Vec<(usize, usize)> {
+    let diff = Diff::for_tokenizer(&[child, parent], &find_line_ranges);
+    let mut child_line = 0;
+    let mut parent_line = 0;
+    let mut result = vec![];
+    for hunk in diff.hunks() {
+        match hunk {
+            DiffHunk::Matching(content) => {
+                for _ in find_line_ranges(content) {
+                    result.push((child_line, parent_line));
+                    child_line += 1;
+                    parent_line += 1;
+                }
+            }
+            DiffHunk::Different(contents) => {
+                child_line += find_line_ranges(contents[0]).len();
+                parent_line += find_line_ranges(contents[1]).len();
+            }
+        }
+    }
+    result
+}
+
+/// Attributes each line of the file at `path` in `starting_commit` to the
+/// commit that introduced it.
+///
+/// Merges are followed into all parents: a line is attributed to a merge
+/// commit only if none of its parents contains it.
+pub fn get_annotation_for_file(
+    repo: &dyn Repo,
+    starting_commit: &Commit,
+    path: &RepoPath,
+) -> Result<FileAnnotation, AnnotateError> {
+    if starting_commit.tree()?.path_value(path).is_absent() {
+        return Err(AnnotateError::PathNotFound);
+    }
+    let contents =
+        get_file_contents(repo, starting_commit, path)?.ok_or(AnnotateError::NotAFile)?;
+    let attributions = annotate_contents(repo, starting_commit, &contents, path, None)?;
+    let lines = find_line_ranges(&contents)
+        .into_iter()
+        .zip(attributions)
+        .map(|(range, commit_id)| {
+            let commit_id = commit_id.expect("all lines should have been attributed");
+            (commit_id, contents[range].to_vec())
+        })
+        .collect();
+    Ok(FileAnnotation { lines })
+}
+
+/// Attributes each line of `contents` to the commit that introduced it,
+/// treating `contents` as the version of the file at `path` in
+/// `starting_commit`.
+///
+/// If `domain` is given, the history is only walked within it, and lines that
+/// were introduced by a commit outside of it are attributed to `None`. The
+/// starting commit itself is always walked.
+pub(crate) fn annotate_contents(
+    repo: &dyn Repo,
+    starting_commit: &Commit,
+    contents: &[u8],
+    path: &RepoPath,
+    domain: Option<&HashSet<CommitId>>,
+) -> Result<Vec<Option<CommitId>>, AnnotateError> {
+    let num_lines = find_line_ranges(contents).len();
+    let mut attributions: Vec<Option<CommitId>> = vec![None; num_lines];
+    let mut num_unattributed = num_lines;
+
+    let mut pending: HashMap<CommitId, PendingLines> = HashMap::new();
+    pending.insert(
+        starting_commit.id().clone(),
+        PendingLines {
+            contents: contents.to_vec(),
+            line_map: (0..num_lines).map(|i| (i, vec![i])).collect(),
+        },
+    );
+
+    // Ancestors are visited children first, so all lines have been passed
+    // down to a commit before it's processed.
+    let starting_expression = RevsetExpression::commit(starting_commit.id().clone());
+    let walk_expression = match domain {
+        Some(domain) => starting_expression.ancestors().intersection(
+            &RevsetExpression::commits(domain.iter().cloned().collect())
+                .union(&starting_expression),
+        ),
+        None => starting_expression.ancestors(),
+    };
+    let revset = walk_expression.evaluate_programmatic(repo)?;
+    for commit_id in revset.iter() {
+        if num_unattributed == 0 {
+            break;
+        }
+        let Some(PendingLines {
+            contents,
+            mut line_map,
+        }) = pending.remove(&commit_id)
+        else {
+            continue;
+        };
+        let commit = repo.store().get_commit(&commit_id)?;
+        for parent in commit.parents() {
+            if line_map.is_empty() {
+                break;
+            }
+            if parent.id() == repo.store().root_commit_id() {
+                continue;
+            }
+            let Some(parent_contents) = get_file_contents(repo, &parent, path)? else {
+                continue;
+            };
+            let mut parent_line_map: HashMap<usize, Vec<usize>> = HashMap::new();
+            for (child_line, parent_line) in matching_lines(&contents, &parent_contents) {
+                if let Some(original_lines) = line_map.remove(&child_line) {
+                    parent_line_map
+                        .entry(parent_line)
+                        .or_default()
+                        .extend(original_lines);
+                }
+            }
+            if parent_line_map.is_empty() {
+                continue;
+            }
+            if domain.is_some_and(|domain| !domain.contains(parent.id())) {
+                // The lines come from outside the domain, so they stay
+                // unattributed.
+                num_unattributed -= parent_line_map.values().map(Vec::len).sum::<usize>();
+                continue;
+            }
+            // Another child may already have passed lines down to the parent.
+            let parent_pending =
+                pending
+                    .entry(parent.id().clone())
+                    .or_insert_with(|| PendingLines {
+                        contents: parent_contents,
+                        line_map: HashMap::new(),
+                    });
+            for (parent_line, original_lines) in parent_line_map {
+                parent_pending
+                    .line_map
+                    .entry(parent_line)
+                    .or_default()
+                    .extend(original_lines);
+            }
+        }
+        // Lines that weren't found in any parent were introduced here.
+        for original_line in line_map.into_values().flatten() {
+            attributions[original_line] = Some(commit_id.clone());
+            num_unattributed -= 1;
+        }
+    }
+    Ok(attributions)
+}
@@ -29,7 +29,7 @@ use crate::content_hash::ContentHash;
 use crate::index::Index;
 use crate::merge::Merge;
 use crate::object_id::{id_type, ObjectId};
-use crate::repo_path::{RepoPath, RepoPathComponent, RepoPathComponentBuf};
+use crate::repo_path::{RepoPath, RepoPathBuf, RepoPathComponent, RepoPathComponentBuf};
 use crate::signing::SignResult;
 
 id_type!(pub CommitId);
@@ -85,6 +85,16 @@ content_hash! {
     }
 }
 
+content_hash! {
+    /// Records that the file at `target` was copied (or moved, if `source` no
+    /// longer exists) from `source` in the parent commit.
+    #[derive(Debug, PartialEq, Eq, Clone, Hash)]
+    pub struct CopyRecord {
+        pub target: RepoPathBuf,
+        pub source: RepoPathBuf,
+    }
+}
+
 pub type SigningFn<'a> = dyn FnMut(&[u8]) -> SignResult<Vec<u8>> + 'a;
 
 /// Identifies a single legacy tree, which may have path-level conflicts, or a
@@ -140,17 +150,36 @@ impl MergedTreeId {
     }
 }
 
-content_hash! {
-    #[derive(Debug, PartialEq, Eq, Clone)]
-    pub struct Commit {
-        pub parents: Vec<CommitId>,
-        pub predecessors: Vec<CommitId>,
-        pub root_tree: MergedTreeId,
-        pub change_id: ChangeId,
-        pub description: String,
-        pub author: Signature,
-        pub committer: Signature,
-        pub secure_sig: Option<SecureSig>,
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Commit {
+    pub parents: Vec<CommitId>,
+    pub predecessors: Vec<CommitId>,
+    pub root_tree: MergedTreeId,
+    pub change_id: ChangeId,
+    pub description: String,
+    pub author: Signature,
+    pub committer: Signature,
+    pub secure_sig: Option<SecureSig>,
+    /// Copies and renames recorded by the user. Backends that can't store
+    /// them drop them on write.
+    pub copy_records: Vec<CopyRecord>,
+}
+
+impl ContentHash for Commit {
+    fn hash(&self, state: &mut impl digest::Update) {
+        ContentHash::hash(&self.parents, state);
+        ContentHash::hash(&self.predecessors, state);
+        ContentHash::hash(&self.root_tree, state);
+        ContentHash::hash(&self.change_id, state);
+        ContentHash::hash(&self.description, state);
+        ContentHash::hash(&self.author, state);
+        ContentHash::hash(&self.committer, state);
+        ContentHash::hash(&self.secure_sig, state);
+        // Omitted if empty so that commits without copy records keep the same
+        // hash as before the field was added.
+        if !self.copy_records.is_empty() {
+            ContentHash::hash(&self.copy_records, state);
+        }
     }
 }
 
@@ -380,6 +409,7 @@ pub fn make_root_commit(root_change_id: ChangeId, empty_tree_id: TreeId) -> Comm
         author: signature.clone(),
         committer: signature,
         secure_sig: None,
+        copy_records: vec![],
     }
 }
 
@@ -414,6 +444,24 @@ pub trait Backend: Send + Sync + Debug {
 
     async fn read_file(&self, path: &RepoPath, id: &FileId) -> BackendResult<Box<dyn Read>>;
 
+    /// Returns the size of the file content in bytes. The default
+    /// implementation reads the whole file.
+    async fn file_size(&self, path: &RepoPath, id: &FileId) -> BackendResult<u64> {
+        let mut reader = self.read_file(path, id).await?;
+        std::io::copy(&mut reader, &mut std::io::sink()).map_err(|err| BackendError::ReadObject {
+            object_type: id.object_type(),
+            hash: id.hex(),
+            source: err.into(),
+        })
+    }
+
+    /// Prepares the files to be read, e.g. by fetching them from a remote in a
+    /// single request. Called before many files are read. The default
+    /// implementation does nothing.
+    fn prefetch_files(&self, _ids: &[FileId]) -> BackendResult<()> {
+        Ok(())
+    }
+
     fn write_file(&self, path: &RepoPath, contents: &mut dyn Read) -> BackendResult<FileId>;
 
     async fn read_symlink(&self, path: &RepoPath, id: &SymlinkId) -> BackendResult<String>;
@@ -0,0 +1,211 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bisection of a range of commits to find the first bad one.
+//!
+//! A commit is assumed to be good if any of its descendants was marked good,
+//! and bad if any of its ancestors was marked bad. Each step picks the
+//! candidate that splits the remaining commits most evenly, taking the shape
+//! of the DAG into account.
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::backend::{BackendError, CommitId};
+use crate::commit::Commit;
+use crate::repo::Repo;
+use crate::revset::{RevsetEvaluationError, RevsetExpression, RevsetIteratorExt};
+use crate::revset_graph::{RevsetGraphEdge, RevsetGraphEdgeType};
+
+/// Error while bisecting.
+#[derive(Debug, Error)]
+pub enum BisectionError {
+    /// Failed to evaluate the candidate commits.
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+    /// Failed to read a commit.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+}
+
+/// The result of testing a commit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Evaluation {
+    /// The commit doesn't have the property being looked for.
+    Good,
+    /// The commit has the property being looked for.
+    Bad,
+    /// The commit can't be tested.
+    Skip,
+}
+
+/// What to do after the current state of the bisection.
+#[derive(Clone, Debug)]
+pub enum NextStep {
+    /// The given commit should be tested next.
+    Evaluate(Commit),
+    /// The bisection is complete.
+    Done(BisectionResult),
+}
+
+/// The outcome of a complete bisection.
+#[derive(Clone, Debug)]
+pub enum BisectionResult {
+    /// No commit in the range was marked or assumed bad.
+    Indeterminate,
+    /// The first bad commit is one of these. There's more than one only if
+    /// some of the commits were skipped.
+    Found(Vec<Commit>),
+}
+
+/// Finds the first bad commit in a range of commits.
+pub struct Bisector<'repo> {
+    repo: &'repo dyn Repo,
+    input_range: Rc<RevsetExpression>,
+    good_commits: HashSet<CommitId>,
+    bad_commits: HashSet<CommitId>,
+    skipped_commits: HashSet<CommitId>,
+}
+
+impl<'repo> Bisector<'repo> {
+    /// Creates a bisector for the commits in `input_range`, which must be
+    /// resolved programmatically, i.e. not contain any symbols.
+    pub fn new(repo: &'repo dyn Repo, input_range: Rc<RevsetExpression>) -> Self {
+        Bisector {
+            repo,
+            input_range,
+            good_commits: HashSet::new(),
+            bad_commits: HashSet::new(),
+            skipped_commits: HashSet::new(),
+        }
+    }
+
+    /// Records the result of testing a commit. A later evaluation of the same
+    /// commit replaces the earlier one.
+    pub fn mark(&mut self, id: CommitId, evaluation: Evaluation) {
+        self.good_commits.remove(&id);
+        self.bad_commits.remove(&id);
+        self.skipped_commits.remove(&id);
+        match evaluation {
+            Evaluation::Good => self.good_commits.insert(id),
+            Evaluation::Bad => self.bad_commits.insert(id),
+            Evaluation::Skip => self.skipped_commits.insert(id),
+        };
+    }
+
+    /// Commits in the range that may be the first bad commit: ancestors of a
+    /// bad commit that aren't ancestors of a good commit.
+    fn suspects(&self) -> Rc<RevsetExpression> {
+        let bad = RevsetExpression::commits(self.bad_commits.iter().cloned().collect());
+        let good = RevsetExpression::commits(self.good_commits.iter().cloned().collect());
+        self.input_range
+            .intersection(&bad.ancestors())
+            .minus(&good.ancestors())
+    }
+
+    /// Returns the commit to test next, or the result if there's nothing left
+    /// to test.
+    pub fn next_step(&self) -> Result<NextStep, BisectionError> {
+        let bad = RevsetExpression::commits(self.bad_commits.iter().cloned().collect());
+        let skipped = RevsetExpression::commits(self.skipped_commits.iter().cloned().collect());
+        let suspects = self.suspects();
+        // Descendants of bad commits are bad themselves, so only the commits
+        // before them are left to test.
+        let untested = suspects.minus(&bad.descendants());
+        let candidates = untested
+            .minus(&skipped)
+            .evaluate_programmatic(self.repo)?
+            .iter_graph()
+            .collect_vec();
+        if candidates.is_empty() {
+            // The first bad commits are the earliest known bad ones, or any
+            // of the skipped commits before them.
+            let first_bad = suspects
+                .intersection(&bad.descendants())
+                .roots()
+                .union(&untested)
+                .evaluate_programmatic(self.repo)?
+                .iter()
+                .commits(self.repo.store())
+                .try_collect()?;
+            return Ok(NextStep::Done(if self.bad_commits.is_empty() {
+                BisectionResult::Indeterminate
+            } else {
+                BisectionResult::Found(first_bad)
+            }));
+        }
+
+        // The best candidate is the one that leaves the fewest commits to test
+        // in the worst case, whether it turns out to be good or bad.
+        let num_candidates = candidates.len();
+        let num_ancestors = count_ancestors(&candidates);
+        let mut best: Option<(usize, CommitId)> = None;
+        for (id, _) in candidates {
+            let num_ancestors = num_ancestors[&id];
+            let score = num_ancestors.min(num_candidates - num_ancestors);
+            if best
+                .as_ref()
+                .map_or(true, |(best_score, _)| score > *best_score)
+            {
+                best = Some((score, id));
+            }
+        }
+        let (_, id) = best.unwrap();
+        Ok(NextStep::Evaluate(self.repo.store().get_commit(&id)?))
+    }
+}
+
+/// Counts the ancestors of each commit within `graph` (including the commit
+/// itself). The graph must be in reverse topological order, as returned by
+/// `Revset::iter_graph()`.
+///
+/// The counts are computed in a single pass from the roots. A commit with a
+/// single parent in the graph has one more ancestor than its parent. Only
+/// merges need their ancestors to be walked, since their parents' ancestors
+/// may overlap.
+fn count_ancestors(graph: &[(CommitId, Vec<RevsetGraphEdge>)]) -> HashMap<CommitId, usize> {
+    let parents: HashMap<&CommitId, Vec<&CommitId>> = graph
+        .iter()
+        .map(|(id, edges)| {
+            let parents = edges
+                .iter()
+                .filter(|edge| edge.edge_type != RevsetGraphEdgeType::Missing)
+                .map(|edge| &edge.target)
+                .collect_vec();
+            (id, parents)
+        })
+        .collect();
+    let mut counts: HashMap<CommitId, usize> = HashMap::new();
+    for (id, _) in graph.iter().rev() {
+        let count = match parents[id].as_slice() {
+            [] => 1,
+            [parent] => counts[*parent] + 1,
+            _ => {
+                let mut visited = HashSet::new();
+                let mut to_visit = vec![id];
+                while let Some(id) = to_visit.pop() {
+                    if visited.insert(id) {
+                        to_visit.extend(&parents[id]);
+                    }
+                }
+                visited.len()
+            }
+        };
+        counts.insert(id.clone(), count);
+    }
+    counts
+}
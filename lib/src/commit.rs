@@ -20,7 +20,7 @@ use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 use crate::backend;
-use crate::backend::{BackendError, ChangeId, CommitId, MergedTreeId, Signature};
+use crate::backend::{BackendError, ChangeId, CommitId, CopyRecord, MergedTreeId, Signature};
 use crate::merged_tree::MergedTree;
 use crate::signing::{SignResult, Verification};
 use crate::store::Store;
@@ -137,6 +137,12 @@ impl Commit {
         &self.data.committer
     }
 
+    /// Copies and renames recorded in this commit, relative to its parents.
+    /// These are only stored by backends that support them.
+    pub fn copy_records(&self) -> &[CopyRecord] {
+        &self.data.copy_records
+    }
+
     /// A commit is discardable if it has one parent, no change from its
     /// parent, and an empty description.
     pub fn is_discardable(&self) -> bool {
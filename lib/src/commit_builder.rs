@@ -16,7 +16,9 @@
 
 use std::sync::Arc;
 
-use crate::backend::{self, BackendResult, ChangeId, CommitId, MergedTreeId, Signature, SigningFn};
+use crate::backend::{
+    self, BackendResult, ChangeId, CommitId, CopyRecord, MergedTreeId, Signature, SigningFn,
+};
 use crate::commit::Commit;
 use crate::repo::{MutableRepo, Repo};
 use crate::settings::{JJRng, SignSettings, UserSettings};
@@ -52,6 +54,7 @@ impl CommitBuilder<'_> {
             author: signature.clone(),
             committer: signature,
             secure_sig: None,
+            copy_records: vec![],
         };
         CommitBuilder {
             mut_repo,
@@ -163,6 +166,15 @@ impl CommitBuilder<'_> {
         self
     }
 
+    pub fn copy_records(&self) -> &[CopyRecord] {
+        &self.commit.copy_records
+    }
+
+    pub fn set_copy_records(mut self, copy_records: Vec<CopyRecord>) -> Self {
+        self.commit.copy_records = copy_records;
+        self
+    }
+
     pub fn sign_settings(&self) -> &SignSettings {
         &self.sign_settings
     }
@@ -24,6 +24,7 @@ use crate::backend::{BackendResult, CommitId, FileId, SymlinkId, TreeId, TreeVal
 use crate::diff::{find_line_ranges, Diff, DiffHunk};
 use crate::files;
 use crate::files::{ContentHunk, MergeResult};
+use crate::gitattributes::{GitAttributeValue, GitAttributesFile};
 use crate::merge::{Merge, MergeBuilder, MergedTreeValue};
 use crate::repo_path::RepoPath;
 use crate::store::Store;
@@ -88,15 +89,41 @@ pub async fn extract_as_single_hunk(
     builder.build()
 }
 
+/// How conflicts in a file are materialized, as specified by the `merge`
+/// attribute in .gitattributes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergeDriver {
+    /// Conflicting hunks are written with conflict markers.
+    #[default]
+    Text,
+    /// The file is treated as an opaque blob. The first side of the conflict
+    /// is written as is, without conflict markers.
+    Binary,
+}
+
+impl MergeDriver {
+    pub fn for_path(git_attributes: &GitAttributesFile, path: &RepoPath) -> Self {
+        match git_attributes.get(path.as_internal_file_string(), "merge") {
+            Some(GitAttributeValue::Unset) => MergeDriver::Binary,
+            Some(GitAttributeValue::Value(value)) if value == "binary" => MergeDriver::Binary,
+            _ => MergeDriver::Text,
+        }
+    }
+}
+
 pub async fn materialize(
     conflict: &MergedTreeValue,
     store: &Store,
     path: &RepoPath,
+    merge_driver: MergeDriver,
     output: &mut dyn Write,
 ) -> std::io::Result<()> {
     if let Some(file_merge) = conflict.to_file_merge() {
         let content = extract_as_single_hunk(&file_merge, store, path).await;
-        materialize_merge_result(&content, output)
+        match merge_driver {
+            MergeDriver::Text => materialize_merge_result(&content, output),
+            MergeDriver::Binary => output.write_all(&content.first().0),
+        }
     } else {
         // Unless all terms are regular files, we can't do much better than to try to
         // describe the merge.
@@ -141,6 +168,7 @@ pub async fn materialize_tree_value(
     store: &Store,
     path: &RepoPath,
     value: MergedTreeValue,
+    merge_driver: MergeDriver,
 ) -> BackendResult<MaterializedTreeValue> {
     match value.into_resolved() {
         Ok(None) => Ok(MaterializedTreeValue::Absent),
@@ -163,7 +191,7 @@ pub async fn materialize_tree_value(
         }
         Err(conflict) => {
             let mut contents = vec![];
-            materialize(&conflict, store, path, &mut contents)
+            materialize(&conflict, store, path, merge_driver, &mut contents)
                 .await
                 .expect("Failed to materialize conflict to in-memory buffer");
             Ok(MaterializedTreeValue::Conflict {
@@ -360,11 +388,13 @@ fn parse_conflict_hunk(input: &[u8]) -> Merge<ContentHunk> {
 
 /// Parses conflict markers in `content` and returns an updated version of
 /// `file_ids` with the new contents. If no (valid) conflict markers remain, a
-/// single resolves `FileId` will be returned.
+/// single resolves `FileId` will be returned. With `MergeDriver::Binary`, any
+/// change to the content resolves the conflict.
 pub async fn update_from_content(
     file_ids: &Merge<Option<FileId>>,
     store: &Store,
     path: &RepoPath,
+    merge_driver: MergeDriver,
     content: &[u8],
 ) -> BackendResult<Merge<Option<FileId>>> {
     // First check if the new content is unchanged compared to the old content. If
@@ -374,12 +404,19 @@ pub async fn update_from_content(
     // copy.
     let mut old_content = Vec::with_capacity(content.len());
     let merge_hunk = extract_as_single_hunk(file_ids, store, path).await;
-    materialize_merge_result(&merge_hunk, &mut old_content).unwrap();
+    match merge_driver {
+        MergeDriver::Text => materialize_merge_result(&merge_hunk, &mut old_content).unwrap(),
+        MergeDriver::Binary => old_content.extend_from_slice(&merge_hunk.first().0),
+    }
     if content == old_content {
         return Ok(file_ids.clone());
     }
 
-    let Some(hunks) = parse_conflict(content, file_ids.num_sides()) else {
+    let parsed_hunks = match merge_driver {
+        MergeDriver::Text => parse_conflict(content, file_ids.num_sides()),
+        MergeDriver::Binary => None,
+    };
+    let Some(hunks) = parsed_hunks else {
         // Either there are no self markers of they don't have the expected arity
         let file_id = store.write_file(path, &mut &content[..])?;
         return Ok(Merge::normal(file_id));
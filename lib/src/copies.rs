@@ -0,0 +1,524 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of copied and renamed files in a tree diff.
+//!
+//! Trees don't record where a file came from, so renames are detected when the
+//! diff is computed: each removed file is compared with each added file, and
+//! pairs whose contents are similar enough are reported as renames. Copies
+//! recorded in the commit by backends that support it take precedence over
+//! detected renames.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use futures::StreamExt;
+use itertools::Itertools;
+use pollster::FutureExt;
+
+use crate::backend::{BackendError, BackendResult, CopyRecord, FileId, TreeValue};
+use crate::diff::{find_line_ranges, Diff, DiffHunk};
+use crate::matchers::{EverythingMatcher, Matcher};
+use crate::merge::{Merge, MergedTreeValue};
+use crate::merged_tree::{MergedTree, MergedTreeBuilder};
+use crate::object_id::ObjectId;
+use crate::repo_path::{RepoPath, RepoPathBuf};
+use crate::store::Store;
+use crate::tree::{try_resolve_file_conflict, TreeMergeError};
+
+/// Maximum number of pairs of removed and added files to compare by content.
+/// Above this, only renames of files with identical content are detected.
+const RENAME_PAIR_LIMIT: usize = 10_000;
+
+/// How the target file was derived from its source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CopyOperation {
+    /// The source file still exists.
+    Copy,
+    /// The source file was removed.
+    Rename,
+}
+
+/// The file a diff entry was copied or renamed from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CopySource {
+    /// Path of the source file in the "from" tree.
+    pub path: RepoPathBuf,
+    /// Whether the source file still exists in the "to" tree.
+    pub operation: CopyOperation,
+    /// Percentage of the content shared between the source and the target.
+    pub similarity: u32,
+}
+
+/// A tree diff entry which may have been copied or renamed from another path.
+#[derive(Debug)]
+pub struct CopiesTreeDiffEntry {
+    /// Path of the file in the "to" tree.
+    pub target: RepoPathBuf,
+    /// Where the file was copied from, if anywhere.
+    pub source: Option<CopySource>,
+    /// Values before and after. If the file was copied, the value before is the
+    /// value of the source file.
+    pub values: BackendResult<(MergedTreeValue, MergedTreeValue)>,
+}
+
+impl CopiesTreeDiffEntry {
+    /// Path of the file in the "from" tree.
+    pub fn source_path(&self) -> &RepoPath {
+        self.source
+            .as_ref()
+            .map_or(&self.target, |source| &source.path)
+    }
+}
+
+/// Options for `diff_with_copies()`.
+#[derive(Clone, Debug)]
+pub struct CopyDetectionOptions {
+    /// Whether to detect renames from file content. Recorded copies are used
+    /// regardless.
+    pub detect_renames: bool,
+    /// Minimum similarity percentage for a removed and an added file to be
+    /// considered a rename.
+    pub threshold: u32,
+}
+
+impl Default for CopyDetectionOptions {
+    fn default() -> Self {
+        CopyDetectionOptions {
+            detect_renames: true,
+            threshold: 50,
+        }
+    }
+}
+
+/// Computes the diff between `from_tree` and `to_tree`, and pairs up copied
+/// and renamed files.
+///
+/// The `copy_records` are typically those of the commit whose tree is
+/// `to_tree`. Records that don't match the diff (e.g. because the target
+/// wasn't added) are ignored. The source of a rename must be matched by
+/// `matcher` to be detected.
+pub fn diff_with_copies(
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    matcher: &dyn Matcher,
+    copy_records: &[CopyRecord],
+    options: &CopyDetectionOptions,
+) -> BackendResult<Vec<CopiesTreeDiffEntry>> {
+    let entries = from_tree
+        .diff_stream(to_tree, matcher)
+        .collect::<Vec<_>>()
+        .block_on();
+    detect_copies(from_tree, entries, copy_records, options)
+}
+
+/// Pairs up copied and renamed files in the diff `entries` from `from_tree`.
+///
+/// The returned entries are in the same order as the input. The entry of a
+/// renamed file replaces the entry of the added file, and the entry of the
+/// removed file is dropped.
+pub fn detect_copies(
+    from_tree: &MergedTree,
+    entries: Vec<(
+        RepoPathBuf,
+        BackendResult<(MergedTreeValue, MergedTreeValue)>,
+    )>,
+    copy_records: &[CopyRecord],
+    options: &CopyDetectionOptions,
+) -> BackendResult<Vec<CopiesTreeDiffEntry>> {
+    let mut entries = entries
+        .into_iter()
+        .map(|(target, values)| CopiesTreeDiffEntry {
+            target,
+            source: None,
+            values,
+        })
+        .collect_vec();
+    let index_by_path: HashMap<&RepoPath, usize> = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (entry.target.as_ref(), index))
+        .collect();
+    let mut contents = FileContentsCache::new(from_tree.store());
+    // (source index, target index, similarity) of renames, and
+    // (target index, source path, source value, similarity) of copies
+    let mut renames: Vec<(usize, usize, u32)> = vec![];
+    let mut copies: Vec<(usize, RepoPathBuf, MergedTreeValue, u32)> = vec![];
+    let mut used_sources: HashSet<usize> = HashSet::new();
+    let mut used_targets: HashSet<usize> = HashSet::new();
+
+    for record in copy_records {
+        let Some(&target_index) = index_by_path.get(record.target.as_ref()) else {
+            continue;
+        };
+        let Some(target_id) = added_file_id(&entries[target_index]) else {
+            continue;
+        };
+        if used_targets.contains(&target_index) {
+            continue;
+        }
+        let target_id = target_id.clone();
+        let removed_source = index_by_path
+            .get(record.source.as_ref())
+            .copied()
+            .filter(|index| !used_sources.contains(index))
+            .filter(|&index| removed_file_id(&entries[index]).is_some());
+        if let Some(source_index) = removed_source {
+            let source_id = removed_file_id(&entries[source_index]).unwrap().clone();
+            let similarity =
+                contents.similarity((&record.source, &source_id), (&record.target, &target_id))?;
+            renames.push((source_index, target_index, similarity));
+            used_sources.insert(source_index);
+        } else {
+            let source_value = from_tree.path_value(&record.source);
+            let Some(source_id) = resolved_file_id(&source_value) else {
+                continue;
+            };
+            let similarity =
+                contents.similarity((&record.source, source_id), (&record.target, &target_id))?;
+            copies.push((
+                target_index,
+                record.source.clone(),
+                source_value,
+                similarity,
+            ));
+        }
+        used_targets.insert(target_index);
+    }
+
+    if options.detect_renames {
+        let removed = entries
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !used_sources.contains(index))
+            .filter_map(|(index, entry)| Some((index, removed_file_id(entry)?.clone())))
+            .collect_vec();
+        let added = entries
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !used_targets.contains(index))
+            .filter_map(|(index, entry)| Some((index, added_file_id(entry)?.clone())))
+            .collect_vec();
+        let compare_contents = removed.len() * added.len() <= RENAME_PAIR_LIMIT;
+        if compare_contents && !removed.is_empty() && !added.is_empty() {
+            let ids = removed.iter().chain(&added).map(|(_, id)| id.clone());
+            from_tree.store().prefetch_files(&ids.collect_vec())?;
+        }
+        let mut candidates = vec![];
+        for (target_index, target_id) in &added {
+            let target = (entries[*target_index].target.as_ref(), target_id);
+            for (source_index, source_id) in &removed {
+                if !compare_contents && source_id != target_id {
+                    continue;
+                }
+                let source = (entries[*source_index].target.as_ref(), source_id);
+                let source_len = contents.size(source)?;
+                let target_len = contents.size(target)?;
+                // Empty files are too common to be paired up meaningfully, and
+                // files of very different sizes can't be similar enough.
+                if source_len == 0
+                    || target_len == 0
+                    || max_similarity(source_len, target_len) < options.threshold
+                {
+                    continue;
+                }
+                let similarity = contents.similarity(source, target)?;
+                if similarity >= options.threshold {
+                    candidates.push((*source_index, *target_index, similarity));
+                }
+            }
+        }
+        // Prefer the most similar pairs. The sort is stable, so ties are
+        // resolved in path order.
+        candidates.sort_by_key(|&(_, _, similarity)| Reverse(similarity));
+        for (source_index, target_index, similarity) in candidates {
+            if used_sources.contains(&source_index) || used_targets.contains(&target_index) {
+                continue;
+            }
+            renames.push((source_index, target_index, similarity));
+            used_sources.insert(source_index);
+            used_targets.insert(target_index);
+        }
+    }
+
+    for (source_index, target_index, similarity) in renames {
+        let source_entry = &entries[source_index];
+        let path = source_entry.target.clone();
+        let source_value = match &source_entry.values {
+            Ok((before, _)) => before.clone(),
+            Err(_) => unreachable!("removed file should have values"),
+        };
+        let target_entry = &mut entries[target_index];
+        target_entry.source = Some(CopySource {
+            path,
+            operation: CopyOperation::Rename,
+            similarity,
+        });
+        if let Ok((before, _)) = &mut target_entry.values {
+            *before = source_value;
+        }
+    }
+    for (target_index, path, source_value, similarity) in copies {
+        let target_entry = &mut entries[target_index];
+        target_entry.source = Some(CopySource {
+            path,
+            operation: CopyOperation::Copy,
+            similarity,
+        });
+        if let Ok((before, _)) = &mut target_entry.values {
+            *before = source_value;
+        }
+    }
+
+    Ok(entries
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !used_sources.contains(index))
+        .map(|(_, entry)| entry)
+        .collect())
+}
+
+/// Carries changes across files renamed on one side of a merge.
+///
+/// If a file was renamed on one side and modified on the other, the `merged`
+/// tree has a modify/delete conflict at the old path. Such conflicts are
+/// resolved by merging the modification into the renamed file and removing
+/// the old path. Renames are only detected if there are such conflicts, and
+/// conflicts which can't be resolved this way are left as is.
+pub(crate) fn merge_renamed_files(
+    side1: &MergedTree,
+    base: &MergedTree,
+    side2: &MergedTree,
+    merged: MergedTree,
+) -> Result<MergedTree, TreeMergeError> {
+    let store = merged.store().clone();
+    let sides = [side1, side2];
+    let mut renames_by_side: [Option<HashMap<RepoPathBuf, RepoPathBuf>>; 2] = [None, None];
+    let mut tree_builder = MergedTreeBuilder::new(merged.id());
+    let mut resolved_any = false;
+    for (path, _) in merged.conflicts() {
+        let base_value = base.path_value(&path);
+        let side_values = sides.map(|side| side.path_value(&path));
+        let Some(removed_side) = side_values.iter().position(|value| value.is_absent()) else {
+            continue;
+        };
+        let modified_value = &side_values[1 - removed_side];
+        if resolved_file_id(&base_value).is_none() || resolved_file_id(modified_value).is_none() {
+            continue;
+        }
+        let renames = match &mut renames_by_side[removed_side] {
+            Some(renames) => renames,
+            None => renames_by_side[removed_side].insert(find_renames(base, sides[removed_side])?),
+        };
+        let Some(target) = renames.get(&path) else {
+            continue;
+        };
+        // The target must have been added by the rename alone.
+        if !sides[1 - removed_side].path_value(target).is_absent() {
+            continue;
+        }
+        // Backends may look up files by path, so the contents at the old path
+        // are copied to the target before they're merged there.
+        let renamed_value = sides[removed_side].path_value(target);
+        let file_merge = Merge::from_removes_adds(
+            [copy_file_value(&store, &path, target, base_value)?],
+            [
+                renamed_value,
+                copy_file_value(&store, &path, target, modified_value.clone())?,
+            ],
+        )
+        .flatten();
+        let Some(value) = try_resolve_file_conflict(&store, target, &file_merge)? else {
+            continue;
+        };
+        tree_builder.set_or_remove(path, Merge::absent());
+        tree_builder.set_or_remove(target.clone(), Merge::normal(value));
+        resolved_any = true;
+    }
+    if !resolved_any {
+        return Ok(merged);
+    }
+    let tree_id = tree_builder.write_tree(&store)?;
+    Ok(store.get_root_tree(&tree_id)?)
+}
+
+/// Writes the contents of the file `value` at `source` to `target`.
+fn copy_file_value(
+    store: &Store,
+    source: &RepoPath,
+    target: &RepoPath,
+    value: MergedTreeValue,
+) -> BackendResult<MergedTreeValue> {
+    match value.into_resolved() {
+        Ok(Some(TreeValue::File { id, executable })) => {
+            let mut content = vec![];
+            store
+                .read_file(source, &id)?
+                .read_to_end(&mut content)
+                .map_err(|err| BackendError::ReadObject {
+                    object_type: id.object_type(),
+                    hash: id.hex(),
+                    source: err.into(),
+                })?;
+            let id = store.write_file(target, &mut content.as_slice())?;
+            Ok(Merge::normal(TreeValue::File { id, executable }))
+        }
+        Ok(value) => Ok(Merge::resolved(value)),
+        Err(value) => Ok(value),
+    }
+}
+
+/// Returns the targets of files renamed between `from_tree` and `to_tree` by
+/// their source paths.
+fn find_renames(
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+) -> BackendResult<HashMap<RepoPathBuf, RepoPathBuf>> {
+    let entries = diff_with_copies(
+        from_tree,
+        to_tree,
+        &EverythingMatcher,
+        &[],
+        &CopyDetectionOptions::default(),
+    )?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let source = entry.source?;
+            (source.operation == CopyOperation::Rename).then_some((source.path, entry.target))
+        })
+        .collect())
+}
+
+fn resolved_file_id(value: &MergedTreeValue) -> Option<&FileId> {
+    match value.as_resolved() {
+        Some(Some(TreeValue::File { id, .. })) => Some(id),
+        _ => None,
+    }
+}
+
+fn added_file_id(entry: &CopiesTreeDiffEntry) -> Option<&FileId> {
+    match &entry.values {
+        Ok((before, after)) if before.is_absent() => resolved_file_id(after),
+        _ => None,
+    }
+}
+
+fn removed_file_id(entry: &CopiesTreeDiffEntry) -> Option<&FileId> {
+    match &entry.values {
+        Ok((before, after)) if after.is_absent() => resolved_file_id(before),
+        _ => None,
+    }
+}
+
+/// Reads each file at most once while comparing many pairs of files.
+struct FileContentsCache<'a> {
+    store: &'a Store,
+    contents: HashMap<FileId, Vec<u8>>,
+    sizes: HashMap<FileId, u64>,
+}
+
+impl<'a> FileContentsCache<'a> {
+    fn new(store: &'a Store) -> Self {
+        FileContentsCache {
+            store,
+            contents: HashMap::new(),
+            sizes: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, path: &RepoPath, id: &FileId) -> BackendResult<&[u8]> {
+        if !self.contents.contains_key(id) {
+            let mut content = vec![];
+            self.store
+                .read_file(path, id)?
+                .read_to_end(&mut content)
+                .map_err(|err| BackendError::ReadObject {
+                    object_type: id.object_type(),
+                    hash: id.hex(),
+                    source: err.into(),
+                })?;
+            self.contents.insert(id.clone(), content);
+        }
+        Ok(&self.contents[id])
+    }
+
+    /// Returns the size of the file without reading its contents if the
+    /// backend supports that.
+    fn size(&mut self, (path, id): (&RepoPath, &FileId)) -> BackendResult<u64> {
+        if let Some(content) = self.contents.get(id) {
+            return Ok(content.len() as u64);
+        }
+        if !self.sizes.contains_key(id) {
+            let size = self.store.file_size(path, id)?;
+            self.sizes.insert(id.clone(), size);
+        }
+        Ok(self.sizes[id])
+    }
+
+    fn similarity(
+        &mut self,
+        (source_path, source_id): (&RepoPath, &FileId),
+        (target_path, target_id): (&RepoPath, &FileId),
+    ) -> BackendResult<u32> {
+        if source_id == target_id {
+            return Ok(100);
+        }
+        self.get(source_path, source_id)?;
+        self.get(target_path, target_id)?;
+        Ok(similarity(
+            &self.contents[source_id],
+            &self.contents[target_id],
+        ))
+    }
+}
+
+/// Returns the percentage of the content shared by `left` and `right`,
+/// measured in bytes of matching lines.
+fn similarity(left: &[u8], right: &[u8]) -> u32 {
+    let total_len = left.len() + right.len();
+    if total_len == 0 {
+        return 100;
+    }
+    let diff = Diff::for_tokenizer(&[left, right], &find_line_ranges);
+    let matching_len: usize = diff
+        .hunks()
+        .map(|hunk| match hunk {
+            DiffHunk::Matching(content) => content.len(),
+            DiffHunk::Different(_) => 0,
+        })
+        .sum();
+    (matching_len * 2 * 100 / total_len) as u32
+}
+
+/// Upper bound of `similarity()` of contents of the given lengths.
+fn max_similarity(left_len: u64, right_len: u64) -> u32 {
+    (left_len.min(right_len) * 2 * 100 / (left_len + right_len)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity(b"", b""), 100);
+        assert_eq!(similarity(b"a\n", b""), 0);
+        assert_eq!(similarity(b"a\nb\n", b"a\nb\n"), 100);
+        assert_eq!(similarity(b"a\nb\n", b"a\nc\n"), 50);
+        assert_eq!(similarity(b"a\nb\nc\nd\n", b"a\nb\nc\n"), 85);
+        assert_eq!(similarity(b"a\nb\n", b"c\nd\n"), 0);
+    }
+}
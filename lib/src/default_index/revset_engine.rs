@@ -16,17 +16,22 @@
 
 use std::cmp::{Ordering, Reverse};
 use std::collections::{BTreeSet, BinaryHeap, HashSet};
-use std::fmt;
+use std::io::Read as _;
 use std::iter::Peekable;
 use std::ops::Range;
 use std::sync::Arc;
+use std::{fmt, str};
 
 use itertools::Itertools;
+use pollster::FutureExt as _;
 
 use super::revset_graph_iterator::RevsetGraphIterator;
 use crate::backend::{ChangeId, CommitId, MillisSinceEpoch};
+use crate::conflicts::{materialize_tree_value, MaterializedTreeValue, MergeDriver};
 use crate::default_index::{AsCompositeIndex, CompositeIndex, IndexEntry, IndexPosition};
-use crate::matchers::{EverythingMatcher, Matcher, PrefixMatcher, Visit};
+use crate::diff::{find_line_ranges, Diff, DiffHunk};
+use crate::matchers::{Matcher, Visit};
+use crate::merge::MergedTreeValue;
 use crate::repo_path::RepoPath;
 use crate::revset::{
     ResolvedExpression, ResolvedPredicateExpression, Revset, RevsetEvaluationError,
@@ -35,6 +40,7 @@ use crate::revset::{
 use crate::revset_graph::RevsetGraphEdge;
 use crate::rewrite;
 use crate::store::Store;
+use crate::str_util::StringPattern;
 
 trait ToPredicateFn: fmt::Debug {
     /// Creates function that tests if the given entry is included in the set.
@@ -919,17 +925,39 @@ fn build_predicate_fn(
                     || pattern.matches(&commit.committer().email)
             })
         }
-        RevsetFilterPredicate::File(paths) => {
-            // TODO: Add support for globs and other formats
-            let matcher: Box<dyn Matcher> = if let Some(paths) = paths {
-                Box::new(PrefixMatcher::new(paths))
-            } else {
-                Box::new(EverythingMatcher)
-            };
+        RevsetFilterPredicate::AuthorDate(pattern) => {
+            let pattern = pattern.clone();
+            box_pure_predicate_fn(move |_index, entry| {
+                let commit = store.get_commit(&entry.commit_id()).unwrap();
+                pattern.matches(&commit.author().timestamp)
+            })
+        }
+        RevsetFilterPredicate::CommitterDate(pattern) => {
+            let pattern = pattern.clone();
+            box_pure_predicate_fn(move |_index, entry| {
+                let commit = store.get_commit(&entry.commit_id()).unwrap();
+                pattern.matches(&commit.committer().timestamp)
+            })
+        }
+        RevsetFilterPredicate::File(expr) => {
+            let matcher = expr.to_matcher();
             box_pure_predicate_fn(move |index, entry| {
                 has_diff_from_parent(&store, index, entry, matcher.as_ref())
             })
         }
+        RevsetFilterPredicate::DiffContains { text, files } => {
+            let text_pattern = text.clone();
+            let files_matcher = files.to_matcher();
+            box_pure_predicate_fn(move |index, entry| {
+                matches_diff_from_parent(
+                    &store,
+                    index,
+                    entry,
+                    &text_pattern,
+                    files_matcher.as_ref(),
+                )
+            })
+        }
         RevsetFilterPredicate::HasConflict => box_pure_predicate_fn(move |_index, entry| {
             let commit = store.get_commit(&entry.commit_id()).unwrap();
             commit.has_conflict().unwrap()
@@ -959,6 +987,69 @@ fn has_diff_from_parent(
     from_tree.diff(&to_tree, matcher).next().is_some()
 }
 
+fn matches_diff_from_parent(
+    store: &Arc<Store>,
+    index: CompositeIndex<'_>,
+    entry: &IndexEntry<'_>,
+    text_pattern: &StringPattern,
+    files_matcher: &dyn Matcher,
+) -> bool {
+    let commit = store.get_commit(&entry.commit_id()).unwrap();
+    let parents = commit.parents();
+    let from_tree = rewrite::merge_commit_trees_without_repo(store, &index, &parents).unwrap();
+    let to_tree = commit.tree().unwrap();
+    from_tree.diff(&to_tree, files_matcher).any(|(path, diff)| {
+        let (left_value, right_value) = diff.unwrap();
+        let left_content = to_file_content(store, &path, left_value);
+        let right_content = to_file_content(store, &path, right_value);
+        // Filter lines prior to comparison. This might produce inferior
+        // hunks due to lack of contexts, but is way faster than full diff.
+        let left_lines = match_lines(&left_content, text_pattern);
+        let right_lines = match_lines(&right_content, text_pattern);
+        if left_lines.eq(right_lines) {
+            return false; // no need to run expensive diff
+        }
+        let diff = Diff::for_tokenizer(&[&left_content, &right_content], &find_line_ranges);
+        diff.hunks().any(|hunk| match hunk {
+            DiffHunk::Matching(_) => false,
+            DiffHunk::Different(contents) => contents
+                .iter()
+                .any(|content| match_lines(content, text_pattern).next().is_some()),
+        })
+    })
+}
+
+/// Iterates over lines of the `text` which match the `pattern`.
+fn match_lines<'a: 'b, 'b>(
+    text: &'a [u8],
+    pattern: &'b StringPattern,
+) -> impl Iterator<Item = &'a [u8]> + 'b {
+    // The pattern is matched line by line so that it can be anchored to line
+    // start/end. For example, exact:"" will match blank lines.
+    text.split_inclusive(|b| *b == b'\n').filter(|line| {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        str::from_utf8(line).is_ok_and(|line| pattern.matches(line))
+    })
+}
+
+fn to_file_content(store: &Store, path: &RepoPath, value: MergedTreeValue) -> Vec<u8> {
+    match materialize_tree_value(store, path, value, MergeDriver::Text)
+        .block_on()
+        .unwrap()
+    {
+        MaterializedTreeValue::File { mut reader, .. } => {
+            let mut content = vec![];
+            reader.read_to_end(&mut content).unwrap();
+            content
+        }
+        MaterializedTreeValue::Conflict { contents, .. } => contents,
+        MaterializedTreeValue::Absent
+        | MaterializedTreeValue::Symlink { .. }
+        | MaterializedTreeValue::Tree(_)
+        | MaterializedTreeValue::GitSubmodule(_) => vec![],
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,635 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Functional language for selecting a set of paths.
+
+#![allow(missing_docs)]
+
+use std::path::Path;
+use std::{error, fmt, slice};
+
+use once_cell::sync::Lazy;
+use pest::iterators::{Pair, Pairs};
+use pest::pratt_parser::{Assoc, Op, PrattParser};
+use pest::Parser;
+use pest_derive::Parser;
+use thiserror::Error;
+
+use crate::matchers::{
+    DifferenceMatcher, EverythingMatcher, FileGlobMatcher, FilesMatcher, IntersectionMatcher,
+    Matcher, NothingMatcher, PrefixMatcher, UnionMatcher,
+};
+use crate::repo_path::{FsPathParseError, RepoPathBuf};
+
+#[derive(Parser)]
+#[grammar = "fileset.pest"]
+struct FilesetParser;
+
+impl Rule {
+    fn to_symbol(self) -> Option<&'static str> {
+        match self {
+            Rule::EOI => None,
+            Rule::whitespace => None,
+            Rule::identifier => None,
+            Rule::glob_char => None,
+            Rule::pattern_kind => None,
+            Rule::literal_string => None,
+            Rule::bare_string => None,
+            Rule::pattern_kind_op => Some(":"),
+            Rule::negate_op => Some("~"),
+            Rule::union_op => Some("|"),
+            Rule::intersection_op => Some("&"),
+            Rule::difference_op => Some("~"),
+            Rule::infix_op => None,
+            Rule::function_name => None,
+            Rule::function => None,
+            Rule::string_pattern => None,
+            Rule::primary => None,
+            Rule::expression => None,
+            Rule::program => None,
+            Rule::bare_string_pattern => None,
+            Rule::program_or_bare_string => None,
+        }
+    }
+}
+
+/// Paths needed to resolve the file patterns relative to the current
+/// directory.
+#[derive(Clone, Copy, Debug)]
+pub struct FilesetParseContext<'a> {
+    pub cwd: &'a Path,
+    pub workspace_root: &'a Path,
+}
+
+pub type FilesetParseResult<T> = Result<T, FilesetParseError>;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct FilesetParseError {
+    kind: FilesetParseErrorKind,
+    pest_error: Box<pest::error::Error<Rule>>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FilesetParseErrorKind {
+    #[error("Syntax error")]
+    SyntaxError,
+    #[error(r#"Function "{0}" doesn't exist"#)]
+    NoSuchFunction(String),
+    #[error(r#"Invalid file pattern kind "{0}:""#)]
+    InvalidKind(String),
+    #[error("Invalid file pattern: {0}")]
+    FsPathParseError(#[source] FsPathParseError),
+    #[error("Invalid glob pattern: {0}")]
+    InvalidGlob(String),
+}
+
+impl FilesetParseError {
+    fn with_span(kind: FilesetParseErrorKind, span: pest::Span<'_>) -> Self {
+        let err = pest::error::Error::new_from_span(
+            pest::error::ErrorVariant::CustomError {
+                message: kind.to_string(),
+            },
+            span,
+        );
+        FilesetParseError {
+            kind,
+            pest_error: Box::new(err),
+        }
+    }
+
+    pub fn kind(&self) -> &FilesetParseErrorKind {
+        &self.kind
+    }
+}
+
+impl From<pest::error::Error<Rule>> for FilesetParseError {
+    fn from(err: pest::error::Error<Rule>) -> Self {
+        FilesetParseError {
+            kind: FilesetParseErrorKind::SyntaxError,
+            pest_error: Box::new(err.renamed_rules(|rule| {
+                rule.to_symbol()
+                    .map(|sym| format!("`{sym}`"))
+                    .unwrap_or_else(|| format!("<{rule:?}>"))
+            })),
+        }
+    }
+}
+
+impl fmt::Display for FilesetParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.pest_error.fmt(f)
+    }
+}
+
+impl error::Error for FilesetParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.kind {
+            // SyntaxError is a wrapper for pest::error::Error.
+            FilesetParseErrorKind::SyntaxError => Some(&*self.pest_error as &dyn error::Error),
+            // Otherwise the kind represents this error.
+            e => e.source(),
+        }
+    }
+}
+
+/// Basic pattern to match `RepoPath`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FilePattern {
+    /// Matches the file at the path.
+    FilePath(RepoPathBuf),
+    /// Matches the file at the path, and everything under the directory at the
+    /// path.
+    PrefixPath(RepoPathBuf),
+    /// Matches the files under the directory whose paths relative to the
+    /// directory match the glob.
+    FileGlob {
+        dir: RepoPathBuf,
+        pattern: glob::Pattern,
+    },
+}
+
+impl FilePattern {
+    /// Parses the `input` string as a pattern of the given `kind`.
+    ///
+    /// Patterns of the `cwd` kinds are relative to the current directory, and
+    /// the `root` kinds are relative to the workspace root.
+    pub fn from_str_kind(
+        ctx: &FilesetParseContext,
+        input: &str,
+        kind: &str,
+    ) -> Result<Self, FilesetParseErrorKind> {
+        match kind {
+            "cwd" => Ok(FilePattern::PrefixPath(parse_cwd_path(ctx, input)?)),
+            "file" | "cwd-file" => Ok(FilePattern::FilePath(parse_cwd_path(ctx, input)?)),
+            "glob" | "cwd-glob" => {
+                let (dir, pattern) = split_glob_path(input);
+                let dir = parse_cwd_path(ctx, dir)?;
+                Ok(FilePattern::FileGlob {
+                    dir,
+                    pattern: parse_glob(pattern)?,
+                })
+            }
+            "root" => Ok(FilePattern::PrefixPath(parse_root_path(input)?)),
+            "root-file" => Ok(FilePattern::FilePath(parse_root_path(input)?)),
+            "root-glob" => {
+                let (dir, pattern) = split_glob_path(input);
+                let dir = parse_root_path(dir)?;
+                Ok(FilePattern::FileGlob {
+                    dir,
+                    pattern: parse_glob(pattern)?,
+                })
+            }
+            _ => Err(FilesetParseErrorKind::InvalidKind(kind.to_owned())),
+        }
+    }
+}
+
+fn parse_cwd_path(
+    ctx: &FilesetParseContext,
+    input: &str,
+) -> Result<RepoPathBuf, FilesetParseErrorKind> {
+    RepoPathBuf::parse_fs_path(ctx.cwd, ctx.workspace_root, input)
+        .map_err(FilesetParseErrorKind::FsPathParseError)
+}
+
+fn parse_root_path(input: &str) -> Result<RepoPathBuf, FilesetParseErrorKind> {
+    if input.is_empty() {
+        return Ok(RepoPathBuf::root());
+    }
+    RepoPathBuf::from_relative_path(input).ok_or_else(|| {
+        FilesetParseErrorKind::FsPathParseError(FsPathParseError::InputNotInRepo(input.into()))
+    })
+}
+
+fn parse_glob(input: &str) -> Result<glob::Pattern, FilesetParseErrorKind> {
+    glob::Pattern::new(input).map_err(|err| FilesetParseErrorKind::InvalidGlob(err.to_string()))
+}
+
+/// Splits `input` into the literal directory part and the glob part.
+fn split_glob_path(input: &str) -> (&str, &str) {
+    let prefix_len = input.find(['*', '?', '[']).unwrap_or(input.len());
+    match input[..prefix_len].rfind('/') {
+        Some(pos) => (&input[..pos], &input[pos + 1..]),
+        None => ("", input),
+    }
+}
+
+/// AST-level representation of the fileset expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FilesetExpression {
+    /// Matches nothing.
+    None,
+    /// Matches everything.
+    All,
+    /// Matches basic pattern.
+    Pattern(FilePattern),
+    /// Matches any of the expressions.
+    UnionAll(Vec<FilesetExpression>),
+    /// Matches both expressions.
+    Intersection(Box<FilesetExpression>, Box<FilesetExpression>),
+    /// Matches the first expression, but not the second expression.
+    Difference(Box<FilesetExpression>, Box<FilesetExpression>),
+}
+
+impl FilesetExpression {
+    pub fn none() -> Self {
+        FilesetExpression::None
+    }
+
+    pub fn all() -> Self {
+        FilesetExpression::All
+    }
+
+    pub fn pattern(pattern: FilePattern) -> Self {
+        FilesetExpression::Pattern(pattern)
+    }
+
+    pub fn file_path(path: RepoPathBuf) -> Self {
+        FilesetExpression::Pattern(FilePattern::FilePath(path))
+    }
+
+    pub fn prefix_path(path: RepoPathBuf) -> Self {
+        FilesetExpression::Pattern(FilePattern::PrefixPath(path))
+    }
+
+    /// Expression that matches any of the given `expressions`.
+    pub fn union_all(expressions: Vec<FilesetExpression>) -> Self {
+        match expressions.len() {
+            0 => FilesetExpression::none(),
+            1 => expressions.into_iter().next().unwrap(),
+            _ => FilesetExpression::UnionAll(expressions),
+        }
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        FilesetExpression::Intersection(Box::new(self), Box::new(other))
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        FilesetExpression::Difference(Box::new(self), Box::new(other))
+    }
+
+    fn as_union_all(&self) -> &[Self] {
+        match self {
+            FilesetExpression::UnionAll(exprs) => exprs,
+            _ => slice::from_ref(self),
+        }
+    }
+
+    /// Transforms the expression tree to `Matcher` object.
+    pub fn to_matcher(&self) -> Box<dyn Matcher> {
+        build_union_matcher(self.as_union_all())
+    }
+}
+
+fn build_union_matcher(expressions: &[FilesetExpression]) -> Box<dyn Matcher> {
+    // Paths are merged into a single matcher, which is cheaper to evaluate.
+    let mut file_paths = vec![];
+    let mut prefix_paths = vec![];
+    let mut matchers: Vec<Box<dyn Matcher>> = vec![];
+    for expression in expressions {
+        let matcher: Box<dyn Matcher> = match expression {
+            FilesetExpression::None => continue,
+            FilesetExpression::All => Box::new(EverythingMatcher),
+            FilesetExpression::Pattern(FilePattern::FilePath(path)) => {
+                file_paths.push(path);
+                continue;
+            }
+            FilesetExpression::Pattern(FilePattern::PrefixPath(path)) => {
+                prefix_paths.push(path);
+                continue;
+            }
+            FilesetExpression::Pattern(FilePattern::FileGlob { dir, pattern }) => {
+                Box::new(FileGlobMatcher::new(dir.clone(), pattern.clone()))
+            }
+            FilesetExpression::UnionAll(exprs) => build_union_matcher(exprs),
+            FilesetExpression::Intersection(expr1, expr2) => Box::new(IntersectionMatcher::new(
+                expr1.to_matcher(),
+                expr2.to_matcher(),
+            )),
+            FilesetExpression::Difference(expr1, expr2) => Box::new(DifferenceMatcher::new(
+                expr1.to_matcher(),
+                expr2.to_matcher(),
+            )),
+        };
+        matchers.push(matcher);
+    }
+    if !file_paths.is_empty() {
+        matchers.push(Box::new(FilesMatcher::new(file_paths)));
+    }
+    if !prefix_paths.is_empty() {
+        matchers.push(Box::new(PrefixMatcher::new(prefix_paths)));
+    }
+    matchers
+        .into_iter()
+        .reduce(|matcher1, matcher2| Box::new(UnionMatcher::new(matcher1, matcher2)))
+        .unwrap_or_else(|| Box::new(NothingMatcher))
+}
+
+fn parse_expression_rule(
+    pairs: Pairs<Rule>,
+    ctx: &FilesetParseContext,
+) -> FilesetParseResult<FilesetExpression> {
+    static PRATT: Lazy<PrattParser<Rule>> = Lazy::new(|| {
+        PrattParser::new()
+            .op(Op::infix(Rule::union_op, Assoc::Left))
+            .op(Op::infix(Rule::intersection_op, Assoc::Left)
+                | Op::infix(Rule::difference_op, Assoc::Left))
+            .op(Op::prefix(Rule::negate_op))
+    });
+    PRATT
+        .map_primary(|primary| match primary.as_rule() {
+            Rule::primary => parse_primary_rule(primary, ctx),
+            r => panic!("unexpected primary rule {r:?}"),
+        })
+        .map_prefix(|op, rhs| match op.as_rule() {
+            Rule::negate_op => Ok(FilesetExpression::all().difference(rhs?)),
+            r => panic!("unexpected prefix operator rule {r:?}"),
+        })
+        .map_infix(|lhs, op, rhs| match op.as_rule() {
+            Rule::union_op => {
+                let mut expressions = match lhs? {
+                    FilesetExpression::UnionAll(expressions) => expressions,
+                    expression => vec![expression],
+                };
+                expressions.push(rhs?);
+                Ok(FilesetExpression::UnionAll(expressions))
+            }
+            Rule::intersection_op => Ok(lhs?.intersection(rhs?)),
+            Rule::difference_op => Ok(lhs?.difference(rhs?)),
+            r => panic!("unexpected infix operator rule {r:?}"),
+        })
+        .parse(pairs)
+}
+
+fn parse_primary_rule(
+    pair: Pair<Rule>,
+    ctx: &FilesetParseContext,
+) -> FilesetParseResult<FilesetExpression> {
+    let first = pair.into_inner().next().unwrap();
+    let span = first.as_span();
+    match first.as_rule() {
+        Rule::expression => parse_expression_rule(first.into_inner(), ctx),
+        Rule::function => {
+            let name = first.into_inner().next().unwrap().as_str();
+            match name {
+                "all" => Ok(FilesetExpression::all()),
+                "none" => Ok(FilesetExpression::none()),
+                _ => Err(FilesetParseError::with_span(
+                    FilesetParseErrorKind::NoSuchFunction(name.to_owned()),
+                    span,
+                )),
+            }
+        }
+        Rule::string_pattern => parse_pattern_rule(first, ctx),
+        Rule::identifier => parse_pattern(ctx, first.as_str(), "cwd", span),
+        Rule::literal_string => parse_pattern(ctx, unquote(&first), "cwd", span),
+        r => panic!("unexpected primary rule {r:?}"),
+    }
+}
+
+/// Parses `kind:value` pattern.
+fn parse_pattern_rule(
+    pair: Pair<Rule>,
+    ctx: &FilesetParseContext,
+) -> FilesetParseResult<FilesetExpression> {
+    let span = pair.as_span();
+    let mut inner = pair.into_inner();
+    let kind = inner.next().unwrap();
+    assert_eq!(inner.next().unwrap().as_rule(), Rule::pattern_kind_op);
+    let value = inner.next().unwrap();
+    let input = match value.as_rule() {
+        Rule::literal_string => unquote(&value),
+        _ => value.as_str(),
+    };
+    parse_pattern(ctx, input, kind.as_str(), span)
+}
+
+fn parse_pattern(
+    ctx: &FilesetParseContext,
+    input: &str,
+    kind: &str,
+    span: pest::Span<'_>,
+) -> FilesetParseResult<FilesetExpression> {
+    let pattern = FilePattern::from_str_kind(ctx, input, kind)
+        .map_err(|kind| FilesetParseError::with_span(kind, span))?;
+    Ok(FilesetExpression::pattern(pattern))
+}
+
+fn unquote<'a>(pair: &Pair<'a, Rule>) -> &'a str {
+    let quoted = pair.as_str();
+    &quoted[1..quoted.len() - 1]
+}
+
+/// Parses text into `FilesetExpression`.
+pub fn parse(text: &str, ctx: &FilesetParseContext) -> FilesetParseResult<FilesetExpression> {
+    let mut pairs = FilesetParser::parse(Rule::program, text)?;
+    let first = pairs.next().unwrap();
+    parse_expression_rule(first.into_inner(), ctx)
+}
+
+/// Parses text into `FilesetExpression`, or as a single path if the whole
+/// text can't be parsed as an expression.
+///
+/// This is intended for command-line arguments, so that a file path
+/// containing spaces doesn't have to be quoted.
+pub fn parse_maybe_bare(
+    text: &str,
+    ctx: &FilesetParseContext,
+) -> FilesetParseResult<FilesetExpression> {
+    let mut pairs = FilesetParser::parse(Rule::program_or_bare_string, text)?;
+    let first = pairs.next().unwrap();
+    let span = first.as_span();
+    match first.as_rule() {
+        Rule::expression => parse_expression_rule(first.into_inner(), ctx),
+        Rule::bare_string_pattern => parse_pattern_rule(first, ctx),
+        Rule::bare_string => parse_pattern(ctx, first.as_str(), "cwd", span),
+        r => panic!("unexpected program rule {r:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn repo_path_buf(value: &str) -> RepoPathBuf {
+        RepoPathBuf::from_internal_string(value)
+    }
+
+    fn glob(dir: &str, pattern: &str) -> FilesetExpression {
+        FilesetExpression::pattern(FilePattern::FileGlob {
+            dir: repo_path_buf(dir),
+            pattern: glob::Pattern::new(pattern).unwrap(),
+        })
+    }
+
+    fn with_ctx<T>(f: impl FnOnce(&FilesetParseContext) -> T) -> T {
+        let ctx = FilesetParseContext {
+            cwd: Path::new("/ws/cur"),
+            workspace_root: Path::new("/ws"),
+        };
+        f(&ctx)
+    }
+
+    fn parse_kind(text: &str) -> Result<FilesetExpression, FilesetParseErrorKind> {
+        with_ctx(|ctx| parse(text, ctx)).map_err(|err| err.kind)
+    }
+
+    fn parse_bare_kind(text: &str) -> Result<FilesetExpression, FilesetParseErrorKind> {
+        with_ctx(|ctx| parse_maybe_bare(text, ctx)).map_err(|err| err.kind)
+    }
+
+    #[test]
+    fn test_parse_patterns() {
+        assert_eq!(
+            parse_kind("foo"),
+            Ok(FilesetExpression::prefix_path(repo_path_buf("cur/foo")))
+        );
+        assert_eq!(
+            parse_kind(r#""foo bar""#),
+            Ok(FilesetExpression::prefix_path(repo_path_buf("cur/foo bar")))
+        );
+        assert_eq!(
+            parse_kind("cwd:../foo"),
+            Ok(FilesetExpression::prefix_path(repo_path_buf("foo")))
+        );
+        assert_eq!(
+            parse_kind("file:foo"),
+            Ok(FilesetExpression::file_path(repo_path_buf("cur/foo")))
+        );
+        assert_eq!(
+            parse_kind("root:foo"),
+            Ok(FilesetExpression::prefix_path(repo_path_buf("foo")))
+        );
+        assert_eq!(
+            parse_kind(r#"root-file:"foo/bar""#),
+            Ok(FilesetExpression::file_path(repo_path_buf("foo/bar")))
+        );
+        assert_eq!(parse_kind(r#"glob:"*.rs""#), Ok(glob("cur", "*.rs")));
+        assert_eq!(
+            parse_kind(r#"glob:"src/**/*.rs""#),
+            Ok(glob("cur/src", "**/*.rs"))
+        );
+        // Glob characters don't have to be quoted
+        assert_eq!(parse_kind("glob:src/*.[ch]"), Ok(glob("cur/src", "*.[ch]")));
+        assert_eq!(
+            parse_kind(r#"root-glob:"*/gen/*.rs""#),
+            Ok(glob("", "*/gen/*.rs"))
+        );
+        assert_eq!(
+            parse_kind("../../foo"),
+            Err(FilesetParseErrorKind::FsPathParseError(
+                FsPathParseError::InputNotInRepo(PathBuf::from("../../foo"))
+            ))
+        );
+        assert_eq!(
+            parse_kind("root:../foo"),
+            Err(FilesetParseErrorKind::FsPathParseError(
+                FsPathParseError::InputNotInRepo(PathBuf::from("../foo"))
+            ))
+        );
+        assert_eq!(
+            parse_kind("bad:foo"),
+            Err(FilesetParseErrorKind::InvalidKind("bad".to_owned()))
+        );
+        assert!(matches!(
+            parse_kind(r#"glob:"[""#),
+            Err(FilesetParseErrorKind::InvalidGlob(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_operators() {
+        let foo = || FilesetExpression::prefix_path(repo_path_buf("cur/foo"));
+        let bar = || FilesetExpression::prefix_path(repo_path_buf("cur/bar"));
+        let baz = || FilesetExpression::prefix_path(repo_path_buf("cur/baz"));
+        assert_eq!(
+            parse_kind("~foo"),
+            Ok(FilesetExpression::all().difference(foo()))
+        );
+        assert_eq!(
+            parse_kind("foo | bar | baz"),
+            Ok(FilesetExpression::union_all(vec![foo(), bar(), baz()]))
+        );
+        assert_eq!(
+            parse_kind("foo | bar & baz"),
+            Ok(FilesetExpression::union_all(vec![
+                foo(),
+                bar().intersection(baz())
+            ]))
+        );
+        assert_eq!(
+            parse_kind("(foo | bar) ~ baz"),
+            Ok(FilesetExpression::union_all(vec![foo(), bar()]).difference(baz()))
+        );
+        assert_eq!(
+            parse_kind("all() ~ none()"),
+            Ok(FilesetExpression::all().difference(FilesetExpression::none()))
+        );
+        assert_eq!(
+            parse_kind("foo()"),
+            Err(FilesetParseErrorKind::NoSuchFunction("foo".to_owned()))
+        );
+        assert_eq!(parse_kind("foo |"), Err(FilesetParseErrorKind::SyntaxError));
+        assert_eq!(
+            parse_kind("foo bar"),
+            Err(FilesetParseErrorKind::SyntaxError)
+        );
+    }
+
+    #[test]
+    fn test_parse_maybe_bare() {
+        assert_eq!(
+            parse_bare_kind("foo bar"),
+            Ok(FilesetExpression::prefix_path(repo_path_buf("cur/foo bar")))
+        );
+        assert_eq!(
+            parse_bare_kind("root-file:foo bar"),
+            Ok(FilesetExpression::file_path(repo_path_buf("foo bar")))
+        );
+        assert_eq!(
+            parse_bare_kind("foo | bar"),
+            Ok(FilesetExpression::union_all(vec![
+                FilesetExpression::prefix_path(repo_path_buf("cur/foo")),
+                FilesetExpression::prefix_path(repo_path_buf("cur/bar")),
+            ]))
+        );
+        assert_eq!(
+            parse_bare_kind(r#"glob:"*.rs" ~ glob:"gen_*""#),
+            Ok(glob("cur", "*.rs").difference(glob("cur", "gen_*")))
+        );
+    }
+
+    #[test]
+    fn test_to_matcher() {
+        let matcher = with_ctx(|ctx| parse(r#"glob:"**/*.rs" ~ cwd:gen"#, ctx))
+            .unwrap()
+            .to_matcher();
+        let path = |value| RepoPathBuf::from_internal_string(value);
+        assert!(matcher.matches(&path("cur/lib.rs")));
+        assert!(matcher.matches(&path("cur/src/lib.rs")));
+        assert!(!matcher.matches(&path("cur/gen/lib.rs")));
+        assert!(!matcher.matches(&path("cur/lib.c")));
+        assert!(!matcher.matches(&path("lib.rs")));
+
+        let matcher = with_ctx(|ctx| parse("foo | file:bar", ctx))
+            .unwrap()
+            .to_matcher();
+        assert!(matcher.matches(&path("cur/foo/a")));
+        assert!(matcher.matches(&path("cur/bar")));
+        assert!(!matcher.matches(&path("cur/bar/a")));
+    }
+}
@@ -18,8 +18,10 @@ use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::default::Default;
 use std::io::Read;
-use std::path::PathBuf;
-use std::{fmt, iter, str};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::{fmt, io, iter, str};
 
 use git2::Oid;
 use itertools::Itertools;
@@ -1086,6 +1088,34 @@ pub enum GitFetchError {
     // TODO: I'm sure there are other errors possible, such as transport-level errors.
     #[error("Unexpected git error when fetching: {0}")]
     InternalGitError(#[from] git2::Error),
+    #[error("Failed to run git command: {0}")]
+    GitCommand(#[source] io::Error),
+    #[error("git command exited with an error: {status}\n{stderr}")]
+    GitCommandErrorStatus { status: ExitStatus, stderr: String },
+    #[error("Failed to read shallow commits of the git repo")]
+    ReadShallowCommits(#[source] io::Error),
+}
+
+/// Limits on the history and objects to be downloaded by `fetch()`.
+///
+/// Shallow and partial fetches aren't supported by libgit2, so they are
+/// performed by the `git` command.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GitFetchOptions {
+    /// Fetch only the specified number of commits from the tip of each branch.
+    pub depth: Option<NonZeroU32>,
+    /// Fetch only commits more recent than the specified date.
+    pub shallow_since: Option<String>,
+    /// Object filter spec (e.g. `blob:none`) to make a partial clone. The
+    /// remote is recorded as a promisor remote, from which missing objects
+    /// will be fetched on demand.
+    pub filter: Option<String>,
+}
+
+impl GitFetchOptions {
+    fn is_shallow(&self) -> bool {
+        self.depth.is_some() || self.shallow_since.is_some()
+    }
 }
 
 /// Describes successful `fetch()` result.
@@ -1095,6 +1125,10 @@ pub struct GitFetchStats {
     pub default_branch: Option<String>,
     /// Changes made by the import.
     pub import_stats: GitImportStats,
+    /// Indexed commits which were at the shallow boundary, but whose parents
+    /// have been fetched. The index has to be rebuilt since the parents of
+    /// these commits have changed.
+    pub unshallowed_commits: Vec<CommitId>,
 }
 
 #[tracing::instrument(skip(mut_repo, git_repo, callbacks))]
@@ -1103,6 +1137,7 @@ pub fn fetch(
     git_repo: &git2::Repository,
     remote_name: &str,
     branch_names: &[StringPattern],
+    options: &GitFetchOptions,
     callbacks: RemoteCallbacks<'_>,
     git_settings: &GitSettings,
 ) -> Result<GitFetchStats, GitFetchError> {
@@ -1115,12 +1150,6 @@ pub fn fetch(
             GitFetchError::InternalGitError(err)
         }
     })?;
-    let mut fetch_options = git2::FetchOptions::new();
-    let mut proxy_options = git2::ProxyOptions::new();
-    proxy_options.auto();
-    fetch_options.proxy_options(proxy_options);
-    let callbacks = callbacks.into_git();
-    fetch_options.remote_callbacks(callbacks);
     // At this point, we are only updating Git's remote tracking branches, not the
     // local branches.
     let refspecs: Vec<_> = branch_names
@@ -1140,30 +1169,34 @@ pub fn fetch(
             import_stats: GitImportStats {
                 abandoned_commits: vec![],
             },
+            unshallowed_commits: vec![],
         };
         return Ok(stats);
     }
-    tracing::debug!("remote.download");
-    remote.download(&refspecs, Some(&mut fetch_options))?;
-    tracing::debug!("remote.prune");
-    remote.prune(None)?;
-    tracing::debug!("remote.update_tips");
-    remote.update_tips(None, false, git2::AutotagOption::Unspecified, None)?;
-    // TODO: We could make it optional to get the default branch since we only care
-    // about it on clone.
-    let mut default_branch = None;
-    if let Ok(default_ref_buf) = remote.default_branch() {
-        if let Some(default_ref) = default_ref_buf.as_str() {
-            // LocalBranch here is the local branch on the remote, so it's really the remote
-            // branch
-            if let Some(RefName::LocalBranch(branch_name)) = parse_git_ref(default_ref) {
-                tracing::debug!(default_branch = branch_name);
-                default_branch = Some(branch_name);
-            }
+    let (default_branch, unshallowed_commits) = if options.is_shallow()
+        || options.filter.is_some()
+        || is_shallow_or_partial_repo(git_repo, &remote)
+    {
+        if let Some(filter) = &options.filter {
+            let mut config = git_repo.config()?;
+            config.set_bool(&format!("remote.{remote_name}.promisor"), true)?;
+            config.set_str(&format!("remote.{remote_name}.partialclonefilter"), filter)?;
         }
-    }
-    tracing::debug!("remote.disconnect");
-    remote.disconnect()?;
+        let old_shallow_commits = read_shallow_commits(git_repo.path())?;
+        let default_branch =
+            fetch_with_git_command(git_repo.path(), remote_name, &refspecs, options)?;
+        let new_shallow_commits = read_shallow_commits(git_repo.path())?;
+        let index = mut_repo.index();
+        let unshallowed_commits = old_shallow_commits
+            .difference(&new_shallow_commits)
+            .filter(|id| index.has_id(id))
+            .cloned()
+            .collect();
+        (default_branch, unshallowed_commits)
+    } else {
+        let default_branch = fetch_with_git2(&mut remote, &refspecs, callbacks)?;
+        (default_branch, vec![])
+    };
 
     // Import the remote-tracking branches into the jj repo and update jj's
     // local branches. We also import local tags since remote tags should have
@@ -1177,10 +1210,141 @@ pub fn fetch(
     let stats = GitFetchStats {
         default_branch,
         import_stats,
+        unshallowed_commits,
     };
     Ok(stats)
 }
 
+fn fetch_with_git2(
+    remote: &mut git2::Remote,
+    refspecs: &[String],
+    callbacks: RemoteCallbacks<'_>,
+) -> Result<Option<String>, git2::Error> {
+    let mut fetch_options = git2::FetchOptions::new();
+    let mut proxy_options = git2::ProxyOptions::new();
+    proxy_options.auto();
+    fetch_options.proxy_options(proxy_options);
+    let callbacks = callbacks.into_git();
+    fetch_options.remote_callbacks(callbacks);
+    tracing::debug!("remote.download");
+    remote.download(refspecs, Some(&mut fetch_options))?;
+    tracing::debug!("remote.prune");
+    remote.prune(None)?;
+    tracing::debug!("remote.update_tips");
+    remote.update_tips(None, false, git2::AutotagOption::Unspecified, None)?;
+    // TODO: We could make it optional to get the default branch since we only care
+    // about it on clone.
+    let mut default_branch = None;
+    if let Ok(default_ref_buf) = remote.default_branch() {
+        if let Some(default_ref) = default_ref_buf.as_str() {
+            default_branch = parse_remote_default_branch(default_ref);
+        }
+    }
+    tracing::debug!("remote.disconnect");
+    remote.disconnect()?;
+    Ok(default_branch)
+}
+
+/// Fetches by the `git` command, which supports shallow and partial fetches.
+///
+/// Credentials are looked up by `git` itself.
+fn fetch_with_git_command(
+    git_dir: &Path,
+    remote_name: &str,
+    refspecs: &[String],
+    options: &GitFetchOptions,
+) -> Result<Option<String>, GitFetchError> {
+    let mut args = vec!["fetch".to_owned(), "--prune".to_owned()];
+    if let Some(depth) = options.depth {
+        args.push(format!("--depth={depth}"));
+    }
+    if let Some(date) = &options.shallow_since {
+        args.push(format!("--shallow-since={date}"));
+    }
+    if let Some(filter) = &options.filter {
+        args.push(format!("--filter={filter}"));
+    }
+    args.push("--".to_owned());
+    args.push(remote_name.to_owned());
+    args.extend(refspecs.iter().cloned());
+    tracing::debug!(?args, "git fetch");
+    run_git_command(git_dir, &args)?;
+
+    tracing::debug!("git ls-remote");
+    let output = run_git_command(git_dir, ["ls-remote", "--symref", remote_name, "HEAD"])?;
+    // The symbolic ref is reported as "ref: refs/heads/<branch>\tHEAD".
+    let default_branch = output.lines().find_map(|line| {
+        let (target, name) = line.strip_prefix("ref: ")?.split_once('\t')?;
+        (name == "HEAD").then(|| parse_remote_default_branch(target))?
+    });
+    Ok(default_branch)
+}
+
+/// Runs `git` in the `git_dir`, and returns the standard output.
+fn run_git_command(
+    git_dir: &Path,
+    args: impl IntoIterator<Item = impl AsRef<std::ffi::OsStr>>,
+) -> Result<String, GitFetchError> {
+    let mut git = Command::new("git");
+    git.arg("--git-dir=."); // turn off discovery
+    git.args(args);
+    // Don't specify it by GIT_DIR/--git-dir. On Windows, the "\\?\" path might
+    // not be supported by git.
+    git.current_dir(git_dir);
+    git.stdin(Stdio::null());
+    let output = git.output().map_err(GitFetchError::GitCommand)?;
+    if !output.status.success() {
+        return Err(GitFetchError::GitCommandErrorStatus {
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr)
+                .trim_end()
+                .to_owned(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads the commits at the shallow boundary from the `shallow` file of the
+/// git repo.
+fn read_shallow_commits(git_dir: &Path) -> Result<HashSet<CommitId>, GitFetchError> {
+    let content = match std::fs::read_to_string(git_dir.join("shallow")) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(err) => return Err(GitFetchError::ReadShallowCommits(err)),
+    };
+    let ids = content
+        .lines()
+        .filter_map(|line| CommitId::try_from_hex(line.trim()).ok())
+        .collect();
+    Ok(ids)
+}
+
+/// Returns true if the repo has a shallow history, or if the `remote` is a
+/// promisor remote of a partial clone. libgit2 can't update such repos.
+fn is_shallow_or_partial_repo(git_repo: &git2::Repository, remote: &git2::Remote) -> bool {
+    if git_repo.is_shallow() {
+        return true;
+    }
+    let Some(remote_name) = remote.name() else {
+        return false;
+    };
+    git_repo
+        .config()
+        .and_then(|config| config.get_bool(&format!("remote.{remote_name}.promisor")))
+        .unwrap_or(false)
+}
+
+fn parse_remote_default_branch(default_ref: &str) -> Option<String> {
+    // LocalBranch here is the local branch on the remote, so it's really the remote
+    // branch
+    if let Some(RefName::LocalBranch(branch_name)) = parse_git_ref(default_ref) {
+        tracing::debug!(default_branch = branch_name);
+        Some(branch_name)
+    } else {
+        None
+    }
+}
+
 #[derive(Error, Debug, PartialEq)]
 pub enum GitPushError {
     #[error("No git remote named '{0}'")]
@@ -17,9 +17,9 @@
 use std::any::Any;
 use std::collections::HashSet;
 use std::fmt::{Debug, Error, Formatter};
-use std::io::{Cursor, Read};
+use std::io::{Cursor, Read, Write as _};
 use std::path::{Path, PathBuf};
-use std::process::{Command, ExitStatus};
+use std::process::{Command, ExitStatus, Stdio};
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::SystemTime;
 use std::{fs, io, str};
@@ -33,16 +33,16 @@ use thiserror::Error;
 
 use crate::backend::{
     make_root_commit, Backend, BackendError, BackendInitError, BackendLoadError, BackendResult,
-    ChangeId, Commit, CommitId, Conflict, ConflictId, ConflictTerm, FileId, MergedTreeId,
-    MillisSinceEpoch, SecureSig, Signature, SigningFn, SymlinkId, Timestamp, Tree, TreeId,
-    TreeValue,
+    ChangeId, Commit, CommitId, Conflict, ConflictId, ConflictTerm, CopyRecord, FileId,
+    MergedTreeId, MillisSinceEpoch, SecureSig, Signature, SigningFn, SymlinkId, Timestamp, Tree,
+    TreeId, TreeValue,
 };
 use crate::file_util::{IoResultExt as _, PathError};
 use crate::index::Index;
 use crate::lock::FileLock;
 use crate::merge::{Merge, MergeBuilder};
 use crate::object_id::ObjectId;
-use crate::repo_path::{RepoPath, RepoPathComponentBuf};
+use crate::repo_path::{RepoPath, RepoPathBuf, RepoPathComponentBuf};
 use crate::settings::UserSettings;
 use crate::stacked_table::{
     MutableTable, ReadonlyTable, TableSegment, TableStore, TableStoreError,
@@ -378,14 +378,38 @@ impl GitBackend {
     }
 
     fn read_file_sync(&self, id: &FileId) -> BackendResult<Box<dyn Read>> {
+        let data = self.read_blob(id)?;
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    /// Reads the blob content. If the blob is missing in a partial clone, it is
+    /// fetched from the promisor remote.
+    fn read_blob(&self, id: &impl ObjectId) -> BackendResult<Vec<u8>> {
         let git_blob_id = validate_git_object_id(id)?;
+        match self.read_existing_blob(git_blob_id, id) {
+            // The repo isn't locked while fetching, which may take a while.
+            Err(BackendError::ObjectNotFound { .. })
+                if fetch_missing_objects(self.git_repo_path(), &[git_blob_id]) =>
+            {
+                self.read_existing_blob(git_blob_id, id)
+            }
+            result => result,
+        }
+    }
+
+    fn read_existing_blob(
+        &self,
+        git_blob_id: gix::ObjectId,
+        id: &impl ObjectId,
+    ) -> BackendResult<Vec<u8>> {
         let locked_repo = self.lock_git_repo();
-        let mut blob = locked_repo
+        let git_object = locked_repo
             .find_object(git_blob_id)
-            .map_err(|err| map_not_found_err(err, id))?
+            .map_err(|err| map_not_found_err(err, id))?;
+        let mut blob = git_object
             .try_into_blob()
             .map_err(|err| to_read_object_err(err, id))?;
-        Ok(Box::new(Cursor::new(blob.take_data())))
+        Ok(blob.take_data())
     }
 }
 
@@ -491,6 +515,8 @@ fn commit_from_git_without_root_parent(
         author,
         committer,
         secure_sig,
+        // Git commits don't record copies.
+        copy_records: vec![],
     })
 }
 
@@ -558,6 +584,14 @@ fn serialize_extras(commit: &Commit) -> Vec<u8> {
     for predecessor in &commit.predecessors {
         proto.predecessors.push(predecessor.to_bytes());
     }
+    proto.copy_records = commit
+        .copy_records
+        .iter()
+        .map(|record| crate::protos::git_store::commit::CopyRecord {
+            target: record.target.as_internal_file_string().to_owned(),
+            source: record.source.as_internal_file_string().to_owned(),
+        })
+        .collect();
     proto.encode_to_vec()
 }
 
@@ -585,6 +619,14 @@ fn deserialize_extras(commit: &mut Commit, bytes: &[u8]) {
     for predecessor in &proto.predecessors {
         commit.predecessors.push(CommitId::from_bytes(predecessor));
     }
+    commit.copy_records = proto
+        .copy_records
+        .into_iter()
+        .map(|record| CopyRecord {
+            target: RepoPathBuf::from_internal_string(record.target),
+            source: RepoPathBuf::from_internal_string(record.source),
+        })
+        .collect();
 }
 
 /// Returns `RefEdit` that will create a ref in `refs/jj/keep` if not exist.
@@ -704,6 +746,74 @@ fn run_git_gc(git_dir: &Path) -> Result<(), GitGcError> {
     Ok(())
 }
 
+/// Returns true if the commit is at the shallow boundary, i.e. its parents are
+/// missing in the repo.
+fn is_shallow_commit(git_repo: &gix::Repository, id: &gix::ObjectId) -> BackendResult<bool> {
+    let shallow_commits = git_repo
+        .shallow_commits()
+        .map_err(|err| BackendError::Other(err.into()))?;
+    Ok(shallow_commits.map_or(false, |commits| commits.binary_search(id).is_ok()))
+}
+
+/// Fetches the missing objects from the promisor remote of a partial clone in
+/// a single request. Returns true if the objects have been fetched.
+fn fetch_missing_objects(git_dir: &Path, ids: &[gix::ObjectId]) -> bool {
+    let Some(remote_name) = find_promisor_remote(git_dir) else {
+        return false;
+    };
+    // Same arguments as git uses to fetch missing objects internally.
+    let mut git = Command::new("git");
+    git.arg("--git-dir=."); // turn off discovery
+    git.args(["-c", "fetch.negotiationAlgorithm=noop", "fetch"]);
+    git.args([
+        "--no-tags",
+        "--no-write-fetch-head",
+        "--recurse-submodules=no",
+    ]);
+    git.args(["--filter=blob:none", "--quiet", "--stdin"]);
+    git.arg(&remote_name);
+    // Don't specify it by GIT_DIR/--git-dir. On Windows, the "\\?\" path might
+    // not be supported by git.
+    git.current_dir(git_dir);
+    git.stdin(Stdio::piped());
+    tracing::info!(?git, count = ids.len(), "fetching missing objects");
+    let result = git.spawn().and_then(|mut child| {
+        // The ids are passed through stdin since there may be too many for the
+        // command line.
+        let mut stdin = child.stdin.take().unwrap();
+        let write_result = ids.iter().try_for_each(|id| writeln!(stdin, "{id}"));
+        drop(stdin);
+        let status = child.wait()?;
+        write_result.map(|()| status)
+    });
+    match result {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            tracing::warn!(%status, "failed to fetch missing objects");
+            false
+        }
+        Err(err) => {
+            tracing::warn!(?err, "failed to run git to fetch missing objects");
+            false
+        }
+    }
+}
+
+/// Returns the name of the remote from which missing objects can be fetched.
+fn find_promisor_remote(git_dir: &Path) -> Option<String> {
+    // Look up the on-disk config since the remote may have been set up after
+    // the gix repo was loaded.
+    let git_repo = git2::Repository::open(git_dir).ok()?;
+    let config = git_repo.config().ok()?;
+    let remotes = git_repo.remotes().ok()?;
+    let remote_name = remotes.iter().flatten().find(|name| {
+        config
+            .get_bool(&format!("remote.{name}.promisor"))
+            .unwrap_or(false)
+    })?;
+    Some(remote_name.to_owned())
+}
+
 fn validate_git_object_id(id: &impl ObjectId) -> Result<gix::ObjectId, BackendError> {
     if id.as_bytes().len() != HASH_LENGTH {
         return Err(BackendError::InvalidHashLength {
@@ -769,6 +879,9 @@ fn import_extra_metadata_entries_from_heads(
         let commit =
             commit_from_git_without_root_parent(&id, &git_object, uses_tree_conflict_format)?;
         mut_table.add_entry(id.to_bytes(), serialize_extras(&commit));
+        if is_shallow_commit(git_repo, &git_object.id)? {
+            continue;
+        }
         work_ids.extend(
             commit
                 .parents
@@ -825,6 +938,35 @@ impl Backend for GitBackend {
         self.read_file_sync(id)
     }
 
+    async fn file_size(&self, _path: &RepoPath, id: &FileId) -> BackendResult<u64> {
+        let git_blob_id = validate_git_object_id(id)?;
+        let header = self.lock_git_repo().find_header(git_blob_id);
+        match header {
+            Ok(header) => Ok(header.size()),
+            // The blob may be missing in a partial clone
+            Err(gix::object::find::existing::Error::NotFound { .. }) => {
+                Ok(self.read_blob(id)?.len() as u64)
+            }
+            Err(err) => Err(map_not_found_err(err, id)),
+        }
+    }
+
+    fn prefetch_files(&self, ids: &[FileId]) -> BackendResult<()> {
+        let missing_ids: Vec<_> = {
+            let locked_repo = self.lock_git_repo();
+            ids.iter()
+                .map(validate_git_object_id)
+                .filter_ok(|id| !locked_repo.has_object(id))
+                .unique_by(|result| result.as_ref().ok().copied())
+                .try_collect()?
+        };
+        if !missing_ids.is_empty() {
+            // Files which couldn't be fetched are reported when they're read.
+            fetch_missing_objects(self.git_repo_path(), &missing_ids);
+        }
+        Ok(())
+    }
+
     fn write_file(&self, _path: &RepoPath, contents: &mut dyn Read) -> BackendResult<FileId> {
         let mut bytes = Vec::new();
         contents.read_to_end(&mut bytes).unwrap();
@@ -839,14 +981,8 @@ impl Backend for GitBackend {
     }
 
     async fn read_symlink(&self, _path: &RepoPath, id: &SymlinkId) -> Result<String, BackendError> {
-        let git_blob_id = validate_git_object_id(id)?;
-        let locked_repo = self.lock_git_repo();
-        let mut blob = locked_repo
-            .find_object(git_blob_id)
-            .map_err(|err| map_not_found_err(err, id))?
-            .try_into_blob()
-            .map_err(|err| to_read_object_err(err, id))?;
-        let target = String::from_utf8(blob.take_data())
+        let data = self.read_blob(id)?;
+        let target = String::from_utf8(data)
             .map_err(|err| to_invalid_utf8_err(err.utf8_error(), id))?
             .to_owned();
         Ok(target)
@@ -1033,7 +1169,13 @@ impl Backend for GitBackend {
             let git_object = locked_repo
                 .find_object(git_commit_id)
                 .map_err(|err| map_not_found_err(err, id))?;
-            commit_from_git_without_root_parent(id, &git_object, false)?
+            let mut commit = commit_from_git_without_root_parent(id, &git_object, false)?;
+            // The parents of commits at the shallow boundary haven't been
+            // fetched, so these commits are treated as root commits.
+            if is_shallow_commit(&locked_repo, &git_commit_id)? {
+                commit.parents.clear();
+            }
+            commit
         };
         if commit.parents.is_empty() {
             commit.parents.push(self.root_commit_id.clone());
@@ -1616,6 +1758,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copy_records: vec![],
         };
 
         // No parents
@@ -1663,6 +1806,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_shallow_git_commit() {
+        let settings = user_settings();
+        let temp_dir = testutils::new_temp_dir();
+        let store_path = temp_dir.path();
+        let git_repo_path = temp_dir.path().join("git");
+        let git_repo = git2::Repository::init(git_repo_path).unwrap();
+
+        let backend = GitBackend::init_external(&settings, store_path, git_repo.path()).unwrap();
+        let mut commit = Commit {
+            parents: vec![backend.root_commit_id().clone()],
+            predecessors: vec![],
+            root_tree: MergedTreeId::Legacy(backend.empty_tree_id().clone()),
+            change_id: ChangeId::from_hex("abc123"),
+            description: "".to_string(),
+            author: create_signature(),
+            committer: create_signature(),
+            secure_sig: None,
+            copy_records: vec![],
+        };
+        let first_id = backend.write_commit(commit.clone(), None).unwrap().0;
+        commit.parents = vec![first_id.clone()];
+        let second_id = backend.write_commit(commit.clone(), None).unwrap().0;
+
+        // The parent of the commit at the shallow boundary is hidden
+        fs::write(
+            git_repo.path().join("shallow"),
+            format!("{}\n", second_id.hex()),
+        )
+        .unwrap();
+        let second_commit = backend.read_commit(&second_id).block_on().unwrap();
+        assert_eq!(
+            second_commit.parents,
+            vec![backend.root_commit_id().clone()]
+        );
+        let second_git_commit = git_repo.find_commit(git_id(&second_id)).unwrap();
+        assert_eq!(
+            second_git_commit.parent_ids().collect_vec(),
+            vec![git_id(&first_id)]
+        );
+    }
+
     #[test]
     fn write_tree_conflicts() {
         let settings = user_settings();
@@ -1694,6 +1879,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copy_records: vec![],
         };
 
         // When writing a tree-level conflict, the root tree on the git side has the
@@ -1777,6 +1963,7 @@ mod tests {
             author: signature.clone(),
             committer: signature,
             secure_sig: None,
+            copy_records: vec![],
         };
         let commit_id = backend.write_commit(commit, None).unwrap().0;
         let git_refs: Vec<_> = git_repo
@@ -1854,6 +2041,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copy_records: vec![],
         };
         // libgit2 doesn't seem to preserve negative timestamps, so set it to at least 1
         // second after the epoch, so the timestamp adjustment can remove 1
@@ -1881,6 +2069,43 @@ mod tests {
         assert_eq!(actual_commit2, commit2);
     }
 
+    #[test]
+    fn write_commit_with_copy_records() {
+        let settings = user_settings();
+        let temp_dir = testutils::new_temp_dir();
+        let backend = GitBackend::init_internal(&settings, temp_dir.path()).unwrap();
+        let copy_records = vec![CopyRecord {
+            target: RepoPathBuf::from_internal_string("dir/new"),
+            source: RepoPathBuf::from_internal_string("old"),
+        }];
+        let commit = Commit {
+            parents: vec![backend.root_commit_id().clone()],
+            predecessors: vec![],
+            root_tree: MergedTreeId::Legacy(backend.empty_tree_id().clone()),
+            change_id: ChangeId::from_hex("abc123"),
+            description: "".to_string(),
+            author: create_signature(),
+            committer: create_signature(),
+            secure_sig: None,
+            copy_records: copy_records.clone(),
+        };
+        let (commit_id, written_commit) = backend.write_commit(commit.clone(), None).unwrap();
+        assert_eq!(written_commit.copy_records, copy_records);
+        // The records are stored in the extras table
+        let read_commit = backend.read_commit(&commit_id).block_on().unwrap();
+        assert_eq!(read_commit.copy_records, copy_records);
+
+        // The same Git commit with different records gets a different id
+        let commit2 = Commit {
+            copy_records: vec![],
+            ..commit
+        };
+        let (commit_id2, _) = backend.write_commit(commit2, None).unwrap();
+        assert_ne!(commit_id2, commit_id);
+        let read_commit2 = backend.read_commit(&commit_id2).block_on().unwrap();
+        assert_eq!(read_commit2.copy_records, vec![]);
+    }
+
     #[test]
     fn write_signed_commit() {
         let settings = user_settings();
@@ -1896,6 +2121,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copy_records: vec![],
         };
 
         let mut signer = |data: &_| {
@@ -0,0 +1,1248 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Git LFS support: pointer files, the local object store in
+//! `<git dir>/lfs/objects`, and object transfers over the LFS batch API.
+
+#![allow(missing_docs)]
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tempfile::NamedTempFile;
+use thiserror::Error;
+
+use crate::file_util::persist_content_addressed_temp_file;
+use crate::git_backend::GitBackend;
+use crate::gitattributes::GitAttributesFile;
+use crate::store::Store;
+use crate::str_util::StringPattern;
+
+const POINTER_VERSION: &str = "https://git-lfs.github.com/spec/v1";
+/// Maximum number of objects to ask about in a single batch request.
+const BATCH_SIZE: usize = 100;
+const LFS_MEDIA_TYPE: &str = "application/vnd.git-lfs+json";
+/// File in the LFS directory recording the remote heads whose objects have been
+/// downloaded. `git lfs` doesn't use it.
+const FETCHED_HEADS_FILE_NAME: &str = "jj-fetched-heads";
+
+/// Returns whether the file at `path` should be stored in LFS according to its
+/// `filter` attribute.
+pub fn is_lfs_path(attributes: &GitAttributesFile, path: &str) -> bool {
+    attributes.get_value(path, "filter") == Some("lfs")
+}
+
+/// Contents of an LFS pointer file, which is stored in the Git repository in
+/// place of the actual file contents.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LfsPointer {
+    /// Hex-encoded SHA-256 hash of the file contents.
+    pub oid: String,
+    /// Size of the file contents in bytes.
+    pub size: u64,
+}
+
+impl LfsPointer {
+    /// Pointer files are never larger than this.
+    pub const MAX_SIZE: usize = 1024;
+
+    /// Parses the contents of a pointer file. Returns `None` if `data` isn't a
+    /// valid pointer.
+    pub fn parse(data: &[u8]) -> Option<LfsPointer> {
+        if data.len() > LfsPointer::MAX_SIZE {
+            return None;
+        }
+        let text = std::str::from_utf8(data).ok()?;
+        let mut lines = text.strip_suffix('\n')?.split('\n');
+        if lines.next()? != format!("version {POINTER_VERSION}") {
+            return None;
+        }
+        let mut oid = None;
+        let mut size = None;
+        for line in lines {
+            let (key, value) = line.split_once(' ')?;
+            match key {
+                "oid" => {
+                    let hex = value.strip_prefix("sha256:")?;
+                    if hex.len() != 64
+                        || !hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+                    {
+                        return None;
+                    }
+                    oid = Some(hex.to_owned());
+                }
+                "size" => size = Some(value.parse().ok()?),
+                // Extension keys are allowed but not supported
+                _ => {}
+            }
+        }
+        Some(LfsPointer {
+            oid: oid?,
+            size: size?,
+        })
+    }
+
+    /// Serializes the pointer into the canonical pointer file format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "version {POINTER_VERSION}\noid sha256:{}\nsize {}\n",
+            self.oid, self.size
+        )
+        .into_bytes()
+    }
+}
+
+/// The local LFS object store, which uses the same layout as `git lfs` so that
+/// objects can be shared with it in colocated repos.
+#[derive(Clone, Debug)]
+pub struct LfsObjectStore {
+    root: PathBuf,
+}
+
+impl LfsObjectStore {
+    /// Creates a store for the repository at `git_dir`. The directory is
+    /// created lazily.
+    pub fn new(git_dir: &Path) -> Self {
+        LfsObjectStore {
+            root: git_dir.join("lfs"),
+        }
+    }
+
+    /// Returns the LFS store of the underlying Git repository, or `None` if the
+    /// repository isn't backed by Git.
+    pub fn for_store(store: &Store) -> Option<Self> {
+        let git_backend = store.backend_impl().downcast_ref::<GitBackend>()?;
+        Some(LfsObjectStore::new(git_backend.git_repo_path()))
+    }
+
+    fn object_path(&self, oid: &str) -> PathBuf {
+        self.root
+            .join("objects")
+            .join(&oid[0..2])
+            .join(&oid[2..4])
+            .join(oid)
+    }
+
+    /// Returns whether the object referenced by `pointer` is available locally.
+    pub fn contains(&self, pointer: &LfsPointer) -> bool {
+        fs::metadata(self.object_path(&pointer.oid))
+            .map_or(false, |metadata| metadata.len() == pointer.size)
+    }
+
+    /// Opens the object referenced by `pointer`, or returns `None` if it's not
+    /// available locally.
+    pub fn open(&self, pointer: &LfsPointer) -> io::Result<Option<File>> {
+        if !self.contains(pointer) {
+            return Ok(None);
+        }
+        File::open(self.object_path(&pointer.oid)).map(Some)
+    }
+
+    fn fetched_heads_path(&self) -> PathBuf {
+        self.root.join(FETCHED_HEADS_FILE_NAME)
+    }
+
+    /// Reads the remote-tracking refs whose objects have been downloaded.
+    fn read_fetched_heads(&self) -> io::Result<HashMap<String, git2::Oid>> {
+        let content = match fs::read_to_string(self.fetched_heads_path()) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(err) => return Err(err),
+        };
+        // Same format as packed-refs: "<id> <ref name>" on each line
+        let heads = content
+            .lines()
+            .filter_map(|line| {
+                let (id, name) = line.split_once(' ')?;
+                Some((name.to_owned(), git2::Oid::from_str(id).ok()?))
+            })
+            .collect();
+        Ok(heads)
+    }
+
+    fn write_fetched_heads(&self, heads: &HashMap<String, git2::Oid>) -> io::Result<()> {
+        let mut temp_file = self.new_temp_file()?;
+        for (name, id) in heads.iter().sorted() {
+            writeln!(temp_file, "{id} {name}")?;
+        }
+        temp_file
+            .persist(self.fetched_heads_path())
+            .map_err(|err| err.error)?;
+        Ok(())
+    }
+
+    fn new_temp_file(&self) -> io::Result<NamedTempFile> {
+        let tmp_dir = self.root.join("tmp");
+        fs::create_dir_all(&tmp_dir)?;
+        NamedTempFile::new_in(tmp_dir)
+    }
+
+    /// Adds `contents` to the store and returns the pointer referencing it.
+    /// This is the equivalent of the LFS "clean" filter.
+    pub fn insert(&self, contents: &mut dyn Read) -> io::Result<LfsPointer> {
+        let mut temp_file = self.new_temp_file()?;
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+        let mut buf = vec![0; 65536];
+        loop {
+            let bytes_read = contents.read(&mut buf)?;
+            if bytes_read == 0 {
+                break;
+            }
+            hasher.update(&buf[..bytes_read]);
+            temp_file.write_all(&buf[..bytes_read])?;
+            size += bytes_read as u64;
+        }
+        let pointer = LfsPointer {
+            oid: hex::encode(hasher.finalize()),
+            size,
+        };
+        if !self.contains(&pointer) {
+            let object_path = self.object_path(&pointer.oid);
+            fs::create_dir_all(object_path.parent().unwrap())?;
+            persist_content_addressed_temp_file(temp_file, object_path)?;
+        }
+        Ok(pointer)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LfsError {
+    #[error("Git LFS is not supported for remote URL {0}")]
+    UnsupportedUrl(String),
+    #[error(transparent)]
+    InternalGitError(#[from] git2::Error),
+    #[error("Failed to run {program}, which is required for Git LFS transfers")]
+    Command {
+        program: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to get credentials for {url}: {message}")]
+    Credential { url: String, message: String },
+    #[error("Failed to authenticate to the Git LFS server over SSH: {0}")]
+    SshAuthentication(String),
+    #[error("Git LFS request to {url} failed: {message}")]
+    Request { url: String, message: String },
+    #[error("Invalid response from the Git LFS server")]
+    InvalidResponse(#[source] serde_json::Error),
+    #[error("Failed to transfer Git LFS object {oid}: {message}")]
+    Object { oid: String, message: String },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// HTTP request to a Git LFS server.
+pub struct LfsRequest<'a> {
+    /// The `http` or `https` URL to send the request to.
+    pub url: &'a str,
+    /// Headers in the `Name: value` form.
+    pub headers: Vec<&'a str>,
+    /// Credentials for basic authentication in the `username:password` form.
+    pub user: Option<&'a str>,
+    pub body: LfsRequestBody<'a>,
+    /// File to write the response body to. If not set, the body is returned
+    /// in the response.
+    pub output: Option<&'a Path>,
+}
+
+/// Body of an [`LfsRequest`], which also determines the HTTP method.
+pub enum LfsRequestBody<'a> {
+    /// `GET` request without a body.
+    None,
+    /// `POST` request with the given data.
+    Data(&'a [u8]),
+    /// `PUT` request uploading the contents of the file.
+    File(&'a Path),
+}
+
+/// Response to an [`LfsRequest`].
+pub struct LfsResponse {
+    /// HTTP status code.
+    pub status: u32,
+    /// Response body, which is empty if it was written to the output file.
+    pub body: Vec<u8>,
+}
+
+/// Sends requests to Git LFS servers and looks up their credentials.
+///
+/// The library doesn't implement a transport itself, so the application
+/// decides how HTTP requests are made and which external programs are run.
+pub trait LfsTransport {
+    /// Sends the request. HTTP error statuses are returned in the response,
+    /// and only transport failures are reported as errors. Redirects must be
+    /// followed only to `http` and `https` URLs.
+    fn send(&self, request: &LfsRequest) -> Result<LfsResponse, LfsError>;
+
+    /// Runs `command` on the SSH server at `destination` (`[user@]host`), and
+    /// returns its standard output.
+    fn run_ssh(
+        &self,
+        destination: &str,
+        port: Option<&str>,
+        command: &str,
+    ) -> Result<Vec<u8>, LfsError>;
+
+    /// Runs `git credential <action>` for the Git repo at `git_dir` with the
+    /// given input, and returns its output. `url` is used in error messages.
+    fn run_git_credential(
+        &self,
+        git_dir: &Path,
+        url: &str,
+        action: &str,
+        input: &str,
+    ) -> Result<String, LfsError>;
+}
+
+#[derive(Serialize)]
+struct BatchRequest<'a> {
+    operation: &'a str,
+    transfers: [&'a str; 1],
+    objects: Vec<BatchObjectSpec<'a>>,
+    hash_algo: &'a str,
+}
+
+#[derive(Serialize)]
+struct BatchObjectSpec<'a> {
+    oid: &'a str,
+    size: u64,
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    #[serde(default)]
+    objects: Vec<BatchObject>,
+}
+
+#[derive(Deserialize)]
+struct BatchObject {
+    oid: String,
+    #[serde(default)]
+    actions: HashMap<String, BatchAction>,
+    error: Option<BatchObjectError>,
+}
+
+#[derive(Deserialize)]
+struct BatchAction {
+    href: String,
+    #[serde(default)]
+    header: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct BatchObjectError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+/// Response of `git-lfs-authenticate`.
+#[derive(Deserialize)]
+struct SshAuthResponse {
+    href: String,
+    #[serde(default)]
+    header: HashMap<String, String>,
+}
+
+/// The batch API endpoint of an LFS server, and how to authenticate to it.
+struct LfsEndpoint {
+    url: String,
+    /// Headers provided by `git-lfs-authenticate` for SSH remotes.
+    headers: Vec<String>,
+    /// Output of `git credential fill`, which is run if the server requires
+    /// authentication.
+    credential: Option<String>,
+}
+
+impl LfsEndpoint {
+    fn new(url: String) -> Self {
+        LfsEndpoint {
+            url,
+            headers: vec![],
+            credential: None,
+        }
+    }
+}
+
+/// Returns the LFS endpoint for the remote, as configured by
+/// `remote.<name>.lfsurl` or `lfs.url`, or derived from the remote's URL.
+///
+/// For SSH remotes, the endpoint and the credentials are requested from the
+/// server by `git-lfs-authenticate`. If that fails, the HTTPS endpoint of the
+/// same host is used as `git lfs` does.
+fn lfs_endpoint(
+    git_repo: &git2::Repository,
+    remote_name: &str,
+    operation: &str,
+    transport: &dyn LfsTransport,
+) -> Result<LfsEndpoint, LfsError> {
+    let config = git_repo.config()?;
+    for key in [format!("remote.{remote_name}.lfsurl"), "lfs.url".to_owned()] {
+        if let Ok(url) = config.get_string(&key) {
+            return Ok(LfsEndpoint::new(url));
+        }
+    }
+    let remote = git_repo.find_remote(remote_name)?;
+    let url = remote.url().unwrap_or_default();
+    if let Some(ssh_url) = SshUrl::parse(url) {
+        match authenticate_ssh(&ssh_url, operation, transport) {
+            Ok(endpoint) => return Ok(endpoint),
+            Err(err) => tracing::warn!(?err, "failed to authenticate to Git LFS server"),
+        }
+    }
+    let lfs_url = derive_lfs_url(url).ok_or_else(|| LfsError::UnsupportedUrl(url.to_owned()))?;
+    Ok(LfsEndpoint::new(lfs_url))
+}
+
+/// SSH remote URL in the `ssh://[user@]host[:port]/path` or `[user@]host:path`
+/// form.
+struct SshUrl<'a> {
+    user_host: &'a str,
+    port: Option<&'a str>,
+    path: &'a str,
+}
+
+impl<'a> SshUrl<'a> {
+    fn parse(url: &'a str) -> Option<Self> {
+        if let Some(rest) = url.strip_prefix("ssh://") {
+            let (authority, path) = rest.split_once('/')?;
+            let (user_host, port) = match authority.rsplit_once(':') {
+                Some((user_host, port)) => (user_host, Some(port)),
+                None => (authority, None),
+            };
+            if port.map_or(false, |port| !port.bytes().all(|b| b.is_ascii_digit())) {
+                return None;
+            }
+            SshUrl {
+                user_host,
+                port,
+                path,
+            }
+            .validate()
+        } else if url.contains("://") {
+            None
+        } else {
+            let (user_host, path) = url.split_once(':')?;
+            // A single letter is a drive letter of a Windows path
+            if user_host.contains('/') || path.starts_with("//") || user_host.len() < 2 {
+                return None;
+            }
+            SshUrl {
+                user_host,
+                port: None,
+                path,
+            }
+            .validate()
+        }
+    }
+
+    /// Rejects hosts which could be interpreted as ssh options.
+    fn validate(self) -> Option<Self> {
+        let valid = !self.user_host.is_empty()
+            && !self.user_host.starts_with('-')
+            && !self.host().starts_with('-');
+        valid.then_some(self)
+    }
+
+    fn host(&self) -> &'a str {
+        self.user_host
+            .rsplit_once('@')
+            .map_or(self.user_host, |(_, host)| host)
+    }
+}
+
+/// Asks the server for the LFS endpoint and the headers to authenticate with by
+/// running `git-lfs-authenticate` over SSH.
+fn authenticate_ssh(
+    ssh_url: &SshUrl,
+    operation: &str,
+    transport: &dyn LfsTransport,
+) -> Result<LfsEndpoint, LfsError> {
+    // The remote command is interpreted by the shell on the server
+    let quoted_path = format!("'{}'", ssh_url.path.replace('\'', r"'\''"));
+    let command = format!("git-lfs-authenticate {quoted_path} {operation}");
+    tracing::info!(?command, "running git-lfs-authenticate");
+    let output = transport.run_ssh(ssh_url.user_host, ssh_url.port, &command)?;
+    let response: SshAuthResponse =
+        serde_json::from_slice(&output).map_err(LfsError::InvalidResponse)?;
+    Ok(LfsEndpoint {
+        url: response.href,
+        headers: format_headers(&response.header),
+        credential: None,
+    })
+}
+
+fn derive_lfs_url(url: &str) -> Option<String> {
+    let base = if is_http_url(url) {
+        url.to_owned()
+    } else {
+        let ssh_url = SshUrl::parse(url)?;
+        format!("https://{}/{}", ssh_url.host(), ssh_url.path)
+    };
+    let base = base.trim_end_matches('/');
+    if base.ends_with(".git") {
+        Some(format!("{base}/info/lfs"))
+    } else {
+        Some(format!("{base}.git/info/lfs"))
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// Sends the request, and returns an error if the server responded with an
+/// error status.
+fn send_checked(
+    transport: &dyn LfsTransport,
+    request: &LfsRequest,
+) -> Result<LfsResponse, LfsError> {
+    let response = transport.send(request)?;
+    check_status(request.url, &response)?;
+    Ok(response)
+}
+
+fn check_status(url: &str, response: &LfsResponse) -> Result<(), LfsError> {
+    if (200..300).contains(&response.status) {
+        return Ok(());
+    }
+    // LFS servers describe errors in JSON
+    let message = match serde_json::from_slice::<ErrorResponse>(&response.body) {
+        Ok(error) => format!("{} (HTTP status {})", error.message, response.status),
+        Err(_) => format!("HTTP status {}", response.status),
+    };
+    Err(LfsError::Request {
+        url: url.to_owned(),
+        message,
+    })
+}
+
+/// Extracts `username:password` from the output of `git credential fill`.
+fn credential_user(credential: &str) -> String {
+    let get = |key: &str| {
+        credential
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .unwrap_or_default()
+    };
+    format!("{}:{}", get("username"), get("password"))
+}
+
+/// Sends a request to the batch API. If the server requires authentication,
+/// the credentials are looked up by `git credential`, and stored in the
+/// `endpoint` for the subsequent requests.
+fn send_batch_request(
+    git_dir: &Path,
+    transport: &dyn LfsTransport,
+    endpoint: &mut LfsEndpoint,
+    operation: &str,
+    pointers: &[&LfsPointer],
+) -> Result<BatchResponse, LfsError> {
+    let request = BatchRequest {
+        operation,
+        transfers: ["basic"],
+        objects: pointers
+            .iter()
+            .map(|pointer| BatchObjectSpec {
+                oid: &pointer.oid,
+                size: pointer.size,
+            })
+            .collect(),
+        hash_algo: "sha256",
+    };
+    let body = serde_json::to_string(&request).unwrap();
+    let url = format!("{}/objects/batch", endpoint.url.trim_end_matches('/'));
+    if !is_http_url(&url) {
+        return Err(LfsError::UnsupportedUrl(endpoint.url.clone()));
+    }
+    let accept = format!("Accept: {LFS_MEDIA_TYPE}");
+    let content_type = format!("Content-Type: {LFS_MEDIA_TYPE}");
+    let send = |endpoint: &LfsEndpoint| {
+        let user = endpoint.credential.as_deref().map(credential_user);
+        let mut headers = vec![accept.as_str(), content_type.as_str()];
+        headers.extend(endpoint.headers.iter().map(String::as_str));
+        transport.send(&LfsRequest {
+            url: &url,
+            headers,
+            user: user.as_deref(),
+            body: LfsRequestBody::Data(body.as_bytes()),
+            output: None,
+        })
+    };
+    let mut response = send(endpoint)?;
+    if response.status == 401 && endpoint.credential.is_none() {
+        let credential =
+            transport.run_git_credential(git_dir, &url, "fill", &format!("url={url}\n"))?;
+        endpoint.credential = Some(credential);
+        response = send(endpoint)?;
+        let action = if response.status == 401 {
+            "reject"
+        } else {
+            "approve"
+        };
+        transport.run_git_credential(
+            git_dir,
+            &url,
+            action,
+            endpoint.credential.as_ref().unwrap(),
+        )?;
+    }
+    check_status(&url, &response)?;
+    serde_json::from_slice(&response.body).map_err(LfsError::InvalidResponse)
+}
+
+fn format_headers(headers: &HashMap<String, String>) -> Vec<String> {
+    headers
+        .iter()
+        .sorted()
+        .map(|(name, value)| format!("{name}: {value}"))
+        .collect()
+}
+
+fn check_object_error(object: &BatchObject) -> Result<(), LfsError> {
+    if let Some(error) = &object.error {
+        return Err(LfsError::Object {
+            oid: object.oid.clone(),
+            message: format!("{} (error code {})", error.message, error.code),
+        });
+    }
+    Ok(())
+}
+
+/// Returns an error if the server asks us to transfer the object to a URL that
+/// isn't HTTP or HTTPS.
+fn check_action_url(object: &BatchObject, action: &BatchAction) -> Result<(), LfsError> {
+    if !is_http_url(&action.href) {
+        return Err(LfsError::Object {
+            oid: object.oid.clone(),
+            message: format!("Unsupported transfer URL {}", action.href),
+        });
+    }
+    Ok(())
+}
+
+/// Searches the trees of commits for LFS pointers. Only blobs at paths with the
+/// `filter=lfs` attribute are considered.
+struct PointerFinder<'repo> {
+    git_repo: &'repo git2::Repository,
+    odb: git2::Odb<'repo>,
+    visited: HashSet<(git2::Oid, String, Vec<git2::Oid>)>,
+    pointers: BTreeSet<LfsPointer>,
+}
+
+impl<'repo> PointerFinder<'repo> {
+    fn new(git_repo: &'repo git2::Repository) -> Result<Self, git2::Error> {
+        Ok(PointerFinder {
+            git_repo,
+            odb: git_repo.odb()?,
+            visited: HashSet::new(),
+            pointers: BTreeSet::new(),
+        })
+    }
+
+    /// Searches the tree of the commit. Paths that are unchanged from the
+    /// `base` commit are skipped, since they have been searched already.
+    fn add_commit(
+        &mut self,
+        commit_id: git2::Oid,
+        base_id: Option<git2::Oid>,
+    ) -> Result<(), git2::Error> {
+        let tree = self.git_repo.find_commit(commit_id)?.tree()?;
+        // The base commit may have been garbage-collected
+        let base_tree = base_id.and_then(|id| self.git_repo.find_commit(id).ok()?.tree().ok());
+        self.add_tree(
+            &tree,
+            base_tree.as_ref(),
+            "",
+            &GitAttributesFile::empty(),
+            &[],
+        )
+    }
+
+    fn add_tree(
+        &mut self,
+        tree: &git2::Tree,
+        base_tree: Option<&git2::Tree>,
+        prefix: &str,
+        attributes: &Arc<GitAttributesFile>,
+        attributes_ids: &[git2::Oid],
+    ) -> Result<(), git2::Error> {
+        // The same subtree can only contain different pointers if it's
+        // inherited different attributes
+        if !self
+            .visited
+            .insert((tree.id(), prefix.to_owned(), attributes_ids.to_vec()))
+        {
+            return Ok(());
+        }
+        let attributes_entry = tree.get_name(".gitattributes");
+        // Unchanged paths can only be skipped if the attributes are the same
+        let attributes_entry_id = attributes_entry.as_ref().map(|entry| entry.id());
+        let base_tree = base_tree.filter(|base_tree| {
+            base_tree.get_name(".gitattributes").map(|entry| entry.id()) == attributes_entry_id
+        });
+        let mut attributes = attributes.clone();
+        let mut attributes_ids = attributes_ids.to_vec();
+        if let Some(entry) = attributes_entry {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                let blob = self.git_repo.find_blob(entry.id())?;
+                attributes = attributes.chain(prefix, blob.content());
+                attributes_ids.push(entry.id());
+            }
+        }
+        for entry in tree.iter() {
+            let Some(name) = entry.name() else {
+                continue;
+            };
+            let base_entry = base_tree.and_then(|base_tree| base_tree.get_name(name));
+            if base_entry.as_ref().map_or(false, |base_entry| {
+                base_entry.id() == entry.id() && base_entry.filemode() == entry.filemode()
+            }) {
+                continue;
+            }
+            let path = format!("{prefix}{name}");
+            match entry.kind() {
+                Some(git2::ObjectType::Tree) => {
+                    let subtree = self.git_repo.find_tree(entry.id())?;
+                    let base_subtree = base_entry
+                        .filter(|base_entry| base_entry.kind() == Some(git2::ObjectType::Tree))
+                        .map(|base_entry| self.git_repo.find_tree(base_entry.id()))
+                        .transpose()?;
+                    self.add_tree(
+                        &subtree,
+                        base_subtree.as_ref(),
+                        &format!("{path}/"),
+                        &attributes,
+                        &attributes_ids,
+                    )?;
+                }
+                Some(git2::ObjectType::Blob) => {
+                    if entry.filemode() == i32::from(git2::FileMode::Link)
+                        || !is_lfs_path(&attributes, &path)
+                    {
+                        continue;
+                    }
+                    let (size, _) = self.odb.read_header(entry.id())?;
+                    if size > LfsPointer::MAX_SIZE {
+                        continue;
+                    }
+                    let blob = self.git_repo.find_blob(entry.id())?;
+                    if let Some(pointer) = LfsPointer::parse(blob.content()) {
+                        self.pointers.insert(pointer);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Downloads the LFS objects referenced by the heads of the remote-tracking
+/// branches of `remote_name` matching `branch_names` that are not yet
+/// available locally. Returns the number of downloaded objects.
+///
+/// The heads are recorded once their objects have been downloaded, and only
+/// the branches updated since then are searched by the next fetch, and only
+/// for the paths changed in them. Objects which failed to download are looked
+/// for again by the next fetch.
+pub fn fetch_objects(
+    git_repo: &git2::Repository,
+    remote_name: &str,
+    branch_names: &[StringPattern],
+    transport: &dyn LfsTransport,
+) -> Result<usize, LfsError> {
+    let lfs_store = LfsObjectStore::new(git_repo.path());
+    let ref_prefix = format!("refs/remotes/{remote_name}/");
+    let is_fetched_ref = |name: &str| {
+        name.strip_prefix(&ref_prefix).map_or(false, |branch| {
+            branch_names.iter().any(|pattern| pattern.matches(branch))
+        })
+    };
+    let mut heads = HashMap::new();
+    for reference in git_repo.references_glob(&format!("{ref_prefix}*"))? {
+        let reference = reference?;
+        if reference.kind() == Some(git2::ReferenceType::Symbolic) {
+            continue;
+        }
+        let Some(name) = reference.name().filter(|name| is_fetched_ref(name)) else {
+            continue;
+        };
+        if let Ok(commit) = reference.peel_to_commit() {
+            heads.insert(name.to_owned(), commit.id());
+        }
+    }
+    // Only the changes since the previous successful fetch are searched
+    let mut fetched_heads = lfs_store.read_fetched_heads()?;
+    let mut finder = PointerFinder::new(git_repo)?;
+    for (name, id) in &heads {
+        let base_id = fetched_heads.get(name).copied();
+        if base_id != Some(*id) {
+            finder.add_commit(*id, base_id)?;
+        }
+    }
+    let missing = finder
+        .pointers
+        .into_iter()
+        .filter(|pointer| !lfs_store.contains(pointer))
+        .collect_vec();
+    if !missing.is_empty() {
+        download_objects(git_repo, remote_name, transport, &lfs_store, &missing)?;
+    }
+    fetched_heads.retain(|name, _| !is_fetched_ref(name));
+    fetched_heads.extend(heads);
+    lfs_store.write_fetched_heads(&fetched_heads)?;
+    Ok(missing.len())
+}
+
+fn download_objects(
+    git_repo: &git2::Repository,
+    remote_name: &str,
+    transport: &dyn LfsTransport,
+    lfs_store: &LfsObjectStore,
+    missing: &[LfsPointer],
+) -> Result<(), LfsError> {
+    let mut endpoint = lfs_endpoint(git_repo, remote_name, "download", transport)?;
+    for chunk in &missing.iter().chunks(BATCH_SIZE) {
+        let chunk = chunk.collect_vec();
+        let response = send_batch_request(
+            git_repo.path(),
+            transport,
+            &mut endpoint,
+            "download",
+            &chunk,
+        )?;
+        for object in &response.objects {
+            check_object_error(object)?;
+            let pointer = chunk
+                .iter()
+                .find(|pointer| pointer.oid == object.oid)
+                .ok_or_else(|| LfsError::Object {
+                    oid: object.oid.clone(),
+                    message: "The server returned an unexpected object".to_owned(),
+                })?;
+            let action = object
+                .actions
+                .get("download")
+                .ok_or_else(|| LfsError::Object {
+                    oid: object.oid.clone(),
+                    message: "The server didn't provide a download URL".to_owned(),
+                })?;
+            check_action_url(object, action)?;
+            let temp_file = lfs_store.new_temp_file()?;
+            let headers = format_headers(&action.header);
+            send_checked(
+                transport,
+                &LfsRequest {
+                    url: &action.href,
+                    headers: headers.iter().map(String::as_str).collect(),
+                    user: None,
+                    body: LfsRequestBody::None,
+                    output: Some(temp_file.path()),
+                },
+            )?;
+            let inserted = lfs_store.insert(&mut temp_file.reopen()?)?;
+            if inserted != **pointer {
+                return Err(LfsError::Object {
+                    oid: object.oid.clone(),
+                    message: "The downloaded contents don't match the pointer".to_owned(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Uploads the LFS objects referenced by the given commits and their ancestors
+/// that are not reachable from the remote-tracking branches of `remote_name`.
+/// Only objects that the server doesn't have yet are uploaded. Returns the
+/// number of uploaded objects.
+pub fn push_objects(
+    git_repo: &git2::Repository,
+    remote_name: &str,
+    new_heads: &[git2::Oid],
+    transport: &dyn LfsTransport,
+) -> Result<usize, LfsError> {
+    let lfs_store = LfsObjectStore::new(git_repo.path());
+    let mut revwalk = git_repo.revwalk()?;
+    for head in new_heads {
+        revwalk.push(*head)?;
+    }
+    revwalk.hide_glob(&format!("refs/remotes/{remote_name}/*"))?;
+    let mut finder = PointerFinder::new(git_repo)?;
+    for commit_id in revwalk {
+        finder.add_commit(commit_id?, None)?;
+    }
+    let pointers = finder.pointers;
+    if pointers.is_empty() {
+        return Ok(0);
+    }
+    let mut endpoint = lfs_endpoint(git_repo, remote_name, "upload", transport)?;
+    let mut num_uploaded = 0;
+    for chunk in &pointers.iter().chunks(BATCH_SIZE) {
+        let chunk = chunk.collect_vec();
+        let response =
+            send_batch_request(git_repo.path(), transport, &mut endpoint, "upload", &chunk)?;
+        for object in &response.objects {
+            check_object_error(object)?;
+            // Objects without an upload action are already on the server
+            let Some(action) = object.actions.get("upload") else {
+                continue;
+            };
+            check_action_url(object, action)?;
+            let object_path = lfs_store.object_path(&object.oid);
+            let pointer = chunk.iter().find(|pointer| pointer.oid == object.oid);
+            if !pointer.map_or(false, |pointer| lfs_store.contains(pointer)) {
+                return Err(LfsError::Object {
+                    oid: object.oid.clone(),
+                    message: "The object is not available locally".to_owned(),
+                });
+            }
+            let headers = format_headers(&action.header);
+            let mut request_headers = vec!["Content-Type: application/octet-stream"];
+            request_headers.extend(headers.iter().map(String::as_str));
+            send_checked(
+                transport,
+                &LfsRequest {
+                    url: &action.href,
+                    headers: request_headers,
+                    user: None,
+                    body: LfsRequestBody::File(&object_path),
+                    output: None,
+                },
+            )?;
+            if let Some(action) = object.actions.get("verify") {
+                check_action_url(object, action)?;
+                let pointer = pointer.unwrap();
+                let body = serde_json::to_string(&BatchObjectSpec {
+                    oid: &pointer.oid,
+                    size: pointer.size,
+                })
+                .unwrap();
+                let content_type = format!("Content-Type: {LFS_MEDIA_TYPE}");
+                let headers = format_headers(&action.header);
+                let mut request_headers = vec![content_type.as_str()];
+                request_headers.extend(headers.iter().map(String::as_str));
+                send_checked(
+                    transport,
+                    &LfsRequest {
+                        url: &action.href,
+                        headers: request_headers,
+                        user: None,
+                        body: LfsRequestBody::Data(body.as_bytes()),
+                        output: None,
+                    },
+                )?;
+            }
+            num_uploaded += 1;
+        }
+    }
+    Ok(num_uploaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pointer_round_trip() {
+        let pointer = LfsPointer {
+            oid: "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393".to_owned(),
+            size: 12345,
+        };
+        let bytes = pointer.to_bytes();
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap(),
+            "version https://git-lfs.github.com/spec/v1\noid \
+             sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\nsize \
+             12345\n"
+        );
+        assert_eq!(LfsPointer::parse(&bytes), Some(pointer));
+    }
+
+    #[test]
+    fn test_pointer_parse_invalid() {
+        let valid = "version https://git-lfs.github.com/spec/v1\noid \
+                     sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\nsize \
+                     12345\n";
+        assert!(LfsPointer::parse(valid.as_bytes()).is_some());
+        // Missing trailing newline
+        assert!(LfsPointer::parse(valid.trim_end().as_bytes()).is_none());
+        // Wrong version
+        assert!(LfsPointer::parse(valid.replace("v1", "v2").as_bytes()).is_none());
+        // Malformed oid and size
+        assert!(LfsPointer::parse(valid.replace("sha256:4d", "sha256:").as_bytes()).is_none());
+        assert!(LfsPointer::parse(valid.replace("sha256:", "md5:").as_bytes()).is_none());
+        assert!(LfsPointer::parse(valid.replace("12345", "-1").as_bytes()).is_none());
+        assert!(LfsPointer::parse(b"").is_none());
+        assert!(LfsPointer::parse(b"some file contents\n").is_none());
+    }
+
+    #[test]
+    fn test_object_store() {
+        let temp_dir = testutils::new_temp_dir();
+        let store = LfsObjectStore::new(temp_dir.path());
+        let pointer = store.insert(&mut b"hello\n".as_slice()).unwrap();
+        assert_eq!(
+            pointer,
+            LfsPointer {
+                oid: "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03".to_owned(),
+                size: 6,
+            }
+        );
+        assert!(store.contains(&pointer));
+        assert!(temp_dir
+            .path()
+            .join("lfs/objects/58/91")
+            .join(&pointer.oid)
+            .is_file());
+        let mut contents = vec![];
+        store
+            .open(&pointer)
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        assert_eq!(contents, b"hello\n");
+        // Inserting the same contents again is a no-op
+        assert_eq!(store.insert(&mut b"hello\n".as_slice()).unwrap(), pointer);
+
+        let missing = LfsPointer {
+            oid: "0".repeat(64),
+            size: 1,
+        };
+        assert!(!store.contains(&missing));
+        assert!(store.open(&missing).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_derive_lfs_url() {
+        assert_eq!(
+            derive_lfs_url("https://example.com/foo/bar.git").as_deref(),
+            Some("https://example.com/foo/bar.git/info/lfs")
+        );
+        assert_eq!(
+            derive_lfs_url("https://example.com/foo/bar/").as_deref(),
+            Some("https://example.com/foo/bar.git/info/lfs")
+        );
+        assert_eq!(
+            derive_lfs_url("git@example.com:foo/bar.git").as_deref(),
+            Some("https://example.com/foo/bar.git/info/lfs")
+        );
+        assert_eq!(
+            derive_lfs_url("ssh://git@example.com:22/foo/bar").as_deref(),
+            Some("https://example.com/foo/bar.git/info/lfs")
+        );
+        assert_eq!(derive_lfs_url("/path/to/repo"), None);
+        assert_eq!(derive_lfs_url("file:///path/to/repo"), None);
+        assert_eq!(derive_lfs_url("C:/path/to/repo"), None);
+    }
+
+    #[test]
+    fn test_ssh_url_parse() {
+        let parse = |url| SshUrl::parse(url).map(|url| (url.user_host, url.port, url.path));
+        assert_eq!(
+            parse("git@example.com:foo/bar.git"),
+            Some(("git@example.com", None, "foo/bar.git"))
+        );
+        assert_eq!(
+            parse("ssh://git@example.com:2222/foo/bar.git"),
+            Some(("git@example.com", Some("2222"), "foo/bar.git"))
+        );
+        assert_eq!(
+            parse("ssh://example.com/foo"),
+            Some(("example.com", None, "foo"))
+        );
+        assert_eq!(parse("https://example.com/foo"), None);
+        assert_eq!(parse("/path/to/repo"), None);
+        assert_eq!(parse("C:/path/to/repo"), None);
+        // Hosts and ports which could be interpreted as ssh options
+        assert_eq!(parse("-oProxyCommand=foo:bar"), None);
+        assert_eq!(parse("git@-oProxyCommand=foo:bar"), None);
+        assert_eq!(parse("ssh://-oProxyCommand=foo/bar"), None);
+        assert_eq!(parse("ssh://example.com:-oProxyCommand=foo/bar"), None);
+    }
+
+    /// Transport serving the objects from memory. The download URLs start with
+    /// `href_prefix`.
+    struct TestTransport {
+        objects: HashMap<String, Vec<u8>>,
+        href_prefix: String,
+        requests: std::cell::RefCell<Vec<String>>,
+    }
+
+    impl LfsTransport for TestTransport {
+        fn send(&self, request: &LfsRequest) -> Result<LfsResponse, LfsError> {
+            self.requests.borrow_mut().push(request.url.to_owned());
+            if request.url == "https://lfs.example.com/objects/batch" {
+                let LfsRequestBody::Data(data) = request.body else {
+                    panic!("batch request without data");
+                };
+                let batch: serde_json::Value = serde_json::from_slice(data).unwrap();
+                let objects = batch["objects"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|object| {
+                        let oid = object["oid"].as_str().unwrap();
+                        let href = format!("{}{oid}", self.href_prefix);
+                        serde_json::json!({"oid": oid, "actions": {"download": {"href": href}}})
+                    })
+                    .collect_vec();
+                let body = serde_json::json!({ "objects": objects }).to_string();
+                return Ok(LfsResponse {
+                    status: 200,
+                    body: body.into_bytes(),
+                });
+            }
+            let oid = request.url.strip_prefix(&self.href_prefix).unwrap();
+            fs::write(request.output.unwrap(), &self.objects[oid]).unwrap();
+            Ok(LfsResponse {
+                status: 200,
+                body: vec![],
+            })
+        }
+
+        fn run_ssh(&self, _: &str, _: Option<&str>, _: &str) -> Result<Vec<u8>, LfsError> {
+            panic!("unexpected ssh command");
+        }
+
+        fn run_git_credential(
+            &self,
+            _: &Path,
+            _: &str,
+            _: &str,
+            _: &str,
+        ) -> Result<String, LfsError> {
+            panic!("unexpected git credential command");
+        }
+    }
+
+    /// Creates a commit with an LFS pointer to `contents`, and points the
+    /// remote-tracking branch `origin/<branch>` to it.
+    fn write_remote_branch(
+        git_repo: &git2::Repository,
+        branch: &str,
+        contents: &[u8],
+    ) -> LfsPointer {
+        let pointer = LfsPointer {
+            oid: hex::encode(Sha256::digest(contents)),
+            size: contents.len() as u64,
+        };
+        let mut tree_builder = git_repo.treebuilder(None).unwrap();
+        let attributes_id = git_repo.blob(b"*.bin filter=lfs\n").unwrap();
+        tree_builder
+            .insert(".gitattributes", attributes_id, 0o100644)
+            .unwrap();
+        let pointer_id = git_repo.blob(&pointer.to_bytes()).unwrap();
+        tree_builder
+            .insert("file.bin", pointer_id, 0o100644)
+            .unwrap();
+        let tree = git_repo.find_tree(tree_builder.write().unwrap()).unwrap();
+        let signature = git2::Signature::now("Someone", "someone@example.com").unwrap();
+        git_repo
+            .commit(
+                Some(&format!("refs/remotes/origin/{branch}")),
+                &signature,
+                &signature,
+                branch,
+                &tree,
+                &[],
+            )
+            .unwrap();
+        pointer
+    }
+
+    #[test]
+    fn test_fetch_objects() {
+        let temp_dir = testutils::new_temp_dir();
+        let git_repo = git2::Repository::init_bare(temp_dir.path()).unwrap();
+        git_repo
+            .config()
+            .unwrap()
+            .set_str("lfs.url", "https://lfs.example.com")
+            .unwrap();
+        let main_pointer = write_remote_branch(&git_repo, "main", b"main contents\n");
+        let other_pointer = write_remote_branch(&git_repo, "other", b"other contents\n");
+        let transport = TestTransport {
+            objects: HashMap::from([
+                (main_pointer.oid.clone(), b"main contents\n".to_vec()),
+                (other_pointer.oid.clone(), b"other contents\n".to_vec()),
+            ]),
+            href_prefix: "https://lfs.example.com/objects/".to_owned(),
+            requests: Default::default(),
+        };
+        let lfs_store = LfsObjectStore::new(git_repo.path());
+
+        // Only the objects of the fetched branches are downloaded
+        let main_pattern = [StringPattern::exact("main")];
+        let count = fetch_objects(&git_repo, "origin", &main_pattern, &transport).unwrap();
+        assert_eq!(count, 1);
+        assert!(lfs_store.contains(&main_pointer));
+        assert!(!lfs_store.contains(&other_pointer));
+        assert_eq!(transport.requests.take().len(), 2);
+
+        // Branches which haven't moved aren't searched again
+        let all_pattern = [StringPattern::everything()];
+        let count = fetch_objects(&git_repo, "origin", &all_pattern, &transport).unwrap();
+        assert_eq!(count, 1);
+        assert!(lfs_store.contains(&other_pointer));
+        assert_eq!(transport.requests.take().len(), 2);
+        let count = fetch_objects(&git_repo, "origin", &all_pattern, &transport).unwrap();
+        assert_eq!(count, 0);
+        assert!(transport.requests.take().is_empty());
+    }
+
+    #[test]
+    fn test_fetch_objects_non_http_url() {
+        let temp_dir = testutils::new_temp_dir();
+        let git_repo = git2::Repository::init_bare(temp_dir.path()).unwrap();
+        git_repo
+            .config()
+            .unwrap()
+            .set_str("lfs.url", "https://lfs.example.com")
+            .unwrap();
+        let pointer = write_remote_branch(&git_repo, "main", b"contents\n");
+        let transport = TestTransport {
+            objects: HashMap::new(),
+            href_prefix: "file:///etc/".to_owned(),
+            requests: Default::default(),
+        };
+
+        // The object isn't downloaded from the URL returned by the server
+        let pattern = [StringPattern::everything()];
+        let err = fetch_objects(&git_repo, "origin", &pattern, &transport).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Failed to transfer Git LFS object {}: Unsupported transfer URL file:///etc/{}",
+                pointer.oid, pointer.oid
+            )
+        );
+        assert_eq!(
+            transport.requests.take(),
+            ["https://lfs.example.com/objects/batch"]
+        );
+    }
+}
@@ -0,0 +1,494 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(missing_docs)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use regex::Regex;
+
+use crate::backend::{BackendError, BackendResult, TreeValue};
+use crate::gitignore::pattern_to_regex;
+use crate::merged_tree::MergedTree;
+use crate::object_id::ObjectId;
+use crate::repo_path::{RepoPath, RepoPathBuf, RepoPathComponent};
+
+/// The state of an attribute on a path, as set by a .gitattributes line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GitAttributeValue {
+    /// The attribute was listed by itself (`attr`).
+    Set,
+    /// The attribute was listed with a leading "-" (`-attr`).
+    Unset,
+    /// The attribute was assigned a value (`attr=value`).
+    Value(String),
+}
+
+#[derive(Debug)]
+struct GitAttributesLine {
+    regex: Regex,
+    /// Attributes in the order they appear on the line. `None` means that the
+    /// attribute was reset to unspecified (`!attr`).
+    attributes: Vec<(String, Option<GitAttributeValue>)>,
+}
+
+impl GitAttributesLine {
+    fn parse(prefix: &str, input: &str) -> Option<GitAttributesLine> {
+        assert!(prefix.is_empty() || prefix.ends_with('/'));
+        let input = input.strip_suffix('\r').unwrap_or(input);
+        let mut fields = input.split_ascii_whitespace();
+        let pattern = fields.next()?;
+        // Negative patterns are forbidden, and patterns matching only
+        // directories never apply to files. Macro definitions aren't supported
+        // except for the built-in "binary" macro.
+        if pattern.starts_with('#')
+            || pattern.starts_with('!')
+            || pattern.ends_with('/')
+            || pattern.starts_with("[attr]")
+        {
+            return None;
+        }
+        let mut attributes = vec![];
+        for (name, value) in fields
+            .map(|field| {
+                if let Some(name) = field.strip_prefix('-') {
+                    (name.to_owned(), Some(GitAttributeValue::Unset))
+                } else if let Some(name) = field.strip_prefix('!') {
+                    (name.to_owned(), None)
+                } else if let Some((name, value)) = field.split_once('=') {
+                    (
+                        name.to_owned(),
+                        Some(GitAttributeValue::Value(value.to_owned())),
+                    )
+                } else {
+                    (field.to_owned(), Some(GitAttributeValue::Set))
+                }
+            })
+            .filter(|(name, _)| !name.is_empty())
+        {
+            let expand_binary = name == "binary" && value == Some(GitAttributeValue::Set);
+            attributes.push((name, value));
+            if expand_binary {
+                for name in ["diff", "merge", "text"] {
+                    attributes.push((name.to_owned(), Some(GitAttributeValue::Unset)));
+                }
+            }
+        }
+        let regex = pattern_to_regex(prefix, pattern, false);
+        Some(GitAttributesLine { regex, attributes })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+}
+
+/// Models the effective contents of multiple .gitattributes files.
+#[derive(Debug)]
+pub struct GitAttributesFile {
+    parent: Option<Arc<GitAttributesFile>>,
+    lines: Vec<GitAttributesLine>,
+}
+
+impl GitAttributesFile {
+    pub fn empty() -> Arc<GitAttributesFile> {
+        Arc::new(GitAttributesFile {
+            parent: None,
+            lines: vec![],
+        })
+    }
+
+    pub fn chain(
+        self: &Arc<GitAttributesFile>,
+        prefix: &str,
+        input: &[u8],
+    ) -> Arc<GitAttributesFile> {
+        let mut lines = vec![];
+        for input_line in input.split(|b| *b == b'\n') {
+            // Skip non-utf8 lines
+            if let Ok(line_string) = std::str::from_utf8(input_line) {
+                if let Some(line) = GitAttributesLine::parse(prefix, line_string) {
+                    lines.push(line);
+                }
+            }
+        }
+        if lines.is_empty() {
+            return self.clone();
+        }
+
+        Arc::new(GitAttributesFile {
+            parent: Some(self.clone()),
+            lines,
+        })
+    }
+
+    pub fn chain_with_file(
+        self: &Arc<GitAttributesFile>,
+        prefix: &str,
+        file: PathBuf,
+    ) -> Arc<GitAttributesFile> {
+        if file.is_file() {
+            let buf = fs::read(file).unwrap();
+            self.chain(prefix, &buf)
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Returns whether no attributes are defined at all.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+            && self
+                .parent
+                .as_ref()
+                .map_or(true, |parent| parent.is_empty())
+    }
+
+    fn all_lines_reversed<'a>(&'a self) -> Box<dyn Iterator<Item = &'a GitAttributesLine> + 'a> {
+        if let Some(parent) = &self.parent {
+            Box::new(self.lines.iter().rev().chain(parent.all_lines_reversed()))
+        } else {
+            Box::new(self.lines.iter().rev())
+        }
+    }
+
+    /// Returns the state of the attribute `name` on the specified file path,
+    /// or `None` if the attribute is unspecified.
+    pub fn get(&self, path: &str, name: &str) -> Option<&GitAttributeValue> {
+        // Later lines and files in subdirectories take precedence, so check them
+        // in reverse
+        for line in self.all_lines_reversed() {
+            if !line.matches(path) {
+                continue;
+            }
+            if let Some((_, value)) = line.attributes.iter().rev().find(|(n, _)| n == name) {
+                return value.as_ref();
+            }
+        }
+        None
+    }
+
+    /// Returns the value of the attribute `name` on the specified file path
+    /// if it is set to a value.
+    pub fn get_value(&self, path: &str, name: &str) -> Option<&str> {
+        match self.get(path, name) {
+            Some(GitAttributeValue::Value(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Looks up the .gitattributes files in a tree. The files are read lazily and
+/// cached per directory.
+#[derive(Debug)]
+pub struct TreeGitAttributes {
+    tree: MergedTree,
+    cache: Mutex<HashMap<RepoPathBuf, Arc<GitAttributesFile>>>,
+}
+
+impl TreeGitAttributes {
+    pub fn new(tree: MergedTree) -> Self {
+        TreeGitAttributes {
+            tree,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the attributes that apply to files in `dir`.
+    pub fn for_dir(&self, dir: &RepoPath) -> BackendResult<Arc<GitAttributesFile>> {
+        if let Some(git_attributes) = self.cache.lock().unwrap().get(dir) {
+            return Ok(git_attributes.clone());
+        }
+        let parent_attributes = match dir.parent() {
+            Some(parent) => self.for_dir(parent)?,
+            None => GitAttributesFile::empty(),
+        };
+        let path = dir.join(RepoPathComponent::new(".gitattributes"));
+        let git_attributes = match self.tree.path_value(&path).into_resolved() {
+            Ok(Some(TreeValue::File { id, .. })) => {
+                let mut contents = vec![];
+                self.tree
+                    .store()
+                    .read_file(&path, &id)?
+                    .read_to_end(&mut contents)
+                    .map_err(|err| BackendError::ReadObject {
+                        object_type: id.object_type(),
+                        hash: id.hex(),
+                        source: err.into(),
+                    })?;
+                parent_attributes.chain(&dir.to_internal_dir_string(), &contents)
+            }
+            _ => parent_attributes,
+        };
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(dir.to_owned(), git_attributes.clone());
+        Ok(git_attributes)
+    }
+
+    /// Returns the attributes that apply to the file at `path`.
+    pub fn for_file(&self, path: &RepoPath) -> BackendResult<Arc<GitAttributesFile>> {
+        match path.parent() {
+            Some(dir) => self.for_dir(dir),
+            None => Ok(GitAttributesFile::empty()),
+        }
+    }
+}
+
+/// Number of bytes to look at when guessing whether a file is binary, same as
+/// Git.
+const BINARY_DETECTION_LENGTH: usize = 8000;
+
+/// Returns whether `contents` looks like binary data, using the same heuristic
+/// as Git.
+pub fn is_binary_content(contents: &[u8]) -> bool {
+    contents[..contents.len().min(BINARY_DETECTION_LENGTH)].contains(&0)
+}
+
+/// End-of-line conversion to apply to a file as specified by the `text` and
+/// `eol` attributes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EolConversion {
+    /// The file is stored as is.
+    None,
+    /// The file is text. Line endings are normalized to LF in the repo, and
+    /// converted to CRLF in the working copy if `crlf` is set.
+    Text { crlf: bool },
+    /// Like `Text`, but only if the content doesn't look like binary data.
+    Auto { crlf: bool },
+}
+
+impl EolConversion {
+    pub fn for_path(git_attributes: &GitAttributesFile, path: &str) -> Self {
+        let eol = git_attributes.get_value(path, "eol");
+        let crlf = eol == Some("crlf");
+        match git_attributes.get(path, "text") {
+            Some(GitAttributeValue::Set) => EolConversion::Text { crlf },
+            Some(GitAttributeValue::Value(value)) if value == "auto" => {
+                EolConversion::Auto { crlf }
+            }
+            Some(GitAttributeValue::Unset) => EolConversion::None,
+            // Setting "eol" on a path with unspecified "text" marks it as text.
+            Some(GitAttributeValue::Value(_)) | None if eol.is_some() => {
+                EolConversion::Text { crlf }
+            }
+            Some(GitAttributeValue::Value(_)) | None => EolConversion::None,
+        }
+    }
+
+    fn applies_to(&self, contents: &[u8]) -> bool {
+        match self {
+            EolConversion::None => false,
+            EolConversion::Text { .. } => true,
+            EolConversion::Auto { .. } => !is_binary_content(contents),
+        }
+    }
+
+    /// Converts the contents of a file in the working copy to the contents to
+    /// store in the repo.
+    pub fn convert_to_repo(&self, contents: Vec<u8>) -> Vec<u8> {
+        if !self.applies_to(&contents) || !contents.contains(&b'\r') {
+            return contents;
+        }
+        let mut converted = Vec::with_capacity(contents.len());
+        let mut iter = contents.iter().peekable();
+        while let Some(&b) = iter.next() {
+            if b == b'\r' && iter.peek() == Some(&&b'\n') {
+                continue;
+            }
+            converted.push(b);
+        }
+        converted
+    }
+
+    /// Converts the contents of a file in the repo to the contents to write to
+    /// the working copy.
+    pub fn convert_to_working_copy(&self, contents: Vec<u8>) -> Vec<u8> {
+        let crlf = match self {
+            EolConversion::None => false,
+            EolConversion::Text { crlf } | EolConversion::Auto { crlf } => *crlf,
+        };
+        if !crlf || !self.applies_to(&contents) {
+            return contents;
+        }
+        let mut converted = Vec::with_capacity(contents.len());
+        let mut prev = None;
+        for &b in &contents {
+            if b == b'\n' && prev != Some(b'\r') {
+                converted.push(b'\r');
+            }
+            converted.push(b);
+            prev = Some(b);
+        }
+        converted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(input: &[u8], path: &str, name: &str) -> Option<GitAttributeValue> {
+        let file = GitAttributesFile::empty().chain("", input);
+        file.get(path, name).cloned()
+    }
+
+    #[test]
+    fn test_gitattributes_empty_file() {
+        let file = GitAttributesFile::empty();
+        assert!(file.is_empty());
+        assert_eq!(file.get("foo", "text"), None);
+        let file = file.chain("", b"# comment\n\n");
+        assert!(file.is_empty());
+    }
+
+    #[test]
+    fn test_gitattributes_states() {
+        let input = b"foo a -b c=d !e\n";
+        assert_eq!(get(input, "foo", "a"), Some(GitAttributeValue::Set));
+        assert_eq!(get(input, "foo", "b"), Some(GitAttributeValue::Unset));
+        assert_eq!(
+            get(input, "foo", "c"),
+            Some(GitAttributeValue::Value("d".to_owned()))
+        );
+        assert_eq!(get(input, "foo", "e"), None);
+        assert_eq!(get(input, "foo", "f"), None);
+        assert_eq!(get(input, "bar", "a"), None);
+    }
+
+    #[test]
+    fn test_gitattributes_patterns() {
+        assert_eq!(
+            get(b"*.bin a\n", "x.bin", "a"),
+            Some(GitAttributeValue::Set)
+        );
+        assert_eq!(
+            get(b"*.bin a\n", "dir/x.bin", "a"),
+            Some(GitAttributeValue::Set)
+        );
+        assert_eq!(get(b"/x.bin a\n", "dir/x.bin", "a"), None);
+        assert_eq!(
+            get(b"dir/*.bin a\n", "dir/x.bin", "a"),
+            Some(GitAttributeValue::Set)
+        );
+        assert_eq!(get(b"dir/*.bin a\n", "sub/dir/x.bin", "a"), None);
+        // Unlike .gitignore, patterns don't apply to files in matched directories
+        assert_eq!(get(b"dir a\n", "dir/x.bin", "a"), None);
+        assert_eq!(
+            get(b"dir/** a\n", "dir/x.bin", "a"),
+            Some(GitAttributeValue::Set)
+        );
+        // Negative and directory-only patterns are ignored
+        assert_eq!(get(b"!x.bin a\n", "x.bin", "a"), None);
+        assert_eq!(get(b"dir/ a\n", "dir/x.bin", "a"), None);
+        // A single CR at EOL is ignored
+        assert_eq!(
+            get(b"x.bin a\r\n", "x.bin", "a"),
+            Some(GitAttributeValue::Set)
+        );
+    }
+
+    #[test]
+    fn test_gitattributes_precedence() {
+        let input = b"*.bin a=1 b\nx.bin a=2\n*.bin !b\n";
+        assert_eq!(
+            get(input, "x.bin", "a"),
+            Some(GitAttributeValue::Value("2".to_owned()))
+        );
+        assert_eq!(
+            get(input, "y.bin", "a"),
+            Some(GitAttributeValue::Value("1".to_owned()))
+        );
+        assert_eq!(get(input, "x.bin", "b"), None);
+        // Later attributes on the same line win
+        assert_eq!(get(b"x a -a\n", "x", "a"), Some(GitAttributeValue::Unset));
+
+        let file1 = GitAttributesFile::empty().chain("", b"*.bin a\n");
+        let file2 = file1.chain("dir/", b"*.bin -a\n");
+        assert_eq!(file2.get("x.bin", "a"), Some(&GitAttributeValue::Set));
+        assert_eq!(file2.get("dir/x.bin", "a"), Some(&GitAttributeValue::Unset));
+        assert_eq!(file2.get_value("dir/x.bin", "a"), None);
+    }
+
+    #[test]
+    fn test_gitattributes_binary_macro() {
+        let input = b"*.bin binary\n";
+        assert_eq!(get(input, "x.bin", "diff"), Some(GitAttributeValue::Unset));
+        assert_eq!(get(input, "x.bin", "merge"), Some(GitAttributeValue::Unset));
+        assert_eq!(get(input, "x.bin", "text"), Some(GitAttributeValue::Unset));
+        // Attributes after the macro override it
+        assert_eq!(
+            get(b"*.bin binary diff\n", "x.bin", "diff"),
+            Some(GitAttributeValue::Set)
+        );
+        assert_eq!(get(b"*.bin -binary\n", "x.bin", "diff"), None);
+        // Macro definitions are ignored
+        assert_eq!(get(b"[attr]foo -diff\n", "[attr]foo", "diff"), None);
+    }
+
+    #[test]
+    fn test_eol_conversion_for_path() {
+        let for_path = |input: &[u8], path: &str| {
+            EolConversion::for_path(&GitAttributesFile::empty().chain("", input), path)
+        };
+        assert_eq!(for_path(b"", "x"), EolConversion::None);
+        assert_eq!(
+            for_path(b"x text\n", "x"),
+            EolConversion::Text { crlf: false }
+        );
+        assert_eq!(
+            for_path(b"x text eol=crlf\n", "x"),
+            EolConversion::Text { crlf: true }
+        );
+        assert_eq!(
+            for_path(b"x text=auto eol=crlf\n", "x"),
+            EolConversion::Auto { crlf: true }
+        );
+        assert_eq!(
+            for_path(b"x eol=lf\n", "x"),
+            EolConversion::Text { crlf: false }
+        );
+        assert_eq!(for_path(b"x -text eol=crlf\n", "x"), EolConversion::None);
+        assert_eq!(for_path(b"x binary\n", "x"), EolConversion::None);
+    }
+
+    #[test]
+    fn test_eol_conversion() {
+        let text = EolConversion::Text { crlf: false };
+        let text_crlf = EolConversion::Text { crlf: true };
+        let auto_crlf = EolConversion::Auto { crlf: true };
+        assert_eq!(text.convert_to_repo(b"a\r\nb\rc\n".to_vec()), b"a\nb\rc\n");
+        assert_eq!(text.convert_to_working_copy(b"a\nb\n".to_vec()), b"a\nb\n");
+        assert_eq!(
+            text_crlf.convert_to_working_copy(b"a\nb\r\n".to_vec()),
+            b"a\r\nb\r\n"
+        );
+        assert_eq!(
+            EolConversion::None.convert_to_repo(b"a\r\n".to_vec()),
+            b"a\r\n"
+        );
+        // Binary content is left alone in auto mode
+        assert_eq!(auto_crlf.convert_to_repo(b"a\0\r\n".to_vec()), b"a\0\r\n");
+        assert_eq!(
+            auto_crlf.convert_to_working_copy(b"a\0\n".to_vec()),
+            b"a\0\n"
+        );
+        assert_eq!(auto_crlf.convert_to_repo(b"a\r\n".to_vec()), b"a\n");
+    }
+}
@@ -64,86 +64,94 @@ impl GitIgnoreLine {
             return None;
         }
 
-        let (matches_only_directory, input) = match input.strip_suffix('/') {
-            None => (false, input),
-            Some(rest) => (true, rest),
-        };
-        let (mut is_rooted, input) = match input.strip_prefix('/') {
-            None => (false, input),
-            Some(rest) => (true, rest),
-        };
-        is_rooted |= input.contains('/');
+        let regex = pattern_to_regex(prefix, input, true);
+        Some(GitIgnoreLine { is_negative, regex })
+    }
 
-        let mut regex = String::new();
-        regex.push('^');
-        regex.push_str(prefix);
-        if !is_rooted {
-            regex.push_str("(.*/)?");
-        }
+    fn matches(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+}
 
-        let components = input.split('/').collect_vec();
-        for (i, component) in components.iter().enumerate() {
-            if *component == "**" {
-                if i == components.len() - 1 {
-                    regex.push_str(".*");
-                } else {
-                    regex.push_str("(.*/)?");
-                }
+/// Translates a gitignore-style pattern (without any leading "!") into a regex
+/// matching repository-relative paths. If `match_descendants` is true, the
+/// pattern also matches all paths within the matched directories.
+pub(crate) fn pattern_to_regex(prefix: &str, input: &str, match_descendants: bool) -> Regex {
+    let (matches_only_directory, input) = match input.strip_suffix('/') {
+        None => (false, input),
+        Some(rest) => (true, rest),
+    };
+    let (mut is_rooted, input) = match input.strip_prefix('/') {
+        None => (false, input),
+        Some(rest) => (true, rest),
+    };
+    is_rooted |= input.contains('/');
+
+    let mut regex = String::new();
+    regex.push('^');
+    regex.push_str(prefix);
+    if !is_rooted {
+        regex.push_str("(.*/)?");
+    }
+
+    let components = input.split('/').collect_vec();
+    for (i, component) in components.iter().enumerate() {
+        if *component == "**" {
+            if i == components.len() - 1 {
+                regex.push_str(".*");
             } else {
-                let mut in_escape = false;
-                let mut character_class: Option<String> = None;
-                for c in component.chars() {
-                    if in_escape {
-                        in_escape = false;
-                        if !matches!(c, ' ' | '#' | '!' | '?' | '\\' | '*') {
-                            regex.push_str(&regex_escape("\\"));
-                        }
-                        regex.push_str(&regex_escape(&c.to_string()));
-                    } else if c == '\\' {
-                        in_escape = true;
-                    } else if let Some(characters) = &mut character_class {
-                        if c == ']' {
-                            regex.push('[');
-                            regex.push_str(characters);
-                            regex.push(']');
-                            character_class = None;
-                        } else {
-                            characters.push(c);
-                        }
+                regex.push_str("(.*/)?");
+            }
+        } else {
+            let mut in_escape = false;
+            let mut character_class: Option<String> = None;
+            for c in component.chars() {
+                if in_escape {
+                    in_escape = false;
+                    if !matches!(c, ' ' | '#' | '!' | '?' | '\\' | '*') {
+                        regex.push_str(&regex_escape("\\"));
+                    }
+                    regex.push_str(&regex_escape(&c.to_string()));
+                } else if c == '\\' {
+                    in_escape = true;
+                } else if let Some(characters) = &mut character_class {
+                    if c == ']' {
+                        regex.push('[');
+                        regex.push_str(characters);
+                        regex.push(']');
+                        character_class = None;
                     } else {
-                        in_escape = false;
-                        if c == '?' {
-                            regex.push_str("[^/]");
-                        } else if c == '*' {
-                            regex.push_str("[^/]*");
-                        } else if c == '[' {
-                            character_class = Some(String::new());
-                        } else {
-                            regex.push_str(&regex_escape(&c.to_string()));
-                        }
+                        characters.push(c);
+                    }
+                } else {
+                    in_escape = false;
+                    if c == '?' {
+                        regex.push_str("[^/]");
+                    } else if c == '*' {
+                        regex.push_str("[^/]*");
+                    } else if c == '[' {
+                        character_class = Some(String::new());
+                    } else {
+                        regex.push_str(&regex_escape(&c.to_string()));
                     }
-                }
-                if in_escape {
-                    regex.push_str(&regex_escape("\\"));
-                }
-                if i < components.len() - 1 {
-                    regex.push('/');
                 }
             }
+            if in_escape {
+                regex.push_str(&regex_escape("\\"));
+            }
+            if i < components.len() - 1 {
+                regex.push('/');
+            }
         }
-        if matches_only_directory {
-            regex.push_str("/.*");
-        } else {
-            regex.push_str("(/.*|$)");
-        }
-        let regex = Regex::new(&regex).unwrap();
-
-        Some(GitIgnoreLine { is_negative, regex })
     }
-
-    fn matches(&self, path: &str) -> bool {
-        self.regex.is_match(path)
+    if matches_only_directory {
+        regex.push_str("/.*");
+    } else if match_descendants {
+        regex.push_str("(/.*|$)");
+    } else {
+        regex.push('$');
     }
+    Regex::new(&regex).unwrap()
 }
 
 /// Models the effective contents of multiple .gitignore files.
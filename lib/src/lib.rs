@@ -21,21 +21,29 @@
 #[macro_use]
 pub mod content_hash;
 
+pub mod absorb;
+pub mod annotate;
 pub mod backend;
+pub mod bisect;
 pub mod commit;
 pub mod commit_builder;
 pub mod conflicts;
+pub mod copies;
 pub mod dag_walk;
 pub mod default_index;
 pub mod default_submodule_store;
 pub mod diff;
 pub mod file_util;
 pub mod files;
+pub mod fileset;
 pub mod fmt_util;
 pub mod fsmonitor;
 pub mod git;
 pub mod git_backend;
+pub mod git_lfs;
+pub mod gitattributes;
 pub mod gitignore;
+pub mod gpg_signing;
 pub mod hex_util;
 pub mod id_prefix;
 pub mod index;
@@ -62,10 +70,14 @@ pub mod settings;
 pub mod signing;
 pub mod simple_op_heads_store;
 pub mod simple_op_store;
+pub mod ssh_signing;
 pub mod stacked_table;
 pub mod store;
 pub mod str_util;
 pub mod submodule_store;
+#[cfg(feature = "testing")]
+pub mod test_signing_backend;
+pub mod time_util;
 pub mod transaction;
 pub mod tree;
 pub mod tree_builder;
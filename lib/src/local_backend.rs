@@ -29,15 +29,15 @@ use tempfile::NamedTempFile;
 
 use crate::backend::{
     make_root_commit, Backend, BackendError, BackendResult, ChangeId, Commit, CommitId, Conflict,
-    ConflictId, ConflictTerm, FileId, MergedTreeId, MillisSinceEpoch, SecureSig, Signature,
-    SigningFn, SymlinkId, Timestamp, Tree, TreeId, TreeValue,
+    ConflictId, ConflictTerm, CopyRecord, FileId, MergedTreeId, MillisSinceEpoch, SecureSig,
+    Signature, SigningFn, SymlinkId, Timestamp, Tree, TreeId, TreeValue,
 };
 use crate::content_hash::blake2b_hash;
 use crate::file_util::persist_content_addressed_temp_file;
 use crate::index::Index;
 use crate::merge::MergeBuilder;
 use crate::object_id::ObjectId;
-use crate::repo_path::{RepoPath, RepoPathComponentBuf};
+use crate::repo_path::{RepoPath, RepoPathBuf, RepoPathComponentBuf};
 
 const COMMIT_ID_LENGTH: usize = 64;
 const CHANGE_ID_LENGTH: usize = 16;
@@ -327,6 +327,14 @@ pub fn commit_to_proto(commit: &Commit) -> crate::protos::local_store::Commit {
     proto.description = commit.description.clone();
     proto.author = Some(signature_to_proto(&commit.author));
     proto.committer = Some(signature_to_proto(&commit.committer));
+    proto.copy_records = commit
+        .copy_records
+        .iter()
+        .map(|record| crate::protos::local_store::commit::CopyRecord {
+            target: record.target.as_internal_file_string().to_owned(),
+            source: record.source.as_internal_file_string().to_owned(),
+        })
+        .collect();
     proto
 }
 
@@ -348,6 +356,14 @@ fn commit_from_proto(mut proto: crate::protos::local_store::Commit) -> Commit {
         MergedTreeId::Legacy(TreeId::new(proto.root_tree[0].to_vec()))
     };
     let change_id = ChangeId::new(proto.change_id);
+    let copy_records = proto
+        .copy_records
+        .into_iter()
+        .map(|record| CopyRecord {
+            target: RepoPathBuf::from_internal_string(record.target),
+            source: RepoPathBuf::from_internal_string(record.source),
+        })
+        .collect();
     Commit {
         parents,
         predecessors,
@@ -357,6 +373,7 @@ fn commit_from_proto(mut proto: crate::protos::local_store::Commit) -> Commit {
         author: signature_from_proto(proto.author.unwrap_or_default()),
         committer: signature_from_proto(proto.committer.unwrap_or_default()),
         secure_sig,
+        copy_records,
     }
 }
 
@@ -514,6 +531,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copy_records: vec![],
         };
 
         // No parents
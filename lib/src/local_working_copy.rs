@@ -45,10 +45,12 @@ use crate::backend::{
     BackendError, FileId, MergedTreeId, MillisSinceEpoch, SymlinkId, TreeId, TreeValue,
 };
 use crate::commit::Commit;
-use crate::conflicts::{self, materialize_tree_value, MaterializedTreeValue};
+use crate::conflicts::{self, materialize_tree_value, MaterializedTreeValue, MergeDriver};
 #[cfg(feature = "watchman")]
 use crate::fsmonitor::watchman;
 use crate::fsmonitor::FsmonitorKind;
+use crate::git_lfs::{is_lfs_path, LfsObjectStore, LfsPointer};
+use crate::gitattributes::{EolConversion, GitAttributesFile, TreeGitAttributes};
 use crate::gitignore::GitIgnoreFile;
 use crate::lock::FileLock;
 use crate::matchers::{
@@ -289,6 +291,8 @@ pub struct TreeState {
     // Currently only path prefixes
     sparse_patterns: Vec<RepoPathBuf>,
     own_mtime: MillisSinceEpoch,
+    /// The Git LFS object store if the repo is backed by Git.
+    lfs_store: Option<LfsObjectStore>,
 
     /// The most recent clock value returned by Watchman. Will only be set if
     /// the repo is configured to use the Watchman filesystem monitor and
@@ -472,6 +476,7 @@ struct DirectoryToVisit<'a> {
     dir: RepoPathBuf,
     disk_dir: PathBuf,
     git_ignore: Arc<GitIgnoreFile>,
+    git_attributes: Arc<GitAttributesFile>,
     file_states: FileStates<'a>,
 }
 
@@ -534,6 +539,7 @@ impl TreeState {
 
     fn empty(store: Arc<Store>, working_copy_path: PathBuf, state_path: PathBuf) -> TreeState {
         let tree_id = store.empty_merged_tree_id();
+        let lfs_store = LfsObjectStore::for_store(&store);
         // Canonicalize the working copy path because "repo/." makes libgit2 think that
         // everything should be ignored
         TreeState {
@@ -544,6 +550,7 @@ impl TreeState {
             file_states: FileStatesMap::new(),
             sparse_patterns: vec![RepoPathBuf::root()],
             own_mtime: MillisSinceEpoch(0),
+            lfs_store,
             watchman_clock: None,
         }
     }
@@ -610,7 +617,7 @@ impl TreeState {
         Ok(())
     }
 
-    fn save(&mut self) -> Result<(), TreeStateError> {
+    pub fn save(&mut self) -> Result<(), TreeStateError> {
         let mut proto: crate::protos::working_copy::TreeState = Default::default();
         match &self.tree_id {
             MergedTreeId::Legacy(tree_id) => {
@@ -660,15 +667,48 @@ impl TreeState {
         self.store.get_root_tree(&self.tree_id)
     }
 
+    /// Returns the LFS object store if the file at `path` should be stored in
+    /// LFS.
+    fn lfs_store_for_path(
+        &self,
+        git_attributes: &GitAttributesFile,
+        path: &RepoPath,
+    ) -> Option<&LfsObjectStore> {
+        self.lfs_store
+            .as_ref()
+            .filter(|_| is_lfs_path(git_attributes, path.as_internal_file_string()))
+    }
+
     fn write_file_to_store(
         &self,
         path: &RepoPath,
         disk_path: &Path,
+        git_attributes: &GitAttributesFile,
     ) -> Result<FileId, SnapshotError> {
         let mut file = File::open(disk_path).map_err(|err| SnapshotError::Other {
             message: format!("Failed to open file {}", disk_path.display()),
             err: err.into(),
         })?;
+        if let Some(lfs_store) = self.lfs_store_for_path(git_attributes, path) {
+            let pointer_data =
+                clean_lfs_file(lfs_store, &mut file).map_err(|err| SnapshotError::Other {
+                    message: format!("Failed to store file {} in Git LFS", disk_path.display()),
+                    err: err.into(),
+                })?;
+            return Ok(self.store.write_file(path, &mut pointer_data.as_slice())?);
+        }
+        let eol_conversion =
+            EolConversion::for_path(git_attributes, path.as_internal_file_string());
+        if eol_conversion != EolConversion::None {
+            let mut contents = vec![];
+            file.read_to_end(&mut contents)
+                .map_err(|err| SnapshotError::Other {
+                    message: format!("Failed to read file {}", disk_path.display()),
+                    err: err.into(),
+                })?;
+            let contents = eol_conversion.convert_to_repo(contents);
+            return Ok(self.store.write_file(path, &mut contents.as_slice())?);
+        }
         Ok(self.store.write_file(path, &mut file)?)
     }
 
@@ -753,6 +793,7 @@ impl TreeState {
                 dir: RepoPathBuf::root(),
                 disk_dir: self.working_copy_path.clone(),
                 git_ignore: base_ignores,
+                git_attributes: GitAttributesFile::empty(),
                 file_states: self.file_states.all(),
             };
             self.visit_directory(
@@ -842,6 +883,7 @@ impl TreeState {
             dir,
             disk_dir,
             git_ignore,
+            git_attributes,
             file_states,
         } = directory_to_visit;
 
@@ -851,6 +893,10 @@ impl TreeState {
 
         let git_ignore =
             git_ignore.chain_with_file(&dir.to_internal_dir_string(), disk_dir.join(".gitignore"));
+        let git_attributes = git_attributes.chain_with_file(
+            &dir.to_internal_dir_string(),
+            disk_dir.join(".gitattributes"),
+        );
         let dir_entries = disk_dir
             .read_dir()
             .unwrap()
@@ -917,6 +963,7 @@ impl TreeState {
                                     Some(&current_file_state),
                                     current_tree,
                                     &new_file_state,
+                                    &git_attributes,
                                 )?;
                                 if let Some(tree_value) = update {
                                     tree_entries_tx
@@ -935,6 +982,7 @@ impl TreeState {
                             dir: path,
                             disk_dir: entry.path(),
                             git_ignore: git_ignore.clone(),
+                            git_attributes: git_attributes.clone(),
                             file_states,
                         };
                         self.visit_directory(
@@ -963,7 +1011,10 @@ impl TreeState {
                             message: format!("Failed to stat file {}", entry.path().display()),
                             err: err.into(),
                         })?;
-                        if maybe_current_file_state.is_none() && metadata.len() > max_new_file_size
+                        // Files stored in Git LFS aren't subject to the size limit
+                        if maybe_current_file_state.is_none()
+                            && metadata.len() > max_new_file_size
+                            && self.lfs_store_for_path(&git_attributes, &path).is_none()
                         {
                             return Err(SnapshotError::NewFileTooLarge {
                                 path: entry.path().clone(),
@@ -979,6 +1030,7 @@ impl TreeState {
                                 maybe_current_file_state.as_ref(),
                                 current_tree,
                                 &new_file_state,
+                                &git_attributes,
                             )?;
                             if let Some(tree_value) = update {
                                 tree_entries_tx.send((path.clone(), tree_value)).ok();
@@ -1047,6 +1099,7 @@ impl TreeState {
         maybe_current_file_state: Option<&FileState>,
         current_tree: &MergedTree,
         new_file_state: &FileState,
+        git_attributes: &GitAttributesFile,
     ) -> Result<Option<MergedTreeValue>, SnapshotError> {
         let clean = match maybe_current_file_state {
             None => {
@@ -1069,6 +1122,7 @@ impl TreeState {
                 &disk_path,
                 &current_tree_values,
                 new_file_type,
+                git_attributes,
             )?;
             if new_tree_values != current_tree_values {
                 Ok(Some(new_tree_values))
@@ -1084,6 +1138,7 @@ impl TreeState {
         disk_path: &Path,
         current_tree_values: &MergedTreeValue,
         file_type: FileType,
+        git_attributes: &GitAttributesFile,
     ) -> Result<MergedTreeValue, SnapshotError> {
         let executable = match file_type {
             FileType::Normal { executable } => executable,
@@ -1099,7 +1154,7 @@ impl TreeState {
         if let Some(current_tree_value) = current_tree_values.as_resolved() {
             #[cfg(unix)]
             let _ = current_tree_value; // use the variable
-            let id = self.write_file_to_store(repo_path, disk_path)?;
+            let id = self.write_file_to_store(repo_path, disk_path, git_attributes)?;
             // On Windows, we preserve the executable bit from the current tree.
             #[cfg(windows)]
             let executable = {
@@ -1116,10 +1171,14 @@ impl TreeState {
                 message: format!("Failed to open file {}", disk_path.display()),
                 err: err.into(),
             })?;
+            let content =
+                EolConversion::for_path(git_attributes, repo_path.as_internal_file_string())
+                    .convert_to_repo(content);
             let new_file_ids = conflicts::update_from_content(
                 &old_file_ids,
                 self.store.as_ref(),
                 repo_path,
+                MergeDriver::for_path(git_attributes, repo_path),
                 &content,
             )
             .block_on()?;
@@ -1177,6 +1236,32 @@ impl TreeState {
         Ok(FileState::for_file(executable, size, &metadata))
     }
 
+    /// Writes the file at `disk_path`, replacing the contents with the Git LFS
+    /// object if `contents` is a pointer to an object in the local store.
+    fn write_lfs_file(
+        &self,
+        disk_path: &Path,
+        lfs_store: &LfsObjectStore,
+        contents: &mut dyn Read,
+        executable: bool,
+    ) -> Result<FileState, CheckoutError> {
+        let mut buf = vec![];
+        let object = contents
+            .read_to_end(&mut buf)
+            .and_then(|_| match LfsPointer::parse(&buf) {
+                Some(pointer) => lfs_store.open(&pointer),
+                None => Ok(None),
+            })
+            .map_err(|err| CheckoutError::Other {
+                message: format!("Failed to read Git LFS object for {}", disk_path.display()),
+                err: err.into(),
+            })?;
+        match object {
+            Some(mut object) => self.write_file(disk_path, &mut object, executable),
+            None => self.write_file(disk_path, &mut buf.as_slice(), executable),
+        }
+    }
+
     #[cfg_attr(windows, allow(unused_variables))]
     fn write_symlink(&self, disk_path: &Path, target: String) -> Result<FileState, CheckoutError> {
         #[cfg(windows)]
@@ -1302,22 +1387,38 @@ impl TreeState {
         };
         let mut changed_file_states = Vec::new();
         let mut deleted_files = HashSet::new();
+        let tree_git_attributes = TreeGitAttributes::new(new_tree.clone());
+        let diff_entries: Vec<_> = old_tree.diff_stream(new_tree, matcher).collect().await;
+        // Let the backend fetch the new contents in one go (e.g. the missing
+        // blobs of a partial clone) instead of one file at a time.
+        let new_file_ids = diff_entries
+            .iter()
+            .filter_map(|(_, diff)| diff.as_ref().ok())
+            .flat_map(|(_, after)| after.iter().flatten())
+            .filter_map(|value| match value {
+                TreeValue::File { id, .. } => Some(id.clone()),
+                _ => None,
+            })
+            .collect_vec();
+        self.store.prefetch_files(&new_file_ids)?;
         let mut diff_stream = Box::pin(
-            old_tree
-                .diff_stream(new_tree, matcher)
+            futures::stream::iter(diff_entries)
                 .map(|(path, diff)| async {
-                    match diff {
-                        Ok((before, after)) => {
-                            let result = materialize_tree_value(&self.store, &path, after).await;
-                            (path, result.map(|value| (before.is_present(), value)))
-                        }
-                        Err(err) => (path, Err(err)),
+                    let result = async {
+                        let (before, after) = diff?;
+                        let git_attributes = tree_git_attributes.for_file(&path)?;
+                        let merge_driver = MergeDriver::for_path(&git_attributes, &path);
+                        let value =
+                            materialize_tree_value(&self.store, &path, after, merge_driver).await?;
+                        Ok::<_, BackendError>((before.is_present(), value, git_attributes))
                     }
+                    .await;
+                    (path, result)
                 })
                 .buffered(self.store.concurrency().max(1)),
         );
         while let Some((path, data)) = diff_stream.next().await {
-            let (present_before, after) = data?;
+            let (present_before, after, git_attributes) = data?;
             if after.is_absent() {
                 stats.removed_files += 1;
             } else if !present_before {
@@ -1359,7 +1460,28 @@ impl TreeState {
                     executable,
                     mut reader,
                     ..
-                } => self.write_file(&disk_path, &mut reader, executable)?,
+                } => {
+                    let eol_conversion =
+                        EolConversion::for_path(&git_attributes, path.as_internal_file_string());
+                    if let Some(lfs_store) = self.lfs_store_for_path(&git_attributes, &path) {
+                        self.write_lfs_file(&disk_path, lfs_store, &mut reader, executable)?
+                    } else if eol_conversion != EolConversion::None {
+                        let mut contents = vec![];
+                        reader
+                            .read_to_end(&mut contents)
+                            .map_err(|err| CheckoutError::Other {
+                                message: format!(
+                                    "Failed to read file {}",
+                                    path.as_internal_file_string()
+                                ),
+                                err: err.into(),
+                            })?;
+                        let contents = eol_conversion.convert_to_working_copy(contents);
+                        self.write_file(&disk_path, &mut contents.as_slice(), executable)?
+                    } else {
+                        self.write_file(&disk_path, &mut reader, executable)?
+                    }
+                }
                 MaterializedTreeValue::Symlink { id: _, target } => {
                     self.write_symlink(&disk_path, target)?
                 }
@@ -1371,6 +1493,9 @@ impl TreeState {
                     panic!("unexpected tree entry in diff at {path:?}");
                 }
                 MaterializedTreeValue::Conflict { id: _, contents } => {
+                    let contents =
+                        EolConversion::for_path(&git_attributes, path.as_internal_file_string())
+                            .convert_to_working_copy(contents);
                     self.write_conflict(&disk_path, contents)?
                 }
             };
@@ -1438,6 +1563,21 @@ impl TreeState {
     }
 }
 
+/// Adds the contents of `file` to the LFS store and returns the pointer data to
+/// store in the tree. If the file is already a pointer (because the object
+/// wasn't available at checkout), it's returned as is.
+fn clean_lfs_file(lfs_store: &LfsObjectStore, file: &mut File) -> std::io::Result<Vec<u8>> {
+    let mut head = vec![];
+    Read::by_ref(file)
+        .take(LfsPointer::MAX_SIZE as u64 + 1)
+        .read_to_end(&mut head)?;
+    if LfsPointer::parse(&head).is_some() {
+        return Ok(head);
+    }
+    let pointer = lfs_store.insert(&mut head.as_slice().chain(file))?;
+    Ok(pointer.to_bytes())
+}
+
 fn checkout_error_for_stat_error(err: std::io::Error, path: &Path) -> CheckoutError {
     CheckoutError::Other {
         message: format!("Failed to stat file {}", path.display()),
@@ -40,6 +40,19 @@ mod tests {
         assert!(!lock_path.exists());
     }
 
+    #[test]
+    fn lock_try() {
+        let temp_dir = testutils::new_temp_dir();
+        let lock_path = temp_dir.path().join("test.lock");
+        {
+            let _lock = FileLock::try_lock(lock_path.clone()).unwrap();
+            assert!(lock_path.exists());
+            assert!(FileLock::try_lock(lock_path.clone()).is_none());
+        }
+        assert!(!lock_path.exists());
+        assert!(FileLock::try_lock(lock_path.clone()).is_some());
+    }
+
     #[test]
     fn lock_concurrent() {
         let temp_dir = testutils::new_temp_dir();
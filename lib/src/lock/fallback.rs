@@ -62,6 +62,24 @@ impl FileLock {
             Ok(file_lock) => file_lock,
         }
     }
+
+    /// Like `lock()`, but returns `None` instead of waiting if the lock file
+    /// already exists.
+    pub fn try_lock(path: PathBuf) -> Option<FileLock> {
+        let mut options = OpenOptions::new();
+        options.create_new(true);
+        options.write(true);
+        match options.open(&path) {
+            Ok(file) => Some(FileLock { path, _file: file }),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => None,
+            Err(err) if cfg!(windows) && err.kind() == std::io::ErrorKind::PermissionDenied => None,
+            Err(err) => panic!(
+                "failed to create lock file {}: {}",
+                path.to_string_lossy(),
+                err
+            ),
+        }
+    }
 }
 
 impl Drop for FileLock {
@@ -46,6 +46,27 @@ impl FileLock {
             return Self { path, file };
         }
     }
+
+    /// Like `lock()`, but returns `None` instead of waiting if the lock is
+    /// held by someone else.
+    pub fn try_lock(path: PathBuf) -> Option<FileLock> {
+        loop {
+            let file = File::create(&path).expect("failed to open lockfile");
+            match rustix::fs::flock(&file, FlockOperation::NonBlockingLockExclusive) {
+                Ok(()) => {}
+                Err(rustix::io::Errno::WOULDBLOCK) => return None,
+                Err(err) => panic!("failed to lock lockfile: {err}"),
+            }
+
+            let stat = rustix::fs::fstat(&file).expect("failed to stat lockfile");
+            if stat.st_nlink == 0 {
+                // See `lock()`.
+                continue;
+            }
+
+            return Some(Self { path, file });
+        }
+    }
 }
 
 impl Drop for FileLock {
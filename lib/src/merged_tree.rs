@@ -33,7 +33,7 @@ use crate::repo_path::{RepoPath, RepoPathBuf, RepoPathComponent, RepoPathCompone
 use crate::store::Store;
 use crate::tree::{try_resolve_file_conflict, Tree, TreeMergeError};
 use crate::tree_builder::TreeBuilder;
-use crate::{backend, tree};
+use crate::{backend, copies, tree};
 
 /// Presents a view of a merged set of trees.
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -361,7 +361,8 @@ impl MergedTree {
         })
     }
 
-    /// Merges this tree with `other`, using `base` as base.
+    /// Merges this tree with `other`, using `base` as base. Changes to a file
+    /// renamed on the other side are carried over to the renamed file.
     pub fn merge(
         &self,
         base: &MergedTree,
@@ -400,7 +401,16 @@ impl MergedTree {
                 let re_merged = merge_trees(&tree).unwrap();
                 debug_assert_eq!(re_merged, tree);
             }
-            Ok(MergedTree::Merge(tree))
+            let merged = MergedTree::Merge(tree);
+            if merged.has_conflict()
+                && !self.has_conflict()
+                && !base.has_conflict()
+                && !other.has_conflict()
+            {
+                copies::merge_renamed_files(self, base, other, merged)
+            } else {
+                Ok(merged)
+            }
         }
     }
 }
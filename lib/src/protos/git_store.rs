@@ -18,4 +18,18 @@ pub struct Commit {
     #[deprecated]
     #[prost(bool, tag = "9")]
     pub is_pruned: bool,
+    /// Git commits can't record copies, so they are stored here.
+    #[prost(message, repeated, tag = "11")]
+    pub copy_records: ::prost::alloc::vec::Vec<commit::CopyRecord>,
+}
+/// Nested message and enum types in `Commit`.
+pub mod commit {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct CopyRecord {
+        #[prost(string, tag = "1")]
+        pub target: ::prost::alloc::string::String,
+        #[prost(string, tag = "2")]
+        pub source: ::prost::alloc::string::String,
+    }
 }
@@ -33,15 +33,16 @@ use thiserror::Error;
 
 use crate::backend::{BackendError, BackendResult, ChangeId, CommitId};
 use crate::commit::Commit;
+use crate::fileset::{self, FilesetExpression, FilesetParseContext, FilesetParseError};
 use crate::git;
 use crate::hex_util::to_forward_hex;
 use crate::object_id::{HexPrefix, PrefixResolution};
 use crate::op_store::WorkspaceId;
 use crate::repo::Repo;
-use crate::repo_path::{FsPathParseError, RepoPathBuf};
 use crate::revset_graph::RevsetGraphEdge;
 use crate::store::Store;
 use crate::str_util::StringPattern;
+use crate::time_util::{DatePattern, DatePatternContext};
 
 /// Error occurred during symbol resolution.
 #[derive(Debug, Error)]
@@ -174,8 +175,8 @@ pub enum RevsetParseErrorKind {
     },
     #[error("Invalid arguments to revset function \"{name}\": {message}")]
     InvalidFunctionArguments { name: String, message: String },
-    #[error("Invalid file pattern: {0}")]
-    FsPathParseError(#[source] FsPathParseError),
+    #[error("Invalid fileset expression")]
+    FilesetParseError(#[source] FilesetParseError),
     #[error("Cannot resolve file pattern without workspace")]
     FsPathWithoutWorkspace,
     #[error(r#"Cannot resolve "@" without workspace"#)]
@@ -336,8 +337,17 @@ pub enum RevsetFilterPredicate {
     Author(StringPattern),
     /// Commits with committer's name or email containing the needle.
     Committer(StringPattern),
-    /// Commits modifying the paths specified by the pattern.
-    File(Option<Vec<RepoPathBuf>>), // TODO: embed matcher expression?
+    /// Commits with author dates matching the given date pattern.
+    AuthorDate(DatePattern),
+    /// Commits with committer dates matching the given date pattern.
+    CommitterDate(DatePattern),
+    /// Commits modifying the paths specified by the fileset.
+    File(FilesetExpression),
+    /// Commits containing diffs matching the `text` pattern within the `files`.
+    DiffContains {
+        text: StringPattern,
+        files: FilesetExpression,
+    },
     /// Commits with conflicts
     HasConflict,
 }
@@ -835,6 +845,7 @@ struct ParseState<'a> {
     aliases_expanding: &'a [RevsetAliasId<'a>],
     locals: &'a HashMap<&'a str, Rc<RevsetExpression>>,
     user_email: &'a str,
+    date_pattern_context: DatePatternContext,
     workspace_ctx: &'a Option<RevsetWorkspaceContext<'a>>,
 }
 
@@ -860,6 +871,7 @@ impl ParseState<'_> {
             aliases_expanding: &aliases_expanding,
             locals,
             user_email: self.user_email,
+            date_pattern_context: self.date_pattern_context,
             workspace_ctx: self.workspace_ctx,
         };
         f(expanding_state).map_err(|e| {
@@ -1274,29 +1286,39 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
             pattern,
         )))
     });
+    map.insert("author_date", |name, arguments_pair, state| {
+        let arg = expect_one_argument(name, arguments_pair)?;
+        let pattern = parse_function_argument_to_date_pattern(name, arg, state)?;
+        Ok(RevsetExpression::filter(RevsetFilterPredicate::AuthorDate(
+            pattern,
+        )))
+    });
+    map.insert("committer_date", |name, arguments_pair, state| {
+        let arg = expect_one_argument(name, arguments_pair)?;
+        let pattern = parse_function_argument_to_date_pattern(name, arg, state)?;
+        Ok(RevsetExpression::filter(
+            RevsetFilterPredicate::CommitterDate(pattern),
+        ))
+    });
     map.insert("empty", |name, arguments_pair, _state| {
         expect_no_arguments(name, arguments_pair)?;
-        Ok(RevsetExpression::filter(RevsetFilterPredicate::File(None)).negated())
+        Ok(
+            RevsetExpression::filter(RevsetFilterPredicate::File(FilesetExpression::all()))
+                .negated(),
+        )
     });
     map.insert("file", |name, arguments_pair, state| {
         if let Some(ctx) = state.workspace_ctx {
             let arguments_span = arguments_pair.as_span();
-            let paths: Vec<_> = arguments_pair
+            let fileset_ctx = FilesetParseContext {
+                cwd: ctx.cwd,
+                workspace_root: ctx.workspace_root,
+            };
+            let expressions: Vec<_> = arguments_pair
                 .into_inner()
-                .map(|arg| -> Result<_, RevsetParseError> {
-                    let span = arg.as_span();
-                    let needle = parse_function_argument_to_string(name, arg, state)?;
-                    let path = RepoPathBuf::parse_fs_path(ctx.cwd, ctx.workspace_root, needle)
-                        .map_err(|e| {
-                            RevsetParseError::with_span(
-                                RevsetParseErrorKind::FsPathParseError(e),
-                                span,
-                            )
-                        })?;
-                    Ok(path)
-                })
+                .map(|arg| parse_function_argument_as_fileset(arg, state, &fileset_ctx))
                 .try_collect()?;
-            if paths.is_empty() {
+            if expressions.is_empty() {
                 Err(RevsetParseError::with_span(
                     RevsetParseErrorKind::InvalidFunctionArguments {
                         name: name.to_owned(),
@@ -1305,9 +1327,9 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
                     arguments_span,
                 ))
             } else {
-                Ok(RevsetExpression::filter(RevsetFilterPredicate::File(Some(
-                    paths,
-                ))))
+                Ok(RevsetExpression::filter(RevsetFilterPredicate::File(
+                    FilesetExpression::union_all(expressions),
+                )))
             }
         } else {
             Err(RevsetParseError::new(
@@ -1315,6 +1337,25 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
             ))
         }
     });
+    map.insert("diff_contains", |name, arguments_pair, state| {
+        let ([text_arg], [files_opt_arg]) = expect_arguments(name, arguments_pair)?;
+        let text = parse_function_argument_to_string_pattern(name, text_arg, state)?;
+        let files = if let Some(files_arg) = files_opt_arg {
+            let ctx = state.workspace_ctx.as_ref().ok_or_else(|| {
+                RevsetParseError::new(RevsetParseErrorKind::FsPathWithoutWorkspace)
+            })?;
+            let fileset_ctx = FilesetParseContext {
+                cwd: ctx.cwd,
+                workspace_root: ctx.workspace_root,
+            };
+            parse_function_argument_as_fileset(files_arg, state, &fileset_ctx)?
+        } else {
+            FilesetExpression::all()
+        };
+        Ok(RevsetExpression::filter(
+            RevsetFilterPredicate::DiffContains { text, files },
+        ))
+    });
     map.insert("conflict", |name, arguments_pair, _state| {
         expect_no_arguments(name, arguments_pair)?;
         Ok(RevsetExpression::filter(RevsetFilterPredicate::HasConflict))
@@ -1449,14 +1490,6 @@ fn expect_named_arguments_vec<'i>(
     Ok((required, optional))
 }
 
-fn parse_function_argument_to_string(
-    name: &str,
-    pair: Pair<Rule>,
-    state: ParseState,
-) -> Result<String, RevsetParseError> {
-    parse_function_argument_as_literal("string", name, pair, state)
-}
-
 fn parse_function_argument_to_string_pattern(
     name: &str,
     pair: Pair<Rule>,
@@ -1501,6 +1534,66 @@ fn parse_function_argument_to_string_pattern(
     Ok(pattern)
 }
 
+fn parse_function_argument_to_date_pattern(
+    name: &str,
+    pair: Pair<Rule>,
+    state: ParseState,
+) -> Result<DatePattern, RevsetParseError> {
+    let span = pair.as_span();
+    let make_error = |message| {
+        RevsetParseError::with_span(
+            RevsetParseErrorKind::InvalidFunctionArguments {
+                name: name.to_string(),
+                message,
+            },
+            span,
+        )
+    };
+    let make_type_error = || make_error("Expected function argument of date pattern".to_owned());
+    let expression = parse_expression_rule(pair.into_inner(), state)?;
+    // A date pattern is parsed as "kind:needle" range in the revset grammar.
+    let RevsetExpression::DagRange {
+        roots,
+        heads,
+        is_legacy: true,
+    } = expression.as_ref()
+    else {
+        return Err(make_type_error());
+    };
+    let RevsetExpression::CommitRef(RevsetCommitRef::Symbol(kind)) = roots.as_ref() else {
+        return Err(make_type_error());
+    };
+    let RevsetExpression::CommitRef(RevsetCommitRef::Symbol(needle)) = heads.as_ref() else {
+        return Err(make_type_error());
+    };
+    state
+        .date_pattern_context
+        .parse_relative(needle, kind)
+        .map_err(|err| make_error(err.to_string()))
+}
+
+/// Parses the argument as a fileset expression. An identifier or a string
+/// literal, possibly substituted by an alias, is parsed like a command-line
+/// argument, so it may be a bare file path.
+fn parse_function_argument_as_fileset(
+    pair: Pair<Rule>,
+    state: ParseState,
+    ctx: &FilesetParseContext,
+) -> Result<FilesetExpression, RevsetParseError> {
+    let span = pair.as_span();
+    let text = pair.as_str();
+    if let Ok(expression) = parse_expression_rule(pair.into_inner(), state) {
+        if let RevsetExpression::CommitRef(RevsetCommitRef::Symbol(symbol)) = expression.as_ref() {
+            return fileset::parse_maybe_bare(symbol, ctx).map_err(|err| {
+                RevsetParseError::with_span(RevsetParseErrorKind::FilesetParseError(err), span)
+            });
+        }
+    }
+    fileset::parse(text, ctx).map_err(|err| {
+        RevsetParseError::with_span(RevsetParseErrorKind::FilesetParseError(err), span)
+    })
+}
+
 fn parse_function_argument_as_literal<T: FromStr>(
     type_name: &str,
     name: &str,
@@ -1535,6 +1628,7 @@ pub fn parse(
         aliases_expanding: &[],
         locals: &HashMap::new(),
         user_email: &context.user_email,
+        date_pattern_context: context.date_pattern_context,
         workspace_ctx: &context.workspace,
     };
     parse_program(revset_str, state)
@@ -1711,6 +1805,15 @@ fn internalize_filter(expression: &Rc<RevsetExpression>) -> TransformedExpressio
         )
     }
 
+    // Filter which needs to compute diffs of file contents. Cheaper filters
+    // should be evaluated first.
+    fn is_expensive_filter(expression: &RevsetExpression) -> bool {
+        matches!(
+            expression,
+            RevsetExpression::Filter(RevsetFilterPredicate::DiffContains { .. })
+        )
+    }
+
     fn is_filter_tree(expression: &RevsetExpression) -> bool {
         is_filter(expression) || as_filter_intersection(expression).is_some()
     }
@@ -1736,8 +1839,23 @@ fn internalize_filter(expression: &Rc<RevsetExpression>) -> TransformedExpressio
     ) -> TransformedExpression {
         let recurse = |e1, e2| intersect_down(e1, e2).unwrap_or_else(|| e1.intersection(e2));
         match (expression1.as_ref(), expression2.as_ref()) {
-            // Don't reorder 'f1 & f2'
-            (_, e2) if is_filter(e2) => None,
+            // Don't reorder 'f1 & f2' unless f1 is more expensive than f2
+            (e1, e2) if is_filter(e2) => {
+                if is_expensive_filter(e2) {
+                    None
+                } else if is_expensive_filter(e1) {
+                    // f1 & f2 -> f2 & f1
+                    Some(expression2.intersection(expression1))
+                } else {
+                    match as_filter_intersection(e1) {
+                        // (c1 & f1) & f2 -> (c1 & f2) & f1
+                        Some((c1, f1)) if is_expensive_filter(f1) => {
+                            Some(recurse(c1, expression2).intersection(f1))
+                        }
+                        _ => None,
+                    }
+                }
+            }
             // f1 & e2 -> e2 & f1
             (e1, _) if is_filter(e1) => Some(expression2.intersection(expression1)),
             (e1, e2) => match (as_filter_intersection(e1), as_filter_intersection(e2)) {
@@ -2474,6 +2592,7 @@ impl Iterator for ReverseRevsetIterator {
 pub struct RevsetParseContext<'a> {
     pub aliases_map: &'a RevsetAliasesMap,
     pub user_email: String,
+    pub date_pattern_context: DatePatternContext,
     pub workspace: Option<RevsetWorkspaceContext<'a>>,
 }
 
@@ -2488,6 +2607,8 @@ pub struct RevsetWorkspaceContext<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fileset::FilePattern;
+    use crate::repo_path::RepoPathBuf;
 
     fn parse(revset_str: &str) -> Result<Rc<RevsetExpression>, RevsetParseErrorKind> {
         parse_with_aliases(revset_str, [] as [(&str, &str); 0])
@@ -2500,6 +2621,11 @@ mod tests {
         parse_with_aliases_and_workspace(revset_str, [] as [(&str, &str); 0], workspace_id)
     }
 
+    fn test_date_pattern_context() -> DatePatternContext {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-01-31T12:00:00+09:00").unwrap();
+        DatePatternContext::new(now)
+    }
+
     fn parse_with_aliases(
         revset_str: &str,
         aliases: impl IntoIterator<Item = (impl AsRef<str>, impl Into<String>)>,
@@ -2511,6 +2637,7 @@ mod tests {
         let context = RevsetParseContext {
             aliases_map: &aliases_map,
             user_email: "test.user@example.com".to_string(),
+            date_pattern_context: test_date_pattern_context(),
             workspace: None,
         };
         // Map error to comparable object
@@ -2535,6 +2662,7 @@ mod tests {
         let context = RevsetParseContext {
             aliases_map: &aliases_map,
             user_email: "test.user@example.com".to_string(),
+            date_pattern_context: test_date_pattern_context(),
             workspace: Some(workspace_ctx),
         };
         // Map error to comparable object
@@ -2852,9 +2980,9 @@ mod tests {
                 StringPattern::Substring("arg1".to_string())
             ))
             .minus(&RevsetExpression::filter(RevsetFilterPredicate::File(
-                Some(vec![
-                    RepoPathBuf::from_internal_string("arg1"),
-                    RepoPathBuf::from_internal_string("arg2"),
+                FilesetExpression::union_all(vec![
+                    FilesetExpression::prefix_path(RepoPathBuf::from_internal_string("arg1")),
+                    FilesetExpression::prefix_path(RepoPathBuf::from_internal_string("arg2")),
                 ])
             )))
             .minus(&RevsetExpression::visible_heads()))
@@ -3100,28 +3228,108 @@ mod tests {
                 StringPattern::Exact("test.user@example.com".to_string())
             )))
         );
+        assert_eq!(
+            parse(r#"author_date(after:"2024-01-01")"#),
+            Ok(RevsetExpression::filter(RevsetFilterPredicate::AuthorDate(
+                test_date_pattern_context()
+                    .parse_relative("2024-01-01", "after")
+                    .unwrap()
+            )))
+        );
+        assert_eq!(
+            parse(r#"committer_date(before:"2 weeks ago")"#),
+            Ok(RevsetExpression::filter(
+                RevsetFilterPredicate::CommitterDate(
+                    test_date_pattern_context()
+                        .parse_relative("2 weeks ago", "before")
+                        .unwrap()
+                )
+            ))
+        );
+        assert!(parse("author_date(2024-01-01)").is_err());
+        assert!(parse(r#"author_date(since:"2024-01-01")"#).is_err());
+        assert!(parse(r#"committer_date(after:"yesteryear")"#).is_err());
         assert_eq!(
             parse_with_workspace("empty()", &WorkspaceId::default()),
-            Ok(RevsetExpression::filter(RevsetFilterPredicate::File(None)).negated())
+            Ok(
+                RevsetExpression::filter(RevsetFilterPredicate::File(FilesetExpression::all()))
+                    .negated()
+            )
         );
         assert!(parse_with_workspace("empty(foo)", &WorkspaceId::default()).is_err());
         assert!(parse_with_workspace("file()", &WorkspaceId::default()).is_err());
         assert_eq!(
             parse_with_workspace("file(foo)", &WorkspaceId::default()),
-            Ok(RevsetExpression::filter(RevsetFilterPredicate::File(Some(
-                vec![RepoPathBuf::from_internal_string("foo")]
-            ))))
+            Ok(RevsetExpression::filter(RevsetFilterPredicate::File(
+                FilesetExpression::prefix_path(RepoPathBuf::from_internal_string("foo"))
+            )))
         );
         assert_eq!(
             parse_with_workspace("file(foo, bar, baz)", &WorkspaceId::default()),
-            Ok(RevsetExpression::filter(RevsetFilterPredicate::File(Some(
-                vec![
-                    RepoPathBuf::from_internal_string("foo"),
-                    RepoPathBuf::from_internal_string("bar"),
-                    RepoPathBuf::from_internal_string("baz"),
-                ]
-            ))))
+            Ok(RevsetExpression::filter(RevsetFilterPredicate::File(
+                FilesetExpression::union_all(vec![
+                    FilesetExpression::prefix_path(RepoPathBuf::from_internal_string("foo")),
+                    FilesetExpression::prefix_path(RepoPathBuf::from_internal_string("bar")),
+                    FilesetExpression::prefix_path(RepoPathBuf::from_internal_string("baz")),
+                ])
+            )))
+        );
+        assert_eq!(
+            parse_with_workspace(r#"file(glob:"*.rs" ~ "foo")"#, &WorkspaceId::default()),
+            Ok(RevsetExpression::filter(RevsetFilterPredicate::File(
+                FilesetExpression::pattern(FilePattern::FileGlob {
+                    dir: RepoPathBuf::root(),
+                    pattern: glob::Pattern::new("*.rs").unwrap(),
+                })
+                .difference(FilesetExpression::prefix_path(
+                    RepoPathBuf::from_internal_string("foo")
+                ))
+            )))
         );
+        // A quoted argument is parsed like a command-line argument
+        assert_eq!(
+            parse_with_workspace(r#"file("glob:*.rs")"#, &WorkspaceId::default()),
+            parse_with_workspace(r#"file(glob:"*.rs")"#, &WorkspaceId::default())
+        );
+        assert_eq!(
+            parse_with_workspace(r#"file("a&b")"#, &WorkspaceId::default()),
+            parse_with_workspace("file(a & b)", &WorkspaceId::default())
+        );
+        assert_eq!(
+            parse_with_workspace(r#"file("foo bar")"#, &WorkspaceId::default()),
+            Ok(RevsetExpression::filter(RevsetFilterPredicate::File(
+                FilesetExpression::prefix_path(RepoPathBuf::from_internal_string("foo bar"))
+            )))
+        );
+        assert_eq!(
+            parse_with_workspace(r#"file(file:"a&b")"#, &WorkspaceId::default()),
+            Ok(RevsetExpression::filter(RevsetFilterPredicate::File(
+                FilesetExpression::file_path(RepoPathBuf::from_internal_string("a&b"))
+            )))
+        );
+        assert_eq!(
+            parse_with_workspace("diff_contains(foo)", &WorkspaceId::default()),
+            Ok(RevsetExpression::filter(
+                RevsetFilterPredicate::DiffContains {
+                    text: StringPattern::Substring("foo".to_owned()),
+                    files: FilesetExpression::all(),
+                }
+            ))
+        );
+        assert_eq!(
+            parse_with_workspace("diff_contains(exact:foo, bar)", &WorkspaceId::default()),
+            Ok(RevsetExpression::filter(
+                RevsetFilterPredicate::DiffContains {
+                    text: StringPattern::Exact("foo".to_owned()),
+                    files: FilesetExpression::prefix_path(RepoPathBuf::from_internal_string("bar")),
+                }
+            ))
+        );
+        assert_eq!(
+            parse("diff_contains(foo, bar)"),
+            Err(RevsetParseErrorKind::FsPathWithoutWorkspace)
+        );
+        assert!(parse_with_workspace("diff_contains()", &WorkspaceId::default()).is_err());
     }
 
     #[test]
@@ -3192,6 +3400,10 @@ mod tests {
         );
 
         // Alias can be substituted to string literal.
+        assert_eq!(
+            parse_with_aliases("author(A)", [("A", "a")]).unwrap(),
+            parse("author(a)").unwrap()
+        );
         assert_eq!(
             parse_with_aliases_and_workspace("file(A)", [("A", "a")], &WorkspaceId::default())
                 .unwrap(),
@@ -3281,6 +3493,17 @@ mod tests {
             parse("(x|(x|a))&y").unwrap()
         );
 
+        // Function parameter can be substituted to file path.
+        assert_eq!(
+            parse_with_aliases_and_workspace(
+                "F(a)",
+                [("F(x)", "file(x)")],
+                &WorkspaceId::default()
+            )
+            .unwrap(),
+            parse_with_workspace("file(a)", &WorkspaceId::default()).unwrap()
+        );
+
         // Function parameter should precede the symbol alias.
         assert_eq!(
             parse_with_aliases("F(a)|X", [("F(X)", "X"), ("X", "x")]).unwrap(),
@@ -3718,7 +3941,7 @@ mod tests {
         insta::assert_debug_snapshot!(optimize(parse("~empty()").unwrap()), @r###"
         Filter(
             File(
-                None,
+                All,
             ),
         )
         "###);
@@ -3955,15 +4178,71 @@ mod tests {
             ),
             Filter(
                 File(
-                    Some(
-                        [
+                    Pattern(
+                        PrefixPath(
                             "bar",
-                        ],
+                        ),
                     ),
                 ),
             ),
         )
         "###);
+        insta::assert_debug_snapshot!(
+            optimize(parse_with_workspace("diff_contains(foo) & description(bar) & baz", &WorkspaceId::default()).unwrap()), @r###"
+        Intersection(
+            Intersection(
+                CommitRef(
+                    Symbol(
+                        "baz",
+                    ),
+                ),
+                Filter(
+                    Description(
+                        Substring(
+                            "bar",
+                        ),
+                    ),
+                ),
+            ),
+            Filter(
+                DiffContains {
+                    text: Substring(
+                        "foo",
+                    ),
+                    files: All,
+                },
+            ),
+        )
+        "###);
+        insta::assert_debug_snapshot!(
+            optimize(parse_with_workspace("description(foo) & diff_contains(bar) & author(baz)", &WorkspaceId::default()).unwrap()), @r###"
+        Intersection(
+            Intersection(
+                Filter(
+                    Description(
+                        Substring(
+                            "foo",
+                        ),
+                    ),
+                ),
+                Filter(
+                    Author(
+                        Substring(
+                            "baz",
+                        ),
+                    ),
+                ),
+            ),
+            Filter(
+                DiffContains {
+                    text: Substring(
+                        "bar",
+                    ),
+                    files: All,
+                },
+            ),
+        )
+        "###);
         insta::assert_debug_snapshot!(
             optimize(parse_with_workspace("committer(foo) & file(bar) & author(baz)", &WorkspaceId::default()).unwrap()), @r###"
         Intersection(
@@ -3977,10 +4256,10 @@ mod tests {
                 ),
                 Filter(
                     File(
-                        Some(
-                            [
+                        Pattern(
+                            PrefixPath(
                                 "bar",
-                            ],
+                            ),
                         ),
                     ),
                 ),
@@ -4010,10 +4289,10 @@ mod tests {
             ),
             Filter(
                 File(
-                    Some(
-                        [
+                    Pattern(
+                        PrefixPath(
                             "bar",
-                        ],
+                        ),
                     ),
                 ),
             ),
@@ -22,7 +22,9 @@ use std::sync::RwLock;
 use thiserror::Error;
 
 use crate::backend::CommitId;
+use crate::gpg_signing::GpgBackend;
 use crate::settings::UserSettings;
+use crate::ssh_signing::SshBackend;
 
 /// A status of the signature, part of the [Verification] type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -151,9 +153,11 @@ impl Signer {
     /// chooses one of them to be used for signing depending on the config.
     pub fn from_settings(settings: &UserSettings) -> Result<Self, SignInitError> {
         let mut backends: Vec<Box<dyn SigningBackend>> = vec![
-            // Box::new(GpgBackend::from_settings(settings)?),
-            // Box::new(SshBackend::from_settings(settings)?),
+            Box::new(GpgBackend::from_config(settings.config())),
+            Box::new(SshBackend::from_config(settings.config())),
             // Box::new(X509Backend::from_settings(settings)?),
+            #[cfg(feature = "testing")]
+            Box::new(crate::test_signing_backend::TestSigningBackend),
         ];
 
         let main_backend = settings
@@ -0,0 +1,327 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signing backend that shells out to `ssh-keygen -Y`, compatible with Git's
+//! `gpg.format = ssh`.
+
+use std::ffi::{OsStr, OsString};
+use std::fmt::Debug;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+
+use either::Either;
+use thiserror::Error;
+
+use crate::signing::{SigStatus, SignError, SignResult, SigningBackend, Verification};
+
+/// The namespace Git uses for commit signatures. Signatures made for another
+/// namespace don't verify.
+const NAMESPACE: &str = "git";
+
+/// A signing backend that creates and verifies SSH signatures with an
+/// external `ssh-keygen` program.
+#[derive(Debug)]
+pub struct SshBackend {
+    program: OsString,
+    allowed_signers: Option<OsString>,
+}
+
+/// An error while running the `ssh-keygen` program.
+#[derive(Debug, Error)]
+pub enum SshError {
+    /// The `ssh-keygen` program exited unsuccessfully.
+    #[error("SSH sign failed with {exit_status}:\n{stderr}")]
+    Command {
+        /// The exit status of the program.
+        exit_status: ExitStatus,
+        /// The standard error output of the program.
+        stderr: String,
+    },
+    /// The output of the program couldn't be parsed.
+    #[error("Failed to parse ssh program response")]
+    BadResult,
+    /// The program couldn't be run, or communicating with it failed.
+    #[error("Failed to run ssh-keygen: {0}")]
+    Io(#[from] std::io::Error),
+    /// SSH signing needs an explicit key.
+    #[error("Signing key required")]
+    MissingKey,
+}
+
+impl From<SshError> for SignError {
+    fn from(e: SshError) -> Self {
+        SignError::Backend(Box::new(e))
+    }
+}
+
+type SshResult<T> = Result<T, SshError>;
+
+fn parse_utf8_string(data: Vec<u8>) -> SshResult<String> {
+    String::from_utf8(data).map_err(|_| SshError::BadResult)
+}
+
+fn run_command(command: &mut Command, stdin: &[u8]) -> SshResult<Vec<u8>> {
+    tracing::info!(?command, "running SSH signing command");
+    let process = command.spawn()?;
+    let write_result = process.stdin.as_ref().unwrap().write_all(stdin);
+    let output = process.wait_with_output()?;
+    tracing::info!(?command, ?output.status, "SSH signing command exited");
+    if output.status.success() {
+        write_result?;
+        Ok(output.stdout)
+    } else {
+        Err(SshError::Command {
+            exit_status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim_end().into(),
+        })
+    }
+}
+
+/// Expands a leading `~/` to the home directory.
+fn expand_home_path(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = std::env::var_os("HOME") {
+            return Path::new(&home).join(rest);
+        }
+    }
+    PathBuf::from(path)
+}
+
+/// Converts the key data into a file and returns its path. If the key is
+/// already a path to a key on disk, it's returned as is.
+///
+/// Like Git, a key that starts with `ssh-` is a public key itself rather
+/// than a path. `ssh-keygen` then uses the corresponding private key from the
+/// SSH agent.
+fn ensure_key_as_file(key: &str) -> SshResult<Either<PathBuf, tempfile::TempPath>> {
+    let is_inlined_ssh_key = key.starts_with("ssh-");
+    if !is_inlined_ssh_key {
+        return Ok(Either::Left(expand_home_path(key)));
+    }
+
+    let mut pub_key_file = tempfile::Builder::new()
+        .prefix("jj-signing-key-")
+        .tempfile()?;
+    pub_key_file.write_all(key.as_bytes())?;
+    pub_key_file.flush()?;
+
+    // Convert into a TempPath so that the underlying file handle is closed. On
+    // Windows this is required for other programs to be able to read the
+    // file.
+    Ok(Either::Right(pub_key_file.into_temp_path()))
+}
+
+/// Extracts the key fingerprint from the "Good signature ... with ED25519 key
+/// SHA256:..." message of `ssh-keygen -Y verify`.
+fn parse_key_fingerprint(output: &str) -> Option<String> {
+    let line = output.lines().find(|line| line.starts_with("Good "))?;
+    let (_, fingerprint) = line.rsplit_once(" key ")?;
+    Some(fingerprint.trim().to_owned())
+}
+
+impl SshBackend {
+    /// Creates a backend that runs `program`. Signatures are verified against
+    /// the `allowed_signers` file, if any.
+    pub fn new(program: OsString, allowed_signers: Option<OsString>) -> Self {
+        Self {
+            program,
+            allowed_signers,
+        }
+    }
+
+    /// Creates a backend from the `signing.backends.ssh` config table.
+    pub fn from_config(config: &config::Config) -> Self {
+        let program = config
+            .get_string("signing.backends.ssh.program")
+            .unwrap_or_else(|_| "ssh-keygen".into());
+        let allowed_signers = config
+            .get_string("signing.backends.ssh.allowed-signers")
+            .ok()
+            .map(|path| expand_home_path(&path).into_os_string());
+        Self::new(program.into(), allowed_signers)
+    }
+
+    fn create_command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+
+    /// Looks up the principal (usually an email address) the signing key
+    /// belongs to in the allowed signers file.
+    fn find_principal(&self, signature_file_path: &Path) -> SshResult<Option<String>> {
+        let Some(allowed_signers) = &self.allowed_signers else {
+            return Ok(None);
+        };
+
+        let mut command = self.create_command();
+        command
+            .arg("-Y")
+            .arg("find-principals")
+            .arg("-f")
+            .arg(allowed_signers)
+            .arg("-s")
+            .arg(signature_file_path);
+
+        // `-Y find-principals` exits with a non-zero code if no principals are
+        // found, which isn't an error here.
+        tracing::info!(?command, "running SSH signing command");
+        let output = command.stdin(Stdio::null()).output()?;
+        tracing::info!(?command, ?output.status, "SSH signing command exited");
+
+        let principal = parse_utf8_string(output.stdout)?
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_owned();
+        Ok((!principal.is_empty()).then_some(principal))
+    }
+}
+
+impl SigningBackend for SshBackend {
+    fn name(&self) -> &str {
+        "ssh"
+    }
+
+    fn can_read(&self, signature: &[u8]) -> bool {
+        signature.starts_with(b"-----BEGIN SSH SIGNATURE-----")
+    }
+
+    fn sign(&self, data: &[u8], key: Option<&str>) -> SignResult<Vec<u8>> {
+        let Some(key) = key else {
+            return Err(SshError::MissingKey.into());
+        };
+
+        // The `-f` flag expects a file containing either a private or a public
+        // key, but we also accept public keys inlined in the config.
+        let key_path = ensure_key_as_file(key)?;
+        let key_path: &OsStr = match &key_path {
+            Either::Left(path) => path.as_os_str(),
+            Either::Right(path) => path.as_os_str(),
+        };
+
+        let mut command = self.create_command();
+        command
+            .arg("-Y")
+            .arg("sign")
+            .arg("-f")
+            .arg(key_path)
+            .arg("-n")
+            .arg(NAMESPACE);
+        Ok(run_command(&mut command, data)?)
+    }
+
+    fn verify(&self, data: &[u8], signature: &[u8]) -> SignResult<Verification> {
+        let mut signature_file = tempfile::Builder::new()
+            .prefix(".jj-ssh-sig-")
+            .tempfile()
+            .map_err(SshError::Io)?;
+        signature_file.write_all(signature).map_err(SshError::Io)?;
+        signature_file.flush().map_err(SshError::Io)?;
+        let signature_file_path = signature_file.into_temp_path();
+
+        let principal = self.find_principal(&signature_file_path)?;
+        let mut command = self.create_command();
+        match (principal, self.allowed_signers.as_ref()) {
+            (Some(principal), Some(allowed_signers)) => {
+                command
+                    .arg("-Y")
+                    .arg("verify")
+                    .arg("-s")
+                    .arg(&signature_file_path)
+                    .arg("-I")
+                    .arg(&principal)
+                    .arg("-f")
+                    .arg(allowed_signers)
+                    .arg("-n")
+                    .arg(NAMESPACE);
+                match run_command(&mut command, data) {
+                    Ok(output) => Ok(Verification {
+                        status: SigStatus::Good,
+                        key: parse_key_fingerprint(&String::from_utf8_lossy(&output)),
+                        display: Some(principal),
+                    }),
+                    Err(SshError::Command { .. }) => Ok(Verification {
+                        status: SigStatus::Bad,
+                        key: None,
+                        display: Some(principal),
+                    }),
+                    Err(err) => Err(err.into()),
+                }
+            }
+            _ => {
+                // Without a known principal, we can still check that the
+                // signature matches the data.
+                command
+                    .arg("-Y")
+                    .arg("check-novalidate")
+                    .arg("-s")
+                    .arg(&signature_file_path)
+                    .arg("-n")
+                    .arg(NAMESPACE);
+                match run_command(&mut command, data) {
+                    Ok(output) => Ok(Verification {
+                        status: SigStatus::Unknown,
+                        key: parse_key_fingerprint(&String::from_utf8_lossy(&output)),
+                        display: None,
+                    }),
+                    Err(SshError::Command { .. }) => Ok(Verification {
+                        status: SigStatus::Bad,
+                        key: None,
+                        display: None,
+                    }),
+                    Err(err) => Err(err.into()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_fingerprint() {
+        assert_eq!(
+            parse_key_fingerprint(
+                "Good \"git\" signature for test@example.com with ED25519 key SHA256:3WH4xn+Ss\n"
+            ),
+            Some("SHA256:3WH4xn+Ss".to_owned())
+        );
+        assert_eq!(
+            parse_key_fingerprint("Good \"git\" signature with RSA key SHA256:abc"),
+            Some("SHA256:abc".to_owned())
+        );
+        assert_eq!(parse_key_fingerprint("Could not verify signature."), None);
+    }
+
+    #[test]
+    fn test_ensure_key_as_file() {
+        let path = ensure_key_as_file("ssh-ed25519 AAAA test@example.com").unwrap();
+        let path = path.right().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "ssh-ed25519 AAAA test@example.com"
+        );
+
+        let path = ensure_key_as_file("/path/to/key").unwrap();
+        assert_eq!(path.left().unwrap(), Path::new("/path/to/key"));
+    }
+}
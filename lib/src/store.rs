@@ -226,6 +226,15 @@ impl Store {
         self.backend.read_file(path, id).await
     }
 
+    pub fn file_size(&self, path: &RepoPath, id: &FileId) -> BackendResult<u64> {
+        self.backend.file_size(path, id).block_on()
+    }
+
+    /// Prepares the files to be read. See `Backend::prefetch_files()`.
+    pub fn prefetch_files(&self, ids: &[FileId]) -> BackendResult<()> {
+        self.backend.prefetch_files(ids)
+    }
+
     pub fn write_file(&self, path: &RepoPath, contents: &mut dyn Read) -> BackendResult<FileId> {
         self.backend.write_file(path, contents)
     }
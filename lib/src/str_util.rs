@@ -30,18 +30,31 @@ pub enum StringPatternParseError {
     /// Failed to parse glob pattern.
     #[error(transparent)]
     GlobPattern(glob::PatternError),
+    /// Failed to parse regular expression.
+    #[error(transparent)]
+    Regex(regex::Error),
 }
 
 /// Pattern to be tested against string property like commit description or
 /// branch name.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum StringPattern {
     /// Matches strings exactly equal to `string`.
     Exact(String),
+    /// Matches strings that are case-insensitively equal to `string`.
+    ExactI(String),
     /// Unix-style shell wildcard pattern.
     Glob(glob::Pattern),
+    /// Unix-style shell wildcard pattern, matched case-insensitively.
+    GlobI(glob::Pattern),
     /// Matches strings that contain `substring`.
     Substring(String),
+    /// Matches strings that case-insensitively contain `substring`.
+    SubstringI(String),
+    /// Matches substrings with a regular expression.
+    Regex(regex::Regex),
+    /// Matches substrings with a case-insensitive regular expression.
+    RegexI(regex::Regex),
 }
 
 impl StringPattern {
@@ -64,12 +77,49 @@ impl StringPattern {
         Ok(StringPattern::Glob(pattern))
     }
 
+    /// Parses the given string as case-insensitive glob pattern.
+    pub fn glob_i(src: &str) -> Result<Self, StringPatternParseError> {
+        let pattern = glob::Pattern::new(src).map_err(StringPatternParseError::GlobPattern)?;
+        Ok(StringPattern::GlobI(pattern))
+    }
+
+    /// Parses the given string as regular expression.
+    pub fn regex(src: &str) -> Result<Self, StringPatternParseError> {
+        let pattern = regex::Regex::new(src).map_err(StringPatternParseError::Regex)?;
+        Ok(StringPattern::Regex(pattern))
+    }
+
+    /// Parses the given string as case-insensitive regular expression.
+    pub fn regex_i(src: &str) -> Result<Self, StringPatternParseError> {
+        let pattern = regex::RegexBuilder::new(src)
+            .case_insensitive(true)
+            .build()
+            .map_err(StringPatternParseError::Regex)?;
+        Ok(StringPattern::RegexI(pattern))
+    }
+
+    /// Parses the given string as a `StringPattern`. Everything before the
+    /// first ":" is considered the pattern kind. If no kind is specified, the
+    /// string is matched exactly.
+    pub fn parse(src: &str) -> Result<Self, StringPatternParseError> {
+        if let Some((kind, pat)) = src.split_once(':') {
+            StringPattern::from_str_kind(pat, kind)
+        } else {
+            Ok(StringPattern::exact(src))
+        }
+    }
+
     /// Parses the given string as pattern of the specified `kind`.
     pub fn from_str_kind(src: &str, kind: &str) -> Result<Self, StringPatternParseError> {
         match kind {
             "exact" => Ok(StringPattern::exact(src)),
+            "exact-i" => Ok(StringPattern::ExactI(src.to_owned())),
             "glob" => StringPattern::glob(src),
+            "glob-i" => StringPattern::glob_i(src),
             "substring" => Ok(StringPattern::Substring(src.to_owned())),
+            "substring-i" => Ok(StringPattern::SubstringI(src.to_owned())),
+            "regex" => StringPattern::regex(src),
+            "regex-i" => StringPattern::regex_i(src),
             _ => Err(StringPatternParseError::InvalidKind(kind.to_owned())),
         }
     }
@@ -85,7 +135,7 @@ impl StringPattern {
     pub fn as_exact(&self) -> Option<&str> {
         match self {
             StringPattern::Exact(literal) => Some(literal),
-            StringPattern::Glob(_) | StringPattern::Substring(_) => None,
+            _ => None,
         }
     }
 
@@ -93,15 +143,19 @@ impl StringPattern {
     pub fn as_str(&self) -> &str {
         match self {
             StringPattern::Exact(literal) => literal,
+            StringPattern::ExactI(literal) => literal,
             StringPattern::Glob(pattern) => pattern.as_str(),
+            StringPattern::GlobI(pattern) => pattern.as_str(),
             StringPattern::Substring(needle) => needle,
+            StringPattern::SubstringI(needle) => needle,
+            StringPattern::Regex(pattern) => pattern.as_str(),
+            StringPattern::RegexI(pattern) => pattern.as_str(),
         }
     }
 
     /// Converts this pattern to a glob string. Returns `None` if the pattern
     /// can't be represented as a glob.
     pub fn to_glob(&self) -> Option<Cow<'_, str>> {
-        // TODO: If we add Regex pattern, it will return None.
         match self {
             StringPattern::Exact(literal) => Some(glob::Pattern::escape(literal).into()),
             StringPattern::Glob(pattern) => Some(pattern.as_str().into()),
@@ -109,6 +163,13 @@ impl StringPattern {
             StringPattern::Substring(needle) => {
                 Some(format!("*{}*", glob::Pattern::escape(needle)).into())
             }
+            // Case-insensitive patterns and regular expressions can't be
+            // translated to glob.
+            StringPattern::ExactI(_)
+            | StringPattern::GlobI(_)
+            | StringPattern::SubstringI(_)
+            | StringPattern::Regex(_)
+            | StringPattern::RegexI(_) => None,
         }
     }
 
@@ -116,8 +177,22 @@ impl StringPattern {
     pub fn matches(&self, haystack: &str) -> bool {
         match self {
             StringPattern::Exact(literal) => haystack == literal,
+            StringPattern::ExactI(literal) => haystack.to_lowercase() == literal.to_lowercase(),
             StringPattern::Glob(pattern) => pattern.matches(haystack),
+            StringPattern::GlobI(pattern) => pattern.matches_with(
+                haystack,
+                glob::MatchOptions {
+                    case_sensitive: false,
+                    ..glob::MatchOptions::new()
+                },
+            ),
             StringPattern::Substring(needle) => haystack.contains(needle),
+            StringPattern::SubstringI(needle) => {
+                haystack.to_lowercase().contains(&needle.to_lowercase())
+            }
+            StringPattern::Regex(pattern) | StringPattern::RegexI(pattern) => {
+                pattern.is_match(haystack)
+            }
         }
     }
 
@@ -132,8 +207,31 @@ impl StringPattern {
             Either::Right(map.iter().filter(|&(key, _)| self.matches(key.borrow())))
         }
     }
+
+    fn kind_name(&self) -> &'static str {
+        match self {
+            StringPattern::Exact(_) => "exact",
+            StringPattern::ExactI(_) => "exact-i",
+            StringPattern::Glob(_) => "glob",
+            StringPattern::GlobI(_) => "glob-i",
+            StringPattern::Substring(_) => "substring",
+            StringPattern::SubstringI(_) => "substring-i",
+            StringPattern::Regex(_) => "regex",
+            StringPattern::RegexI(_) => "regex-i",
+        }
+    }
 }
 
+// regex::Regex doesn't implement PartialEq. Patterns are compared by kind and
+// source string instead.
+impl PartialEq for StringPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind_name() == other.kind_name() && self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for StringPattern {}
+
 impl fmt::Display for StringPattern {
     /// Shows the original string of this pattern.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -162,5 +260,72 @@ mod tests {
             StringPattern::Substring("*".into()).to_glob(),
             Some("*[*]*".into())
         );
+        assert_eq!(StringPattern::SubstringI("a".into()).to_glob(), None);
+        assert_eq!(StringPattern::regex("a").unwrap().to_glob(), None);
+    }
+
+    #[test]
+    fn test_string_pattern_matches() {
+        let parse = |src: &str, kind: &str| StringPattern::from_str_kind(src, kind).unwrap();
+        assert!(parse("foo", "exact").matches("foo"));
+        assert!(!parse("foo", "exact").matches("Foo"));
+        assert!(parse("foo", "exact-i").matches("Foo"));
+        assert!(!parse("foo", "exact-i").matches("foobar"));
+
+        assert!(parse("o", "substring").matches("foo"));
+        assert!(!parse("O", "substring").matches("foo"));
+        assert!(parse("O", "substring-i").matches("foo"));
+        assert!(parse("Ä", "substring-i").matches("bär"));
+
+        assert!(parse("f*", "glob").matches("foo"));
+        assert!(!parse("F*", "glob").matches("foo"));
+        assert!(parse("F*", "glob-i").matches("foo"));
+
+        // Regex matches substring unless anchored
+        assert!(parse("o+", "regex").matches("foo"));
+        assert!(!parse("^o+", "regex").matches("foo"));
+        assert!(parse("^(feat|fix)\\(", "regex").matches("fix(cli): foo"));
+        assert!(!parse("^(feat|fix)\\(", "regex").matches("Fix(cli): foo"));
+        assert!(parse("^(feat|fix)\\(", "regex-i").matches("Fix(cli): foo"));
+    }
+
+    #[test]
+    fn test_string_pattern_parse() {
+        assert_eq!(
+            StringPattern::parse("foo").unwrap(),
+            StringPattern::exact("foo")
+        );
+        assert_eq!(
+            StringPattern::parse("substring-i:foo:bar").unwrap(),
+            StringPattern::SubstringI("foo:bar".into())
+        );
+        assert_eq!(
+            StringPattern::parse("regex:^a+$").unwrap(),
+            StringPattern::regex("^a+$").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_string_pattern_parse_error() {
+        assert!(matches!(
+            StringPattern::from_str_kind("foo", "bad"),
+            Err(StringPatternParseError::InvalidKind(_))
+        ));
+        assert!(matches!(
+            StringPattern::from_str_kind("[", "glob-i"),
+            Err(StringPatternParseError::GlobPattern(_))
+        ));
+        assert!(matches!(
+            StringPattern::from_str_kind("(", "regex"),
+            Err(StringPatternParseError::Regex(_))
+        ));
+    }
+
+    #[test]
+    fn test_string_pattern_eq() {
+        let parse = |src: &str, kind: &str| StringPattern::from_str_kind(src, kind).unwrap();
+        assert_eq!(parse("a+", "regex"), parse("a+", "regex"));
+        assert_ne!(parse("a+", "regex"), parse("a+", "regex-i"));
+        assert_ne!(parse("a+", "regex"), parse("a+", "glob"));
     }
 }
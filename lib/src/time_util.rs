@@ -0,0 +1,360 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing and matching of date ranges.
+
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone,
+};
+use thiserror::Error;
+
+use crate::backend::{MillisSinceEpoch, Timestamp};
+
+/// Error occurred during date pattern parsing.
+#[derive(Debug, Error)]
+pub enum DatePatternParseError {
+    /// Unknown pattern kind is specified.
+    #[error(r#"Invalid date pattern kind "{0}""#)]
+    InvalidKind(String),
+    /// Failed to parse the date.
+    #[error(r#"Invalid date "{0}""#)]
+    InvalidDate(String),
+}
+
+/// Represents a range of dates that may be matched against.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DatePattern {
+    /// Represents all dates at or after the given instant.
+    AtOrAfter(MillisSinceEpoch),
+    /// Represents all dates before, but not including, the given instant.
+    Before(MillisSinceEpoch),
+}
+
+impl DatePattern {
+    /// Returns true if the given timestamp is in this range.
+    pub fn matches(&self, timestamp: &Timestamp) -> bool {
+        match self {
+            DatePattern::AtOrAfter(earliest) => earliest <= &timestamp.timestamp,
+            DatePattern::Before(latest) => &timestamp.timestamp < latest,
+        }
+    }
+}
+
+/// Context needed to resolve relative dates and dates without time zone.
+#[derive(Copy, Clone, Debug)]
+pub enum DatePatternContext {
+    /// Dates are resolved in the local time zone, so a date on the other side
+    /// of a daylight saving time transition gets its own UTC offset.
+    Local(DateTime<Local>),
+    /// Dates are resolved in the fixed UTC offset of `now`.
+    Fixed(DateTime<FixedOffset>),
+}
+
+impl DatePatternContext {
+    /// Creates context which resolves dates relative to `now`. Dates without
+    /// explicit time zone are interpreted in the time zone of `now`.
+    pub fn new(now: DateTime<FixedOffset>) -> Self {
+        DatePatternContext::Fixed(now)
+    }
+
+    /// Creates context for the current local time.
+    pub fn from_current_time() -> Self {
+        DatePatternContext::Local(Local::now())
+    }
+
+    /// Parses the given string as date pattern of the specified `kind`.
+    ///
+    /// The date may be an absolute date such as `2024-01-31`,
+    /// `2024-01-31 12:34`, or `2024-01-31 12:34:56.789 +09:00`, or a relative
+    /// date such as `now`, `yesterday`, or `2 weeks ago`.
+    pub fn parse_relative(
+        &self,
+        src: &str,
+        kind: &str,
+    ) -> Result<DatePattern, DatePatternParseError> {
+        let datetime = match self {
+            DatePatternContext::Local(now) => parse_datetime(src, now)?,
+            DatePatternContext::Fixed(now) => parse_datetime(src, now)?,
+        };
+        let millis = MillisSinceEpoch(datetime.timestamp_millis());
+        match kind {
+            "after" => Ok(DatePattern::AtOrAfter(millis)),
+            "before" => Ok(DatePattern::Before(millis)),
+            _ => Err(DatePatternParseError::InvalidKind(kind.to_owned())),
+        }
+    }
+}
+
+fn parse_datetime<Tz: TimeZone>(
+    src: &str,
+    now: &DateTime<Tz>,
+) -> Result<DateTime<FixedOffset>, DatePatternParseError> {
+    let make_error = || DatePatternParseError::InvalidDate(src.to_owned());
+    let text = src.trim().to_ascii_lowercase();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    match words.as_slice() {
+        ["now"] => return Ok(now.fixed_offset()),
+        ["today"] => return resolve_local(now, now.date_naive().into()),
+        ["yesterday"] => {
+            let date = now.date_naive() - Duration::days(1);
+            return resolve_local(now, date.into());
+        }
+        [count, unit, "ago"] => {
+            let count: i64 = count.parse().map_err(|_| make_error())?;
+            // Days and weeks are counted in local time, so "1 day ago" is the
+            // same time of day even if daylight saving time started since.
+            if let Some(unit_millis) = parse_calendar_unit_millis(unit) {
+                let millis = unit_millis.checked_mul(count).ok_or_else(make_error)?;
+                let datetime = now
+                    .naive_local()
+                    .checked_sub_signed(Duration::milliseconds(millis))
+                    .ok_or_else(make_error)?;
+                return resolve_local(now, datetime);
+            }
+            let unit_millis = parse_duration_unit_millis(unit).ok_or_else(make_error)?;
+            let millis = unit_millis.checked_mul(count).ok_or_else(make_error)?;
+            return now
+                .clone()
+                .checked_sub_signed(Duration::milliseconds(millis))
+                .map(|datetime| datetime.fixed_offset())
+                .ok_or_else(make_error);
+        }
+        _ => {}
+    }
+
+    let src = src.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(src) {
+        return Ok(datetime);
+    }
+    // Format used by "jj log" to display absolute timestamps
+    if let Ok(datetime) = DateTime::parse_from_str(src, "%Y-%m-%d %H:%M:%S%.f %:z") {
+        return Ok(datetime);
+    }
+    for format in [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+    ] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(src, format) {
+            return resolve_local(now, datetime);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(src, "%Y-%m-%d") {
+        return resolve_local(now, date.into());
+    }
+    Err(make_error())
+}
+
+/// Resolves `datetime` in the time zone of `now`, using the UTC offset in
+/// effect at that date.
+fn resolve_local<Tz: TimeZone>(
+    now: &DateTime<Tz>,
+    datetime: NaiveDateTime,
+) -> Result<DateTime<FixedOffset>, DatePatternParseError> {
+    match now.timezone().from_local_datetime(&datetime) {
+        LocalResult::Single(datetime) | LocalResult::Ambiguous(datetime, _) => {
+            Ok(datetime.fixed_offset())
+        }
+        LocalResult::None => Err(DatePatternParseError::InvalidDate(datetime.to_string())),
+    }
+}
+
+const SECOND: i64 = 1000;
+
+fn parse_calendar_unit_millis(unit: &str) -> Option<i64> {
+    const DAY: i64 = 24 * 60 * 60 * SECOND;
+    let unit = unit.strip_suffix('s').unwrap_or(unit);
+    match unit {
+        "day" => Some(DAY),
+        "week" => Some(7 * DAY),
+        _ => None,
+    }
+}
+
+fn parse_duration_unit_millis(unit: &str) -> Option<i64> {
+    const MONTH: i64 = 2_628_003 * SECOND;
+    let unit = unit.strip_suffix('s').unwrap_or(unit);
+    match unit {
+        "second" => Some(SECOND),
+        "minute" => Some(60 * SECOND),
+        "hour" => Some(60 * 60 * SECOND),
+        // Same approximations as the timeago crate, which is used to format
+        // relative timestamps
+        "month" => Some(MONTH),
+        "year" => Some(12 * MONTH),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Datelike as _;
+
+    use super::*;
+
+    fn context() -> DatePatternContext {
+        let now = DateTime::parse_from_rfc3339("2024-01-31T12:00:00+09:00").unwrap();
+        DatePatternContext::new(now)
+    }
+
+    fn millis(src: &str) -> MillisSinceEpoch {
+        MillisSinceEpoch(
+            DateTime::parse_from_rfc3339(src)
+                .unwrap()
+                .timestamp_millis(),
+        )
+    }
+
+    #[test]
+    fn test_parse_absolute() {
+        let ctx = context();
+        assert_eq!(
+            ctx.parse_relative("2024-01-01", "after").unwrap(),
+            DatePattern::AtOrAfter(millis("2024-01-01T00:00:00+09:00"))
+        );
+        assert_eq!(
+            ctx.parse_relative("2024-01-01 12:34", "before").unwrap(),
+            DatePattern::Before(millis("2024-01-01T12:34:00+09:00"))
+        );
+        assert_eq!(
+            ctx.parse_relative("2024-01-01T12:34:56", "after").unwrap(),
+            DatePattern::AtOrAfter(millis("2024-01-01T12:34:56+09:00"))
+        );
+        assert_eq!(
+            ctx.parse_relative("2024-01-01T12:34:56Z", "after").unwrap(),
+            DatePattern::AtOrAfter(millis("2024-01-01T12:34:56+00:00"))
+        );
+        assert_eq!(
+            ctx.parse_relative("2001-02-03 04:05:06.789 +07:00", "after")
+                .unwrap(),
+            DatePattern::AtOrAfter(millis("2001-02-03T04:05:06.789+07:00"))
+        );
+    }
+
+    #[test]
+    fn test_parse_relative() {
+        let ctx = context();
+        assert_eq!(
+            ctx.parse_relative("now", "before").unwrap(),
+            DatePattern::Before(millis("2024-01-31T12:00:00+09:00"))
+        );
+        assert_eq!(
+            ctx.parse_relative("Today", "after").unwrap(),
+            DatePattern::AtOrAfter(millis("2024-01-31T00:00:00+09:00"))
+        );
+        assert_eq!(
+            ctx.parse_relative("yesterday", "after").unwrap(),
+            DatePattern::AtOrAfter(millis("2024-01-30T00:00:00+09:00"))
+        );
+        assert_eq!(
+            ctx.parse_relative("1 hour ago", "after").unwrap(),
+            DatePattern::AtOrAfter(millis("2024-01-31T11:00:00+09:00"))
+        );
+        assert_eq!(
+            ctx.parse_relative(" 2 weeks  ago ", "after").unwrap(),
+            DatePattern::AtOrAfter(millis("2024-01-17T12:00:00+09:00"))
+        );
+    }
+
+    /// Time zone which is UTC+1 from April to October, and UTC otherwise.
+    #[derive(Clone, Copy, Debug)]
+    struct DstZone;
+
+    impl DstZone {
+        fn offset(month: u32) -> FixedOffset {
+            let hours = if (4..=10).contains(&month) { 1 } else { 0 };
+            FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for DstZone {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            DstZone
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            LocalResult::Single(Self::offset(local.month()))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            LocalResult::Single(Self::offset(local.month()))
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            Self::offset(utc.month())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            Self::offset(utc.month())
+        }
+    }
+
+    #[test]
+    fn test_parse_across_dst_transition() {
+        let now = DstZone.from_utc_datetime(
+            &DateTime::parse_from_rfc3339("2024-04-01T12:00:00Z")
+                .unwrap()
+                .naive_utc(),
+        );
+        let parse = |src: &str| {
+            let datetime = parse_datetime(src, &now).unwrap();
+            MillisSinceEpoch(datetime.timestamp_millis())
+        };
+        // Dates before the transition are resolved in the offset at that time
+        assert_eq!(parse("yesterday"), millis("2024-03-31T00:00:00Z"));
+        assert_eq!(parse("2024-03-01 12:00"), millis("2024-03-01T12:00:00Z"));
+        assert_eq!(parse("today"), millis("2024-04-01T00:00:00+01:00"));
+        // Days are counted in local time, but shorter units aren't
+        assert_eq!(parse("2 days ago"), millis("2024-03-30T13:00:00Z"));
+        assert_eq!(parse("1 week ago"), millis("2024-03-25T13:00:00Z"));
+        assert_eq!(parse("3 hours ago"), millis("2024-04-01T09:00:00Z"));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let ctx = context();
+        assert!(matches!(
+            ctx.parse_relative("2024-01-01", "since"),
+            Err(DatePatternParseError::InvalidKind(_))
+        ));
+        assert!(matches!(
+            ctx.parse_relative("2 fortnights ago", "after"),
+            Err(DatePatternParseError::InvalidDate(_))
+        ));
+        assert!(matches!(
+            ctx.parse_relative("2024-13-01", "after"),
+            Err(DatePatternParseError::InvalidDate(_))
+        ));
+        assert!(matches!(
+            ctx.parse_relative("9223372036854775807 days ago", "after"),
+            Err(DatePatternParseError::InvalidDate(_))
+        ));
+    }
+
+    #[test]
+    fn test_matches() {
+        let timestamp = |src: &str| Timestamp {
+            timestamp: millis(src),
+            tz_offset: 0,
+        };
+        let after = DatePattern::AtOrAfter(millis("2024-01-01T00:00:00Z"));
+        assert!(!after.matches(&timestamp("2023-12-31T23:59:59Z")));
+        assert!(after.matches(&timestamp("2024-01-01T00:00:00Z")));
+        let before = DatePattern::Before(millis("2024-01-01T00:00:00Z"));
+        assert!(before.matches(&timestamp("2023-12-31T23:59:59Z")));
+        assert!(!before.matches(&timestamp("2024-01-01T00:00:00Z")));
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Write as _;
+
+use assert_matches::assert_matches;
+use jj_lib::annotate::{get_annotation_for_file, AnnotateError};
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::repo::{MutableRepo, Repo};
+use jj_lib::repo_path::RepoPath;
+use jj_lib::settings::UserSettings;
+use testutils::{create_tree, TestRepo};
+
+fn create_commit(
+    mut_repo: &mut MutableRepo,
+    settings: &UserSettings,
+    parents: &[&Commit],
+    path_contents: &[(&RepoPath, &str)],
+) -> Commit {
+    let tree = create_tree(mut_repo.base_repo(), path_contents);
+    let parent_ids = if parents.is_empty() {
+        vec![mut_repo.store().root_commit_id().clone()]
+    } else {
+        parents.iter().map(|commit| commit.id().clone()).collect()
+    };
+    mut_repo
+        .new_commit(settings, parent_ids, tree.id())
+        .write()
+        .unwrap()
+}
+
+fn annotate(
+    repo: &dyn Repo,
+    commit: &Commit,
+    path: &RepoPath,
+    names: &[(&Commit, &str)],
+) -> String {
+    let name_of = |id: &CommitId| {
+        names
+            .iter()
+            .find(|(commit, _)| commit.id() == id)
+            .map(|(_, name)| *name)
+            .unwrap()
+    };
+    let annotation = get_annotation_for_file(repo, commit, path).unwrap();
+    let mut output = String::new();
+    for (commit_id, line) in annotation.lines() {
+        let line = std::str::from_utf8(line).unwrap();
+        write!(output, "{}: {line}", name_of(commit_id)).unwrap();
+    }
+    output
+}
+
+#[test]
+fn test_annotate_linear() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let file_path = RepoPath::from_internal_string("file");
+    let other_path = RepoPath::from_internal_string("other");
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.mut_repo();
+    let commit1 = create_commit(mut_repo, &settings, &[], &[(file_path, "1\n")]);
+    let commit2 = create_commit(
+        mut_repo,
+        &settings,
+        &[&commit1],
+        &[(file_path, "1\n2\n3\n")],
+    );
+    // Commits that don't touch the file are skipped
+    let commit3 = create_commit(
+        mut_repo,
+        &settings,
+        &[&commit2],
+        &[(file_path, "1\n2\n3\n"), (other_path, "other\n")],
+    );
+    let commit4 = create_commit(
+        mut_repo,
+        &settings,
+        &[&commit3],
+        &[(file_path, "0\n1\n2 modified\n3")],
+    );
+    let names = [
+        (&commit1, "commit1"),
+        (&commit2, "commit2"),
+        (&commit3, "commit3"),
+        (&commit4, "commit4"),
+    ];
+
+    insta::assert_snapshot!(annotate(mut_repo, &commit4, file_path, &names), @r###"
+    commit4: 0
+    commit1: 1
+    commit4: 2 modified
+    commit4: 3
+    "###);
+    insta::assert_snapshot!(annotate(mut_repo, &commit3, file_path, &names), @r###"
+    commit1: 1
+    commit2: 2
+    commit2: 3
+    "###);
+}
+
+#[test]
+fn test_annotate_merge() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let file_path = RepoPath::from_internal_string("file");
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.mut_repo();
+    let commit1 = create_commit(mut_repo, &settings, &[], &[(file_path, "1\n2\n3\n")]);
+    let commit2 = create_commit(
+        mut_repo,
+        &settings,
+        &[&commit1],
+        &[(file_path, "1\n2 left\n3\n4\n")],
+    );
+    let commit3 = create_commit(
+        mut_repo,
+        &settings,
+        &[&commit1],
+        &[(file_path, "0\n1\n2\n3\n")],
+    );
+    let commit4 = create_commit(
+        mut_repo,
+        &settings,
+        &[&commit2, &commit3],
+        &[(file_path, "0\n1\n2 left\n3\n4\nmerge\n")],
+    );
+    let names = [
+        (&commit1, "commit1"),
+        (&commit2, "commit2"),
+        (&commit3, "commit3"),
+        (&commit4, "commit4"),
+    ];
+
+    // Lines are followed into both parents of the merge
+    insta::assert_snapshot!(annotate(mut_repo, &commit4, file_path, &names), @r###"
+    commit3: 0
+    commit1: 1
+    commit2: 2 left
+    commit1: 3
+    commit2: 4
+    commit4: merge
+    "###);
+}
+
+#[test]
+fn test_annotate_file_added_and_removed() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let file_path = RepoPath::from_internal_string("file");
+    let dir_path = RepoPath::from_internal_string("dir");
+    let other_path = RepoPath::from_internal_string("dir/other");
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.mut_repo();
+    let commit1 = create_commit(mut_repo, &settings, &[], &[(file_path, "1\n")]);
+    let commit2 = create_commit(mut_repo, &settings, &[&commit1], &[(other_path, "x\n")]);
+    // Identical contents that are re-added are attributed to the re-adding
+    // commit
+    let commit3 = create_commit(
+        mut_repo,
+        &settings,
+        &[&commit2],
+        &[(file_path, "1\n"), (other_path, "x\n")],
+    );
+    let names = [
+        (&commit1, "commit1"),
+        (&commit2, "commit2"),
+        (&commit3, "commit3"),
+    ];
+
+    insta::assert_snapshot!(annotate(mut_repo, &commit3, file_path, &names), @r###"
+    commit3: 1
+    "###);
+    assert_matches!(
+        get_annotation_for_file(mut_repo, &commit2, file_path),
+        Err(AnnotateError::PathNotFound)
+    );
+    assert_matches!(
+        get_annotation_for_file(mut_repo, &commit3, dir_path),
+        Err(AnnotateError::NotAFile)
+    );
+}
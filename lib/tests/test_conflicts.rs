@@ -15,6 +15,7 @@
 use jj_lib::backend::FileId;
 use jj_lib::conflicts::{
     extract_as_single_hunk, materialize_merge_result, parse_conflict, update_from_content,
+    MergeDriver,
 };
 use jj_lib::merge::Merge;
 use jj_lib::repo::Repo;
@@ -663,7 +664,7 @@ fn test_update_conflict_from_content() {
     // old conflict id back.
     let materialized = materialize_conflict_string(store, path, &conflict);
     let parse = |content| {
-        update_from_content(&conflict, store, path, content)
+        update_from_content(&conflict, store, path, MergeDriver::Text, content)
             .block_on()
             .unwrap()
     };
@@ -712,7 +713,7 @@ fn test_update_conflict_from_content_modify_delete() {
     // old conflict id back.
     let materialized = materialize_conflict_string(store, path, &conflict);
     let parse = |content| {
-        update_from_content(&conflict, store, path, content)
+        update_from_content(&conflict, store, path, MergeDriver::Text, content)
             .block_on()
             .unwrap()
     };
@@ -0,0 +1,255 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::backend::CopyRecord;
+use jj_lib::copies::{diff_with_copies, CopiesTreeDiffEntry, CopyDetectionOptions};
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPath;
+use test_case::test_case;
+use testutils::{create_tree, TestRepo, TestRepoBackend};
+
+fn format_entries(entries: &[CopiesTreeDiffEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .map(|entry| {
+            let target = entry.target.as_internal_file_string();
+            match &entry.source {
+                Some(source) => format!(
+                    "{:?} {} => {target} ({}%)",
+                    source.operation,
+                    source.path.as_internal_file_string(),
+                    source.similarity,
+                ),
+                None => target.to_owned(),
+            }
+        })
+        .collect()
+}
+
+fn diff(
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    copy_records: &[CopyRecord],
+    options: &CopyDetectionOptions,
+) -> Vec<String> {
+    let entries = diff_with_copies(
+        from_tree,
+        to_tree,
+        &EverythingMatcher,
+        copy_records,
+        options,
+    )
+    .unwrap();
+    format_entries(&entries)
+}
+
+#[test]
+fn test_detect_renames() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let exact_before = RepoPath::from_internal_string("exact");
+    let exact_after = RepoPath::from_internal_string("dir/exact");
+    let similar_before = RepoPath::from_internal_string("similar");
+    let similar_after = RepoPath::from_internal_string("similar2");
+    let removed = RepoPath::from_internal_string("removed");
+    let added = RepoPath::from_internal_string("added");
+    let empty_removed = RepoPath::from_internal_string("empty-removed");
+    let empty_added = RepoPath::from_internal_string("empty-added");
+
+    let tree1 = create_tree(
+        repo,
+        &[
+            (exact_before, "exact\n"),
+            (similar_before, "a\nb\nc\nd\n"),
+            (removed, "removed\n"),
+            (empty_removed, ""),
+        ],
+    );
+    let tree2 = create_tree(
+        repo,
+        &[
+            (exact_after, "exact\n"),
+            (similar_after, "a\nb\nc\nD\n"),
+            (added, "added\n"),
+            (empty_added, ""),
+        ],
+    );
+
+    let options = CopyDetectionOptions::default();
+    insta::assert_debug_snapshot!(diff(&tree1, &tree2, &[], &options), @r###"
+    [
+        "added",
+        "Rename exact => dir/exact (100%)",
+        "empty-added",
+        "empty-removed",
+        "removed",
+        "Rename similar => similar2 (75%)",
+    ]
+    "###);
+
+    // The values before are those of the source files
+    let entries = diff_with_copies(&tree1, &tree2, &EverythingMatcher, &[], &options).unwrap();
+    let entry = entries
+        .iter()
+        .find(|entry| entry.target.as_ref() == similar_after);
+    let (before, after) = entry.unwrap().values.as_ref().unwrap();
+    assert_eq!(*before, tree1.path_value(similar_before));
+    assert_eq!(*after, tree2.path_value(similar_after));
+
+    // Threshold
+    let options = CopyDetectionOptions {
+        threshold: 80,
+        ..Default::default()
+    };
+    insta::assert_debug_snapshot!(diff(&tree1, &tree2, &[], &options), @r###"
+    [
+        "added",
+        "Rename exact => dir/exact (100%)",
+        "empty-added",
+        "empty-removed",
+        "removed",
+        "similar",
+        "similar2",
+    ]
+    "###);
+
+    // Detection disabled
+    let options = CopyDetectionOptions {
+        detect_renames: false,
+        ..Default::default()
+    };
+    insta::assert_debug_snapshot!(diff(&tree1, &tree2, &[], &options), @r###"
+    [
+        "added",
+        "dir/exact",
+        "empty-added",
+        "empty-removed",
+        "exact",
+        "removed",
+        "similar",
+        "similar2",
+    ]
+    "###);
+}
+
+#[test]
+fn test_detect_renames_prefers_most_similar() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let source = RepoPath::from_internal_string("source");
+    let target1 = RepoPath::from_internal_string("target1");
+    let target2 = RepoPath::from_internal_string("target2");
+
+    let tree1 = create_tree(repo, &[(source, "a\nb\nc\nd\n")]);
+    let tree2 = create_tree(
+        repo,
+        &[(target1, "a\nb\nc\nD\n"), (target2, "a\nb\nc\nd\n")],
+    );
+
+    let options = CopyDetectionOptions::default();
+    insta::assert_debug_snapshot!(diff(&tree1, &tree2, &[], &options), @r###"
+    [
+        "target1",
+        "Rename source => target2 (100%)",
+    ]
+    "###);
+}
+
+#[test]
+fn test_recorded_copies() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let source = RepoPath::from_internal_string("source");
+    let copied = RepoPath::from_internal_string("copied");
+    let moved_before = RepoPath::from_internal_string("moved-before");
+    let moved_after = RepoPath::from_internal_string("moved-after");
+    let unrelated = RepoPath::from_internal_string("unrelated");
+
+    let tree1 = create_tree(
+        repo,
+        &[
+            (source, "source\n"),
+            (moved_before, "before\n"),
+            (unrelated, "unrelated\n"),
+        ],
+    );
+    let tree2 = create_tree(
+        repo,
+        &[
+            (source, "source\n"),
+            (copied, "copied\n"),
+            (moved_after, "after\n"),
+        ],
+    );
+    let copy_records = vec![
+        CopyRecord {
+            target: copied.to_owned(),
+            source: source.to_owned(),
+        },
+        CopyRecord {
+            target: moved_after.to_owned(),
+            source: moved_before.to_owned(),
+        },
+        // The target wasn't added, so this is ignored
+        CopyRecord {
+            target: source.to_owned(),
+            source: unrelated.to_owned(),
+        },
+    ];
+
+    let options = CopyDetectionOptions::default();
+    insta::assert_debug_snapshot!(diff(&tree1, &tree2, &copy_records, &options), @r###"
+    [
+        "Copy source => copied (0%)",
+        "Rename moved-before => moved-after (0%)",
+        "unrelated",
+    ]
+    "###);
+}
+
+#[test_case(TestRepoBackend::Local; "local backend")]
+#[test_case(TestRepoBackend::Git; "git backend")]
+fn test_copy_records_storage(backend: TestRepoBackend) {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init_with_backend(backend);
+    let repo = &test_repo.repo;
+
+    let copy_records = vec![CopyRecord {
+        target: RepoPath::from_internal_string("target").to_owned(),
+        source: RepoPath::from_internal_string("source").to_owned(),
+    }];
+    let mut tx = repo.start_transaction(&settings);
+    let commit = tx
+        .mut_repo()
+        .new_commit(
+            &settings,
+            vec![repo.store().root_commit_id().clone()],
+            repo.store().empty_merged_tree_id(),
+        )
+        .set_copy_records(copy_records.clone())
+        .write()
+        .unwrap();
+    tx.commit("test");
+
+    // Reload the repo so the commit is read back from the backend
+    let repo = testutils::load_repo_at_head(&settings, test_repo.repo.repo_path());
+    let stored_commit = repo.store().get_commit(commit.id()).unwrap();
+    assert_eq!(stored_commit.copy_records(), copy_records);
+    assert_eq!(commit.copy_records(), stored_commit.copy_records());
+}
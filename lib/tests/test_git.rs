@@ -2016,6 +2016,7 @@ fn test_fetch_empty_repo() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &git::GitFetchOptions::default(),
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2042,6 +2043,7 @@ fn test_fetch_initial_commit() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &git::GitFetchOptions::default(),
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2092,6 +2094,7 @@ fn test_fetch_success() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &git::GitFetchOptions::default(),
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2115,6 +2118,7 @@ fn test_fetch_success() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &git::GitFetchOptions::default(),
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2172,6 +2176,7 @@ fn test_fetch_prune_deleted_ref() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &git::GitFetchOptions::default(),
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2191,6 +2196,7 @@ fn test_fetch_prune_deleted_ref() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &git::GitFetchOptions::default(),
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2214,6 +2220,7 @@ fn test_fetch_no_default_branch() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &git::GitFetchOptions::default(),
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2237,6 +2244,7 @@ fn test_fetch_no_default_branch() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &git::GitFetchOptions::default(),
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2258,6 +2266,7 @@ fn test_fetch_empty_refspecs() {
         &test_data.git_repo,
         "origin",
         &[],
+        &git::GitFetchOptions::default(),
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2284,6 +2293,7 @@ fn test_fetch_no_such_remote() {
         &test_data.git_repo,
         "invalid-remote",
         &[StringPattern::everything()],
+        &git::GitFetchOptions::default(),
         git::RemoteCallbacks::default(),
         &git_settings,
     );
@@ -20,6 +20,7 @@ use std::time::SystemTime;
 use jj_lib::backend::CommitId;
 use jj_lib::git_backend::GitBackend;
 use jj_lib::repo::{ReadonlyRepo, Repo};
+use jj_lib::repo_path::RepoPath;
 use maplit::hashset;
 use testutils::{create_random_commit, CommitGraphBuilder, TestRepo, TestRepoBackend};
 
@@ -187,3 +188,14 @@ fn test_gc() {
     repo.store().gc(base_index.as_index(), now).unwrap();
     assert_eq!(collect_no_gc_refs(&git_repo), hashset! {});
 }
+
+#[test]
+fn test_file_size() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let store = test_repo.repo.store();
+    let path = RepoPath::from_internal_string("file");
+    let id = store.write_file(path, &mut b"abc\n".as_slice()).unwrap();
+    assert_eq!(store.file_size(path, &id).unwrap(), 4);
+    let id = store.write_file(path, &mut b"".as_slice()).unwrap();
+    assert_eq!(store.file_size(path, &id).unwrap(), 0);
+}
@@ -1486,3 +1486,42 @@ fn test_merge_simplify_file_conflict_with_absent() {
     let merged = child1_merged.merge(&parent_merged, &child2_merged).unwrap();
     assert_eq!(merged, expected_merged);
 }
+
+/// Merge a file renamed on one side and modified on the other
+#[test]
+fn test_merge_renamed_file() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let old_path = RepoPath::from_internal_string("old");
+    let new_path = RepoPath::from_internal_string("dir/new");
+    let base = create_single_tree(repo, &[(old_path, "1\n2\n3\n4\n")]);
+    let renamed = create_single_tree(repo, &[(new_path, "one\n2\n3\n4\n")]);
+    let modified = create_single_tree(repo, &[(old_path, "1\n2\n3\nfour\n")]);
+    let expected = create_single_tree(repo, &[(new_path, "one\n2\n3\nfour\n")]);
+    let base_merged = MergedTree::resolved(base);
+    let renamed_merged = MergedTree::resolved(renamed);
+    let modified_merged = MergedTree::resolved(modified);
+    let expected_merged = MergedTree::resolved(expected);
+
+    // The rename can be on either side
+    let merged = renamed_merged
+        .merge(&base_merged, &modified_merged)
+        .unwrap();
+    assert_eq!(merged, expected_merged);
+    let merged = modified_merged
+        .merge(&base_merged, &renamed_merged)
+        .unwrap();
+    assert_eq!(merged, expected_merged);
+
+    // Conflicting changes to the contents are left as a modify/delete conflict
+    let modified = create_single_tree(repo, &[(old_path, "ONE\n2\n3\n4\n")]);
+    let modified_merged = MergedTree::resolved(modified);
+    let merged = renamed_merged
+        .merge(&base_merged, &modified_merged)
+        .unwrap();
+    assert_eq!(
+        merged.conflicts().map(|(path, _)| path).collect_vec(),
+        vec![old_path.to_owned()]
+    );
+}
@@ -23,6 +23,7 @@ use assert_matches::assert_matches;
 use itertools::Itertools;
 use jj_lib::backend::{CommitId, MillisSinceEpoch, Signature, Timestamp};
 use jj_lib::commit::Commit;
+use jj_lib::fileset::FilesetExpression;
 use jj_lib::git;
 use jj_lib::git_backend::GitBackend;
 use jj_lib::object_id::ObjectId;
@@ -36,6 +37,7 @@ use jj_lib::revset::{
 };
 use jj_lib::revset_graph::{ReverseRevsetGraphIterator, RevsetGraphEdge};
 use jj_lib::settings::GitSettings;
+use jj_lib::time_util::DatePatternContext;
 use jj_lib::workspace::Workspace;
 use test_case::test_case;
 use testutils::{
@@ -47,6 +49,7 @@ fn resolve_symbol(repo: &dyn Repo, symbol: &str) -> Result<Vec<CommitId>, Revset
     let context = RevsetParseContext {
         aliases_map: &RevsetAliasesMap::new(),
         user_email: String::new(),
+        date_pattern_context: DatePatternContext::from_current_time(),
         workspace: None,
     };
     let expression = parse(symbol, &context).unwrap();
@@ -175,6 +178,7 @@ fn test_resolve_symbol_commit_id() {
     let context = RevsetParseContext {
         aliases_map: &RevsetAliasesMap::new(),
         user_email: settings.user_email(),
+        date_pattern_context: DatePatternContext::new(chrono::Utc::now().fixed_offset()),
         workspace: None,
     };
     assert_matches!(
@@ -793,11 +797,20 @@ fn test_resolve_symbol_git_refs() {
     );
 }
 
+fn timestamp_from_rfc3339(src: &str) -> Timestamp {
+    let datetime = chrono::DateTime::parse_from_rfc3339(src).unwrap();
+    Timestamp {
+        timestamp: MillisSinceEpoch(datetime.timestamp_millis()),
+        tz_offset: datetime.offset().local_minus_utc() / 60,
+    }
+}
+
 fn resolve_commit_ids(repo: &dyn Repo, revset_str: &str) -> Vec<CommitId> {
     let settings = testutils::user_settings();
     let context = RevsetParseContext {
         aliases_map: &RevsetAliasesMap::new(),
         user_email: settings.user_email(),
+        date_pattern_context: DatePatternContext::new(chrono::Utc::now().fixed_offset()),
         workspace: None,
     };
     let expression = optimize(parse(revset_str, &context).unwrap());
@@ -823,6 +836,7 @@ fn resolve_commit_ids_in_workspace(
     let context = RevsetParseContext {
         aliases_map: &RevsetAliasesMap::new(),
         user_email: settings.user_email(),
+        date_pattern_context: DatePatternContext::new(chrono::Utc::now().fixed_offset()),
         workspace: Some(workspace_ctx),
     };
     let expression = optimize(parse(revset_str, &context).unwrap());
@@ -2105,6 +2119,22 @@ fn test_evaluate_expression_description() {
     assert_eq!(
         resolve_commit_ids(mut_repo, "visible_heads() & description(\"commit 2\")"),
         vec![]
+    ); // Can match with regular expression and case-insensitively
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(regex:\"^commit [13]\")"),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(regex:\"^Commit\")"),
+        vec![]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(regex-i:\"^Commit 1\")"),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(substring-i:\"COMMIT 3\")"),
+        vec![commit3.id().clone()]
     );
 }
 
@@ -2181,6 +2211,189 @@ fn test_evaluate_expression_author() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_author_date() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.mut_repo();
+
+    let timestamp1 = timestamp_from_rfc3339("2023-03-25T11:30:00Z");
+    let timestamp2 = timestamp_from_rfc3339("2023-03-25T12:30:00Z");
+    let timestamp3 = timestamp_from_rfc3339("2023-03-25T13:30:00Z");
+
+    let root_commit = repo.store().root_commit();
+    let commit1 = create_random_commit(mut_repo, &settings)
+        .set_author(Signature {
+            name: "name1".to_string(),
+            email: "email1".to_string(),
+            timestamp: timestamp1.clone(),
+        })
+        .set_committer(Signature {
+            name: "name1".to_string(),
+            email: "email1".to_string(),
+            timestamp: timestamp2.clone(),
+        })
+        .write()
+        .unwrap();
+    let commit2 = create_random_commit(mut_repo, &settings)
+        .set_parents(vec![commit1.id().clone()])
+        .set_author(Signature {
+            name: "name2".to_string(),
+            email: "email2".to_string(),
+            timestamp: timestamp2.clone(),
+        })
+        .set_committer(Signature {
+            name: "name1".to_string(),
+            email: "email1".to_string(),
+            timestamp: timestamp2.clone(),
+        })
+        .write()
+        .unwrap();
+    let commit3 = create_random_commit(mut_repo, &settings)
+        .set_parents(vec![commit2.id().clone()])
+        .set_author(Signature {
+            name: "name3".to_string(),
+            email: "email3".to_string(),
+            timestamp: timestamp3,
+        })
+        .set_committer(Signature {
+            name: "name1".to_string(),
+            email: "email1".to_string(),
+            timestamp: timestamp2.clone(),
+        })
+        .write()
+        .unwrap();
+
+    // Can find multiple matches
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author_date(after:\"2023-03-25 12:00\")"),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+    // The lower bound is inclusive
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author_date(after:\"2023-03-25T12:30:00Z\")"),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+    // The upper bound is exclusive
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "root().. & author_date(before:\"2023-03-25T12:30:00Z\")"
+        ),
+        vec![commit1.id().clone()]
+    );
+    // Time zone is taken into account
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "author_date(after:\"2023-03-25 20:00:00.000 +07:00\")"
+        ),
+        vec![commit3.id().clone()]
+    );
+    // Relative dates are resolved against the current time
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author_date(after:\"1 week ago\")"),
+        vec![]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author_date(before:\"1 week ago\")"),
+        vec![
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+            root_commit.id().clone(),
+        ]
+    );
+    // Can be combined with other filters
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "author_date(after:\"2023-03-25 12:00\") & author_date(before:\"2023-03-25 13:00\")"
+        ),
+        vec![commit2.id().clone()]
+    );
+}
+
+#[test]
+fn test_evaluate_expression_committer_date() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.mut_repo();
+
+    let timestamp1 = timestamp_from_rfc3339("2023-03-25T11:30:00Z");
+    let timestamp2 = timestamp_from_rfc3339("2023-03-25T12:30:00Z");
+    let timestamp3 = timestamp_from_rfc3339("2023-03-25T13:30:00Z");
+
+    let commit1 = create_random_commit(mut_repo, &settings)
+        .set_author(Signature {
+            name: "name1".to_string(),
+            email: "email1".to_string(),
+            timestamp: timestamp2.clone(),
+        })
+        .set_committer(Signature {
+            name: "name1".to_string(),
+            email: "email1".to_string(),
+            timestamp: timestamp1,
+        })
+        .write()
+        .unwrap();
+    let commit2 = create_random_commit(mut_repo, &settings)
+        .set_parents(vec![commit1.id().clone()])
+        .set_author(Signature {
+            name: "name2".to_string(),
+            email: "email2".to_string(),
+            timestamp: timestamp2.clone(),
+        })
+        .set_committer(Signature {
+            name: "name1".to_string(),
+            email: "email1".to_string(),
+            timestamp: timestamp2.clone(),
+        })
+        .write()
+        .unwrap();
+    let commit3 = create_random_commit(mut_repo, &settings)
+        .set_parents(vec![commit2.id().clone()])
+        .set_author(Signature {
+            name: "name3".to_string(),
+            email: "email3".to_string(),
+            timestamp: timestamp2,
+        })
+        .set_committer(Signature {
+            name: "name1".to_string(),
+            email: "email1".to_string(),
+            timestamp: timestamp3,
+        })
+        .write()
+        .unwrap();
+
+    // Can find multiple matches
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "committer_date(after:\"2023-03-25 12:00\")"),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+    // Searches only among candidates if specified
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "visible_heads() & committer_date(before:\"2023-03-25 12:00\")"
+        ),
+        vec![]
+    );
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "root().. & committer_date(before:\"2023-03-25 12:00\")"
+        ),
+        vec![commit1.id().clone()]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_mine() {
     let settings = testutils::user_settings();
@@ -2633,10 +2846,9 @@ fn test_evaluate_expression_file() {
 
     let resolve = |file_path: &RepoPath| -> Vec<CommitId> {
         let mut_repo = &*mut_repo;
-        let expression =
-            RevsetExpression::filter(RevsetFilterPredicate::File(Some(
-                vec![file_path.to_owned()],
-            )));
+        let expression = RevsetExpression::filter(RevsetFilterPredicate::File(
+            FilesetExpression::prefix_path(file_path.to_owned()),
+        ));
         let revset = expression.evaluate_programmatic(mut_repo).unwrap();
         revset.iter().collect()
     };
@@ -2682,6 +2894,160 @@ fn test_evaluate_expression_file() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_diff_contains() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init(&settings);
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.mut_repo();
+
+    let empty_clean_inserted_deleted =
+        RepoPath::from_internal_string("empty_clean_inserted_deleted");
+    let blank_clean_inserted_clean = RepoPath::from_internal_string("blank_clean_inserted_clean");
+    let noeol_modified_modified_clean =
+        RepoPath::from_internal_string("noeol_modified_modified_clean");
+    let normal_inserted_modified_removed =
+        RepoPath::from_internal_string("normal_inserted_modified_removed");
+    let tree1 = create_tree(
+        repo,
+        &[
+            (empty_clean_inserted_deleted, ""),
+            (blank_clean_inserted_clean, "\n"),
+            (noeol_modified_modified_clean, "1"),
+            (normal_inserted_modified_removed, "1\n"),
+        ],
+    );
+    let tree2 = create_tree(
+        repo,
+        &[
+            (empty_clean_inserted_deleted, ""),
+            (blank_clean_inserted_clean, "\n"),
+            (noeol_modified_modified_clean, "2"),
+            (normal_inserted_modified_removed, "1\n2\n"),
+        ],
+    );
+    let tree3 = create_tree(
+        repo,
+        &[
+            (empty_clean_inserted_deleted, "3"),
+            (blank_clean_inserted_clean, "\n3\n"),
+            (noeol_modified_modified_clean, "2 3"),
+            (normal_inserted_modified_removed, "1 3\n2\n"),
+        ],
+    );
+    let tree4 = create_tree(
+        repo,
+        &[
+            (empty_clean_inserted_deleted, ""),
+            (blank_clean_inserted_clean, "\n3\n"),
+            (noeol_modified_modified_clean, "2 3"),
+            // normal_inserted_modified_removed
+        ],
+    );
+    let commit1 = mut_repo
+        .new_commit(
+            &settings,
+            vec![repo.store().root_commit_id().clone()],
+            tree1.id(),
+        )
+        .write()
+        .unwrap();
+    let commit2 = mut_repo
+        .new_commit(&settings, vec![commit1.id().clone()], tree2.id())
+        .write()
+        .unwrap();
+    let commit3 = mut_repo
+        .new_commit(&settings, vec![commit2.id().clone()], tree3.id())
+        .write()
+        .unwrap();
+    let commit4 = mut_repo
+        .new_commit(&settings, vec![commit3.id().clone()], tree4.id())
+        .write()
+        .unwrap();
+
+    let query = |revset_str: &str| {
+        resolve_commit_ids_in_workspace(
+            mut_repo,
+            revset_str,
+            &test_workspace.workspace,
+            Some(test_workspace.workspace.workspace_root()),
+        )
+    };
+
+    // should match both inserted and deleted lines
+    assert_eq!(
+        query(r#"diff_contains("2")"#),
+        vec![
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit2.id().clone(),
+        ]
+    );
+    assert_eq!(
+        query(r#"diff_contains("3")"#),
+        vec![commit4.id().clone(), commit3.id().clone()]
+    );
+    assert_eq!(query(r#"diff_contains("2 3")"#), vec![commit3.id().clone()]);
+    assert_eq!(
+        query(r#"diff_contains("1 3")"#),
+        vec![commit4.id().clone(), commit3.id().clone()]
+    );
+
+    // should match line with eol
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains(exact:"1", {normal_inserted_modified_removed:?})"#,
+        )),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+
+    // should match line without eol
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains(exact:"1", {noeol_modified_modified_clean:?})"#,
+        )),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+
+    // exact:"" should match blank line
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains(exact:"", {empty_clean_inserted_deleted:?})"#,
+        )),
+        vec![]
+    );
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains(exact:"", {blank_clean_inserted_clean:?})"#,
+        )),
+        vec![commit1.id().clone()]
+    );
+
+    // "" should match anything but clean
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains("", {empty_clean_inserted_deleted:?})"#,
+        )),
+        vec![commit4.id().clone(), commit3.id().clone()]
+    );
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains("", {blank_clean_inserted_clean:?})"#,
+        )),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+
+    // files should be restricted
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains("2", {blank_clean_inserted_clean:?})"#,
+        )),
+        vec![]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_conflict() {
     let settings = testutils::user_settings();
@@ -45,7 +45,7 @@ use tempfile::TempDir;
 use crate::test_backend::TestBackend;
 
 pub mod test_backend;
-pub mod test_signing_backend;
+pub use jj_lib::test_signing_backend;
 
 pub fn hermetic_libgit2() {
     // libgit2 respects init.defaultBranch (and possibly other config
@@ -362,6 +362,7 @@ pub fn commit_with_tree(store: &Arc<Store>, tree_id: MergedTreeId) -> Commit {
         author: signature.clone(),
         committer: signature,
         secure_sig: None,
+        copy_records: vec![],
     };
     store.write_commit(commit, None).unwrap()
 }